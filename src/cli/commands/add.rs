@@ -4,9 +4,11 @@ use serde::{Deserialize, Serialize};
 use std::io::{self, BufRead, Write};
 use std::path::PathBuf;
 
+use crate::cli::resolve::resolve_concept;
 use crate::config::Config;
 use crate::graph::builder::GraphBuilder;
 use crate::graph::neo4j::Neo4jClient;
+use crate::graph::resolver::ConceptResolver;
 use crate::llm::Relation;
 
 static CHECK: Emoji<'_, '_> = Emoji("✅ ", "[OK] ");
@@ -34,6 +36,7 @@ pub async fn run(
     type2: Option<String>,
     interactive: bool,
     from_file: Option<PathBuf>,
+    best_match: bool,
     tenant: Option<&str>,
 ) -> Result<()> {
     let config =
@@ -171,6 +174,15 @@ pub async fn run(
         return Ok(());
     }
 
+    let neo4j_client = Neo4jClient::new(&config.neo4j).await?;
+
+    // Link names to existing concepts so typos and casing don't fork nodes
+    let resolver = ConceptResolver::new(neo4j_client.fetch_concept_names(tenant).await?);
+    for import in &mut relations_to_add {
+        import.node1 = resolve_concept(&resolver, &import.node1, best_match, true)?;
+        import.node2 = resolve_concept(&resolver, &import.node2, best_match, true)?;
+    }
+
     // Build graph with relations
     let mut builder = GraphBuilder::new();
     if let Some(t) = tenant {
@@ -189,8 +201,6 @@ pub async fn run(
     }
 
     // Store in Neo4j
-    let neo4j_client = Neo4jClient::new(&config.neo4j).await?;
-
     print!("{}Merging into Neo4j... ", LINK);
     neo4j_client.merge_graph(&builder).await?;
     println!("{}", style("done").green());
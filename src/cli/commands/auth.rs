@@ -37,7 +37,7 @@ pub async fn run(provider: Option<LlmProvider>, key: Option<String>, list: bool)
     };
 
     // Save to config
-    save_api_key(provider, &api_key)?;
+    save_api_key(&provider, &api_key)?;
 
     println!();
     println!(
@@ -213,9 +213,10 @@ fn select_provider() -> Result<LlmProvider> {
 
 fn prompt_api_key(provider: &LlmProvider) -> Result<String> {
     let prompt = match provider {
-        LlmProvider::Anthropic => "Enter your Anthropic API key",
-        LlmProvider::OpenAI => "Enter your OpenAI API key",
-        LlmProvider::Google => "Enter your Google API key",
+        LlmProvider::Anthropic => "Enter your Anthropic API key".to_string(),
+        LlmProvider::OpenAI => "Enter your OpenAI API key".to_string(),
+        LlmProvider::Google => "Enter your Google API key".to_string(),
+        LlmProvider::Http(name) => format!("Enter the API key for {}", name),
         LlmProvider::Ollama => {
             println!();
             println!("  {} Ollama doesn't require an API key.", style("ℹ").blue());
@@ -225,6 +226,30 @@ fn prompt_api_key(provider: &LlmProvider) -> Result<String> {
             );
             return Ok(String::new());
         }
+        LlmProvider::Local => {
+            println!();
+            println!(
+                "  {} The local provider doesn't require an API key.",
+                style("ℹ").blue()
+            );
+            println!(
+                "  Set {} under [providers.local] in the config.",
+                style("model = \"/path/to/model.gguf\"").cyan()
+            );
+            return Ok(String::new());
+        }
+        LlmProvider::Replay => {
+            println!();
+            println!(
+                "  {} The replay provider doesn't require an API key.",
+                style("ℹ").blue()
+            );
+            println!(
+                "  Record responses with: {} rknowledge build <path> --record --fixtures <dir>",
+                style("$").dim()
+            );
+            return Ok(String::new());
+        }
     };
 
     print!("{} {}: ", style("?").green().bold(), prompt);
@@ -240,21 +265,17 @@ fn prompt_api_key(provider: &LlmProvider) -> Result<String> {
 
     // Basic validation
     match provider {
-        LlmProvider::Anthropic => {
-            if !api_key.starts_with("sk-ant-") {
-                println!(
-                    "  {}",
-                    style("Warning: Anthropic API keys typically start with 'sk-ant-'").yellow()
-                );
-            }
+        LlmProvider::Anthropic if !api_key.starts_with("sk-ant-") => {
+            println!(
+                "  {}",
+                style("Warning: Anthropic API keys typically start with 'sk-ant-'").yellow()
+            );
         }
-        LlmProvider::OpenAI => {
-            if !api_key.starts_with("sk-") {
-                println!(
-                    "  {}",
-                    style("Warning: OpenAI API keys typically start with 'sk-'").yellow()
-                );
-            }
+        LlmProvider::OpenAI if !api_key.starts_with("sk-") => {
+            println!(
+                "  {}",
+                style("Warning: OpenAI API keys typically start with 'sk-'").yellow()
+            );
         }
         _ => {}
     }
@@ -262,7 +283,7 @@ fn prompt_api_key(provider: &LlmProvider) -> Result<String> {
     Ok(api_key)
 }
 
-fn save_api_key(provider: LlmProvider, api_key: &str) -> Result<()> {
+fn save_api_key(provider: &LlmProvider, api_key: &str) -> Result<()> {
     let config_path = Config::config_path()?;
 
     if !config_path.exists() {
@@ -272,10 +293,8 @@ fn save_api_key(provider: LlmProvider, api_key: &str) -> Result<()> {
     let content = fs::read_to_string(&config_path).context("Failed to read config file")?;
 
     let provider_section = match provider {
-        LlmProvider::Anthropic => "[providers.anthropic]",
-        LlmProvider::OpenAI => "[providers.openai]",
-        LlmProvider::Google => "[providers.google]",
-        LlmProvider::Ollama => "[providers.ollama]",
+        LlmProvider::Http(name) => format!("[providers.http.{}]", name),
+        builtin => format!("[providers.{}]", builtin),
     };
 
     // Find and update the API key in the config
@@ -2,19 +2,23 @@ use anyhow::{Context, Result};
 use console::{Emoji, style};
 use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Instant;
-use walkdir::WalkDir;
 
-use crate::cli::{LlmProvider, OutputDestination};
+use crate::cli::model::{ResolvedModel, resolve_model};
+use crate::cli::{ChunkerKind, LlmProvider, OutputDestination};
 use crate::config::{Config, DomainConfig};
 use crate::graph::builder::GraphBuilder;
+use crate::graph::mapping::apply_mapping;
 use crate::graph::neo4j::Neo4jClient;
 use tokio::fs;
 
-use crate::llm::LlmClient;
 use crate::llm::batch_processor::{BatchProcessor, DocumentSelector};
-use crate::parser::DocumentParser;
-use crate::parser::ModelContextLimits;
+use crate::llm::{EmbeddingClient, FixtureManifest, LlmClient, PromptTemplates};
+use crate::parser::{
+    ChunkPlan, Chunker, CollectOptions, DocumentParser, ExternalParser, collect_documents,
+};
+use crate::source::{CrawlOptions, SourceSpec, checkout_git, fetch_web, local_source};
 
 static LOOKING_GLASS: Emoji<'_, '_> = Emoji("🔍 ", "");
 static PAPER: Emoji<'_, '_> = Emoji("📄 ", "");
@@ -24,6 +28,9 @@ static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", "");
 static ROCKET: Emoji<'_, '_> = Emoji("🚀 ", "");
 static CHECK: Emoji<'_, '_> = Emoji("✅ ", "[OK] ");
 static DATABASE: Emoji<'_, '_> = Emoji("💾 ", "");
+static TABLE: Emoji<'_, '_> = Emoji("🗂️  ", "");
+static WARN: Emoji<'_, '_> = Emoji("⚠️  ", "[!] ");
+static GLOBE: Emoji<'_, '_> = Emoji("🌐 ", "");
 
 #[allow(clippy::too_many_arguments)]
 pub async fn run(
@@ -31,13 +38,24 @@ pub async fn run(
     provider: Option<LlmProvider>,
     model: Option<String>,
     output: OutputDestination,
-    chunk_size: usize,
-    chunk_overlap: usize,
+    chunk_size: Option<usize>,
+    chunk_overlap: Option<usize>,
+    chunker: ChunkerKind,
+    coref: bool,
+    fixtures: Option<PathBuf>,
+    record: bool,
     concurrency: usize,
     append: bool,
     domain: Option<String>,
     context: Option<String>,
     context_file: Option<PathBuf>,
+    examples: Option<PathBuf>,
+    prompt: Option<PathBuf>,
+    mappings: Vec<PathBuf>,
+    git_ref: Option<String>,
+    crawl: CrawlOptions,
+    collect: CollectOptions,
+    show_skipped: bool,
     tenant: Option<&str>,
 ) -> Result<()> {
     let started = Instant::now();
@@ -56,22 +74,31 @@ pub async fn run(
         Config::load().context("Failed to load configuration. Run 'rknowledge init' first.")?;
 
     // Determine provider and model
-    let provider = provider.unwrap_or(match config.default_provider.as_str() {
-        "openai" => LlmProvider::OpenAI,
-        "ollama" => LlmProvider::Ollama,
-        "google" => LlmProvider::Google,
-        _ => LlmProvider::Anthropic,
-    });
-
-    let model = model.or(config.default_model.clone());
-    let model_display = model.clone().unwrap_or_else(|| "default".to_string());
+    let resolved = resolve_model(&config, provider, model, fixtures.as_deref())?;
+    let model_display = resolved.display_name();
+    let ResolvedModel {
+        provider,
+        manifest,
+        model,
+        profile,
+    } = resolved;
+
+    // A replay runs with the prompt version it was recorded with
+    let prompts = PromptTemplates::from_config(&config.prompts, prompt.as_deref())?;
+    if let Some(recorded) = manifest.as_ref().and_then(|m| m.prompt_version.as_ref())
+        && recorded != prompts.version()
+    {
+        anyhow::bail!(
+            "The recording was made with prompt version {}, but this build uses {} ({}). Pass the same --prompt, or record again",
+            recorded,
+            prompts.version(),
+            prompts.name()
+        );
+    }
 
     // Auto-detect if we should use adaptive processing for local models
-    let use_adaptive = matches!(provider, LlmProvider::Ollama);
-    let detected_context = model
-        .as_ref()
-        .map(|m| ModelContextLimits::get_context_size(m))
-        .unwrap_or(4096);
+    let use_adaptive = matches!(provider, LlmProvider::Ollama | LlmProvider::Local);
+    let detected_context = profile.spec.context_size;
 
     println!(
         "{}Provider: {}",
@@ -80,6 +107,12 @@ pub async fn run(
     );
     println!("{}Model: {}", BRAIN, style(&model_display).cyan());
     println!("{}Source: {}", PAPER, style(path.display()).cyan());
+    println!(
+        "{}Prompt: {} (version {})",
+        BRAIN,
+        style(prompts.name()).cyan(),
+        style(prompts.version()).cyan()
+    );
     if use_adaptive {
         println!(
             "{}Adaptive chunking: {} ({} tokens)",
@@ -88,6 +121,60 @@ pub async fn run(
             style(detected_context).cyan()
         );
     }
+
+    // The user's chunk size and overlap, within what the model can take.
+    // Semantic chunking fails early on an embedding setup that cannot work.
+    let defaults = match &manifest {
+        Some(m) => (m.chunk_size, m.chunk_overlap, m.batch_size),
+        // Smaller batches for local models
+        None => (
+            config.chunk_size,
+            config.chunk_overlap,
+            if use_adaptive { 3 } else { 5 },
+        ),
+    };
+    let chunk_size = chunk_size.unwrap_or(defaults.0);
+    let chunk_overlap = chunk_overlap.unwrap_or(defaults.1);
+    let plan = ChunkPlan::new(chunk_size, chunk_overlap, defaults.2, &profile);
+    let text_chunker: Box<dyn Chunker> = match chunker {
+        ChunkerKind::Adaptive => Box::new(profile.chunker(&plan)),
+        ChunkerKind::Semantic => Box::new(
+            profile
+                .semantic_chunker(&plan, config.embeddings.breakpoint_percentile)
+                .with_embedder(Arc::new(EmbeddingClient::new(&config)?)),
+        ),
+    };
+    println!(
+        "{}Chunking: {}, {}",
+        BRAIN,
+        style(text_chunker.name()).cyan(),
+        style(&plan).cyan()
+    );
+    if coref {
+        println!(
+            "{}Coreference pre-pass: {}",
+            BRAIN,
+            style("enabled").green()
+        );
+    }
+    if let Some(dir) = &fixtures {
+        let mode = if record {
+            "recording to"
+        } else {
+            "replaying from"
+        };
+        if record || manifest.is_some() {
+            println!(
+                "{}Fixtures: {} {}",
+                DATABASE,
+                mode,
+                style(dir.display()).cyan()
+            );
+        }
+    }
+    if let Some(tokenizer) = &profile.spec.tokenizer {
+        println!("{}Tokenizer: {}", BRAIN, style(tokenizer.display()).cyan());
+    }
     if concurrency > 1 {
         println!("{}Concurrency: {}", BRAIN, style(concurrency).cyan());
     }
@@ -100,133 +187,300 @@ pub async fn run(
     }
     println!();
 
-    // Collect documents
-    print!("{}Scanning for documents... ", LOOKING_GLASS);
-    let documents = collect_documents(&path)?;
+    // Collect documents, including extensions handed to external commands.
+    // Remote sources are fetched or cloned into a temporary directory first.
+    let parser =
+        DocumentParser::new().with_external_parsers(ExternalParser::from_config(&config.parsers)?);
+    let spec = SourceSpec::parse(&path.to_string_lossy(), git_ref.as_deref())?;
+    let output_base = spec.output_base();
+    let mut notes: Vec<String> = Vec::new();
+    let mut skipped: Vec<Skipped> = Vec::new();
+    let (documents, _fetched, _checkout) = match &spec {
+        SourceSpec::Local(local) => {
+            print!("{}Scanning for documents... ", LOOKING_GLASS);
+            let collected = collect_documents(local, &collect, |p| parser.supports(p))?;
+            skipped.extend(collected.skipped.iter().map(|(path, reason)| Skipped {
+                source: path.display().to_string(),
+                kind: reason.kind(),
+                reason: reason.to_string(),
+            }));
+            let documents: Vec<(PathBuf, String)> = collected
+                .documents
+                .into_iter()
+                .map(|p| {
+                    let source = local_source(local, &p);
+                    (p, source)
+                })
+                .collect();
+            (documents, None, None)
+        }
+        SourceSpec::Web(url) => {
+            print!("{}Fetching {}... ", GLOBE, style(url).cyan());
+            let fetched = fetch_web(url, &crawl).await?;
+            notes.extend(
+                fetched
+                    .skipped
+                    .iter()
+                    .map(|s| format!("Not fetched: {}", s)),
+            );
+            let documents: Vec<(PathBuf, String)> = fetched
+                .files
+                .iter()
+                .filter(|f| parser.supports(&f.path))
+                .map(|f| (f.path.clone(), f.source.clone()))
+                .collect();
+            (documents, Some(fetched), None)
+        }
+        SourceSpec::Git { repo, reference } => {
+            print!("{}Cloning {}... ", GLOBE, style(repo).cyan());
+            let checkout = checkout_git(repo, reference.as_deref())?;
+            print!("at {} ", style(checkout.short_commit()).cyan());
+            let collected =
+                collect_documents(checkout.dir.path(), &collect, |p| parser.supports(p))?;
+            skipped.extend(collected.skipped.iter().map(|(path, reason)| Skipped {
+                source: checkout.source(repo, path),
+                kind: reason.kind(),
+                reason: reason.to_string(),
+            }));
+            let documents: Vec<(PathBuf, String)> = collected
+                .documents
+                .into_iter()
+                .map(|p| {
+                    let source = checkout.source(repo, &p);
+                    (p, source)
+                })
+                .collect();
+            (documents, None, Some(checkout))
+        }
+    };
     println!(
         "{}",
         style(format!("found {}", documents.len())).green().bold()
     );
+    for note in notes.drain(..) {
+        println!("{}{}", WARN, style(note).yellow());
+    }
+    report_skipped(&skipped, show_skipped);
 
-    if documents.is_empty() {
+    if documents.is_empty() && mappings.is_empty() {
         println!();
         println!(
             "{}",
-            style("No supported documents found (.pdf, .md, .txt, .html)").yellow()
+            style(format!(
+                "No supported documents (.{}) or source files (.{}) found",
+                crate::parser::SUPPORTED_EXTENSIONS.join(", ."),
+                crate::parser::CODE_EXTENSIONS.join(", .")
+            ))
+            .yellow()
         );
         return Ok(());
     }
 
-    // Parse documents
-    let parser = DocumentParser::new(chunk_size, chunk_overlap);
-    let mut doc_contents: Vec<(String, String)> = Vec::new(); // (source, text)
-
-    let pb = ProgressBar::new(documents.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template(&format!("{}{{spinner:.green}} [{{elapsed_precise}}] {{bar:40.cyan/blue}} {{pos}}/{{len}} {{msg}}", PAPER))
-            .unwrap()
-            .progress_chars("━━╸━"),
-    );
-    pb.enable_steady_tick(std::time::Duration::from_millis(100));
-
-    for doc_path in &documents {
-        let filename = doc_path.file_name().unwrap_or_default().to_string_lossy();
-        pb.set_message(format!("{}", style(filename).dim()));
-        let chunks = parser.parse(doc_path)?;
-        // Combine chunks back into full document text for batch processing
-        let full_text: String = chunks
-            .iter()
-            .map(|c| c.text.clone())
-            .collect::<Vec<_>>()
-            .join("\n\n");
-        doc_contents.push((doc_path.to_string_lossy().to_string(), full_text));
-        pb.inc(1);
+    let mut builder = GraphBuilder::new();
+    if let Some(t) = tenant {
+        builder.set_tenant(t);
     }
-    pb.finish_and_clear();
 
-    // Smart document selection for large codebases
-    let selected_docs = if doc_contents.len() > 100 {
+    // Structured sources go straight into the graph
+    for mapping in &mappings {
         println!(
-            "{}Large codebase detected ({} docs). Selecting representative documents...",
-            BRAIN,
-            doc_contents.len()
+            "{}Mapping structured sources from {}",
+            TABLE,
+            style(mapping.display()).cyan()
         );
-        DocumentSelector::select_representative_docs(&doc_contents, 5)
-    } else {
-        doc_contents
-    };
+        for summary in apply_mapping(mapping, &mut builder)? {
+            let unmatched = if summary.unmatched > 0 {
+                format!(" ({} records matched no rule)", summary.unmatched)
+            } else {
+                String::new()
+            };
+            println!(
+                "  {} {}: {} relations from {} records{}",
+                style("•").cyan(),
+                summary.path.display(),
+                style(summary.relations).green().bold(),
+                summary.records,
+                style(unmatched).yellow()
+            );
+        }
+    }
 
-    println!(
-        "{}Parsed {} documents ({} selected for processing)",
-        CHECK,
-        style(documents.len()).green().bold(),
-        style(selected_docs.len()).green().bold()
-    );
+    if !documents.is_empty() {
+        // Parse documents; a file that fails is reported and skipped
+        let mut doc_contents: Vec<(String, String)> = Vec::new(); // (source, text)
+
+        let pb = ProgressBar::new(documents.len() as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(&format!("{}{{spinner:.green}} [{{elapsed_precise}}] {{bar:40.cyan/blue}} {{pos}}/{{len}} {{msg}}", PAPER))
+                .unwrap()
+                .progress_chars("━━╸━"),
+        );
+        pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+        for (doc_path, source) in &documents {
+            let filename = doc_path.file_name().unwrap_or_default().to_string_lossy();
+            pb.set_message(format!("{}", style(filename).dim()));
+            // Chunking happens once, in the batch processor, on the text as extracted
+            let (full_text, doc_notes) = match parser.extract(doc_path) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    notes.push(format!("Skipped {}: {:#}", source, e));
+                    pb.inc(1);
+                    continue;
+                }
+            };
+            notes.extend(doc_notes);
+            doc_contents.push((source.clone(), full_text));
+            pb.inc(1);
+        }
+        pb.finish_and_clear();
+        for note in &notes {
+            println!("{}{}", WARN, style(note).yellow());
+        }
 
-    // Create LLM client
-    let llm_client = LlmClient::new(provider, &config, model.as_deref())?;
+        // Smart document selection for large codebases
+        let doc_contents_len = doc_contents.len();
+        let selector = DocumentSelector::new(config.selection.clone());
+        let selected_docs = if selector.applies_to(doc_contents.len()) {
+            println!(
+                "{}Large codebase detected ({} docs). Selecting representative documents...",
+                BRAIN,
+                doc_contents.len()
+            );
+            let (selected, dropped) = selector.select_representative_docs(&doc_contents);
+            let dropped: Vec<Skipped> = dropped
+                .into_iter()
+                .map(|(source, reason)| Skipped {
+                    source,
+                    kind: "not selected",
+                    reason,
+                })
+                .collect();
+            report_skipped(&dropped, show_skipped);
+            selected
+        } else {
+            doc_contents
+        };
 
-    // Build knowledge graph
-    println!();
-    println!("{}Extracting knowledge from text...", BRAIN);
+        println!(
+            "{}Parsed {} documents ({} selected for processing)",
+            CHECK,
+            style(doc_contents_len).green().bold(),
+            style(selected_docs.len()).green().bold()
+        );
 
-    // Handle domain and custom context
-    let mut domain_config = DomainConfig {
-        name: domain,
-        context: context.clone(),
-        ..Default::default()
-    };
+        // Create LLM client
+        let llm_client = match (&provider, &fixtures) {
+            (LlmProvider::Replay, Some(dir)) => LlmClient::replay(dir),
+            _ => LlmClient::new(provider.clone(), &config, model.as_deref())?,
+        }
+        .with_prompts(prompts);
+        let llm_client = match &fixtures {
+            Some(dir) if record => {
+                FixtureManifest {
+                    provider: provider.to_string(),
+                    model: model.clone(),
+                    model_spec: profile.spec.clone(),
+                    chunk_size,
+                    chunk_overlap,
+                    batch_size: plan.batch_size,
+                    prompt_version: Some(llm_client.prompts().version().to_string()),
+                }
+                .save(dir)?;
+                llm_client.recording(dir)?
+            }
+            _ => llm_client,
+        };
 
-    // If context_file provided, read it and append/set as context
-    if let Some(file_path) = context_file {
-        let file_context = fs::read_to_string(&file_path).await.context(format!(
-            "Failed to read context file: {}",
-            file_path.display()
-        ))?;
+        // Build knowledge graph
+        println!();
+        println!("{}Extracting knowledge from text...", BRAIN);
+
+        // Handle domain and custom context; flags override [domain] from config
+        let mut domain_config = DomainConfig {
+            name: domain.or_else(|| config.domain.name.clone()),
+            context: context.clone().or_else(|| config.domain.context.clone()),
+            ..config.domain.clone()
+        };
+
+        // If context_file provided, read it and append/set as context
+        if let Some(file_path) = context_file {
+            let file_context = fs::read_to_string(&file_path).await.context(format!(
+                "Failed to read context file: {}",
+                file_path.display()
+            ))?;
+
+            if let Some(ref mut ctx) = domain_config.context {
+                ctx.push_str("\n\n");
+                ctx.push_str(&file_context);
+            } else {
+                domain_config.context = Some(file_context);
+            }
+        }
 
-        if let Some(ref mut ctx) = domain_config.context {
-            ctx.push_str("\n\n");
-            ctx.push_str(&file_context);
-        } else {
-            domain_config.context = Some(file_context);
+        // Few-shot examples take the room the plan leaves beside a chunk
+        domain_config.load_examples(examples.as_deref())?;
+        if !domain_config.examples.is_empty() {
+            let total = domain_config.examples.len();
+            let dropped =
+                llm_client
+                    .prompts()
+                    .fit_examples(&mut domain_config, &plan, &profile.tokenizer);
+            println!(
+                "{}Few-shot examples: {} of {}{}",
+                BRAIN,
+                style(total - dropped).cyan(),
+                total,
+                if dropped > 0 {
+                    " (the rest do not fit the model's context)"
+                } else {
+                    ""
+                }
+            );
         }
-    }
 
-    let mut builder = GraphBuilder::new();
-    if let Some(t) = tenant {
-        builder.set_tenant(t);
-    }
+        // Use batch processor for efficient large codebase processing
+        let mut processor = BatchProcessor::new(
+            llm_client,
+            text_chunker,
+            Arc::clone(&profile.tokenizer),
+            plan.clone(),
+            concurrency.max(1),
+        )
+        .with_domain_config(Some(domain_config))
+        .with_coreference(coref);
+
+        // Enable progress persistence, except when recording or replaying,
+        // which must send every document's prompts
+        if fixtures.is_none() {
+            let output_json_path = output_base.with_extension("kg.json");
+            processor = processor.with_progress_persistence(&output_json_path);
+            processor.load_progress().await?;
+        }
+
+        // Process documents in batches
+        let relations_result = processor.process_documents(selected_docs).await?;
 
-    // Use batch processor for efficient large codebase processing
-    let batch_size = if use_adaptive { 3 } else { 5 }; // Smaller batches for local models
-    let mut processor =
-        BatchProcessor::new(llm_client, &model_display, concurrency.max(1), batch_size)
-            .with_domain_config(Some(domain_config));
-
-    // Enable progress persistence
-    let output_json_path = path.with_extension("kg.json");
-    processor = processor.with_progress_persistence(&output_json_path);
-    processor.load_progress().await?;
-
-    // Process documents in batches
-    let relations_result = processor.process_documents(selected_docs).await?;
-
-    // Add all relations to builder
-    let mut total_relations = 0;
-    for relation in relations_result {
-        total_relations += 1;
-        builder.add_relations(vec![relation], "document");
+        // Add all relations to builder, keyed by where they were read
+        let mut total_relations = 0;
+        for (chunk_id, relations) in relations_result {
+            total_relations += relations.len();
+            builder.add_relations(relations, &chunk_id);
+        }
+        for (name, aliases) in processor.aliases() {
+            builder.add_aliases(name, aliases);
+        }
+        let stats = processor.get_stats();
+        println!(
+            "{}Extracted {} relations from {} documents (batch size: {}, concurrency: {})",
+            CHECK,
+            style(total_relations).green().bold(),
+            style(stats.total_documents).green().bold(),
+            style(plan.batch_size).cyan(),
+            style(concurrency.max(1)).cyan(),
+        );
     }
-    let stats = processor.get_stats();
-    println!(
-        "{}Extracted {} relations from {} documents (batch size: {}, concurrency: {})",
-        CHECK,
-        style(total_relations).green().bold(),
-        style(stats.total_documents).green().bold(),
-        style(batch_size).cyan(),
-        style(concurrency.max(1)).cyan(),
-    );
 
     // Calculate contextual proximity
     print!("{}Calculating contextual proximity... ", LINK);
@@ -267,13 +521,13 @@ pub async fn run(
             println!("  {} rknowledge viz", style("$").dim());
         }
         OutputDestination::Json => {
-            let json_path = path.with_extension("kg.json");
+            let json_path = output_base.with_extension("kg.json");
             crate::export::export_json(&builder, &json_path)?;
             println!("{}Exported to {}", CHECK, style(json_path.display()).cyan());
         }
         OutputDestination::Csv => {
-            let nodes_path = path.with_extension("nodes.csv");
-            let edges_path = path.with_extension("edges.csv");
+            let nodes_path = output_base.with_extension("nodes.csv");
+            let edges_path = output_base.with_extension("edges.csv");
             crate::export::export_csv(&builder, &nodes_path, &edges_path)?;
             println!("{}Exported to:", CHECK);
             println!("  • {}", style(nodes_path.display()).cyan());
@@ -291,32 +545,45 @@ pub async fn run(
     Ok(())
 }
 
-fn collect_documents(path: &PathBuf) -> Result<Vec<PathBuf>> {
-    let mut documents = Vec::new();
+/// A file left out of the build, with a short category and the full reason
+struct Skipped {
+    source: String,
+    kind: &'static str,
+    reason: String,
+}
 
-    if path.is_file() {
-        if is_supported_file(path) {
-            documents.push(path.clone());
+/// List skipped files, or summarize them by category
+fn report_skipped(skipped: &[Skipped], show_all: bool) {
+    if skipped.is_empty() {
+        return;
+    }
+    if show_all {
+        for s in skipped {
+            println!(
+                "  {} {} {}",
+                style("-").dim(),
+                s.source,
+                style(format!("({})", s.reason)).dim()
+            );
         }
-    } else if path.is_dir() {
-        for entry in WalkDir::new(path)
-            .follow_links(true)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let entry_path = entry.path();
-            if entry_path.is_file() && is_supported_file(entry_path) {
-                documents.push(entry_path.to_path_buf());
-            }
+        return;
+    }
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for s in skipped {
+        match counts.iter_mut().find(|(kind, _)| *kind == s.kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((s.kind, 1)),
         }
     }
-
-    Ok(documents)
-}
-
-fn is_supported_file(path: &std::path::Path) -> bool {
-    matches!(
-        path.extension().and_then(|e| e.to_str()),
-        Some("pdf") | Some("txt") | Some("md") | Some("html") | Some("htm")
-    )
+    let summary: Vec<String> = counts
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind))
+        .collect();
+    println!(
+        "  {} Skipped {}: {} {}",
+        style("•").cyan(),
+        skipped.len(),
+        summary.join(", "),
+        style("(--show-skipped lists them)").dim()
+    );
 }
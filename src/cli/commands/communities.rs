@@ -38,23 +38,7 @@ pub async fn run(tenant: Option<&str>) -> Result<()> {
     spinner.set_message("Detecting communities...");
 
     // Rebuild petgraph
-    let mut builder = GraphBuilder::new();
-    for edge in &edges {
-        builder.add_relations(
-            vec![crate::llm::Relation {
-                node_1: edge.source.clone(),
-                node_1_type: None,
-                node_2: edge.target.clone(),
-                node_2_type: None,
-                edge: edge.relation.clone(),
-            }],
-            "neo4j",
-        );
-    }
-    for node in &nodes {
-        builder.ensure_node_public(&node.label);
-    }
-
+    let builder = GraphBuilder::from_graph_data(&nodes, &edges);
     let graph = builder.build();
     let communities = community::label_propagation(&graph, 50);
     let summary = community::community_summary(&graph, &communities);
@@ -117,7 +101,7 @@ pub async fn run(tenant: Option<&str>) -> Result<()> {
             *type_counts.entry(t).or_insert(0) += 1;
         }
         let mut type_list: Vec<(&str, usize)> = type_counts.into_iter().collect();
-        type_list.sort_by(|a, b| b.1.cmp(&a.1));
+        type_list.sort_by_key(|t| std::cmp::Reverse(t.1));
         let type_str: Vec<String> = type_list
             .iter()
             .map(|(t, c)| format!("{} {}", c, t))
@@ -0,0 +1,709 @@
+mod gold;
+
+use anyhow::{Context, Result};
+use console::{Emoji, style};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::cli::LlmProvider;
+use crate::cli::model::{ResolvedModel, resolve_model};
+use crate::config::{Config, DomainConfig};
+use crate::llm::{
+    ContentKind, FixtureManifest, LlmClient, MentionTable, PromptTemplates, Relation,
+};
+use crate::parser::{
+    ChunkPlan, Chunker, CollectOptions, DocumentParser, ExternalParser, Language, collect_documents,
+};
+use gold::{DocumentScores, GoldSet, Scores, TypeAccuracy, score_document};
+
+static LOOKING_GLASS: Emoji<'_, '_> = Emoji("🔍 ", "");
+static BRAIN: Emoji<'_, '_> = Emoji("🧠 ", "");
+static CHART: Emoji<'_, '_> = Emoji("📊 ", "");
+static WARN: Emoji<'_, '_> = Emoji("⚠️  ", "[!] ");
+
+/// How two prompt versions did on the same chunks
+#[derive(Debug, Serialize)]
+pub struct EvalReport {
+    pub provider: String,
+    pub model: Option<String>,
+    /// Chunks in the documents, and how many of them were sent to both prompts
+    pub total_chunks: usize,
+    pub sampled_chunks: usize,
+    pub a: VariantReport,
+    pub b: VariantReport,
+    pub overlap: Overlap,
+}
+
+/// Results of one prompt version
+#[derive(Debug, Default, Serialize)]
+pub struct VariantReport {
+    pub prompt: String,
+    pub version: String,
+    pub relations: usize,
+    pub relations_per_chunk: f64,
+    /// Distinct entity names across the sample
+    pub entities: usize,
+    /// Replies that were not a JSON relation array
+    pub parse_failures: usize,
+    pub parse_failure_rate: f64,
+    /// Calls that failed before a reply came back
+    pub errors: usize,
+}
+
+/// Agreement between the two versions, as Jaccard similarity per chunk
+///
+/// Relations are compared as unordered pairs of node names, so a reworded
+/// edge still counts as the same relation.
+#[derive(Debug, Default, Serialize)]
+pub struct Overlap {
+    pub shared_relations: usize,
+    pub relations: f64,
+    pub shared_entities: usize,
+    pub entities: f64,
+}
+
+/// Scores of one prompt against an annotations file
+#[derive(Debug, Serialize)]
+pub struct GoldReport {
+    pub provider: String,
+    pub model: Option<String>,
+    pub prompt: String,
+    pub prompt_version: String,
+    /// Name similarity at which two entities count as the same
+    pub match_threshold: f64,
+    pub documents: usize,
+    pub chunks: usize,
+    /// Replies that were not a JSON relation array, and calls that failed
+    pub parse_failures: usize,
+    pub errors: usize,
+    /// Micro-averaged over all documents
+    pub entities: Scores,
+    pub relations: Scores,
+    pub entity_types: TypeAccuracy,
+    pub per_document: Vec<DocumentReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DocumentReport {
+    pub source: String,
+    #[serde(flatten)]
+    pub scores: DocumentScores,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    path: Option<PathBuf>,
+    gold: Option<PathBuf>,
+    prompt_a: Option<PathBuf>,
+    prompt_b: Option<PathBuf>,
+    sample: usize,
+    provider: Option<LlmProvider>,
+    model: Option<String>,
+    domain: Option<String>,
+    context: Option<String>,
+    examples: Option<PathBuf>,
+    fixtures: Option<PathBuf>,
+    record: bool,
+    match_threshold: f64,
+    json: bool,
+) -> Result<()> {
+    let config =
+        Config::load().context("Failed to load configuration. Run 'rknowledge init' first.")?;
+
+    let resolved = resolve_model(&config, provider, model, fixtures.as_deref())?;
+    let model_display = resolved.display_name();
+    let ResolvedModel {
+        provider,
+        manifest,
+        model,
+        profile,
+    } = resolved;
+
+    let (chunk_size, chunk_overlap) = manifest
+        .as_ref()
+        .map_or((config.chunk_size, config.chunk_overlap), |m| {
+            (m.chunk_size, m.chunk_overlap)
+        });
+    let plan = ChunkPlan::new(chunk_size, chunk_overlap, 1, &profile);
+    let chunker = profile.chunker(&plan);
+
+    // Both prompts share one recording; each prompt has its own fixtures
+    if let Some(dir) = fixtures.as_ref().filter(|_| record) {
+        FixtureManifest {
+            provider: provider.to_string(),
+            model: model.clone(),
+            model_spec: profile.spec.clone(),
+            chunk_size,
+            chunk_overlap,
+            batch_size: plan.batch_size,
+            prompt_version: None,
+        }
+        .save(dir)?;
+    }
+    let client = |prompt: Option<&Path>| -> Result<LlmClient> {
+        let prompts = PromptTemplates::from_config(&config.prompts, prompt)?;
+        let client = match (&provider, &fixtures) {
+            (LlmProvider::Replay, Some(dir)) => LlmClient::replay(dir),
+            _ => LlmClient::new(provider.clone(), &config, model.as_deref())?,
+        }
+        .with_prompts(prompts);
+        match &fixtures {
+            Some(dir) if record => client.recording(dir),
+            _ => Ok(client),
+        }
+    };
+    let client_a = client(prompt_a.as_deref())?;
+    let client_b = prompt_b.as_deref().map(|p| client(Some(p))).transpose()?;
+
+    if !json {
+        println!();
+        println!(
+            "{}",
+            style(" RKnowledge - Prompt Evaluation ").bold().reverse()
+        );
+        println!();
+        println!(
+            "{}Provider: {}",
+            BRAIN,
+            style(&provider.to_string()).cyan().bold()
+        );
+        println!("{}Model: {}", BRAIN, style(&model_display).cyan());
+        for (label, client) in [("A", Some(&client_a)), ("B", client_b.as_ref())] {
+            let Some(client) = client else { continue };
+            println!(
+                "{}Prompt {}: {} (version {})",
+                BRAIN,
+                label,
+                style(client.prompts().name()).cyan(),
+                style(client.prompts().version()).cyan()
+            );
+        }
+    }
+
+    let parser =
+        DocumentParser::new().with_external_parsers(ExternalParser::from_config(&config.parsers)?);
+    let mut domain_config = DomainConfig {
+        name: domain.or_else(|| config.domain.name.clone()),
+        context: context.or_else(|| config.domain.context.clone()),
+        ..config.domain.clone()
+    };
+    // Both prompts see the same examples, as many as fit beside either
+    domain_config.load_examples(examples.as_deref())?;
+    let total = domain_config.examples.len();
+    for client in [Some(&client_a), client_b.as_ref()].into_iter().flatten() {
+        client
+            .prompts()
+            .fit_examples(&mut domain_config, &plan, &profile.tokenizer);
+    }
+    if total > 0 && !json {
+        println!(
+            "{}Few-shot examples: {} of {}",
+            BRAIN,
+            style(domain_config.examples.len()).cyan(),
+            total
+        );
+    }
+    let run = EvalRun {
+        parser: &parser,
+        chunker: &chunker,
+        domain: &domain_config,
+        provider: provider.to_string(),
+        model,
+        json,
+    };
+
+    match (gold, path, client_b) {
+        (Some(_), _, Some(_)) => {
+            anyhow::bail!(
+                "--gold scores a single prompt; drop --prompt-b or compare without --gold"
+            )
+        }
+        (Some(gold), _, None) => run.score_gold(&gold, &client_a, match_threshold).await,
+        (None, Some(path), Some(client_b)) => {
+            run.compare(&path, &client_a, &client_b, sample).await
+        }
+        _ => anyhow::bail!(
+            "Pass a PATH and --prompt-b to compare two prompts, or --gold to score against annotations"
+        ),
+    }
+}
+
+/// Settings shared by both kinds of evaluation
+struct EvalRun<'a> {
+    parser: &'a DocumentParser,
+    chunker: &'a dyn Chunker,
+    domain: &'a DomainConfig,
+    provider: String,
+    model: Option<String>,
+    json: bool,
+}
+
+impl EvalRun<'_> {
+    /// Send the same sampled chunks to two prompts and compare what they find
+    async fn compare(
+        self,
+        path: &Path,
+        client_a: &LlmClient,
+        client_b: &LlmClient,
+        sample: usize,
+    ) -> Result<()> {
+        if !self.json {
+            print!("{}Scanning for documents... ", LOOKING_GLASS);
+        }
+        // Prose only: code chunks get their symbol hints in the batch processor
+        let collected = collect_documents(path, &CollectOptions::default(), |p| {
+            self.parser.supports(p) && Language::from_path(p).is_none()
+        })?;
+        let mut notes = Vec::new();
+        let mut chunks: Vec<String> = Vec::new();
+        for document in &collected.documents {
+            match self.parser.extract(document) {
+                Ok((text, doc_notes)) => {
+                    notes.extend(doc_notes);
+                    chunks.extend(self.chunker.chunk(&text).await?.into_iter().map(|c| c.text));
+                }
+                Err(e) => notes.push(format!("Skipped {}: {:#}", document.display(), e)),
+            }
+        }
+        if !self.json {
+            println!(
+                "{}",
+                style(format!(
+                    "found {} ({} chunks)",
+                    collected.documents.len(),
+                    chunks.len()
+                ))
+                .green()
+                .bold()
+            );
+            for note in &notes {
+                println!("{}{}", WARN, style(note).yellow());
+            }
+        }
+        if chunks.is_empty() {
+            anyhow::bail!("No text to evaluate in {}", path.display());
+        }
+
+        let sampled = sample_evenly(&chunks, sample);
+        let pb = self.progress(sampled.len(), "chunks");
+        let mut tally_a = Tally::default();
+        let mut tally_b = Tally::default();
+        for (index, text) in sampled.iter().enumerate() {
+            for (client, tally) in [(client_a, &mut tally_a), (client_b, &mut tally_b)] {
+                tally.add(index, self.extract(client, text).await);
+            }
+            pb.inc(1);
+        }
+        pb.finish_and_clear();
+
+        let report = EvalReport {
+            provider: self.provider,
+            model: self.model,
+            total_chunks: chunks.len(),
+            sampled_chunks: sampled.len(),
+            overlap: tally_a.overlap(&tally_b),
+            a: tally_a.report(client_a.prompts(), sampled.len()),
+            b: tally_b.report(client_b.prompts(), sampled.len()),
+        };
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
+        }
+        Ok(())
+    }
+
+    /// Extract every chunk of the annotated documents and score the result
+    async fn score_gold(self, gold_path: &Path, client: &LlmClient, threshold: f64) -> Result<()> {
+        let set = GoldSet::load(gold_path)?;
+        let base = gold_path.parent().unwrap_or(Path::new("."));
+
+        let pb = self.progress(set.documents.len(), "documents");
+        let mut chunk_count = 0;
+        let mut parse_failures = 0;
+        let mut errors = 0;
+        let mut per_document = Vec::new();
+        for (i, document) in set.documents.iter().enumerate() {
+            let (source, text) = match (&document.path, &document.text) {
+                (Some(path), _) => {
+                    let full = base.join(path);
+                    let (text, _) = self
+                        .parser
+                        .extract(&full)
+                        .with_context(|| format!("Failed to read {}", full.display()))?;
+                    (path.display().to_string(), text)
+                }
+                (None, text) => (
+                    format!("document {}", i + 1),
+                    text.clone().unwrap_or_default(),
+                ),
+            };
+
+            let mut relations = Vec::new();
+            for chunk in self.chunker.chunk(&text).await? {
+                chunk_count += 1;
+                match self.extract(client, &chunk.text).await {
+                    Ok(Some(found)) => relations.extend(found),
+                    Ok(None) => parse_failures += 1,
+                    Err(e) => {
+                        tracing::warn!("Extraction failed: {:#}", e);
+                        errors += 1;
+                    }
+                }
+            }
+            per_document.push(DocumentReport {
+                source,
+                scores: score_document(document, &relations, threshold),
+            });
+            pb.inc(1);
+        }
+        pb.finish_and_clear();
+
+        let total = |f: fn(&DocumentScores) -> Scores| {
+            per_document
+                .iter()
+                .map(|d| f(&d.scores))
+                .fold(Scores::new(0, 0, 0), Scores::add)
+        };
+        let report = GoldReport {
+            provider: self.provider,
+            model: self.model,
+            prompt: client.prompts().name().to_string(),
+            prompt_version: client.prompts().version().to_string(),
+            match_threshold: threshold,
+            documents: per_document.len(),
+            chunks: chunk_count,
+            parse_failures,
+            errors,
+            entities: total(|s| s.entities),
+            relations: total(|s| s.relations),
+            entity_types: per_document
+                .iter()
+                .map(|d| d.scores.entity_types)
+                .fold(TypeAccuracy::default(), TypeAccuracy::add),
+            per_document,
+        };
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_gold_report(&report);
+        }
+        Ok(())
+    }
+
+    async fn extract(&self, client: &LlmClient, text: &str) -> Result<Option<Vec<Relation>>> {
+        client
+            .try_extract_relations(
+                text,
+                Some(self.domain),
+                ContentKind::Text,
+                &MentionTable::default(),
+            )
+            .await
+    }
+
+    fn progress(&self, len: usize, unit: &str) -> ProgressBar {
+        if self.json {
+            return ProgressBar::hidden();
+        }
+        let pb = ProgressBar::new(len as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(&format!(
+                    "{}{{spinner:.green}} [{{elapsed_precise}}] {{bar:40.cyan/blue}} {{pos}}/{{len}} {}",
+                    BRAIN, unit
+                ))
+                .unwrap()
+                .progress_chars("━━╸━"),
+        );
+        pb
+    }
+}
+
+/// `n` chunks spread evenly over the documents, or all of them
+fn sample_evenly(chunks: &[String], n: usize) -> Vec<&str> {
+    if n >= chunks.len() {
+        return chunks.iter().map(String::as_str).collect();
+    }
+    (0..n)
+        .map(|i| chunks[i * chunks.len() / n].as_str())
+        .collect()
+}
+
+/// What one prompt version returned, keyed by sampled chunk
+#[derive(Default)]
+struct Tally {
+    relations: usize,
+    parse_failures: usize,
+    errors: usize,
+    /// (chunk, node, node) with the nodes in order
+    pairs: BTreeSet<(usize, String, String)>,
+    entities: BTreeSet<(usize, String)>,
+    /// Chunks that produced an answer, failed or not
+    answered: BTreeSet<usize>,
+}
+
+impl Tally {
+    fn add(&mut self, chunk: usize, outcome: Result<Option<Vec<Relation>>>) {
+        let relations = match outcome {
+            Ok(Some(relations)) => relations,
+            Ok(None) => {
+                self.parse_failures += 1;
+                self.answered.insert(chunk);
+                return;
+            }
+            Err(e) => {
+                tracing::warn!("Extraction failed: {:#}", e);
+                self.errors += 1;
+                return;
+            }
+        };
+        self.answered.insert(chunk);
+        self.relations += relations.len();
+        for relation in relations {
+            let (first, second) = if relation.node_1 <= relation.node_2 {
+                (relation.node_1, relation.node_2)
+            } else {
+                (relation.node_2, relation.node_1)
+            };
+            self.entities.insert((chunk, first.clone()));
+            self.entities.insert((chunk, second.clone()));
+            self.pairs.insert((chunk, first, second));
+        }
+    }
+
+    fn report(&self, prompts: &PromptTemplates, sampled: usize) -> VariantReport {
+        let distinct: BTreeSet<&str> = self.entities.iter().map(|(_, e)| e.as_str()).collect();
+        VariantReport {
+            prompt: prompts.name().to_string(),
+            version: prompts.version().to_string(),
+            relations: self.relations,
+            relations_per_chunk: ratio(self.relations, sampled),
+            entities: distinct.len(),
+            parse_failures: self.parse_failures,
+            parse_failure_rate: ratio(self.parse_failures, self.answered.len()),
+            errors: self.errors,
+        }
+    }
+
+    /// Compared only on chunks both versions answered
+    fn overlap(&self, other: &Tally) -> Overlap {
+        let both: BTreeSet<usize> = self
+            .answered
+            .intersection(&other.answered)
+            .copied()
+            .collect();
+        let (shared_relations, relations) =
+            jaccard(&self.pairs, &other.pairs, |p| both.contains(&p.0));
+        let (shared_entities, entities) =
+            jaccard(&self.entities, &other.entities, |e| both.contains(&e.0));
+        Overlap {
+            shared_relations,
+            relations,
+            shared_entities,
+            entities,
+        }
+    }
+}
+
+/// Shared items and Jaccard similarity of the items that pass `keep`;
+/// two empty sets agree fully
+fn jaccard<T: Ord>(a: &BTreeSet<T>, b: &BTreeSet<T>, keep: impl Fn(&T) -> bool) -> (usize, f64) {
+    let shared = a.intersection(b).filter(|x| keep(x)).count();
+    let union = a.union(b).filter(|x| keep(x)).count();
+    if union == 0 {
+        (0, 1.0)
+    } else {
+        (shared, shared as f64 / union as f64)
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+fn print_report(report: &EvalReport) {
+    println!();
+    println!(
+        "{}Results on {} of {} chunks",
+        CHART,
+        style(report.sampled_chunks).green().bold(),
+        report.total_chunks
+    );
+    println!();
+    println!(
+        "  {:<22} {:>14} {:>14}",
+        "",
+        style(format!("A {}", report.a.version)).bold(),
+        style(format!("B {}", report.b.version)).bold()
+    );
+    let row = |label: &str, a: String, b: String| {
+        println!("  {} {:<20} {:>14} {:>14}", style("•").cyan(), label, a, b);
+    };
+    row(
+        "Relations",
+        report.a.relations.to_string(),
+        report.b.relations.to_string(),
+    );
+    row(
+        "Relations per chunk",
+        format!("{:.1}", report.a.relations_per_chunk),
+        format!("{:.1}", report.b.relations_per_chunk),
+    );
+    row(
+        "Entities",
+        report.a.entities.to_string(),
+        report.b.entities.to_string(),
+    );
+    row(
+        "Parse failures",
+        format!(
+            "{} ({:.0}%)",
+            report.a.parse_failures,
+            report.a.parse_failure_rate * 100.0
+        ),
+        format!(
+            "{} ({:.0}%)",
+            report.b.parse_failures,
+            report.b.parse_failure_rate * 100.0
+        ),
+    );
+    if report.a.errors + report.b.errors > 0 {
+        row(
+            "Failed calls",
+            report.a.errors.to_string(),
+            report.b.errors.to_string(),
+        );
+    }
+    println!();
+    println!("{}Overlap", CHART);
+    println!();
+    println!(
+        "  {} Relations:           {} shared ({:.0}%)",
+        style("•").cyan(),
+        style(report.overlap.shared_relations).green().bold(),
+        report.overlap.relations * 100.0
+    );
+    println!(
+        "  {} Entities:            {} shared ({:.0}%)",
+        style("•").cyan(),
+        style(report.overlap.shared_entities).green().bold(),
+        report.overlap.entities * 100.0
+    );
+    println!();
+}
+
+fn print_gold_report(report: &GoldReport) {
+    println!();
+    println!(
+        "{}Scores on {} documents ({} chunks)",
+        CHART,
+        style(report.documents).green().bold(),
+        report.chunks
+    );
+    println!();
+    println!(
+        "  {:<22} {:>10} {:>10} {:>10}",
+        "",
+        style("Precision").bold(),
+        style("Recall").bold(),
+        style("F1").bold()
+    );
+    for (label, scores) in [
+        ("Entities", &report.entities),
+        ("Relations", &report.relations),
+    ] {
+        println!(
+            "  {} {:<20} {:>10.3} {:>10.3} {:>10}",
+            style("•").cyan(),
+            label,
+            scores.precision,
+            scores.recall,
+            style(format!("{:.3}", scores.f1)).green().bold()
+        );
+    }
+    match report.entity_types.accuracy {
+        Some(accuracy) => println!(
+            "  {} {:<20} {:.3} ({} of {} matched entities)",
+            style("•").cyan(),
+            "Entity types",
+            accuracy,
+            report.entity_types.correct,
+            report.entity_types.compared
+        ),
+        None => println!(
+            "  {} {:<20} no annotated types among the matched entities",
+            style("•").cyan(),
+            "Entity types"
+        ),
+    }
+    if report.parse_failures + report.errors > 0 {
+        println!();
+        println!(
+            "{}{} unparsable replies, {} failed calls",
+            WARN,
+            style(report.parse_failures).yellow(),
+            style(report.errors).yellow()
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relation(a: &str, b: &str) -> Relation {
+        Relation {
+            node_1: a.to_string(),
+            node_1_type: None,
+            node_2: b.to_string(),
+            node_2_type: None,
+            edge: "related".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sample_is_spread_over_the_chunks() {
+        let chunks: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        assert_eq!(sample_evenly(&chunks, 3), vec!["0", "3", "6"]);
+        assert_eq!(sample_evenly(&chunks, 20).len(), 10);
+    }
+
+    #[test]
+    fn test_overlap_ignores_direction_and_failed_chunks() {
+        let mut a = Tally::default();
+        let mut b = Tally::default();
+        a.add(
+            0,
+            Ok(Some(vec![
+                relation("rust", "cargo"),
+                relation("rust", "llvm"),
+            ])),
+        );
+        b.add(0, Ok(Some(vec![relation("cargo", "rust")])));
+        // Only A answered chunk 1, so it is left out of the overlap
+        a.add(1, Ok(Some(vec![relation("tokio", "async")])));
+        b.add(1, Err(anyhow::anyhow!("timeout")));
+        a.add(2, Ok(None));
+        b.add(2, Ok(Some(vec![])));
+
+        let overlap = a.overlap(&b);
+        assert_eq!(overlap.shared_relations, 1);
+        assert_eq!(overlap.relations, 0.5);
+        assert_eq!(overlap.shared_entities, 2);
+
+        let report = a.report(&PromptTemplates::default(), 3);
+        assert_eq!(report.relations, 3);
+        assert_eq!(report.entities, 5);
+        assert_eq!(report.parse_failures, 1);
+        assert_eq!(report.parse_failure_rate, 1.0 / 3.0);
+        assert_eq!(b.report(&PromptTemplates::default(), 3).errors, 1);
+    }
+}
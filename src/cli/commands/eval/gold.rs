@@ -0,0 +1,347 @@
+//! Scoring extracted relations against hand-annotated documents
+//!
+//! Names are matched fuzzily with the concept resolver's similarity, and
+//! each gold entity or relation is matched at most once, best pairs first.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::graph::resolver::similarity;
+use crate::llm::Relation;
+
+/// An annotations file: documents with the entities and relations a good
+/// extraction finds in them
+#[derive(Debug, Deserialize)]
+pub struct GoldSet {
+    pub documents: Vec<GoldDocument>,
+}
+
+/// One annotated document, read from `path` (relative to the annotations
+/// file) or given inline as `text`
+#[derive(Debug, Deserialize)]
+pub struct GoldDocument {
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub entities: Vec<GoldEntity>,
+    #[serde(default)]
+    pub relations: Vec<GoldRelation>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GoldEntity {
+    #[serde(alias = "name")]
+    pub label: String,
+    #[serde(default, alias = "type")]
+    pub entity_type: Option<String>,
+    /// Other names that count as this entity
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// A relation between two entities; its direction is not scored, and a
+/// `relation` description may be given for readers but is ignored
+#[derive(Debug, Deserialize)]
+pub struct GoldRelation {
+    pub source: String,
+    pub target: String,
+}
+
+impl GoldSet {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read annotations {}", path.display()))?;
+        let set: GoldSet = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid annotations {}", path.display()))?;
+        for (i, doc) in set.documents.iter().enumerate() {
+            if doc.path.is_none() == doc.text.is_none() {
+                anyhow::bail!(
+                    "Document {} in {} needs either a \"path\" or a \"text\"",
+                    i + 1,
+                    path.display()
+                );
+            }
+        }
+        Ok(set)
+    }
+}
+
+/// Precision, recall and F1 of one kind of item
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct Scores {
+    pub matched: usize,
+    pub predicted: usize,
+    pub gold: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+impl Scores {
+    /// Nothing predicted against nothing annotated scores 1.0
+    pub fn new(matched: usize, predicted: usize, gold: usize) -> Self {
+        let rate = |n: usize, d: usize| match (n, d) {
+            (_, 0) if predicted == 0 && gold == 0 => 1.0,
+            (_, 0) => 0.0,
+            (n, d) => n as f64 / d as f64,
+        };
+        let precision = rate(matched, predicted);
+        let recall = rate(matched, gold);
+        let f1 = if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        };
+        Self {
+            matched,
+            predicted,
+            gold,
+            precision,
+            recall,
+            f1,
+        }
+    }
+
+    /// Micro-average: counts are summed, then rates recomputed
+    pub fn add(self, other: Scores) -> Self {
+        Self::new(
+            self.matched + other.matched,
+            self.predicted + other.predicted,
+            self.gold + other.gold,
+        )
+    }
+}
+
+/// How often matched entities got the annotated type
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct TypeAccuracy {
+    /// Matched entities with an annotated type
+    pub compared: usize,
+    pub correct: usize,
+    /// `None` when nothing could be compared
+    pub accuracy: Option<f64>,
+}
+
+impl TypeAccuracy {
+    fn new(compared: usize, correct: usize) -> Self {
+        Self {
+            compared,
+            correct,
+            accuracy: (compared > 0).then(|| correct as f64 / compared as f64),
+        }
+    }
+
+    pub fn add(self, other: TypeAccuracy) -> Self {
+        Self::new(self.compared + other.compared, self.correct + other.correct)
+    }
+}
+
+/// Scores of one document
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DocumentScores {
+    pub entities: Scores,
+    pub relations: Scores,
+    pub entity_types: TypeAccuracy,
+}
+
+/// Compare the relations extracted from a document with its annotations
+///
+/// Names count as the same entity when their similarity reaches `threshold`;
+/// a relation matches when both ends do, in either direction.
+pub fn score_document(
+    gold: &GoldDocument,
+    predicted: &[Relation],
+    threshold: f64,
+) -> DocumentScores {
+    // (names, type) of the annotated entities, plus relation ends that were not listed
+    let mut entities: Vec<(Vec<String>, Option<String>)> = gold
+        .entities
+        .iter()
+        .map(|e| {
+            let mut names = vec![e.label.clone()];
+            names.extend(e.aliases.iter().cloned());
+            (names, e.entity_type.clone())
+        })
+        .collect();
+    for relation in &gold.relations {
+        for end in [&relation.source, &relation.target] {
+            if !entities.iter().any(|(names, _)| names.contains(end)) {
+                entities.push((vec![end.clone()], None));
+            }
+        }
+    }
+    let names_of = |end: &str| -> Vec<String> {
+        entities
+            .iter()
+            .find(|(names, _)| names.iter().any(|n| n == end))
+            .map_or_else(|| vec![end.to_string()], |(names, _)| names.clone())
+    };
+    let entity_score = |name: &str, names: &[String]| {
+        names
+            .iter()
+            .map(|n| similarity(name, n))
+            .fold(0.0, f64::max)
+    };
+
+    // Predicted entities with the first type the model gave them
+    let mut types: BTreeMap<&str, Option<&str>> = BTreeMap::new();
+    for relation in predicted {
+        for (name, entity_type) in [
+            (&relation.node_1, &relation.node_1_type),
+            (&relation.node_2, &relation.node_2_type),
+        ] {
+            let entry = types.entry(name.as_str()).or_default();
+            if entry.is_none() {
+                *entry = entity_type.as_deref().filter(|t| !t.trim().is_empty());
+            }
+        }
+    }
+    let names: Vec<&str> = types.keys().copied().collect();
+
+    let entity_matches = match_pairs(names.len(), entities.len(), threshold, |p, g| {
+        entity_score(names[p], &entities[g].0)
+    });
+    let mut compared = 0;
+    let mut correct = 0;
+    for &(p, g) in &entity_matches {
+        if let Some(gold_type) = &entities[g].1 {
+            compared += 1;
+            if types[names[p]].is_some_and(|t| similarity(t, gold_type) >= threshold) {
+                correct += 1;
+            }
+        }
+    }
+
+    // Relations are compared once per distinct pair of ends
+    let mut pairs: Vec<(&str, &str)> = predicted
+        .iter()
+        .map(|r| {
+            let (a, b) = (r.node_1.as_str(), r.node_2.as_str());
+            if a <= b { (a, b) } else { (b, a) }
+        })
+        .collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+    let gold_ends: Vec<(Vec<String>, Vec<String>)> = gold
+        .relations
+        .iter()
+        .map(|r| (names_of(&r.source), names_of(&r.target)))
+        .collect();
+    let relation_matches = match_pairs(pairs.len(), gold_ends.len(), threshold, |p, g| {
+        let (a, b) = pairs[p];
+        let (source, target) = &gold_ends[g];
+        let forward = entity_score(a, source).min(entity_score(b, target));
+        let backward = entity_score(a, target).min(entity_score(b, source));
+        forward.max(backward)
+    });
+
+    DocumentScores {
+        entities: Scores::new(entity_matches.len(), names.len(), entities.len()),
+        relations: Scores::new(relation_matches.len(), pairs.len(), gold_ends.len()),
+        entity_types: TypeAccuracy::new(compared, correct),
+    }
+}
+
+/// One-to-one pairs of predicted and gold items scoring at least `threshold`,
+/// taking the best-scoring pairs first
+fn match_pairs(
+    predicted: usize,
+    gold: usize,
+    threshold: f64,
+    score: impl Fn(usize, usize) -> f64,
+) -> Vec<(usize, usize)> {
+    let mut candidates: Vec<(f64, usize, usize)> = (0..predicted)
+        .flat_map(|p| (0..gold).map(move |g| (p, g)))
+        .map(|(p, g)| (score(p, g), p, g))
+        .filter(|(s, _, _)| *s >= threshold)
+        .collect();
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+    let mut used_predicted = vec![false; predicted];
+    let mut used_gold = vec![false; gold];
+    let mut pairs = Vec::new();
+    for (_, p, g) in candidates {
+        if !used_predicted[p] && !used_gold[g] {
+            used_predicted[p] = true;
+            used_gold[g] = true;
+            pairs.push((p, g));
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relation(a: &str, a_type: &str, b: &str, b_type: &str) -> Relation {
+        Relation {
+            node_1: a.to_string(),
+            node_1_type: Some(a_type.to_string()),
+            node_2: b.to_string(),
+            node_2_type: Some(b_type.to_string()),
+            edge: "related".to_string(),
+        }
+    }
+
+    fn gold() -> GoldDocument {
+        serde_json::from_str(
+            r#"{
+                "text": "unused",
+                "entities": [
+                    {"label": "tokio", "type": "library"},
+                    {"label": "rust", "type": "programming language"},
+                    {"label": "kubernetes", "aliases": ["k8s"], "type": "platform"}
+                ],
+                "relations": [
+                    {"source": "tokio", "target": "rust", "relation": "is written in"},
+                    {"source": "k8s", "target": "docker"}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_scores_match_fuzzily_and_in_either_direction() {
+        let predicted = vec![
+            // Reversed, and "rust language" is close enough to "rust"
+            relation(
+                "rust language",
+                "programming language",
+                "tokio",
+                "framework",
+            ),
+            relation("k8s", "platform", "docker", "tool"),
+            relation("tokio", "framework", "mio", "library"),
+        ];
+        let scores = score_document(&gold(), &predicted, 0.8);
+
+        // Gold entities: the three listed, plus "docker" from a relation
+        assert_eq!(scores.entities, Scores::new(4, 5, 4));
+        assert_eq!(scores.entities.recall, 1.0);
+        assert_eq!(scores.entities.precision, 0.8);
+        assert_eq!(scores.relations, Scores::new(2, 3, 2));
+        // tokio was typed "framework" instead of "library"
+        assert_eq!(scores.entity_types, TypeAccuracy::new(3, 2));
+    }
+
+    #[test]
+    fn test_empty_extraction() {
+        let scores = score_document(&gold(), &[], 0.8);
+        assert_eq!(scores.relations.recall, 0.0);
+        assert_eq!(scores.relations.f1, 0.0);
+        assert_eq!(scores.entity_types.accuracy, None);
+
+        let nothing = Scores::new(0, 0, 0);
+        assert_eq!(nothing.f1, 1.0);
+        assert_eq!(
+            Scores::new(1, 2, 2).add(Scores::new(1, 2, 2)),
+            Scores::new(2, 4, 4)
+        );
+    }
+}
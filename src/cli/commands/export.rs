@@ -1,18 +1,24 @@
 use anyhow::{Context, Result};
 use console::{Emoji, style};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use crate::cli::ExportFormat;
 use crate::config::Config;
-use crate::graph::neo4j::Neo4jClient;
+use crate::export::JsonlWriter;
+use crate::graph::neo4j::{GraphEdge, Neo4jClient};
 
 static DATABASE: Emoji<'_, '_> = Emoji("💾 ", "");
 static CHECK: Emoji<'_, '_> = Emoji("✅ ", "[OK] ");
 static FILE: Emoji<'_, '_> = Emoji("📁 ", "");
 
-pub async fn run(format: ExportFormat, output: PathBuf, tenant: Option<&str>) -> Result<()> {
+pub async fn run(
+    format: ExportFormat,
+    output: PathBuf,
+    base_iri: &str,
+    tenant: Option<&str>,
+) -> Result<()> {
     println!();
     println!("{}", style(" RKnowledge - Export ").bold().reverse());
     println!();
@@ -32,7 +38,15 @@ pub async fn run(format: ExportFormat, output: PathBuf, tenant: Option<&str>) ->
     spinner.set_message("Fetching graph from Neo4j...");
 
     let neo4j_client = Neo4jClient::new(&config.neo4j).await?;
-    let (nodes, edges) = neo4j_client.fetch_graph(tenant).await?;
+
+    // JSON Lines goes straight from the Neo4j cursor to disk
+    if let ExportFormat::Jsonl = format {
+        return stream_jsonl(&neo4j_client, &output, tenant, spinner).await;
+    }
+
+    // Formats that key nodes by id keep concepts of different tenants apart
+    let (nodes, tenant_edges) = neo4j_client.fetch_tenant_graph(tenant).await?;
+    let edges: Vec<GraphEdge> = tenant_edges.iter().cloned().map(GraphEdge::from).collect();
 
     spinner.finish_and_clear();
     println!(
@@ -45,64 +59,143 @@ pub async fn run(format: ExportFormat, output: PathBuf, tenant: Option<&str>) ->
     // Export based on format
     let format_name = match format {
         ExportFormat::Json => "JSON",
+        ExportFormat::Jsonl => "JSON Lines",
         ExportFormat::Csv => "CSV",
+        ExportFormat::Parquet => "Parquet",
         ExportFormat::Graphml => "GraphML",
         ExportFormat::Cypher => "Cypher",
+        ExportFormat::Html => "HTML report",
+        ExportFormat::Turtle => "RDF Turtle",
+        ExportFormat::JsonLd => "JSON-LD",
+        ExportFormat::Gexf => "GEXF",
+        ExportFormat::Dot => "Graphviz DOT",
+        ExportFormat::Neo4jAdmin => "neo4j-admin import CSV",
     };
 
     print!("{}Exporting to {}... ", FILE, style(format_name).cyan());
 
-    match format {
+    let written = match format {
         ExportFormat::Json => {
             crate::export::export_json_from_data(&nodes, &edges, &output)?;
-            println!("{}", style("done").green());
-            println!();
-            println!(
-                "  {} {}",
-                style("→").dim(),
-                style(output.display()).cyan().underlined()
-            );
+            vec![output]
         }
+        ExportFormat::Jsonl => unreachable!("JSON Lines is streamed before the graph is loaded"),
         ExportFormat::Csv => {
             let nodes_path = output.with_extension("nodes.csv");
             let edges_path = output.with_extension("edges.csv");
             crate::export::export_csv_from_data(&nodes, &edges, &nodes_path, &edges_path)?;
-            println!("{}", style("done").green());
-            println!();
-            println!(
-                "  {} {}",
-                style("→").dim(),
-                style(nodes_path.display()).cyan().underlined()
-            );
-            println!(
-                "  {} {}",
-                style("→").dim(),
-                style(edges_path.display()).cyan().underlined()
-            );
+            vec![nodes_path, edges_path]
+        }
+        ExportFormat::Parquet => {
+            let nodes_path = output.with_extension("nodes.parquet");
+            let edges_path = output.with_extension("edges.parquet");
+            crate::export::export_parquet(&nodes, &tenant_edges, &nodes_path, &edges_path)?;
+            vec![nodes_path, edges_path]
         }
         ExportFormat::Graphml => {
-            crate::export::export_graphml(&nodes, &edges, &output)?;
-            println!("{}", style("done").green());
-            println!();
-            println!(
-                "  {} {}",
-                style("→").dim(),
-                style(output.display()).cyan().underlined()
-            );
+            crate::export::export_graphml(&nodes, &tenant_edges, &output)?;
+            vec![output]
         }
         ExportFormat::Cypher => {
-            crate::export::export_cypher(&nodes, &edges, &output)?;
-            println!("{}", style("done").green());
-            println!();
-            println!(
-                "  {} {}",
-                style("→").dim(),
-                style(output.display()).cyan().underlined()
-            );
+            crate::export::export_cypher(&nodes, &tenant_edges, &output)?;
+            vec![output]
+        }
+        ExportFormat::Html => {
+            crate::export::export_html(&nodes, &edges, tenant, &output)?;
+            vec![output]
+        }
+        ExportFormat::Turtle => {
+            crate::export::export_turtle(&nodes, &tenant_edges, base_iri, &output)?;
+            vec![output]
+        }
+        ExportFormat::JsonLd => {
+            crate::export::export_jsonld(&nodes, &tenant_edges, base_iri, &output)?;
+            vec![output]
+        }
+        ExportFormat::Gexf => {
+            crate::export::export_gexf(&nodes, &tenant_edges, &output)?;
+            vec![output]
         }
+        ExportFormat::Dot => {
+            crate::export::export_dot(&nodes, &tenant_edges, &output)?;
+            vec![output]
+        }
+        ExportFormat::Neo4jAdmin => {
+            let nodes_path = output.with_extension("nodes.csv");
+            let relationships_path = output.with_extension("relationships.csv");
+            crate::export::export_neo4j_admin(
+                &nodes,
+                &tenant_edges,
+                &nodes_path,
+                &relationships_path,
+            )?;
+            vec![nodes_path, relationships_path]
+        }
+    };
+
+    println!("{}", style("done").green());
+    println!();
+    for path in &written {
+        println!(
+            "  {} {}",
+            style("→").dim(),
+            style(path.display()).cyan().underlined()
+        );
+    }
+
+    if let ExportFormat::Neo4jAdmin = format {
+        println!();
+        println!(
+            "  {} neo4j-admin database import full --nodes={} --relationships={} <database>",
+            style("Import with:").dim(),
+            written[0].display(),
+            written[1].display()
+        );
     }
 
     println!();
 
     Ok(())
 }
+
+/// Stream every record from Neo4j into a JSON Lines file without holding the graph in memory
+async fn stream_jsonl(
+    client: &Neo4jClient,
+    output: &Path,
+    tenant: Option<&str>,
+    spinner: ProgressBar,
+) -> Result<()> {
+    spinner.set_message("Streaming graph from Neo4j...");
+
+    let mut writer = JsonlWriter::create(output)?;
+    let mut written = 0u64;
+    client
+        .stream_graph(tenant, |record| {
+            writer.write(&record)?;
+            written += 1;
+            if written.is_multiple_of(10_000) {
+                spinner.set_message(format!("Streamed {} records...", written));
+            }
+            Ok(())
+        })
+        .await?;
+    let (nodes, edges) = writer.finish()?;
+
+    spinner.finish_and_clear();
+    println!(
+        "{}Streamed {} nodes, {} edges to {}",
+        CHECK,
+        style(nodes).green().bold(),
+        style(edges).green().bold(),
+        style("JSON Lines").cyan()
+    );
+    println!();
+    println!(
+        "  {} {}",
+        style("→").dim(),
+        style(output.display()).cyan().underlined()
+    );
+    println!();
+
+    Ok(())
+}
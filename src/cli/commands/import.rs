@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use console::{Emoji, style};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::graph::neo4j::{GraphRecord, Neo4jClient};
+
+static DATABASE: Emoji<'_, '_> = Emoji("💾 ", "");
+static CHECK: Emoji<'_, '_> = Emoji("✅ ", "[OK] ");
+static WARN: Emoji<'_, '_> = Emoji("⚠️  ", "[!] ");
+
+pub async fn run(file: PathBuf, batch_size: usize, tenant: Option<&str>) -> Result<()> {
+    println!();
+    println!("{}", style(" RKnowledge - Import ").bold().reverse());
+    println!();
+
+    // Fail on a missing file before touching the database
+    let records = crate::export::read_jsonl(&file)?;
+
+    // Load configuration
+    let config =
+        Config::load().context("Failed to load configuration. Run 'rknowledge init' first.")?;
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template(&format!("{}{{spinner:.green}} {{msg}}", DATABASE))
+            .unwrap(),
+    );
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner.set_message("Connecting to Neo4j...");
+
+    let neo4j_client = Neo4jClient::new(&config.neo4j).await?;
+
+    // --tenant moves every record into that tenant
+    let records = records.map(move |record| {
+        let mut record = record?;
+        if let Some(t) = tenant {
+            match &mut record {
+                GraphRecord::Node(node) => node.tenant = t.to_string(),
+                GraphRecord::Edge(edge) => edge.tenant = t.to_string(),
+            }
+        }
+        Ok(record)
+    });
+
+    let counts = neo4j_client
+        .import_records(records, batch_size, |nodes, edges| {
+            spinner.set_message(format!("Imported {} nodes, {} edges...", nodes, edges));
+        })
+        .await?;
+
+    spinner.finish_and_clear();
+    println!(
+        "{}Imported {} nodes, {} edges from {}",
+        CHECK,
+        style(counts.nodes).green().bold(),
+        style(counts.edges).green().bold(),
+        style(file.display()).cyan()
+    );
+    if counts.skipped_edges > 0 {
+        println!(
+            "{}Skipped {} edges whose endpoints are not nodes of their tenant",
+            WARN,
+            style(counts.skipped_edges).yellow().bold()
+        );
+    }
+    if let Some(t) = tenant {
+        println!("  {} tenant {}", style("→").dim(), style(t).cyan());
+    }
+    println!();
+
+    Ok(())
+}
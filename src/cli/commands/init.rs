@@ -75,6 +75,8 @@ pub async fn run(force: bool) -> Result<()> {
                 base_url: None,
                 model: Some("gemini-2.0-flash".to_string()),
             }),
+            local: None,
+            http: Default::default(),
         },
         neo4j: Neo4jConfig {
             uri: "bolt://localhost:7687".to_string(),
@@ -84,6 +86,11 @@ pub async fn run(force: bool) -> Result<()> {
         },
         tenant: Default::default(),
         domain: Default::default(),
+        parsers: Default::default(),
+        selection: Default::default(),
+        models: Default::default(),
+        embeddings: Default::default(),
+        prompts: Default::default(),
     };
 
     // Write config file
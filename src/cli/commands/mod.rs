@@ -3,9 +3,12 @@ pub mod auth;
 pub mod build;
 pub mod communities;
 pub mod doctor;
+pub mod eval;
 pub mod export;
+pub mod import;
 pub mod init;
 pub mod path;
 pub mod query;
 pub mod stats;
+pub mod subgraph;
 pub mod viz;
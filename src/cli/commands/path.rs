@@ -3,19 +3,32 @@ use console::{Emoji, style};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::time::Duration;
 
+use crate::cli::resolve::resolve_concept;
 use crate::config::Config;
-use crate::graph::analytics;
-use crate::graph::builder::GraphBuilder;
 use crate::graph::neo4j::Neo4jClient;
+use crate::graph::paths::{GraphPath, MAX_SIMPLE_PATHS, PathGraph, PathOptions};
+use crate::graph::resolver::ConceptResolver;
 
 static ROUTE: Emoji<'_, '_> = Emoji("🛤️  ", "");
 static DATABASE: Emoji<'_, '_> = Emoji("💾 ", "");
-#[allow(dead_code)]
-static CHECK: Emoji<'_, '_> = Emoji("✅ ", "[OK] ");
 
-pub async fn run(from: String, to: String, tenant: Option<&str>) -> Result<()> {
+/// Find paths between two concepts.
+///
+/// With `all_paths_max_hops` set, lists every simple path up to that many hops;
+/// otherwise lists the `k` cheapest paths. Both concepts are resolved
+/// against graph labels and aliases first, so typos and partial names work.
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    from: String,
+    to: String,
+    k: usize,
+    all_paths_max_hops: Option<usize>,
+    options: PathOptions,
+    best_match: bool,
+    tenant: Option<&str>,
+) -> Result<()> {
     println!();
-    println!("{}", style(" RKnowledge - Shortest Path ").bold().reverse());
+    println!("{}", style(" RKnowledge - Path Finder ").bold().reverse());
     println!();
 
     let config =
@@ -33,96 +46,139 @@ pub async fn run(from: String, to: String, tenant: Option<&str>) -> Result<()> {
     let neo4j_client = Neo4jClient::new(&config.neo4j).await?;
     let (nodes, edges) = neo4j_client.fetch_graph(tenant).await?;
 
-    spinner.set_message("Finding shortest path...");
-
-    // Rebuild petgraph
-    let mut builder = GraphBuilder::new();
-    for edge in &edges {
-        builder.add_relations(
-            vec![crate::llm::Relation {
-                node_1: edge.source.clone(),
-                node_1_type: None,
-                node_2: edge.target.clone(),
-                node_2_type: None,
-                edge: edge.relation.clone(),
-            }],
-            "neo4j",
-        );
-    }
-    for node in &nodes {
-        builder.ensure_node_public(&node.label);
-    }
+    spinner.finish_and_clear();
 
-    let graph = builder.build();
+    let resolver = ConceptResolver::from_nodes(&nodes);
+    let from = resolve_concept(&resolver, &from, best_match, false)?;
+    let to = resolve_concept(&resolver, &to, best_match, false)?;
 
-    spinner.finish_and_clear();
+    let graph = PathGraph::from_data(&nodes, &edges);
+
+    let paths = match all_paths_max_hops {
+        Some(max_hops) => graph.all_simple_paths(&from, &to, max_hops, &options),
+        None if k <= 1 => graph
+            .shortest_path(&from, &to, &options)
+            .into_iter()
+            .collect(),
+        None => graph.k_shortest_paths(&from, &to, k, &options),
+    };
 
     println!(
         "{}Finding path: {} {} {}",
         ROUTE,
         style(&from).cyan().bold(),
-        style("→").dim(),
+        style(if options.directed { "→" } else { "↔" }).dim(),
         style(&to).cyan().bold()
     );
+    print_filters(&options);
     println!();
 
-    match analytics::shortest_path(&graph, &from, &to) {
-        Some((cost, path)) => {
-            println!("{}", style("Path found!").green().bold());
-            println!();
-
-            for (i, label) in path.iter().enumerate() {
-                if i == 0 {
-                    println!(
-                        "  {} {}",
-                        style("●").green().bold(),
-                        style(label).cyan().bold()
-                    );
-                } else {
-                    // Find the edge relation between previous and current
-                    let prev = &path[i - 1];
-                    let relation = edges
-                        .iter()
-                        .find(|e| {
-                            (e.source.to_lowercase() == prev.to_lowercase()
-                                && e.target.to_lowercase() == label.to_lowercase())
-                                || (e.source.to_lowercase() == label.to_lowercase()
-                                    && e.target.to_lowercase() == prev.to_lowercase())
-                        })
-                        .map(|e| e.relation.as_str())
-                        .unwrap_or("related");
-
-                    println!("  {} {}", style("│").dim(), style(relation).dim());
-                    if i == path.len() - 1 {
-                        println!(
-                            "  {} {}",
-                            style("●").green().bold(),
-                            style(label).cyan().bold()
-                        );
-                    } else {
-                        println!("  {} {}", style("◦").dim(), style(label).cyan());
-                    }
-                }
+    if paths.is_empty() {
+        println!(
+            "{}",
+            style("No path found between these concepts.").yellow()
+        );
+        println!();
+        for concept in [&from, &to] {
+            if !graph.contains(concept) {
+                println!("  Concept {} is not in the graph.", style(concept).yellow());
             }
+        }
+        if options.directed {
+            println!("  Try again without --directed to follow edges both ways.");
+        }
+        println!("  Try: {} rknowledge query \"{}\"", style("$").dim(), &from);
+        println!();
+        return Ok(());
+    }
+
+    let heading = if paths.len() == 1 {
+        "Path found!".to_string()
+    } else {
+        format!("{} paths found", paths.len())
+    };
+    println!("{}", style(heading).green().bold());
+
+    for (i, path) in paths.iter().enumerate() {
+        println!();
+        if paths.len() > 1 {
+            println!(
+                "  {} {}",
+                style(format!("#{}", i + 1)).magenta().bold(),
+                style(format!(
+                    "({} hops, cost: {:.3})",
+                    path.hop_count(),
+                    path.cost
+                ))
+                .dim()
+            );
+        }
+        print_path(path);
+    }
 
-            println!();
+    if paths.len() == 1 {
+        println!();
+        println!(
+            "  Path length: {} hops, cost: {:.3}",
+            style(paths[0].hop_count()).green().bold(),
+            style(paths[0].cost).dim()
+        );
+    }
+    if all_paths_max_hops.is_some() && paths.len() >= MAX_SIMPLE_PATHS {
+        println!();
+        println!(
+            "  {}",
+            style(format!(
+                "Stopped after {} paths; lower --max-hops or add filters.",
+                MAX_SIMPLE_PATHS
+            ))
+            .yellow()
+        );
+    }
+
+    println!();
+    Ok(())
+}
+
+/// Print one path, with the relation and its direction on every hop
+fn print_path(path: &GraphPath) {
+    for (i, label) in path.nodes.iter().enumerate() {
+        if i > 0 {
+            let hop = &path.hops[i - 1];
+            let arrow = if hop.forward { "↓" } else { "↑" };
             println!(
-                "  Path length: {} hops, cost: {:.3}",
-                style(path.len() - 1).green().bold(),
-                style(cost).dim()
+                "  {} {} {}",
+                style("│").dim(),
+                style(arrow).dim(),
+                style(&hop.relation).yellow()
             );
         }
-        None => {
+        if i == 0 || i == path.nodes.len() - 1 {
             println!(
-                "{}",
-                style("No path found between these concepts.").yellow()
+                "  {} {}",
+                style("●").green().bold(),
+                style(label).cyan().bold()
             );
-            println!();
-            println!("  Make sure both concepts exist in the graph.");
-            println!("  Try: {} rknowledge query \"{}\"", style("$").dim(), &from);
+        } else {
+            println!("  {} {}", style("◦").dim(), style(label).cyan());
         }
     }
+}
 
-    println!();
-    Ok(())
+fn print_filters(options: &PathOptions) {
+    let filters = [
+        ("relations", &options.include_relations),
+        ("excluding relations", &options.exclude_relations),
+        ("via types", &options.include_types),
+        ("excluding types", &options.exclude_types),
+    ];
+    for (name, values) in filters {
+        if !values.is_empty() {
+            println!(
+                "  {} {}",
+                style(format!("{}:", name)).dim(),
+                style(values.join(", ")).dim()
+            );
+        }
+    }
 }
@@ -1,13 +1,24 @@
 use anyhow::{Context, Result};
 use console::{Emoji, style};
 
+use crate::cli::resolve::resolve_concept;
 use crate::config::Config;
 use crate::graph::neo4j::Neo4jClient;
+use crate::graph::resolver::ConceptResolver;
 
 static SEARCH: Emoji<'_, '_> = Emoji("🔍 ", "");
 static GRAPH: Emoji<'_, '_> = Emoji("🔗 ", "");
 
-pub async fn run(query: String, depth: usize) -> Result<()> {
+/// Search the graph for a concept, or run a raw Cypher query.
+///
+/// When a search finds nothing, the term is resolved against concept labels
+/// and aliases and the search is retried with the closest concept.
+pub async fn run(
+    query: String,
+    depth: usize,
+    best_match: bool,
+    tenant: Option<&str>,
+) -> Result<()> {
     // Load configuration
     let config =
         Config::load().context("Failed to load configuration. Run 'rknowledge init' first.")?;
@@ -30,37 +41,31 @@ pub async fn run(query: String, depth: usize) -> Result<()> {
         );
         println!("  Query: {}", style(&query).cyan());
 
-        if depth > 1 {
-            // Use variable-length path pattern for deeper traversal
-            let results = neo4j_client.search_concepts_depth(&query, depth).await?;
-            if results.is_empty() {
-                println!();
-                println!("{}", style("No matching concepts found.").yellow());
-            } else {
-                println!();
-                println!("{}Related concepts (up to {} hops):", GRAPH, depth);
-                for (concept, relations) in &results {
-                    println!();
-                    println!("  {}", style(concept).cyan().bold());
-                    for (related, edge) in relations {
-                        println!("    {} {} {}", style("→").dim(), style(edge).dim(), related);
-                    }
-                }
+        let mut results = search(&neo4j_client, &query, depth, tenant).await?;
+
+        if results.is_empty() {
+            let resolver = ConceptResolver::new(neo4j_client.fetch_concept_names(tenant).await?);
+            let resolved = resolve_concept(&resolver, &query, best_match, false)?;
+            if resolved != query {
+                results = search(&neo4j_client, &resolved, depth, tenant).await?;
             }
+        }
+
+        if results.is_empty() {
+            println!();
+            println!("{}", style("No matching concepts found.").yellow());
         } else {
-            let results = neo4j_client.search_concepts(&query).await?;
-            if results.is_empty() {
-                println!();
-                println!("{}", style("No matching concepts found.").yellow());
+            println!();
+            if depth > 1 {
+                println!("{}Related concepts (up to {} hops):", GRAPH, depth);
             } else {
-                println!();
                 println!("{}Related concepts:", GRAPH);
-                for (concept, relations) in &results {
-                    println!();
-                    println!("  {}", style(concept).cyan().bold());
-                    for (related, edge) in relations {
-                        println!("    {} {} {}", style("→").dim(), style(edge).dim(), related);
-                    }
+            }
+            for (concept, relations) in &results {
+                println!();
+                println!("  {}", style(concept).cyan().bold());
+                for (related, edge) in relations {
+                    println!("    {} {} {}", style("→").dim(), style(edge).dim(), related);
                 }
             }
         }
@@ -69,6 +74,20 @@ pub async fn run(query: String, depth: usize) -> Result<()> {
     Ok(())
 }
 
+async fn search(
+    client: &Neo4jClient,
+    term: &str,
+    depth: usize,
+    tenant: Option<&str>,
+) -> Result<Vec<(String, Vec<(String, String)>)>> {
+    if depth > 1 {
+        // Use variable-length path pattern for deeper traversal
+        client.search_concepts_depth(term, depth, tenant).await
+    } else {
+        client.search_concepts(term, tenant).await
+    }
+}
+
 fn print_results(results: &[serde_json::Value]) {
     if results.is_empty() {
         println!("{}", style("No results found.").yellow());
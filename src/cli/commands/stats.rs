@@ -39,24 +39,7 @@ pub async fn run(tenant: Option<&str>) -> Result<()> {
     spinner.set_message("Computing analytics...");
 
     // Rebuild petgraph from Neo4j data
-    let mut builder = GraphBuilder::new();
-    for edge in &edges {
-        builder.add_relations(
-            vec![crate::llm::Relation {
-                node_1: edge.source.clone(),
-                node_1_type: None,
-                node_2: edge.target.clone(),
-                node_2_type: None,
-                edge: edge.relation.clone(),
-            }],
-            "neo4j",
-        );
-    }
-    // Also add isolated nodes
-    for node in &nodes {
-        builder.ensure_node_public(&node.label);
-    }
-
+    let builder = GraphBuilder::from_graph_data(&nodes, &edges);
     let graph = builder.build();
     let stats = analytics::compute_stats(&graph);
 
@@ -144,7 +127,7 @@ pub async fn run(tenant: Option<&str>) -> Result<()> {
     }
     if !type_counts.is_empty() {
         let mut type_vec: Vec<(String, usize)> = type_counts.into_iter().collect();
-        type_vec.sort_by(|a, b| b.1.cmp(&a.1));
+        type_vec.sort_by_key(|t| std::cmp::Reverse(t.1));
 
         println!();
         println!("{}Entity Types", CHART);
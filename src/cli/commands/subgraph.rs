@@ -0,0 +1,122 @@
+use anyhow::{Context, Result, bail};
+use console::{Emoji, style};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cli::SubgraphFormat;
+use crate::cli::resolve::resolve_concept;
+use crate::config::Config;
+use crate::graph::neo4j::{Neo4jClient, TenantEdge};
+use crate::graph::resolver::ConceptResolver;
+use crate::graph::subgraph::{SubgraphOptions, ego_network, tenant_slice};
+
+static DATABASE: Emoji<'_, '_> = Emoji("💾 ", "");
+static CHECK: Emoji<'_, '_> = Emoji("✅ ", "[OK] ");
+static FILE: Emoji<'_, '_> = Emoji("📁 ", "");
+
+/// Extract the ego network of `concept` from Neo4j or a JSON export and write it out
+pub async fn run(
+    concept: String,
+    options: SubgraphOptions,
+    format: SubgraphFormat,
+    output: PathBuf,
+    input: Option<PathBuf>,
+    best_match: bool,
+    tenant: Option<&str>,
+) -> Result<()> {
+    println!();
+    println!("{}", style(" RKnowledge - Subgraph ").bold().reverse());
+    println!();
+
+    let (nodes, edges) = match &input {
+        Some(path) => {
+            let (nodes, edges) = crate::export::load_json(path)?;
+            let edges = crate::export::tag_tenants(&nodes, edges);
+            (nodes, edges)
+        }
+        None => {
+            let config = Config::load()
+                .context("Failed to load configuration. Run 'rknowledge init' first.")?;
+
+            let spinner = ProgressBar::new_spinner();
+            spinner.set_style(
+                ProgressStyle::default_spinner()
+                    .template(&format!("{}{{spinner:.green}} {{msg}}", DATABASE))
+                    .unwrap(),
+            );
+            spinner.enable_steady_tick(Duration::from_millis(100));
+            spinner.set_message("Fetching graph from Neo4j...");
+
+            let neo4j_client = Neo4jClient::new(&config.neo4j).await?;
+            let graph = neo4j_client.fetch_tenant_graph(tenant).await?;
+            spinner.finish_and_clear();
+            graph
+        }
+    };
+
+    let resolver = ConceptResolver::from_nodes(&nodes);
+    let concept = resolve_concept(&resolver, &concept, best_match, false)?;
+
+    // Concept ids repeat across tenants, so walk only the center's tenant
+    let Some((center_tenant, sub_nodes, sub_edges)) = tenant_slice(nodes, edges, &concept, tenant)
+        .and_then(|(center_tenant, nodes, edges)| {
+            let (sub_nodes, sub_edges) = ego_network(&nodes, &edges, &concept, &options)?;
+            Some((center_tenant, sub_nodes, sub_edges))
+        })
+    else {
+        bail!(
+            "Concept '{}' is not in the graph. Try: rknowledge query \"{}\"",
+            concept,
+            concept
+        );
+    };
+    let tenant_edges: Vec<TenantEdge> = sub_edges
+        .iter()
+        .cloned()
+        .map(|e| e.with_tenant(&center_tenant))
+        .collect();
+
+    println!(
+        "{}Extracted {} nodes, {} edges within {} hops of {}",
+        CHECK,
+        style(sub_nodes.len()).green().bold(),
+        style(sub_edges.len()).green().bold(),
+        options.depth,
+        style(&concept).cyan().bold()
+    );
+
+    let format_name = match format {
+        SubgraphFormat::Json => "JSON",
+        SubgraphFormat::Graphml => "GraphML",
+        SubgraphFormat::Cypher => "Cypher",
+        SubgraphFormat::Html => "HTML",
+    };
+    print!("{}Exporting to {}... ", FILE, style(format_name).cyan());
+
+    match format {
+        SubgraphFormat::Json => {
+            crate::export::export_json_from_data(&sub_nodes, &sub_edges, &output)?
+        }
+        SubgraphFormat::Graphml => {
+            crate::export::export_graphml(&sub_nodes, &tenant_edges, &output)?
+        }
+        SubgraphFormat::Cypher => crate::export::export_cypher(&sub_nodes, &tenant_edges, &output)?,
+        SubgraphFormat::Html => {
+            let html = super::viz::generate_viz_html(&sub_nodes, &sub_edges)?;
+            std::fs::write(&output, html)
+                .with_context(|| format!("Failed to write file: {}", output.display()))?;
+        }
+    }
+
+    println!("{}", style("done").green());
+    println!();
+    println!(
+        "  {} {}",
+        style("→").dim(),
+        style(output.display()).cyan().underlined()
+    );
+    println!();
+
+    Ok(())
+}
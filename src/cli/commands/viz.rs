@@ -1,18 +1,32 @@
 use anyhow::{Context, Result};
 use console::{Emoji, style};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::io::Write;
+use std::path::PathBuf;
 use std::time::Duration;
+use tokio::net::TcpListener;
 
 use crate::config::Config;
-use crate::graph::neo4j::Neo4jClient;
+use crate::graph::neo4j::{GraphEdge, GraphNode, Neo4jClient};
+use crate::server::{AppState, GraphStore};
 
 static GRAPH: Emoji<'_, '_> = Emoji("🔗 ", "");
 static BROWSER: Emoji<'_, '_> = Emoji("🌐 ", "");
 static CHECK: Emoji<'_, '_> = Emoji("✅ ", "[OK] ");
 static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", "");
 
-pub async fn run(_port: u16, tenant: Option<&str>) -> Result<()> {
+/// Serve the graph explorer on a local HTTP server until Ctrl-C.
+///
+/// The page starts with the `max_nodes` best-connected concepts; search,
+/// double-click expansion, paths and community filters load the rest
+/// through the JSON API in [`crate::server`].
+pub async fn run(
+    port: u16,
+    host: String,
+    input: Option<PathBuf>,
+    max_nodes: usize,
+    open: bool,
+    tenant: Option<&str>,
+) -> Result<()> {
     println!();
     println!(
         "{}",
@@ -20,24 +34,33 @@ pub async fn run(_port: u16, tenant: Option<&str>) -> Result<()> {
     );
     println!();
 
-    // Load configuration
-    let config =
-        Config::load().context("Failed to load configuration. Run 'rknowledge init' first.")?;
+    let (nodes, edges) = match &input {
+        Some(path) => {
+            let (nodes, edges) = crate::export::load_json(path)?;
+            crate::export::filter_tenant(nodes, edges, tenant)
+        }
+        None => {
+            // Load configuration
+            let config = Config::load()
+                .context("Failed to load configuration. Run 'rknowledge init' first.")?;
+
+            // Connect to Neo4j and fetch data
+            let spinner = ProgressBar::new_spinner();
+            spinner.set_style(
+                ProgressStyle::default_spinner()
+                    .template(&format!("{}{{spinner:.green}} {{msg}}", GRAPH))
+                    .unwrap(),
+            );
+            spinner.enable_steady_tick(Duration::from_millis(100));
+            spinner.set_message("Fetching graph from Neo4j...");
+
+            let neo4j_client = Neo4jClient::new(&config.neo4j).await?;
+            let graph = neo4j_client.fetch_graph(tenant).await?;
+            spinner.finish_and_clear();
+            graph
+        }
+    };
 
-    // Connect to Neo4j and fetch data
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template(&format!("{}{{spinner:.green}} {{msg}}", GRAPH))
-            .unwrap(),
-    );
-    spinner.enable_steady_tick(Duration::from_millis(100));
-    spinner.set_message("Fetching graph from Neo4j...");
-
-    let neo4j_client = Neo4jClient::new(&config.neo4j).await?;
-    let (nodes, edges) = neo4j_client.fetch_graph(tenant).await?;
-
-    spinner.finish_and_clear();
     println!(
         "{}Loaded {} nodes, {} edges",
         CHECK,
@@ -45,86 +68,94 @@ pub async fn run(_port: u16, tenant: Option<&str>) -> Result<()> {
         style(edges.len()).green().bold()
     );
 
-    // Generate HTML visualization
-    let html = generate_viz_html(&nodes, &edges)?;
+    let store = GraphStore::new(nodes, edges);
+    let (initial_nodes, initial_edges) = store.overview(max_nodes);
+    let page = render_page(&initial_nodes, &initial_edges, LIVE_SCRIPT)?;
+
+    let listener = TcpListener::bind((host.as_str(), port))
+        .await
+        .with_context(|| format!("Failed to bind {}:{}", host, port))?;
+    let addr = listener.local_addr()?;
+    let browse_host = if addr.ip().is_unspecified() {
+        "localhost".to_string()
+    } else {
+        addr.ip().to_string()
+    };
+    let url = format!("http://{}:{}/", browse_host, addr.port());
 
-    // Write to temp file and open in browser
-    let temp_dir = std::env::temp_dir();
-    let html_path = temp_dir.join("rknowledge_viz.html");
+    println!();
+    println!("{}Serving on {}", BROWSER, style(&url).cyan().underlined());
+    if addr.ip().is_unspecified() {
+        println!(
+            "{}Listening on all interfaces; share this machine's address on port {}",
+            SPARKLE,
+            addr.port()
+        );
+    }
+    println!("  {}", style("Press Ctrl-C to stop").dim());
 
-    let mut file = std::fs::File::create(&html_path)?;
-    file.write_all(html.as_bytes())?;
+    if open {
+        open_browser(&url);
+    }
+
+    crate::server::run(listener, AppState { store, page }).await
+}
 
-    // Try to open in default browser
+/// Best-effort attempt to open `url` in the default browser
+fn open_browser(url: &str) {
     #[cfg(target_os = "macos")]
     {
-        std::process::Command::new("open")
-            .arg(&html_path)
-            .spawn()
-            .ok();
+        std::process::Command::new("open").arg(url).spawn().ok();
     }
 
     #[cfg(target_os = "linux")]
     {
-        std::process::Command::new("xdg-open")
-            .arg(&html_path)
-            .spawn()
-            .ok();
+        std::process::Command::new("xdg-open").arg(url).spawn().ok();
     }
 
     #[cfg(target_os = "windows")]
     {
         std::process::Command::new("cmd")
-            .args(["/C", "start", html_path.to_str().unwrap_or("")])
+            .args(["/C", "start", url])
             .spawn()
             .ok();
     }
+}
 
-    println!();
-    println!("{}Visualization opened in browser", BROWSER);
-    println!();
-    println!(
-        "{}File: {}",
-        SPARKLE,
-        style(html_path.display()).cyan().underlined()
-    );
+/// Render nodes and edges as a standalone interactive HTML page
+pub fn generate_viz_html(nodes: &[GraphNode], edges: &[GraphEdge]) -> Result<String> {
+    render_page(nodes, edges, "")
+}
 
-    Ok(())
+/// Node as the page's vis.js data expects it
+pub fn node_json(n: &GraphNode) -> serde_json::Value {
+    serde_json::json!({
+        "id": n.id,
+        "label": n.label,
+        "community": n.community.unwrap_or(0),
+        "degree": n.degree.unwrap_or(1),
+        "entityType": n.entity_type.as_deref().unwrap_or("concept"),
+    })
 }
 
-fn generate_viz_html(
-    nodes: &[crate::graph::neo4j::GraphNode],
-    edges: &[crate::graph::neo4j::GraphEdge],
-) -> Result<String> {
-    // Build nodes JSON
-    let nodes_json: Vec<serde_json::Value> = nodes
-        .iter()
-        .map(|n| {
-            serde_json::json!({
-                "id": n.id,
-                "label": n.label,
-                "community": n.community.unwrap_or(0),
-                "degree": n.degree.unwrap_or(1),
-                "entityType": n.entity_type.as_deref().unwrap_or("concept"),
-            })
-        })
-        .collect();
-
-    // Build edges JSON
-    let edges_json: Vec<serde_json::Value> = edges
-        .iter()
-        .map(|e| {
-            let is_proximity = e.relation == "contextual proximity";
-            serde_json::json!({
-                "from": e.source,
-                "to": e.target,
-                "label": if is_proximity { "" } else { &e.relation },
-                "fullLabel": e.relation,
-                "value": e.weight,
-                "isProximity": is_proximity,
-            })
-        })
-        .collect();
+/// Edge as the page's vis.js data expects it; the id lets the live page skip duplicates
+pub fn edge_json(e: &GraphEdge) -> serde_json::Value {
+    let is_proximity = e.relation == "contextual proximity";
+    serde_json::json!({
+        "id": format!("{}|{}|{}", e.source, e.target, e.relation),
+        "from": e.source,
+        "to": e.target,
+        "label": if is_proximity { "" } else { &e.relation },
+        "fullLabel": e.relation,
+        "value": e.weight,
+        "isProximity": is_proximity,
+    })
+}
+
+/// Render the page; `extra_script` is appended after the app code (used by the live server)
+fn render_page(nodes: &[GraphNode], edges: &[GraphEdge], extra_script: &str) -> Result<String> {
+    let nodes_json: Vec<serde_json::Value> = nodes.iter().map(node_json).collect();
+    let edges_json: Vec<serde_json::Value> = edges.iter().map(edge_json).collect();
 
     let explicit_count = edges
         .iter()
@@ -298,7 +329,7 @@ fn generate_viz_html(
             let colorIdx = 0;
             const nodesByType = {{}};
 
-            rawNodes.forEach(n => {{
+            function styleNode(n) {{
                 if (!typeMap[n.entityType]) {{
                     typeMap[n.entityType] = colors[colorIdx % colors.length];
                     colorIdx++;
@@ -310,9 +341,9 @@ fn generate_viz_html(
                 
                 if (!nodesByType[n.entityType]) nodesByType[n.entityType] = [];
                 nodesByType[n.entityType].push(n.id);
-            }});
+            }}
 
-            rawEdges.forEach(e => {{
+            function styleEdge(e) {{
                 if (e.isProximity) {{
                     e.color = {{ color: 'rgba(60,60,100,0.15)', highlight: 'rgba(120,80,200,0.3)' }};
                     e.width = 0.5;
@@ -322,7 +353,10 @@ fn generate_viz_html(
                     e.arrows = {{ to: {{ enabled: true, scaleFactor: 0.5 }} }};
                 }}
                 e.smooth = {{ type: 'curvedCW', roundness: 0.1 }};
-            }});
+            }}
+
+            rawNodes.forEach(styleNode);
+            rawEdges.forEach(styleEdge);
 
             const nodes = new vis.DataSet(rawNodes);
             const edges = new vis.DataSet(rawEdges);
@@ -351,22 +385,26 @@ fn generate_viz_html(
             const typeContainer = document.getElementById('type-filters');
             const hiddenTypes = new Set();
 
-            Object.keys(typeMap).sort().forEach(type => {{
-                const item = document.createElement('div');
-                item.className = 'filter-item';
-                item.innerHTML = `
-                    <span class="filter-dot" style="background: ${{typeMap[type]}}; color: ${{typeMap[type]}}"></span>
-                    <span class="filter-label">${{type}}</span>
-                    <span class="filter-count">${{nodesByType[type].length}}</span>
-                `;
-                item.onclick = () => {{
-                    if (hiddenTypes.has(type)) hiddenTypes.delete(type);
-                    else hiddenTypes.add(type);
-                    item.classList.toggle('hidden');
-                    updateVisibility();
-                }};
-                typeContainer.appendChild(item);
-            }});
+            function renderFilters() {{
+                typeContainer.innerHTML = '';
+                Object.keys(typeMap).sort().forEach(type => {{
+                    const item = document.createElement('div');
+                    item.className = 'filter-item' + (hiddenTypes.has(type) ? ' hidden' : '');
+                    item.innerHTML = `
+                        <span class="filter-dot" style="background: ${{typeMap[type]}}; color: ${{typeMap[type]}}"></span>
+                        <span class="filter-label">${{type}}</span>
+                        <span class="filter-count">${{nodesByType[type].length}}</span>
+                    `;
+                    item.onclick = () => {{
+                        if (hiddenTypes.has(type)) hiddenTypes.delete(type);
+                        else hiddenTypes.add(type);
+                        item.classList.toggle('hidden');
+                        updateVisibility();
+                    }};
+                    typeContainer.appendChild(item);
+                }});
+            }}
+            renderFilters();
 
             function updateVisibility() {{
                 const q = document.getElementById('search').value.toLowerCase();
@@ -457,7 +495,12 @@ fn generate_viz_html(
                 tooltip.style.left = (e.event.pageX + 15) + 'px';
                 tooltip.style.top = (e.event.pageY + 15) + 'px';
             }});
+
+            if (typeof setupLive === 'function') {{
+                setupLive({{ network, nodes, edges, rawNodes, rawEdges, styleNode, styleEdge, renderFilters, updateVisibility, openDetail }});
+            }}
         }}
+{}
     </script>
 </body>
 </html>"#,
@@ -465,8 +508,124 @@ fn generate_viz_html(
         explicit_count,
         proximity_count,
         serde_json::to_string(&nodes_json)?,
-        serde_json::to_string(&edges_json)?
+        serde_json::to_string(&edges_json)?,
+        extra_script
     );
 
     Ok(html)
 }
+
+/// Page script for the live server: search, expansion, paths and communities via the API
+const LIVE_SCRIPT: &str = r#"
+        function setupLive(rk) {
+            const api = (path, params) => fetch(path + '?' + new URLSearchParams(params))
+                .then(r => r.ok ? r.json() : r.json().then(b => { throw new Error(b.error || r.statusText); }));
+            const fail = err => alert(err.message);
+
+            function merge(data) {
+                const newNodes = data.nodes.filter(n => !rk.nodes.get(n.id));
+                const newEdges = data.edges.filter(e => !rk.edges.get(e.id));
+                newNodes.forEach(n => { rk.styleNode(n); rk.rawNodes.push(n); });
+                newEdges.forEach(e => { rk.styleEdge(e); rk.rawEdges.push(e); });
+                rk.nodes.add(newNodes);
+                rk.edges.add(newEdges);
+                if (newNodes.length) rk.renderFilters();
+                document.querySelector('#stats-bar .stat-val').textContent = rk.rawNodes.length;
+            }
+
+            function expand(id) {
+                return api('/api/expand', { id, depth: 1 }).then(d => {
+                    merge(d);
+                    rk.network.focus(d.center, { scale: 1.2, animation: true });
+                    rk.network.selectNodes([d.center]);
+                    rk.openDetail(d.center);
+                }).catch(fail);
+            }
+            rk.network.on('doubleClick', p => { if (p.nodes.length) expand(p.nodes[0]); });
+
+            // Search the whole graph on Enter; typing still filters what is loaded
+            const search = document.getElementById('search');
+            search.placeholder = 'Filter, or Enter to search all...';
+            const results = document.createElement('div');
+            results.style.cssText = 'position:absolute; top:42px; left:0; right:0; background:rgba(10,10,26,0.97); border:1px solid rgba(255,255,255,0.1); border-radius:10px; z-index:200; display:none; max-height:320px; overflow-y:auto;';
+            document.getElementById('search-container').appendChild(results);
+            search.addEventListener('keydown', ev => {
+                if (ev.key === 'Escape') results.style.display = 'none';
+                if (ev.key !== 'Enter' || !search.value.trim()) return;
+                api('/api/search', { q: search.value.trim() }).then(d => {
+                    results.innerHTML = '';
+                    if (!d.results.length) {
+                        const none = document.createElement('div');
+                        none.className = 'filter-item';
+                        none.textContent = 'No matches';
+                        results.appendChild(none);
+                    }
+                    d.results.forEach(r => {
+                        const item = document.createElement('div');
+                        item.className = 'filter-item';
+                        const label = document.createElement('span');
+                        label.className = 'filter-label';
+                        label.textContent = r.label;
+                        const meta = document.createElement('span');
+                        meta.className = 'filter-count';
+                        meta.textContent = r.entityType;
+                        item.append(label, meta);
+                        item.onclick = () => {
+                            results.style.display = 'none';
+                            search.value = '';
+                            rk.updateVisibility();
+                            expand(r.id);
+                        };
+                        results.appendChild(item);
+                    });
+                    results.style.display = 'block';
+                }).catch(fail);
+            });
+
+            const toolbar = document.querySelector('.toolbar');
+            const stats = document.getElementById('stats-bar');
+            const button = (text, onclick) => {
+                const b = document.createElement('button');
+                b.className = 'btn';
+                b.textContent = text;
+                b.onclick = onclick;
+                toolbar.insertBefore(b, stats);
+                return b;
+            };
+
+            button('Path', () => {
+                const from = prompt('From concept');
+                if (!from) return;
+                const to = prompt('To concept');
+                if (!to) return;
+                api('/api/path', { from, to, k: 3 }).then(d => {
+                    if (!d.paths.length) { alert(`No path between ${d.from} and ${d.to}`); return; }
+                    merge(d);
+                    const ids = d.paths[0].ids;
+                    rk.network.selectNodes(ids);
+                    rk.network.fit({ nodes: ids, animation: true });
+                }).catch(fail);
+            });
+
+            // Show only the selected node's community, loading its members first
+            let communityFilter = null;
+            const communityBtn = button('Community', () => {
+                if (communityFilter !== null) {
+                    communityFilter = null;
+                    communityBtn.classList.remove('active');
+                    rk.updateVisibility();
+                    return;
+                }
+                const selected = rk.network.getSelectedNodes()[0];
+                if (!selected) { alert('Select a concept first'); return; }
+                const community = rk.nodes.get(selected).community;
+                api('/api/community', { id: community }).then(d => {
+                    merge(d);
+                    communityFilter = community;
+                    communityBtn.classList.add('active');
+                    rk.nodes.update(rk.rawNodes.map(n => ({ id: n.id, hidden: n.community !== community })));
+                    rk.network.fit({ nodes: d.nodes.map(n => n.id), animation: true });
+                }).catch(fail);
+            });
+        }
+"#;
@@ -1,4 +1,6 @@
 pub mod commands;
+pub mod model;
+pub mod resolve;
 
 use std::path::PathBuf;
 
@@ -20,6 +22,7 @@ pub struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// Initialize configuration and optionally start Neo4j
     Init {
@@ -40,7 +43,7 @@ pub enum Commands {
         Google accepts both GOOGLE_API_KEY and GEMINI_API_KEY environment variables.\n\n\
         Set base_url in ~/.config/rknowledge/config.toml for each provider.")]
     Auth {
-        /// Provider to configure (anthropic, openai, google, ollama)
+        /// Provider to configure (anthropic, openai, google, ollama, local, or a [providers.http.<name>] endpoint)
         #[arg(short, long)]
         provider: Option<LlmProvider>,
 
@@ -55,15 +58,15 @@ pub enum Commands {
 
     /// Process documents and build knowledge graph
     Build {
-        /// Path to document(s) or directory
+        /// Path to document(s) or directory, an https:// page or sitemap, or a git+https:// repository
         #[arg(required = true)]
         path: PathBuf,
 
-        /// LLM provider (anthropic, openai, google, ollama). OpenAI-compatible APIs (Groq, DeepSeek, etc.) use 'openai' with a custom base_url in config
+        /// LLM provider (anthropic, openai, google, ollama, local, replay). OpenAI-compatible APIs (Groq, DeepSeek, etc.) use 'openai' with a custom base_url in config; any other name selects a [providers.http.<name>] endpoint
         #[arg(short, long, env = "RKNOWLEDGE_PROVIDER")]
         provider: Option<LlmProvider>,
 
-        /// Model name (provider-specific, e.g. claude-sonnet-4-20250514, gpt-4o, gemini-2.0-flash, mistral)
+        /// Model name (provider-specific, e.g. claude-sonnet-4-20250514, gpt-4o, gemini-2.0-flash, mistral, or a .gguf path for local)
         #[arg(short, long, env = "RKNOWLEDGE_MODEL")]
         model: Option<String>,
 
@@ -71,13 +74,29 @@ pub enum Commands {
         #[arg(short, long, default_value = "neo4j")]
         output: OutputDestination,
 
-        /// Chunk size for text splitting
-        #[arg(long, default_value = "1500")]
-        chunk_size: usize,
+        /// Tokens per chunk, capped by the model's context window [default: chunk_size from config]
+        #[arg(long)]
+        chunk_size: Option<usize>,
+
+        /// Tokens repeated between consecutive chunks [default: chunk_overlap from config]
+        #[arg(long)]
+        chunk_overlap: Option<usize>,
+
+        /// How text is split before extraction
+        #[arg(long, value_enum, default_value = "adaptive")]
+        chunker: ChunkerKind,
+
+        /// Resolve pronouns and aliases per document before extraction (one extra LLM pass)
+        #[arg(long)]
+        coref: bool,
+
+        /// Fixture directory of recorded LLM responses, read by --provider replay and written by --record
+        #[arg(long, value_name = "DIR")]
+        fixtures: Option<PathBuf>,
 
-        /// Chunk overlap for text splitting
-        #[arg(long, default_value = "150")]
-        chunk_overlap: usize,
+        /// Save every LLM response to --fixtures, so the build can be replayed
+        #[arg(long, requires = "fixtures")]
+        record: bool,
 
         /// Number of concurrent LLM requests
         #[arg(short = 'j', long, default_value = "4")]
@@ -98,6 +117,118 @@ pub enum Commands {
         /// Path to file containing custom context/prompt to inject into extraction
         #[arg(long, env = "RKNOWLEDGE_CONTEXT_FILE")]
         context_file: Option<PathBuf>,
+
+        /// Few-shot examples (JSON array, or TOML with [[examples]]) added to [domain] examples from config
+        #[arg(long, value_name = "FILE")]
+        examples: Option<PathBuf>,
+
+        /// Template file for the extraction system prompt [default: [prompts] extraction from config, else built-in]
+        #[arg(long, value_name = "FILE")]
+        prompt: Option<PathBuf>,
+
+        /// Mapping file (TOML) that turns CSV, JSON, YAML or SQL files into relations without the LLM. Repeatable
+        #[arg(long = "mapping", value_name = "FILE")]
+        mappings: Vec<PathBuf>,
+
+        /// Git ref (branch, tag or commit) to check out; makes a local path a git repository
+        #[arg(long = "ref", value_name = "REF")]
+        git_ref: Option<String>,
+
+        /// Link hops to follow from fetched web pages (sitemap entries are always fetched)
+        #[arg(long, default_value = "0")]
+        depth: usize,
+
+        /// Maximum number of web pages to fetch
+        #[arg(long, default_value = "100")]
+        max_pages: usize,
+
+        /// Domain that web crawling may visit, subdomains included (default: the start URL's). Repeatable
+        #[arg(long = "allow-domain", value_name = "DOMAIN")]
+        allow_domains: Vec<String>,
+
+        /// Only read files matching this glob, relative to PATH (e.g. "docs/**/*.md"). Repeatable
+        #[arg(long = "include", value_name = "GLOB")]
+        include: Vec<String>,
+
+        /// Skip files and directories matching this glob (e.g. "archive" or "*.log"). Repeatable
+        #[arg(long = "exclude", value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Skip files larger than this (e.g. 500K, 10MB)
+        #[arg(long, value_name = "SIZE", value_parser = crate::parser::parse_size)]
+        max_file_size: Option<u64>,
+
+        /// Do not read .gitignore and .rknowledgeignore files
+        #[arg(long)]
+        no_ignore: bool,
+
+        /// List every skipped file with the reason instead of a summary
+        #[arg(long)]
+        show_skipped: bool,
+    },
+
+    /// Compare two extraction prompts on a sample of chunks, or score one against annotations
+    Eval {
+        /// Document or directory to take chunks from
+        #[arg(required_unless_present = "gold", conflicts_with = "gold")]
+        path: Option<PathBuf>,
+
+        /// Annotated documents (JSON) to score extraction against with precision, recall and F1
+        #[arg(long, value_name = "FILE")]
+        gold: Option<PathBuf>,
+
+        /// Template file for prompt A, the one scored with --gold [default: [prompts] extraction from config, else built-in]
+        #[arg(long, visible_alias = "prompt", value_name = "FILE")]
+        prompt_a: Option<PathBuf>,
+
+        /// Template file for prompt B
+        #[arg(
+            long,
+            value_name = "FILE",
+            required_unless_present = "gold",
+            conflicts_with = "gold"
+        )]
+        prompt_b: Option<PathBuf>,
+
+        /// Number of chunks to send to both prompts, spread over the documents
+        #[arg(long, default_value = "20")]
+        sample: usize,
+
+        /// LLM provider, as for build
+        #[arg(short, long, env = "RKNOWLEDGE_PROVIDER")]
+        provider: Option<LlmProvider>,
+
+        /// Model name, as for build
+        #[arg(short, long, env = "RKNOWLEDGE_MODEL")]
+        model: Option<String>,
+
+        /// Domain name filled into the prompts
+        #[arg(long, env = "RKNOWLEDGE_DOMAIN")]
+        domain: Option<String>,
+
+        /// Custom context filled into the prompts
+        #[arg(long, env = "RKNOWLEDGE_CONTEXT")]
+        context: Option<String>,
+
+        /// Few-shot examples file, as for build
+        #[arg(long, value_name = "FILE")]
+        examples: Option<PathBuf>,
+
+        /// Fixture directory of recorded LLM responses, as for build
+        #[arg(long, value_name = "DIR")]
+        fixtures: Option<PathBuf>,
+
+        /// Save every LLM response to --fixtures
+        #[arg(long, requires = "fixtures")]
+        record: bool,
+
+        /// Name similarity (0 to 1) at which an extracted entity matches an annotated one
+        #[arg(long, default_value = "0.8")]
+        match_threshold: f64,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Export knowledge graph to various formats
@@ -109,6 +240,20 @@ pub enum Commands {
         /// Output file path
         #[arg(short, long)]
         output: PathBuf,
+
+        /// Base IRI for concept and vocabulary IRIs (turtle, json-ld)
+        #[arg(long, default_value = "urn:rknowledge:")]
+        base_iri: String,
+    },
+
+    /// Import a JSON Lines export into Neo4j, streaming it in batches
+    Import {
+        /// JSON Lines file written by `export --format jsonl`
+        file: PathBuf,
+
+        /// Records sent to Neo4j per transaction
+        #[arg(long, default_value = "1000")]
+        batch_size: usize,
     },
 
     /// Query the knowledge graph
@@ -119,15 +264,93 @@ pub enum Commands {
         /// Traversal depth for matching concepts (hops from match)
         #[arg(short, long, default_value = "1")]
         depth: usize,
+
+        /// Use the closest matching concept instead of asking when a name is ambiguous
+        #[arg(long, default_value = "false")]
+        best_match: bool,
     },
 
-    /// Find shortest path between two concepts
+    /// Find paths between two concepts, showing the relation on every hop
     Path {
         /// Source concept
         from: String,
 
         /// Target concept
         to: String,
+
+        /// Number of shortest paths to list (Yen's k-shortest paths)
+        #[arg(short = 'k', long, default_value = "1")]
+        k_paths: usize,
+
+        /// List all simple paths up to --max-hops instead of the k shortest
+        #[arg(long, default_value = "false")]
+        all_paths: bool,
+
+        /// Maximum number of hops for --all-paths
+        #[arg(long, default_value = "4")]
+        max_hops: usize,
+
+        /// Only follow edges in their stored direction
+        #[arg(long, default_value = "false")]
+        directed: bool,
+
+        /// Only follow relations containing this text (repeatable)
+        #[arg(long = "relation", value_name = "TEXT")]
+        relations: Vec<String>,
+
+        /// Never follow relations containing this text (repeatable, e.g. "contextual proximity")
+        #[arg(long = "exclude-relation", value_name = "TEXT")]
+        exclude_relations: Vec<String>,
+
+        /// Only pass through concepts of this entity type (repeatable)
+        #[arg(long = "entity-type", value_name = "TYPE")]
+        entity_types: Vec<String>,
+
+        /// Never pass through concepts of this entity type (repeatable)
+        #[arg(long = "exclude-entity-type", value_name = "TYPE")]
+        exclude_entity_types: Vec<String>,
+
+        /// Use the closest matching concept instead of asking when a name is ambiguous
+        #[arg(long, default_value = "false")]
+        best_match: bool,
+    },
+
+    /// Extract the neighborhood of a concept and write it to a file
+    Subgraph {
+        /// Center concept
+        concept: String,
+
+        /// Maximum number of hops from the concept
+        #[arg(short, long, default_value = "2")]
+        depth: usize,
+
+        /// Ignore edges with a weight below this
+        #[arg(long, default_value = "0")]
+        min_weight: f64,
+
+        /// Only follow relations containing this text (repeatable)
+        #[arg(long = "relation-filter", value_name = "TEXT")]
+        relation_filters: Vec<String>,
+
+        /// Never follow relations containing this text (repeatable, e.g. "contextual proximity")
+        #[arg(long = "exclude-relation", value_name = "TEXT")]
+        exclude_relations: Vec<String>,
+
+        /// Output format
+        #[arg(short, long, default_value = "json")]
+        format: SubgraphFormat,
+
+        /// Output file path
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Read the graph from a JSON export instead of Neo4j
+        #[arg(long, value_name = "FILE")]
+        input: Option<PathBuf>,
+
+        /// Use the closest matching concept instead of asking when a name is ambiguous
+        #[arg(long, default_value = "false")]
+        best_match: bool,
     },
 
     /// Show graph statistics and analytics
@@ -141,6 +364,22 @@ pub enum Commands {
         /// Port to serve visualization on
         #[arg(short, long, default_value = "8080")]
         port: u16,
+
+        /// Address to bind (use 0.0.0.0 to share on the LAN)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Serve a JSON export instead of Neo4j
+        #[arg(long, value_name = "FILE")]
+        input: Option<PathBuf>,
+
+        /// Concepts shown on first load; the rest load on demand
+        #[arg(long, default_value = "500")]
+        max_nodes: usize,
+
+        /// Don't open a browser
+        #[arg(long, default_value = "false")]
+        no_open: bool,
     },
 
     /// Check system health and diagnose common problems
@@ -173,16 +412,45 @@ pub enum Commands {
         /// Import from JSON file
         #[arg(long, value_name = "FILE")]
         from_file: Option<PathBuf>,
+
+        /// Link to the closest existing concept instead of asking when a name is close to one
+        #[arg(long, default_value = "false")]
+        best_match: bool,
     },
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+/// A built-in LLM API, or an endpoint described in `[providers.http.<name>]`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum LlmProvider {
     #[default]
     Anthropic,
     OpenAI,
     Ollama,
     Google,
+    /// GGUF model run in-process (cargo feature `local`)
+    Local,
+    /// Responses recorded with `build --record`
+    Replay,
+    /// Endpoint configured with request templates, by name
+    Http(String),
+}
+
+impl std::str::FromStr for LlmProvider {
+    type Err = String;
+
+    /// Built-in names are case-insensitive; any other name refers to `[providers.http.<name>]`
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim().to_lowercase().as_str() {
+            "" => Err("provider name is empty".to_string()),
+            "anthropic" => Ok(LlmProvider::Anthropic),
+            "openai" | "open-ai" => Ok(LlmProvider::OpenAI),
+            "ollama" => Ok(LlmProvider::Ollama),
+            "google" => Ok(LlmProvider::Google),
+            "local" => Ok(LlmProvider::Local),
+            "replay" => Ok(LlmProvider::Replay),
+            _ => Ok(LlmProvider::Http(name.trim().to_string())),
+        }
+    }
 }
 
 impl std::fmt::Display for LlmProvider {
@@ -192,10 +460,22 @@ impl std::fmt::Display for LlmProvider {
             LlmProvider::OpenAI => write!(f, "openai"),
             LlmProvider::Ollama => write!(f, "ollama"),
             LlmProvider::Google => write!(f, "google"),
+            LlmProvider::Local => write!(f, "local"),
+            LlmProvider::Replay => write!(f, "replay"),
+            LlmProvider::Http(name) => write!(f, "{}", name),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum, Default, PartialEq, Eq)]
+pub enum ChunkerKind {
+    /// Paragraphs, then sentences, then words, by size
+    #[default]
+    Adaptive,
+    /// Whole sentences grouped by topic, using the embedding model in `[embeddings]`
+    Semantic,
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum, Default)]
 pub enum OutputDestination {
     #[default]
@@ -207,18 +487,52 @@ pub enum OutputDestination {
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum ExportFormat {
     Json,
+    /// JSON Lines, streamed record by record (for very large graphs)
+    Jsonl,
     Csv,
+    /// Typed Parquet tables (nodes and edges) for DuckDB, Polars, Spark
+    Parquet,
     Graphml,
     Cypher,
+    /// Self-contained offline HTML report
+    Html,
+    /// RDF Turtle
+    #[value(alias = "ttl")]
+    Turtle,
+    /// JSON-LD
+    #[value(name = "json-ld", alias = "jsonld")]
+    JsonLd,
+    /// GEXF for Gephi
+    Gexf,
+    /// Graphviz DOT
+    Dot,
+    /// Header-format CSVs for `neo4j-admin database import`
+    Neo4jAdmin,
 }
 
 impl std::fmt::Display for ExportFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ExportFormat::Json => write!(f, "json"),
+            ExportFormat::Jsonl => write!(f, "jsonl"),
             ExportFormat::Csv => write!(f, "csv"),
+            ExportFormat::Parquet => write!(f, "parquet"),
             ExportFormat::Graphml => write!(f, "graphml"),
             ExportFormat::Cypher => write!(f, "cypher"),
+            ExportFormat::Html => write!(f, "html"),
+            ExportFormat::Turtle => write!(f, "turtle"),
+            ExportFormat::JsonLd => write!(f, "json-ld"),
+            ExportFormat::Gexf => write!(f, "gexf"),
+            ExportFormat::Dot => write!(f, "dot"),
+            ExportFormat::Neo4jAdmin => write!(f, "neo4j-admin"),
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SubgraphFormat {
+    Json,
+    Graphml,
+    Cypher,
+    Html,
+}
@@ -0,0 +1,82 @@
+//! Shared provider and model resolution for `build` and `eval`.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::cli::LlmProvider;
+use crate::config::Config;
+use crate::llm::{FixtureManifest, http_provider_config, local_model_spec};
+use crate::parser::{ModelProfile, ModelRegistry};
+
+/// The provider and model a command runs with, and the limits to chunk for
+pub struct ResolvedModel {
+    pub provider: LlmProvider,
+    /// The recording being replayed, if any
+    pub manifest: Option<FixtureManifest>,
+    pub model: Option<String>,
+    pub profile: ModelProfile,
+}
+
+impl ResolvedModel {
+    /// The model name to show, `default` when none was chosen
+    pub fn display_name(&self) -> String {
+        self.model.clone().unwrap_or_else(|| "default".to_string())
+    }
+}
+
+/// Pick the provider and model from the flags, falling back to the config.
+///
+/// A replay takes its model and limits from the recording in `fixtures`; the
+/// local provider's model is a file path rather than a `default_model` name.
+pub fn resolve_model(
+    config: &Config,
+    provider: Option<LlmProvider>,
+    model: Option<String>,
+    fixtures: Option<&Path>,
+) -> Result<ResolvedModel> {
+    let provider = provider.unwrap_or_else(|| {
+        config
+            .default_provider
+            .parse::<LlmProvider>()
+            .unwrap_or_default()
+    });
+    if let LlmProvider::Http(name) = &provider {
+        http_provider_config(config, name)?;
+    }
+
+    let manifest = match (&provider, fixtures) {
+        (LlmProvider::Replay, Some(dir)) => Some(FixtureManifest::load(dir)?),
+        (LlmProvider::Replay, None) => {
+            anyhow::bail!("--provider replay needs the recording: pass --fixtures <DIR>")
+        }
+        _ => None,
+    };
+
+    let model = match provider {
+        LlmProvider::Local => model.or_else(|| {
+            config
+                .providers
+                .local
+                .as_ref()
+                .and_then(|local| local.model.clone())
+        }),
+        LlmProvider::Replay => model.or_else(|| manifest.as_ref().and_then(|m| m.model.clone())),
+        _ => model.or(config.default_model.clone()),
+    };
+
+    let profile = match (&provider, &manifest) {
+        (LlmProvider::Local, _) => {
+            ModelProfile::heuristic(local_model_spec(config, model.as_deref())?)
+        }
+        (_, Some(manifest)) => ModelProfile::load(manifest.model_spec.clone())
+            .context("Failed to load the recording's tokenizer")?,
+        _ => ModelRegistry::from_config(config).profile(model.as_deref().unwrap_or("default"))?,
+    };
+
+    Ok(ResolvedModel {
+        provider,
+        manifest,
+        model,
+        profile,
+    })
+}
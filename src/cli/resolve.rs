@@ -0,0 +1,140 @@
+//! Shared concept-name resolution for `path`, `query` and `add`.
+
+use anyhow::{Result, bail};
+use console::style;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crate::graph::resolver::{ConceptMatch, ConceptResolver, MatchKind, Resolution};
+
+/// How many candidates to offer when a name is ambiguous
+const MAX_CHOICES: usize = 8;
+
+/// Resolve a user-typed concept name to an existing label.
+///
+/// Exact and alias hits are used directly. Ambiguous names are offered as a
+/// numbered list on a terminal, or resolved to the top candidate with
+/// `best_match`. When `allow_new` is set (as for `add`), the typed name may
+/// be kept as a new concept instead, and fuzzy hits are always confirmed.
+/// Names with no candidates are returned unchanged.
+pub fn resolve_concept(
+    resolver: &ConceptResolver,
+    input: &str,
+    best_match: bool,
+    allow_new: bool,
+) -> Result<String> {
+    let candidates = match resolver.resolve(input) {
+        Resolution::Found(m) if m.kind == MatchKind::Exact => return Ok(m.label),
+        Resolution::Found(m) if m.kind == MatchKind::Alias || !allow_new => {
+            print_resolved(input, &m);
+            return Ok(m.label);
+        }
+        Resolution::Found(m) => vec![m],
+        Resolution::Ambiguous(matches) => matches,
+        Resolution::NotFound => return Ok(input.to_string()),
+    };
+
+    if best_match {
+        print_resolved(input, &candidates[0]);
+        return Ok(candidates[0].label.clone());
+    }
+
+    if io::stdin().is_terminal() && io::stdout().is_terminal() {
+        return prompt_choice(input, &candidates, allow_new);
+    }
+
+    if allow_new {
+        println!(
+            "  {} '{}' looks like {}; adding it as a new concept (use --best-match to link instead)",
+            style("⚠").yellow(),
+            input,
+            describe(&candidates)
+        );
+        return Ok(input.to_string());
+    }
+
+    bail!(
+        "'{}' is ambiguous: could be {}. Use a more specific name or pass --best-match.",
+        input,
+        describe(&candidates)
+    )
+}
+
+/// Ask the user to pick a candidate from a numbered list
+fn prompt_choice(input: &str, candidates: &[ConceptMatch], allow_new: bool) -> Result<String> {
+    let candidates = &candidates[..candidates.len().min(MAX_CHOICES)];
+
+    println!();
+    println!(
+        "  {} '{}' matches several concepts:",
+        style("?").cyan().bold(),
+        style(input).yellow()
+    );
+    if allow_new {
+        println!(
+            "    {} keep '{}' as a new concept",
+            style("0.").dim(),
+            input
+        );
+    }
+    for (i, m) in candidates.iter().enumerate() {
+        println!(
+            "    {} {} {}",
+            style(format!("{}.", i + 1)).dim(),
+            style(&m.label).cyan(),
+            style(format!("({}, {:.2}){}", m.kind, m.score, via(m))).dim()
+        );
+    }
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let first = if allow_new { 0 } else { 1 };
+
+    loop {
+        print!("  Select [{}-{}, default 1]: ", first, candidates.len());
+        stdout.flush()?;
+        let mut choice = String::new();
+        if stdin.lock().read_line(&mut choice)? == 0 {
+            bail!("No concept selected for '{}'", input);
+        }
+
+        let choice = choice.trim();
+        if choice.is_empty() {
+            return Ok(candidates[0].label.clone());
+        }
+        match choice.parse::<usize>() {
+            Ok(0) if allow_new => return Ok(input.to_string()),
+            Ok(n) if (1..=candidates.len()).contains(&n) => {
+                return Ok(candidates[n - 1].label.clone());
+            }
+            _ => println!("  {} Invalid choice", style("⚠").yellow()),
+        }
+    }
+}
+
+fn print_resolved(input: &str, m: &ConceptMatch) {
+    println!(
+        "  {} '{}' → {} {}",
+        style("~").dim(),
+        input,
+        style(&m.label).cyan(),
+        style(format!("({} match{})", m.kind, via(m))).dim()
+    );
+}
+
+/// Name the alias a match came through, if it wasn't the label itself
+fn via(m: &ConceptMatch) -> String {
+    if m.matched == m.label {
+        String::new()
+    } else {
+        format!(" via '{}'", m.matched)
+    }
+}
+
+fn describe(candidates: &[ConceptMatch]) -> String {
+    candidates
+        .iter()
+        .take(5)
+        .map(|m| format!("'{}'", m.label))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,7 +1,10 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::llm::Relation;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -20,6 +23,21 @@ pub struct Config {
     /// Domain-specific configuration for LLM prompts
     #[serde(default)]
     pub domain: DomainConfig,
+    /// External commands for extra file types, keyed by extension
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub parsers: HashMap<String, ExternalParserConfig>,
+    /// How large document sets are thinned out before extraction
+    #[serde(default)]
+    pub selection: SelectionConfig,
+    /// Context windows and tokenizers, keyed by a model name substring
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub models: BTreeMap<String, ModelSpec>,
+    /// Embedding model behind `--chunker semantic`
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+    /// Template files for the extraction prompts
+    #[serde(default)]
+    pub prompts: PromptsConfig,
 }
 
 fn default_provider() -> String {
@@ -40,6 +58,12 @@ pub struct ProvidersConfig {
     pub openai: Option<ProviderConfig>,
     pub ollama: Option<ProviderConfig>,
     pub google: Option<ProviderConfig>,
+    /// In-process GGUF inference, with the `local` cargo feature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local: Option<LocalProviderConfig>,
+    /// Endpoints described by request templates, keyed by provider name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub http: BTreeMap<String, HttpProviderConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +74,50 @@ pub struct ProviderConfig {
     pub model: Option<String>,
 }
 
+/// A GGUF model run on the CPU inside the process
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LocalProviderConfig {
+    /// Path to the `.gguf` file
+    pub model: Option<String>,
+    /// Context window to allocate; capped at what the model was trained for (default 4096)
+    pub context_size: Option<usize>,
+    /// Longest response in tokens (default 1024)
+    pub max_tokens: Option<usize>,
+    /// Sampling temperature; 0 always picks the most likely token
+    #[serde(default)]
+    pub temperature: f64,
+    /// Seed for sampling when the temperature is above 0
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// A chat endpoint that none of the built-in providers speak, such as an
+/// Azure OpenAI deployment or an internal gateway
+///
+/// `{model}`, `{api_key}`, `{system}`, `{user}` and `{max_tokens}` are filled
+/// in across the URL, headers and body. A body string that is exactly one
+/// placeholder takes the value's JSON type, so `"{max_tokens}"` becomes a number.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpProviderConfig {
+    /// Endpoint URL template
+    pub url: String,
+    #[serde(default)]
+    pub api_key: String,
+    /// Header carrying the key; `Authorization` when unset
+    pub auth_header: Option<String>,
+    /// Word before the key, e.g. `Bearer`; none when unset, unless the key
+    /// goes in `Authorization`, where it defaults to `Bearer`
+    pub auth_scheme: Option<String>,
+    /// Further request headers
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// JSON request body template
+    pub body: serde_json::Value,
+    /// JSONPath to the response text, e.g. `$.choices[0].message.content`
+    pub response_path: String,
+    pub model: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Neo4jConfig {
     pub uri: String,
@@ -104,6 +172,250 @@ pub struct DomainConfig {
     /// Specific focus areas for extraction
     #[serde(default)]
     pub focus: Option<String>,
+
+    /// Few-shot demonstrations for the extraction prompt (`[[domain.examples]]`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<DomainExample>,
+
+    /// JSON or TOML file with more examples, relative to the config directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub examples_file: Option<PathBuf>,
+}
+
+/// A sample text and the relations a good extraction finds in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainExample {
+    pub text: String,
+    #[serde(default)]
+    pub relations: Vec<Relation>,
+}
+
+/// An examples file in TOML, with `[[examples]]` tables
+#[derive(Deserialize)]
+struct ExamplesFile {
+    examples: Vec<DomainExample>,
+}
+
+impl DomainConfig {
+    /// Append the examples of `examples_file` and then of `extra`, after
+    /// the ones given inline
+    ///
+    /// Files ending in `.toml` hold `[[examples]]` tables; anything else is a
+    /// JSON array of examples.
+    pub fn load_examples(&mut self, extra: Option<&Path>) -> Result<()> {
+        let configured = match &self.examples_file {
+            Some(path) => Some(Config::config_dir()?.join(path)),
+            None => None,
+        };
+        for path in configured.as_deref().into_iter().chain(extra) {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read examples file {}", path.display()))?;
+            let examples = if path.extension().is_some_and(|ext| ext == "toml") {
+                toml::from_str::<ExamplesFile>(&content)
+                    .map(|file| file.examples)
+                    .map_err(anyhow::Error::from)
+            } else {
+                serde_json::from_str(&content).map_err(anyhow::Error::from)
+            };
+            self.examples.extend(
+                examples.with_context(|| format!("Invalid examples file {}", path.display()))?,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Heuristics for picking representative documents out of a large set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectionConfig {
+    /// Selection only runs when a build has more documents than this
+    #[serde(default = "default_selection_threshold")]
+    pub threshold: usize,
+
+    /// Documents kept per directory
+    #[serde(default = "default_max_per_dir")]
+    pub max_per_dir: usize,
+
+    /// Documents with less text than this are dropped
+    #[serde(default = "default_min_chars")]
+    pub min_chars: usize,
+
+    /// Case-insensitive path substrings that drop a document
+    #[serde(default = "default_skip_patterns")]
+    pub skip_patterns: Vec<String>,
+
+    /// Extensions that are dropped, e.g. data files
+    #[serde(default = "default_skip_extensions")]
+    pub skip_extensions: Vec<String>,
+
+    /// Path substrings and the score they add; higher-scoring documents are kept first
+    #[serde(default = "default_priorities")]
+    pub priorities: BTreeMap<String, i32>,
+}
+
+fn default_selection_threshold() -> usize {
+    100
+}
+
+fn default_max_per_dir() -> usize {
+    5
+}
+
+fn default_min_chars() -> usize {
+    100
+}
+
+fn default_skip_patterns() -> Vec<String> {
+    ["generated", "broken-links", "source-reference-map"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_skip_extensions() -> Vec<String> {
+    vec!["json".to_string()]
+}
+
+fn default_priorities() -> BTreeMap<String, i32> {
+    [
+        ("readme", 100),
+        ("skill", 100),
+        ("toc", 100),
+        ("overview", 50),
+        ("getting-started", 50),
+        ("example", 30),
+        ("guide", 30),
+        ("generated", -50),
+        ("auto", -50),
+        (".md", 10),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect()
+}
+
+impl Default for SelectionConfig {
+    fn default() -> Self {
+        Self {
+            threshold: default_selection_threshold(),
+            max_per_dir: default_max_per_dir(),
+            min_chars: default_min_chars(),
+            skip_patterns: default_skip_patterns(),
+            skip_extensions: default_skip_extensions(),
+            priorities: default_priorities(),
+        }
+    }
+}
+
+/// Embedding model used to find topic shifts when chunking semantically
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingsConfig {
+    /// openai, ollama or google; defaults to `default_provider`. Keys and
+    /// base URLs come from the matching `[providers]` entry
+    #[serde(default)]
+    pub provider: Option<String>,
+
+    /// Embedding model; each provider has a default
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Sentence gaps more distant than this percentile of all gaps start a new chunk
+    #[serde(default = "default_breakpoint_percentile")]
+    pub breakpoint_percentile: f32,
+}
+
+fn default_breakpoint_percentile() -> f32 {
+    90.0
+}
+
+/// Template files that replace the built-in extraction prompts
+///
+/// Relative paths are resolved against the configuration directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptsConfig {
+    /// System prompt for documents and prose
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extraction: Option<PathBuf>,
+
+    /// System prompt for source code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<PathBuf>,
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            model: None,
+            breakpoint_percentile: default_breakpoint_percentile(),
+        }
+    }
+}
+
+/// What the chunker needs to know about a model
+///
+/// The built-in registry covers common models; `[models."<name>"]` tables add
+/// to it or override an entry.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelSpec {
+    /// Total tokens the model accepts, prompt and response together
+    pub context_size: usize,
+
+    /// Tokens reserved for (and requested as) the response
+    #[serde(default = "default_max_output_tokens")]
+    pub max_output_tokens: usize,
+
+    /// Vocabulary for exact counts: a `.tiktoken` BPE rank file or a
+    /// SentencePiece `.model`; relative paths are read from the config directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokenizer: Option<PathBuf>,
+}
+
+fn default_max_output_tokens() -> usize {
+    1024
+}
+
+impl Default for ModelSpec {
+    fn default() -> Self {
+        Self {
+            context_size: 4096,
+            max_output_tokens: default_max_output_tokens(),
+            tokenizer: None,
+        }
+    }
+}
+
+/// An external command whose stdout becomes the document text
+///
+/// Either just the command (`org = "pandoc -t plain {path}"`) or a table with a
+/// `command` and a `timeout_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExternalParserConfig {
+    Command(String),
+    Detailed {
+        command: String,
+        #[serde(default = "default_parser_timeout")]
+        timeout_secs: u64,
+    },
+}
+
+fn default_parser_timeout() -> u64 {
+    60
+}
+
+impl ExternalParserConfig {
+    pub fn command(&self) -> &str {
+        match self {
+            Self::Command(command) | Self::Detailed { command, .. } => command,
+        }
+    }
+
+    pub fn timeout_secs(&self) -> u64 {
+        match self {
+            Self::Command(_) => default_parser_timeout(),
+            Self::Detailed { timeout_secs, .. } => *timeout_secs,
+        }
+    }
 }
 
 impl Config {
@@ -164,6 +476,9 @@ impl Config {
                 provider.api_key = gemini_key;
             }
         }
+        for provider in self.providers.http.values_mut() {
+            provider.api_key = expand_env_var(&provider.api_key);
+        }
         self.neo4j.password = expand_env_var(&self.neo4j.password);
     }
 
@@ -225,6 +540,48 @@ mod tests {
         assert_eq!(expand_env_var(""), "");
     }
 
+    #[test]
+    fn test_domain_examples_inline_and_from_files() {
+        let toml_str = r#"
+            name = "software"
+
+            [[examples]]
+            text = "Tokio is an async runtime for Rust."
+            relations = [{ node_1 = "tokio", node_2 = "rust", edge = "is a runtime for" }]
+        "#;
+        let mut domain: DomainConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(domain.examples.len(), 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("examples.json");
+        fs::write(
+            &json,
+            r#"[{"text": "Serde serializes data.", "relations": []}]"#,
+        )
+        .unwrap();
+        domain.load_examples(Some(&json)).unwrap();
+        let toml_file = dir.path().join("examples.toml");
+        fs::write(
+            &toml_file,
+            "[[examples]]\ntext = \"Axum routes requests.\"\n",
+        )
+        .unwrap();
+        domain.load_examples(Some(&toml_file)).unwrap();
+        let texts: Vec<&str> = domain.examples.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "Tokio is an async runtime for Rust.",
+                "Serde serializes data.",
+                "Axum routes requests."
+            ]
+        );
+
+        fs::write(&json, "[{}]").unwrap();
+        let err = domain.load_examples(Some(&json)).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid examples file"));
+    }
+
     #[test]
     fn test_config_from_toml() {
         let toml_str = r#"
@@ -306,6 +663,8 @@ mod tests {
                 }),
                 ollama: None,
                 google: None,
+                local: None,
+                http: Default::default(),
             },
             neo4j: Neo4jConfig {
                 uri: "bolt://localhost:7687".into(),
@@ -315,6 +674,11 @@ mod tests {
             },
             tenant: Default::default(),
             domain: Default::default(),
+            parsers: HashMap::new(),
+            selection: Default::default(),
+            models: Default::default(),
+            embeddings: Default::default(),
+            prompts: Default::default(),
         };
 
         let serialized = toml::to_string_pretty(&config).unwrap();
@@ -322,4 +686,44 @@ mod tests {
         assert_eq!(deserialized.default_provider, "openai");
         assert_eq!(deserialized.providers.openai.unwrap().api_key, "sk-123");
     }
+
+    #[test]
+    fn test_parsers_section() {
+        let toml_str = r#"
+            [providers]
+            [neo4j]
+            uri = "bolt://localhost:7687"
+            user = "neo4j"
+            password = "test"
+
+            [parsers]
+            org = "pandoc -t plain {path}"
+            djvu = { command = "djvutxt {path}", timeout_secs = 300 }
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.parsers["org"].command(), "pandoc -t plain {path}");
+        assert_eq!(config.parsers["org"].timeout_secs(), 60);
+        assert_eq!(config.parsers["djvu"].command(), "djvutxt {path}");
+        assert_eq!(config.parsers["djvu"].timeout_secs(), 300);
+    }
+
+    #[test]
+    fn test_selection_section() {
+        let toml_str = r#"
+            [providers]
+            [neo4j]
+            uri = "bolt://localhost:7687"
+            user = "neo4j"
+            password = "test"
+
+            [selection]
+            threshold = 20
+            skip_patterns = ["changelog"]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.selection.threshold, 20);
+        assert_eq!(config.selection.skip_patterns, vec!["changelog"]);
+        assert_eq!(config.selection.max_per_dir, 5);
+        assert_eq!(config.selection.priorities["readme"], 100);
+    }
 }
@@ -1,11 +1,22 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{BufRead, BufWriter, Write};
 use std::path::Path;
 
 use crate::graph::builder::GraphBuilder;
-use crate::graph::neo4j::{GraphEdge, GraphNode};
+use crate::graph::neo4j::{GraphEdge, GraphNode, GraphRecord, TenantEdge};
+
+mod parquet;
+mod rdf;
+mod report;
+
+pub use parquet::export_parquet;
+pub use rdf::{export_jsonld, export_turtle};
+
+/// Separator used when a format stores aliases in a single text field
+const ALIAS_SEPARATOR: &str = ";";
 
 /// Export format for JSON
 #[derive(Serialize, Deserialize)]
@@ -26,6 +37,7 @@ pub fn export_json(builder: &GraphBuilder, path: &Path) -> Result<()> {
             degree: Some(n.degree),
             entity_type: n.entity_type,
             tenant: n.tenant,
+            aliases: n.aliases,
         })
         .collect();
 
@@ -37,6 +49,7 @@ pub fn export_json(builder: &GraphBuilder, path: &Path) -> Result<()> {
             target: e.target,
             relation: e.relation,
             weight: e.weight,
+            sources: e.chunk_ids,
         })
         .collect();
 
@@ -59,6 +72,140 @@ pub fn export_json_from_data(nodes: &[GraphNode], edges: &[GraphEdge], path: &Pa
     Ok(())
 }
 
+/// Load nodes and edges from a JSON file written by [`export_json_from_data`]
+pub fn load_json(path: &Path) -> Result<(Vec<GraphNode>, Vec<GraphEdge>)> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let export: JsonExport = serde_json::from_reader(std::io::BufReader::new(file))
+        .with_context(|| format!("Failed to parse graph JSON: {}", path.display()))?;
+
+    Ok((export.nodes, export.edges))
+}
+
+/// Keep only one tenant's nodes (and the edges between them) from a JSON export
+pub fn filter_tenant(
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+    tenant: Option<&str>,
+) -> (Vec<GraphNode>, Vec<GraphEdge>) {
+    let Some(tenant) = tenant else {
+        return (nodes, edges);
+    };
+
+    let nodes: Vec<GraphNode> = nodes.into_iter().filter(|n| n.tenant == tenant).collect();
+    let ids: HashSet<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+    let edges = edges
+        .into_iter()
+        .filter(|e| ids.contains(e.source.as_str()) && ids.contains(e.target.as_str()))
+        .collect();
+
+    (nodes, edges)
+}
+
+/// Give the edges of a JSON export their tenants, which the file does not record
+///
+/// An edge belongs to every tenant that has both of its endpoints.
+pub fn tag_tenants(nodes: &[GraphNode], edges: Vec<GraphEdge>) -> Vec<TenantEdge> {
+    let mut tenants_of: HashMap<&str, Vec<&str>> = HashMap::new();
+    for node in nodes {
+        tenants_of
+            .entry(node.id.as_str())
+            .or_default()
+            .push(node.tenant.as_str());
+    }
+
+    let mut tagged = Vec::with_capacity(edges.len());
+    for edge in edges {
+        let tenants: Vec<&str> = match (
+            tenants_of.get(edge.source.as_str()),
+            tenants_of.get(edge.target.as_str()),
+        ) {
+            (Some(source), Some(target)) => source
+                .iter()
+                .copied()
+                .filter(|t| target.contains(t))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for tenant in tenants {
+            tagged.push(edge.clone().with_tenant(tenant));
+        }
+    }
+    tagged
+}
+
+/// Streaming JSON Lines writer: one node or edge record per line
+///
+/// Records go straight to disk, so memory use does not grow with the graph.
+pub struct JsonlWriter<W: Write> {
+    writer: W,
+    nodes: usize,
+    edges: usize,
+}
+
+impl JsonlWriter<BufWriter<File>> {
+    /// Create (or truncate) a JSON Lines file
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create file: {}", path.display()))?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> JsonlWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            nodes: 0,
+            edges: 0,
+        }
+    }
+
+    /// Append one record
+    pub fn write(&mut self, record: &GraphRecord) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, record).context("Failed to write JSON Lines")?;
+        self.writer.write_all(b"\n")?;
+        match record {
+            GraphRecord::Node(_) => self.nodes += 1,
+            GraphRecord::Edge(_) => self.edges += 1,
+        }
+        Ok(())
+    }
+
+    /// Flush the output and return the number of nodes and edges written
+    pub fn finish(mut self) -> Result<(usize, usize)> {
+        self.writer.flush()?;
+        Ok((self.nodes, self.edges))
+    }
+}
+
+/// Read a JSON Lines file lazily, one record at a time
+///
+/// Blank lines are skipped. A malformed line yields an error naming its line number.
+pub fn read_jsonl(path: &Path) -> Result<impl Iterator<Item = Result<GraphRecord>>> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let display = path.display().to_string();
+
+    Ok(std::io::BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter_map(move |(i, line)| {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e).context(format!("Failed to read {}", display))),
+            };
+            if line.trim().is_empty() {
+                return None;
+            }
+            Some(
+                serde_json::from_str(&line).with_context(|| {
+                    format!("Invalid graph record on line {} of {}", i + 1, display)
+                }),
+            )
+        }))
+}
+
 /// Export graph from builder to CSV files
 pub fn export_csv(builder: &GraphBuilder, nodes_path: &Path, edges_path: &Path) -> Result<()> {
     let nodes: Vec<GraphNode> = builder
@@ -71,6 +218,7 @@ pub fn export_csv(builder: &GraphBuilder, nodes_path: &Path, edges_path: &Path)
             degree: Some(n.degree),
             entity_type: n.entity_type,
             tenant: n.tenant,
+            aliases: n.aliases,
         })
         .collect();
 
@@ -82,6 +230,7 @@ pub fn export_csv(builder: &GraphBuilder, nodes_path: &Path, edges_path: &Path)
             target: e.target,
             relation: e.relation,
             weight: e.weight,
+            sources: e.chunk_ids,
         })
         .collect();
 
@@ -100,15 +249,21 @@ pub fn export_csv_from_data(
         .with_context(|| format!("Failed to create file: {}", nodes_path.display()))?;
     let mut writer = BufWriter::new(file);
 
-    writeln!(writer, "id,label,degree,community")?;
+    writeln!(
+        writer,
+        "id,label,degree,community,entity_type,tenant,aliases"
+    )?;
     for node in nodes {
         writeln!(
             writer,
-            "\"{}\",\"{}\",{},{}",
+            "\"{}\",\"{}\",{},{},\"{}\",\"{}\",\"{}\"",
             escape_csv(&node.id),
             escape_csv(&node.label),
-            node.degree.unwrap_or(0),
-            node.community.unwrap_or(0)
+            optional(node.degree),
+            optional(node.community),
+            escape_csv(node.entity_type.as_deref().unwrap_or_default()),
+            escape_csv(&node.tenant),
+            escape_csv(&node.aliases.join(ALIAS_SEPARATOR))
         )?;
     }
 
@@ -133,7 +288,10 @@ pub fn export_csv_from_data(
 }
 
 /// Export to GraphML format
-pub fn export_graphml(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) -> Result<()> {
+///
+/// Node ids are `tenant:id` (see [`import_key`]), so a concept present in
+/// several tenants stays several nodes; the plain id is kept in the `id` key.
+pub fn export_graphml(nodes: &[GraphNode], edges: &[TenantEdge], path: &Path) -> Result<()> {
     let file =
         File::create(path).with_context(|| format!("Failed to create file: {}", path.display()))?;
     let mut writer = BufWriter::new(file);
@@ -146,6 +304,10 @@ pub fn export_graphml(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) ->
     )?;
 
     // Define attribute keys
+    writeln!(
+        writer,
+        r#"  <key id="id" for="node" attr.name="id" attr.type="string"/>"#
+    )?;
     writeln!(
         writer,
         r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#
@@ -154,6 +316,22 @@ pub fn export_graphml(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) ->
         writer,
         r#"  <key id="degree" for="node" attr.name="degree" attr.type="int"/>"#
     )?;
+    writeln!(
+        writer,
+        r#"  <key id="community" for="node" attr.name="community" attr.type="int"/>"#
+    )?;
+    writeln!(
+        writer,
+        r#"  <key id="entity_type" for="node" attr.name="entity_type" attr.type="string"/>"#
+    )?;
+    writeln!(
+        writer,
+        r#"  <key id="tenant" for="node" attr.name="tenant" attr.type="string"/>"#
+    )?;
+    writeln!(
+        writer,
+        r#"  <key id="aliases" for="node" attr.name="aliases" attr.type="string"/>"#
+    )?;
     writeln!(
         writer,
         r#"  <key id="relation" for="edge" attr.name="relation" attr.type="string"/>"#
@@ -168,17 +346,50 @@ pub fn export_graphml(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) ->
 
     // Write nodes
     for node in nodes {
-        writeln!(writer, r#"    <node id="{}">"#, escape_xml(&node.id))?;
+        writeln!(
+            writer,
+            r#"    <node id="{}">"#,
+            escape_xml(&import_key(&node.tenant, &node.id))
+        )?;
+        writeln!(
+            writer,
+            r#"      <data key="id">{}</data>"#,
+            escape_xml(&node.id)
+        )?;
         writeln!(
             writer,
             r#"      <data key="label">{}</data>"#,
             escape_xml(&node.label)
         )?;
+        if let Some(degree) = node.degree {
+            writeln!(writer, r#"      <data key="degree">{}</data>"#, degree)?;
+        }
+        if let Some(community) = node.community {
+            writeln!(
+                writer,
+                r#"      <data key="community">{}</data>"#,
+                community
+            )?;
+        }
+        if let Some(entity_type) = &node.entity_type {
+            writeln!(
+                writer,
+                r#"      <data key="entity_type">{}</data>"#,
+                escape_xml(entity_type)
+            )?;
+        }
         writeln!(
             writer,
-            r#"      <data key="degree">{}</data>"#,
-            node.degree.unwrap_or(0)
+            r#"      <data key="tenant">{}</data>"#,
+            escape_xml(&node.tenant)
         )?;
+        if !node.aliases.is_empty() {
+            writeln!(
+                writer,
+                r#"      <data key="aliases">{}</data>"#,
+                escape_xml(&node.aliases.join(ALIAS_SEPARATOR))
+            )?;
+        }
         writeln!(writer, r#"    </node>"#)?;
     }
 
@@ -188,8 +399,8 @@ pub fn export_graphml(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) ->
             writer,
             r#"    <edge id="e{}" source="{}" target="{}">"#,
             i,
-            escape_xml(&edge.source),
-            escape_xml(&edge.target)
+            escape_xml(&import_key(&edge.tenant, &edge.source)),
+            escape_xml(&import_key(&edge.tenant, &edge.target))
         )?;
         writeln!(
             writer,
@@ -208,7 +419,10 @@ pub fn export_graphml(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) ->
 }
 
 /// Export to Cypher statements for Neo4j import
-pub fn export_cypher(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) -> Result<()> {
+///
+/// Relationships match their endpoints by id and tenant, so loading a
+/// multi-tenant export never links concepts across tenants.
+pub fn export_cypher(nodes: &[GraphNode], edges: &[TenantEdge], path: &Path) -> Result<()> {
     let file =
         File::create(path).with_context(|| format!("Failed to create file: {}", path.display()))?;
     let mut writer = BufWriter::new(file);
@@ -227,20 +441,36 @@ pub fn export_cypher(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) -> R
     writeln!(writer, "// Create index for faster lookups")?;
     writeln!(
         writer,
-        "CREATE INDEX concept_id IF NOT EXISTS FOR (n:Concept) ON (n.id);"
+        "CREATE INDEX concept_id_tenant IF NOT EXISTS FOR (n:Concept) ON (n.id, n.tenant);"
     )?;
     writeln!(writer)?;
 
     // Create nodes
     writeln!(writer, "// Create nodes")?;
     for node in nodes {
-        writeln!(
-            writer,
-            "CREATE (n:Concept {{id: '{}', label: '{}', degree: {}}});",
-            escape_cypher(&node.id),
-            escape_cypher(&node.label),
-            node.degree.unwrap_or(0)
-        )?;
+        let mut props = vec![
+            format!("id: '{}'", escape_cypher(&node.id)),
+            format!("label: '{}'", escape_cypher(&node.label)),
+            format!("tenant: '{}'", escape_cypher(&node.tenant)),
+        ];
+        if let Some(degree) = node.degree {
+            props.push(format!("degree: {}", degree));
+        }
+        if let Some(community) = node.community {
+            props.push(format!("community: {}", community));
+        }
+        if let Some(entity_type) = &node.entity_type {
+            props.push(format!("entity_type: '{}'", escape_cypher(entity_type)));
+        }
+        if !node.aliases.is_empty() {
+            let aliases: Vec<String> = node
+                .aliases
+                .iter()
+                .map(|a| format!("'{}'", escape_cypher(a)))
+                .collect();
+            props.push(format!("aliases: [{}]", aliases.join(", ")));
+        }
+        writeln!(writer, "CREATE (n:Concept {{{}}});", props.join(", "))?;
     }
     writeln!(writer)?;
 
@@ -249,9 +479,11 @@ pub fn export_cypher(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) -> R
     for edge in edges {
         writeln!(
             writer,
-            "MATCH (a:Concept {{id: '{}'}}), (b:Concept {{id: '{}'}}) CREATE (a)-[:RELATES_TO {{relation: '{}', weight: {}}}]->(b);",
+            "MATCH (a:Concept {{id: '{}', tenant: '{}'}}), (b:Concept {{id: '{}', tenant: '{}'}}) CREATE (a)-[:RELATES_TO {{relation: '{}', weight: {}}}]->(b);",
             escape_cypher(&edge.source),
+            escape_cypher(&edge.tenant),
             escape_cypher(&edge.target),
+            escape_cypher(&edge.tenant),
             escape_cypher(&edge.relation),
             edge.weight
         )?;
@@ -260,6 +492,262 @@ pub fn export_cypher(nodes: &[GraphNode], edges: &[GraphEdge], path: &Path) -> R
     Ok(())
 }
 
+/// Export a self-contained HTML report (graph, analytics, communities, node index)
+pub fn export_html(
+    nodes: &[GraphNode],
+    edges: &[GraphEdge],
+    tenant: Option<&str>,
+    path: &Path,
+) -> Result<()> {
+    let html = report::render(nodes, edges, tenant)?;
+    std::fs::write(path, html)
+        .with_context(|| format!("Failed to create file: {}", path.display()))?;
+    Ok(())
+}
+
+/// Export to GEXF (Gephi), with community and entity type as node attributes
+///
+/// Node ids are `tenant:id` (see [`import_key`]); the plain id is the `id` attribute.
+pub fn export_gexf(nodes: &[GraphNode], edges: &[TenantEdge], path: &Path) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create file: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<gexf xmlns="http://www.gexf.net/1.2draft" version="1.2">"#
+    )?;
+    writeln!(writer, r#"  <meta><creator>RKnowledge</creator></meta>"#)?;
+    writeln!(
+        writer,
+        r#"  <graph mode="static" defaultedgetype="directed">"#
+    )?;
+
+    // Attribute declarations
+    writeln!(writer, r#"    <attributes class="node">"#)?;
+    for (id, kind) in [
+        ("id", "string"),
+        ("degree", "integer"),
+        ("community", "integer"),
+        ("entity_type", "string"),
+        ("tenant", "string"),
+        ("aliases", "string"),
+    ] {
+        writeln!(
+            writer,
+            r#"      <attribute id="{0}" title="{0}" type="{1}"/>"#,
+            id, kind
+        )?;
+    }
+    writeln!(writer, r#"    </attributes>"#)?;
+    writeln!(writer, r#"    <attributes class="edge">"#)?;
+    writeln!(
+        writer,
+        r#"      <attribute id="relation" title="relation" type="string"/>"#
+    )?;
+    writeln!(writer, r#"    </attributes>"#)?;
+
+    // Write nodes
+    writeln!(writer, r#"    <nodes>"#)?;
+    for node in nodes {
+        writeln!(
+            writer,
+            r#"      <node id="{}" label="{}">"#,
+            escape_xml(&import_key(&node.tenant, &node.id)),
+            escape_xml(&node.label)
+        )?;
+        writeln!(writer, r#"        <attvalues>"#)?;
+        let mut values = vec![("id", node.id.clone()), ("tenant", node.tenant.clone())];
+        if let Some(degree) = node.degree {
+            values.push(("degree", degree.to_string()));
+        }
+        if let Some(community) = node.community {
+            values.push(("community", community.to_string()));
+        }
+        if let Some(entity_type) = &node.entity_type {
+            values.push(("entity_type", entity_type.clone()));
+        }
+        if !node.aliases.is_empty() {
+            values.push(("aliases", node.aliases.join(ALIAS_SEPARATOR)));
+        }
+        for (key, value) in values {
+            writeln!(
+                writer,
+                r#"          <attvalue for="{}" value="{}"/>"#,
+                key,
+                escape_xml(&value)
+            )?;
+        }
+        writeln!(writer, r#"        </attvalues>"#)?;
+        writeln!(writer, r#"      </node>"#)?;
+    }
+    writeln!(writer, r#"    </nodes>"#)?;
+
+    // Write edges
+    writeln!(writer, r#"    <edges>"#)?;
+    for (i, edge) in edges.iter().enumerate() {
+        writeln!(
+            writer,
+            r#"      <edge id="e{}" source="{}" target="{}" label="{}" weight="{}">"#,
+            i,
+            escape_xml(&import_key(&edge.tenant, &edge.source)),
+            escape_xml(&import_key(&edge.tenant, &edge.target)),
+            escape_xml(&edge.relation),
+            edge.weight
+        )?;
+        writeln!(
+            writer,
+            r#"        <attvalues><attvalue for="relation" value="{}"/></attvalues>"#,
+            escape_xml(&edge.relation)
+        )?;
+        writeln!(writer, r#"      </edge>"#)?;
+    }
+    writeln!(writer, r#"    </edges>"#)?;
+
+    writeln!(writer, r#"  </graph>"#)?;
+    writeln!(writer, r#"</gexf>"#)?;
+
+    Ok(())
+}
+
+/// Export to Graphviz DOT
+///
+/// Node fields become DOT attributes. Node ids are `tenant:id` (see
+/// [`import_key`]) and the plain id is `concept_id`, since `id` is reserved by
+/// Graphviz. The edge weight is written as `relation_weight` because `dot`
+/// only accepts integer `weight` values.
+pub fn export_dot(nodes: &[GraphNode], edges: &[TenantEdge], path: &Path) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create file: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, "digraph rknowledge {{")?;
+    writeln!(writer, "  node [shape=box, style=rounded];")?;
+    writeln!(writer)?;
+
+    for node in nodes {
+        let mut attrs = vec![
+            format!("label=\"{}\"", escape_dot(&node.label)),
+            format!("concept_id=\"{}\"", escape_dot(&node.id)),
+            format!("tenant=\"{}\"", escape_dot(&node.tenant)),
+        ];
+        if let Some(degree) = node.degree {
+            attrs.push(format!("degree={}", degree));
+        }
+        if let Some(community) = node.community {
+            attrs.push(format!("community={}", community));
+        }
+        if let Some(entity_type) = &node.entity_type {
+            attrs.push(format!("entity_type=\"{}\"", escape_dot(entity_type)));
+        }
+        if !node.aliases.is_empty() {
+            attrs.push(format!(
+                "aliases=\"{}\"",
+                escape_dot(&node.aliases.join(ALIAS_SEPARATOR))
+            ));
+        }
+        writeln!(
+            writer,
+            "  \"{}\" [{}];",
+            escape_dot(&import_key(&node.tenant, &node.id)),
+            attrs.join(", ")
+        )?;
+    }
+    writeln!(writer)?;
+
+    for edge in edges {
+        writeln!(
+            writer,
+            "  \"{}\" -> \"{}\" [label=\"{}\", relation_weight={}];",
+            escape_dot(&import_key(&edge.tenant, &edge.source)),
+            escape_dot(&import_key(&edge.tenant, &edge.target)),
+            escape_dot(&edge.relation),
+            edge.weight
+        )?;
+    }
+
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
+
+/// Export header-format CSVs for `neo4j-admin database import`
+///
+/// Strings are quoted, missing values are left empty (imported as null), and
+/// aliases use the importer's default `;` array delimiter. The import key is
+/// `tenant:id`, so tenants that share a concept id stay apart; the plain id
+/// is kept as the `id` property.
+pub fn export_neo4j_admin(
+    nodes: &[GraphNode],
+    edges: &[TenantEdge],
+    nodes_path: &Path,
+    relationships_path: &Path,
+) -> Result<()> {
+    let file = File::create(nodes_path)
+        .with_context(|| format!("Failed to create file: {}", nodes_path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(
+        writer,
+        ":ID,id,label,degree:int,community:int,entity_type,tenant,aliases:string[],:LABEL"
+    )?;
+    for node in nodes {
+        let entity_type = node
+            .entity_type
+            .as_deref()
+            .map(|t| format!("\"{}\"", escape_csv(t)))
+            .unwrap_or_default();
+        let aliases = if node.aliases.is_empty() {
+            String::new()
+        } else {
+            format!("\"{}\"", escape_csv(&node.aliases.join(ALIAS_SEPARATOR)))
+        };
+        writeln!(
+            writer,
+            "\"{}\",\"{}\",\"{}\",{},{},{},\"{}\",{},Concept",
+            escape_csv(&import_key(&node.tenant, &node.id)),
+            escape_csv(&node.id),
+            escape_csv(&node.label),
+            optional(node.degree),
+            optional(node.community),
+            entity_type,
+            escape_csv(&node.tenant),
+            aliases
+        )?;
+    }
+
+    let file = File::create(relationships_path)
+        .with_context(|| format!("Failed to create file: {}", relationships_path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, ":START_ID,:END_ID,relation,weight:double,:TYPE")?;
+    for edge in edges {
+        writeln!(
+            writer,
+            "\"{}\",\"{}\",\"{}\",{},RELATES_TO",
+            escape_csv(&import_key(&edge.tenant, &edge.source)),
+            escape_csv(&import_key(&edge.tenant, &edge.target)),
+            escape_csv(&edge.relation),
+            edge.weight
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The key of a concept across tenants, used as the neo4j-admin import id and
+/// the GraphML, GEXF and DOT node id; `:` and `%` in the tenant are escaped so
+/// the key cannot be read two ways
+fn import_key(tenant: &str, id: &str) -> String {
+    format!("{}:{}", tenant.replace('%', "%25").replace(':', "%3A"), id)
+}
+
+/// Format an optional number, leaving the field empty when absent
+fn optional(value: Option<usize>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
 /// Escape special characters for CSV
 fn escape_csv(s: &str) -> String {
     s.replace('"', "\"\"")
@@ -274,6 +762,13 @@ fn escape_xml(s: &str) -> String {
         .replace('\'', "&apos;")
 }
 
+/// Escape special characters for DOT quoted strings
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
 /// Escape special characters for Cypher strings
 fn escape_cypher(s: &str) -> String {
     s.replace('\\', "\\\\").replace('\'', "\\'")
@@ -293,6 +788,7 @@ mod tests {
                 degree: Some(3),
                 entity_type: Some("technology".into()),
                 tenant: "default".into(),
+                aliases: Vec::new(),
             },
             GraphNode {
                 id: "tokio".into(),
@@ -301,6 +797,7 @@ mod tests {
                 degree: Some(1),
                 entity_type: None,
                 tenant: "default".into(),
+                aliases: Vec::new(),
             },
         ]
     }
@@ -311,11 +808,118 @@ mod tests {
             target: "tokio".into(),
             relation: "uses".into(),
             weight: 4.0,
+            sources: Vec::new(),
         }]
     }
 
+    fn sample_tenant_edges() -> Vec<TenantEdge> {
+        sample_edges()
+            .into_iter()
+            .map(|e| e.with_tenant("default"))
+            .collect()
+    }
+
+    /// The sample graph in tenants `default` and `acme`, with a different
+    /// relation in each so edges can be told apart
+    fn multi_tenant_graph() -> (Vec<GraphNode>, Vec<TenantEdge>) {
+        let mut nodes = sample_nodes();
+        let mut edges = sample_tenant_edges();
+        for node in sample_nodes() {
+            nodes.push(GraphNode {
+                tenant: "acme".into(),
+                ..node
+            });
+        }
+        let mut other = sample_edges()[0].clone().with_tenant("acme");
+        other.relation = "powers".into();
+        edges.push(other);
+        (nodes, edges)
+    }
+
+    /// Node ids and `(source, target, relation)` edges read back from GraphML or GEXF
+    fn read_xml_graph(xml: &str) -> (Vec<String>, Vec<(String, String, String)>) {
+        use quick_xml::Reader;
+        use quick_xml::events::Event;
+
+        let attr = |e: &quick_xml::events::BytesStart, name: &str| {
+            e.attributes()
+                .flatten()
+                .find(|a| a.key.as_ref() == name.as_bytes())
+                .map(|a| a.unescape_value().unwrap().into_owned())
+        };
+        let mut reader = Reader::from_str(xml);
+        let (mut nodes, mut edges) = (Vec::new(), Vec::new());
+        let mut edge: Option<(String, String)> = None;
+        let mut in_relation = false;
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                    b"node" => nodes.push(attr(&e, "id").unwrap()),
+                    b"edge" => {
+                        let (source, target) = (attr(&e, "source"), attr(&e, "target"));
+                        edge = Some((source.unwrap(), target.unwrap()));
+                    }
+                    b"data" => in_relation = attr(&e, "key").as_deref() == Some("relation"),
+                    b"attvalue" if attr(&e, "for").as_deref() == Some("relation") => {
+                        let (source, target) = edge.take().unwrap();
+                        edges.push((source, target, attr(&e, "value").unwrap()));
+                    }
+                    _ => {}
+                },
+                Event::Text(text) if in_relation => {
+                    let (source, target) = edge.take().unwrap();
+                    edges.push((source, target, text.unescape().unwrap().into_owned()));
+                    in_relation = false;
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        (nodes, edges)
+    }
+
+    /// The ids and edges a multi-tenant export must read back as
+    fn assert_tenants_apart(nodes: &[String], edges: &[(String, String, String)]) {
+        let unique: HashSet<&String> = nodes.iter().collect();
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(unique.len(), 4, "duplicate node ids: {:?}", nodes);
+        assert_eq!(
+            edges,
+            [
+                ("default:rust".into(), "default:tokio".into(), "uses".into()),
+                ("acme:rust".into(), "acme:tokio".into(), "powers".into()),
+            ]
+        );
+    }
+
     // ── JSON ────────────────────────────────────────────────────────
 
+    #[test]
+    fn test_filter_tenant_keeps_its_nodes_and_edges() {
+        let mut nodes = sample_nodes();
+        nodes[1].tenant = "acme".into();
+        let (kept, edges) = filter_tenant(nodes.clone(), sample_edges(), Some("default"));
+        assert_eq!(kept.len(), 1);
+        assert!(edges.is_empty());
+
+        let (all, edges) = filter_tenant(nodes, sample_edges(), None);
+        assert_eq!((all.len(), edges.len()), (2, 1));
+    }
+
+    #[test]
+    fn test_tag_tenants_follows_the_endpoints() {
+        let (mut nodes, _) = multi_tenant_graph();
+        let tagged = tag_tenants(&nodes, sample_edges());
+        let mut tenants: Vec<&str> = tagged.iter().map(|e| e.tenant.as_str()).collect();
+        tenants.sort();
+        assert_eq!(tenants, vec!["acme", "default"]);
+
+        // An edge whose endpoints only meet across tenants belongs to neither
+        nodes.retain(|n| !(n.id == "tokio" && n.tenant == "default"));
+        nodes.retain(|n| !(n.id == "rust" && n.tenant == "acme"));
+        assert!(tag_tenants(&nodes, sample_edges()).is_empty());
+    }
+
     #[test]
     fn test_export_json_creates_valid_file() {
         let dir = tempdir().unwrap();
@@ -342,6 +946,19 @@ mod tests {
         assert_eq!(parsed.edges[0].relation, "uses");
     }
 
+    #[test]
+    fn test_load_json_reads_export() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("graph.json");
+        export_json_from_data(&sample_nodes(), &sample_edges(), &path).unwrap();
+
+        let (nodes, edges) = load_json(&path).unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1].community, Some(1));
+        assert_eq!(edges[0].weight, 4.0);
+        assert!(load_json(&dir.path().join("missing.json")).is_err());
+    }
+
     #[test]
     fn test_export_json_empty() {
         let dir = tempdir().unwrap();
@@ -354,6 +971,72 @@ mod tests {
         assert!(parsed.edges.is_empty());
     }
 
+    // ── JSON Lines ──────────────────────────────────────────────────
+
+    #[test]
+    fn test_jsonl_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("graph.jsonl");
+        let mut nodes = sample_nodes();
+        nodes[1].aliases = vec!["tokio-rs".into()];
+
+        let mut writer = JsonlWriter::create(&path).unwrap();
+        for node in &nodes {
+            writer.write(&GraphRecord::Node(node.clone())).unwrap();
+        }
+        writer
+            .write(&GraphRecord::Edge(TenantEdge {
+                source: "rust".into(),
+                target: "tokio".into(),
+                relation: "uses".into(),
+                weight: 4.0,
+                tenant: "default".into(),
+                sources: Vec::new(),
+            }))
+            .unwrap();
+        assert_eq!(writer.finish().unwrap(), (2, 1));
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        assert!(
+            content
+                .lines()
+                .next()
+                .unwrap()
+                .starts_with(r#"{"type":"node","id":"rust""#)
+        );
+
+        let records: Vec<GraphRecord> = read_jsonl(&path).unwrap().collect::<Result<_>>().unwrap();
+        match &records[1] {
+            GraphRecord::Node(node) => {
+                assert_eq!(node.community, Some(1));
+                assert_eq!(node.aliases, vec!["tokio-rs".to_string()]);
+            }
+            other => panic!("expected node, got {:?}", other),
+        }
+        match &records[2] {
+            GraphRecord::Edge(edge) => assert_eq!(edge.relation, "uses"),
+            other => panic!("expected edge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_jsonl_reports_bad_line() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("graph.jsonl");
+        std::fs::write(
+            &path,
+            "{\"type\":\"node\",\"id\":\"a\",\"label\":\"a\",\"community\":null,\"degree\":null}\n\n{oops\n",
+        )
+        .unwrap();
+
+        let results: Vec<Result<GraphRecord>> = read_jsonl(&path).unwrap().collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        let err = results[1].as_ref().unwrap_err().to_string();
+        assert!(err.contains("line 3"), "{}", err);
+    }
+
     // ── CSV ─────────────────────────────────────────────────────────
 
     #[test]
@@ -376,7 +1059,10 @@ mod tests {
 
         let nodes_csv = std::fs::read_to_string(&np).unwrap();
         let lines: Vec<&str> = nodes_csv.lines().collect();
-        assert_eq!(lines[0], "id,label,degree,community");
+        assert_eq!(
+            lines[0],
+            "id,label,degree,community,entity_type,tenant,aliases"
+        );
         assert_eq!(lines.len(), 3); // header + 2 nodes
 
         let edges_csv = std::fs::read_to_string(&ep).unwrap();
@@ -385,6 +1071,24 @@ mod tests {
         assert_eq!(lines.len(), 2); // header + 1 edge
     }
 
+    #[test]
+    fn test_export_csv_keeps_every_node_field() {
+        let mut nodes = sample_nodes();
+        nodes[0].aliases = vec!["rust-lang".into(), "rs".into()];
+        let dir = tempdir().unwrap();
+        let np = dir.path().join("nodes.csv");
+        let ep = dir.path().join("edges.csv");
+        export_csv_from_data(&nodes, &sample_edges(), &np, &ep).unwrap();
+
+        let nodes_csv = std::fs::read_to_string(&np).unwrap();
+        let lines: Vec<&str> = nodes_csv.lines().collect();
+        assert_eq!(
+            lines[1],
+            r#""rust","Rust",3,,"technology","default","rust-lang;rs""#
+        );
+        assert_eq!(lines[2], r#""tokio","Tokio",1,1,"","default","""#);
+    }
+
     #[test]
     fn test_export_csv_special_characters() {
         let nodes = vec![GraphNode {
@@ -394,6 +1098,7 @@ mod tests {
             degree: Some(0),
             entity_type: None,
             tenant: "default".into(),
+            aliases: Vec::new(),
         }];
         let dir = tempdir().unwrap();
         let np = dir.path().join("n.csv");
@@ -410,13 +1115,14 @@ mod tests {
     fn test_export_graphml_valid_xml() {
         let dir = tempdir().unwrap();
         let path = dir.path().join("test.graphml");
-        export_graphml(&sample_nodes(), &sample_edges(), &path).unwrap();
+        export_graphml(&sample_nodes(), &sample_tenant_edges(), &path).unwrap();
 
         let content = std::fs::read_to_string(&path).unwrap();
         assert!(content.starts_with("<?xml"));
         assert!(content.contains("<graphml"));
         assert!(content.contains("</graphml>"));
-        assert!(content.contains("<node id=\"rust\">"));
+        assert!(content.contains("<node id=\"default:rust\">"));
+        assert!(content.contains(r#"<data key="id">rust</data>"#));
         assert!(content.contains("<edge id=\"e0\""));
     }
 
@@ -429,6 +1135,7 @@ mod tests {
             degree: Some(0),
             entity_type: None,
             tenant: "default".into(),
+            aliases: Vec::new(),
         }];
         let dir = tempdir().unwrap();
         let path = dir.path().join("escape.graphml");
@@ -440,13 +1147,24 @@ mod tests {
         assert!(!content.contains("<script>"));
     }
 
+    #[test]
+    fn test_export_graphml_keeps_tenants_apart() {
+        let (nodes, edges) = multi_tenant_graph();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tenants.graphml");
+        export_graphml(&nodes, &edges, &path).unwrap();
+
+        let (ids, read) = read_xml_graph(&std::fs::read_to_string(&path).unwrap());
+        assert_tenants_apart(&ids, &read);
+    }
+
     // ── Cypher ──────────────────────────────────────────────────────
 
     #[test]
     fn test_export_cypher_statements() {
         let dir = tempdir().unwrap();
         let path = dir.path().join("test.cypher");
-        export_cypher(&sample_nodes(), &sample_edges(), &path).unwrap();
+        export_cypher(&sample_nodes(), &sample_tenant_edges(), &path).unwrap();
 
         let content = std::fs::read_to_string(&path).unwrap();
         assert!(content.contains("CREATE (n:Concept"));
@@ -456,6 +1174,200 @@ mod tests {
         assert!(content.contains("'tokio'"));
     }
 
+    #[test]
+    fn test_export_graphml_and_cypher_keep_every_node_field() {
+        let mut nodes = sample_nodes();
+        nodes[0].aliases = vec!["rs".into()];
+        let dir = tempdir().unwrap();
+
+        let path = dir.path().join("test.graphml");
+        export_graphml(&nodes, &sample_tenant_edges(), &path).unwrap();
+        let graphml = std::fs::read_to_string(&path).unwrap();
+        assert!(graphml.contains(r#"<data key="entity_type">technology</data>"#));
+        assert!(graphml.contains(r#"<data key="community">1</data>"#));
+        assert!(graphml.contains(r#"<data key="tenant">default</data>"#));
+        assert!(graphml.contains(r#"<data key="aliases">rs</data>"#));
+
+        let path = dir.path().join("test.cypher");
+        export_cypher(&nodes, &sample_tenant_edges(), &path).unwrap();
+        let cypher = std::fs::read_to_string(&path).unwrap();
+        assert!(cypher.contains(
+            "CREATE (n:Concept {id: 'rust', label: 'Rust', tenant: 'default', degree: 3, entity_type: 'technology', aliases: ['rs']});"
+        ));
+        assert!(cypher.contains(
+            "CREATE (n:Concept {id: 'tokio', label: 'Tokio', tenant: 'default', degree: 1, community: 1});"
+        ));
+    }
+
+    #[test]
+    fn test_export_gexf_attributes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.gexf");
+        export_gexf(&sample_nodes(), &sample_tenant_edges(), &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains(r#"<gexf xmlns="http://www.gexf.net/1.2draft""#));
+        assert!(
+            content.contains(r#"<attribute id="community" title="community" type="integer"/>"#)
+        );
+        assert!(content.contains(r#"<node id="default:tokio" label="Tokio">"#));
+        assert!(content.contains(r#"<attvalue for="id" value="tokio"/>"#));
+        assert!(content.contains(r#"<attvalue for="community" value="1"/>"#));
+        assert!(content.contains(r#"<attvalue for="entity_type" value="technology"/>"#));
+        assert!(
+            content.contains(
+                r#"source="default:rust" target="default:tokio" label="uses" weight="4""#
+            )
+        );
+        assert!(content.trim_end().ends_with("</gexf>"));
+    }
+
+    #[test]
+    fn test_export_gexf_keeps_tenants_apart() {
+        let (nodes, edges) = multi_tenant_graph();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tenants.gexf");
+        export_gexf(&nodes, &edges, &path).unwrap();
+
+        let (ids, read) = read_xml_graph(&std::fs::read_to_string(&path).unwrap());
+        assert_tenants_apart(&ids, &read);
+    }
+
+    #[test]
+    fn test_export_dot_escaping() {
+        let nodes = vec![GraphNode {
+            id: "say \"hi\"".into(),
+            label: "Say \"hi\"".into(),
+            community: Some(4),
+            degree: None,
+            entity_type: None,
+            tenant: "default".into(),
+            aliases: Vec::new(),
+        }];
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.dot");
+        export_dot(&nodes, &sample_tenant_edges(), &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("digraph rknowledge {"));
+        assert!(
+            content
+                .contains(r#""default:say \"hi\"" [label="Say \"hi\"", concept_id="say \"hi\"", tenant="default", community=4];"#)
+        );
+        assert!(
+            content.contains(
+                r#""default:rust" -> "default:tokio" [label="uses", relation_weight=4];"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_export_dot_keeps_tenants_apart() {
+        let (nodes, edges) = multi_tenant_graph();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tenants.dot");
+        export_dot(&nodes, &edges, &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let node_line = regex::Regex::new(r#"^  "([^"]+)" \["#).unwrap();
+        let edge_line =
+            regex::Regex::new(r#"^  "([^"]+)" -> "([^"]+)" \[label="([^"]+)""#).unwrap();
+        let ids: Vec<String> = content
+            .lines()
+            .filter_map(|l| node_line.captures(l))
+            .map(|c| c[1].to_string())
+            .collect();
+        let read: Vec<(String, String, String)> = content
+            .lines()
+            .filter_map(|l| edge_line.captures(l))
+            .map(|c| (c[1].to_string(), c[2].to_string(), c[3].to_string()))
+            .collect();
+        assert_tenants_apart(&ids, &read);
+    }
+
+    #[test]
+    fn test_export_neo4j_admin_headers() {
+        let mut nodes = sample_nodes();
+        nodes[0].aliases = vec!["rs".into(), "rust-lang".into()];
+        let dir = tempdir().unwrap();
+        let np = dir.path().join("nodes.csv");
+        let rp = dir.path().join("relationships.csv");
+        // The same concept in a second tenant gets its own key
+        let mut other = nodes[0].clone();
+        other.tenant = "acme".into();
+        nodes.push(other);
+        let edges = vec![TenantEdge {
+            source: "rust".into(),
+            target: "tokio".into(),
+            relation: "uses".into(),
+            weight: 4.0,
+            tenant: "default".into(),
+            sources: Vec::new(),
+        }];
+        export_neo4j_admin(&nodes, &edges, &np, &rp).unwrap();
+
+        let nodes_csv = std::fs::read_to_string(&np).unwrap();
+        let lines: Vec<&str> = nodes_csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            ":ID,id,label,degree:int,community:int,entity_type,tenant,aliases:string[],:LABEL"
+        );
+        assert_eq!(
+            lines[1],
+            r#""default:rust","rust","Rust",3,,"technology","default","rs;rust-lang",Concept"#
+        );
+        assert_eq!(
+            lines[2],
+            r#""default:tokio","tokio","Tokio",1,1,,"default",,Concept"#
+        );
+        assert!(lines[3].starts_with(r#""acme:rust","rust","Rust","#));
+
+        let rels = std::fs::read_to_string(&rp).unwrap();
+        let lines: Vec<&str> = rels.lines().collect();
+        assert_eq!(lines[0], ":START_ID,:END_ID,relation,weight:double,:TYPE");
+        assert_eq!(
+            lines[1],
+            r#""default:rust","default:tokio","uses",4,RELATES_TO"#
+        );
+        assert_eq!(import_key("a:b", "c"), "a%3Ab:c");
+    }
+
+    #[test]
+    fn test_export_cypher_keeps_tenants_apart() {
+        let (nodes, edges) = multi_tenant_graph();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tenants.cypher");
+        export_cypher(&nodes, &edges, &path).unwrap();
+
+        // Every MATCH must name a node the script created, by id and tenant
+        let content = std::fs::read_to_string(&path).unwrap();
+        let created = regex::Regex::new(
+            r"^CREATE \(n:Concept \{id: '([^']+)', label: '[^']*', tenant: '([^']+)'",
+        )
+        .unwrap();
+        let matched = regex::Regex::new(
+            r"^MATCH \(a:Concept \{id: '([^']+)', tenant: '([^']+)'\}\), \(b:Concept \{id: '([^']+)', tenant: '([^']+)'\}\) CREATE \(a\)-\[:RELATES_TO \{relation: '([^']+)'",
+        )
+        .unwrap();
+        let ids: Vec<String> = content
+            .lines()
+            .filter_map(|l| created.captures(l))
+            .map(|c| import_key(&c[2], &c[1]))
+            .collect();
+        let read: Vec<(String, String, String)> = content
+            .lines()
+            .filter_map(|l| matched.captures(l))
+            .map(|c| {
+                (
+                    import_key(&c[2], &c[1]),
+                    import_key(&c[4], &c[3]),
+                    c[5].to_string(),
+                )
+            })
+            .collect();
+        assert_tenants_apart(&ids, &read);
+    }
+
     #[test]
     fn test_export_cypher_escaping() {
         let nodes = vec![GraphNode {
@@ -465,6 +1377,7 @@ mod tests {
             degree: Some(0),
             entity_type: None,
             tenant: "default".into(),
+            aliases: Vec::new(),
         }];
         let dir = tempdir().unwrap();
         let path = dir.path().join("esc.cypher");
@@ -0,0 +1,433 @@
+//! Parquet export: typed `nodes.parquet` and `edges.parquet` tables.
+//!
+//! Columns are written straight from `GraphNode`/`TenantEdge` as Arrow
+//! arrays, so numbers stay numbers and lists stay lists. Centralities are
+//! computed here, so analytics tools can load them as ordinary columns.
+//! Concepts are told apart by tenant and id, so tenants sharing a concept
+//! id do not share its edges or scores.
+
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::basic::Compression;
+use ::parquet::file::properties::WriterProperties;
+use anyhow::{Context, Result};
+use arrow_array::builder::{ListBuilder, StringBuilder};
+use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use petgraph::graph::{DiGraph, NodeIndex};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::graph::analytics::pagerank;
+use crate::graph::neo4j::{GraphNode, TenantEdge};
+
+/// Rows per record batch (and so per row group)
+const BATCH_ROWS: usize = 65_536;
+
+/// Export nodes and edges to two Parquet files
+pub fn export_parquet(
+    nodes: &[GraphNode],
+    edges: &[TenantEdge],
+    nodes_path: &Path,
+    edges_path: &Path,
+) -> Result<()> {
+    let centrality = Centrality::compute(nodes, edges);
+
+    let schema = node_schema();
+    write_batches(nodes_path, schema.clone(), nodes.len(), |range| {
+        node_batch(&schema, &nodes[range.clone()], &centrality, range.start)
+    })?;
+
+    let schema = edge_schema();
+    write_batches(edges_path, schema.clone(), edges.len(), |range| {
+        edge_batch(&schema, &edges[range])
+    })?;
+
+    Ok(())
+}
+
+fn node_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("label", DataType::Utf8, false),
+        Field::new("degree", DataType::Int64, true),
+        Field::new("community", DataType::Int64, true),
+        Field::new("entity_type", DataType::Utf8, true),
+        Field::new("tenant", DataType::Utf8, false),
+        Field::new(
+            "aliases",
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            false,
+        ),
+        Field::new("in_degree", DataType::Int64, false),
+        Field::new("out_degree", DataType::Int64, false),
+        Field::new("degree_centrality", DataType::Float64, false),
+        Field::new("pagerank", DataType::Float64, false),
+    ]))
+}
+
+fn edge_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("source", DataType::Utf8, false),
+        Field::new("target", DataType::Utf8, false),
+        Field::new("relation", DataType::Utf8, false),
+        Field::new("weight", DataType::Float64, false),
+        Field::new("tenant", DataType::Utf8, false),
+        Field::new(
+            "sources",
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            false,
+        ),
+    ]))
+}
+
+/// Write `rows` rows in batches of [`BATCH_ROWS`], building each batch on demand
+fn write_batches(
+    path: &Path,
+    schema: SchemaRef,
+    rows: usize,
+    mut batch: impl FnMut(std::ops::Range<usize>) -> Result<RecordBatch>,
+) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create file: {}", path.display()))?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(file, schema, Some(props))
+        .context("Failed to start Parquet writer")?;
+
+    let mut start = 0;
+    while start < rows {
+        let end = (start + BATCH_ROWS).min(rows);
+        writer
+            .write(&batch(start..end)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        start = end;
+    }
+    writer
+        .close()
+        .with_context(|| format!("Failed to finish {}", path.display()))?;
+
+    Ok(())
+}
+
+fn node_batch(
+    schema: &SchemaRef,
+    nodes: &[GraphNode],
+    centrality: &Centrality,
+    offset: usize,
+) -> Result<RecordBatch> {
+    let mut aliases = ListBuilder::new(StringBuilder::new());
+    for node in nodes {
+        for alias in &node.aliases {
+            aliases.values().append_value(alias);
+        }
+        aliases.append(true);
+    }
+    let rows = offset..offset + nodes.len();
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(nodes.iter().map(|n| &n.id))),
+        Arc::new(StringArray::from_iter_values(
+            nodes.iter().map(|n| &n.label),
+        )),
+        Arc::new(Int64Array::from_iter(
+            nodes.iter().map(|n| n.degree.map(|d| d as i64)),
+        )),
+        Arc::new(Int64Array::from_iter(
+            nodes.iter().map(|n| n.community.map(|c| c as i64)),
+        )),
+        Arc::new(StringArray::from_iter(
+            nodes.iter().map(|n| n.entity_type.as_deref()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            nodes.iter().map(|n| &n.tenant),
+        )),
+        Arc::new(aliases.finish()),
+        Arc::new(Int64Array::from_iter_values(
+            centrality.in_degree[rows.clone()].iter().map(|&d| d as i64),
+        )),
+        Arc::new(Int64Array::from_iter_values(
+            centrality.out_degree[rows.clone()]
+                .iter()
+                .map(|&d| d as i64),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            rows.clone().map(|i| centrality.degree_centrality(i)),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            centrality.pagerank[rows].iter().copied(),
+        )),
+    ];
+
+    RecordBatch::try_new(schema.clone(), columns).context("Failed to build node columns")
+}
+
+fn edge_batch(schema: &SchemaRef, edges: &[TenantEdge]) -> Result<RecordBatch> {
+    let mut sources = ListBuilder::new(StringBuilder::new());
+    for edge in edges {
+        for source in &edge.sources {
+            sources.values().append_value(source);
+        }
+        sources.append(true);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            edges.iter().map(|e| &e.source),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            edges.iter().map(|e| &e.target),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            edges.iter().map(|e| &e.relation),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            edges.iter().map(|e| e.weight),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            edges.iter().map(|e| &e.tenant),
+        )),
+        Arc::new(sources.finish()),
+    ];
+
+    RecordBatch::try_new(schema.clone(), columns).context("Failed to build edge columns")
+}
+
+/// Per-node centralities, in the same order as the node slice
+struct Centrality {
+    in_degree: Vec<usize>,
+    out_degree: Vec<usize>,
+    pagerank: Vec<f64>,
+}
+
+impl Centrality {
+    fn compute(nodes: &[GraphNode], edges: &[TenantEdge]) -> Self {
+        let mut graph: DiGraph<String, f64> = DiGraph::with_capacity(nodes.len(), edges.len());
+        let mut index: HashMap<(&str, &str), NodeIndex> = HashMap::with_capacity(nodes.len());
+        let positions: Vec<NodeIndex> = nodes
+            .iter()
+            .map(|n| {
+                *index
+                    .entry((n.tenant.as_str(), n.id.as_str()))
+                    .or_insert_with(|| graph.add_node(n.id.clone()))
+            })
+            .collect();
+
+        for edge in edges {
+            let tenant = edge.tenant.as_str();
+            if let (Some(&a), Some(&b)) = (
+                index.get(&(tenant, edge.source.as_str())),
+                index.get(&(tenant, edge.target.as_str())),
+            ) {
+                graph.add_edge(a, b, edge.weight);
+            }
+        }
+
+        let scores = pagerank(&graph, 0.85, 30);
+        Self {
+            in_degree: positions
+                .iter()
+                .map(|&ni| graph.edges_directed(ni, petgraph::Incoming).count())
+                .collect(),
+            out_degree: positions
+                .iter()
+                .map(|&ni| graph.edges_directed(ni, petgraph::Outgoing).count())
+                .collect(),
+            pagerank: positions
+                .iter()
+                .map(|ni| scores.get(ni).copied().unwrap_or(0.0))
+                .collect(),
+        }
+    }
+
+    /// Degree normalised by the largest possible degree, `n - 1`
+    fn degree_centrality(&self, i: usize) -> f64 {
+        let n = self.in_degree.len();
+        if n < 2 {
+            return 0.0;
+        }
+        (self.in_degree[i] + self.out_degree[i]) as f64 / (n - 1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use arrow_array::{Array, ListArray};
+    use tempfile::tempdir;
+
+    fn node(id: &str, community: Option<usize>, aliases: &[&str]) -> GraphNode {
+        GraphNode {
+            id: id.into(),
+            label: id.to_uppercase(),
+            community,
+            degree: Some(1),
+            entity_type: community.map(|_| "technology".into()),
+            tenant: "acme".into(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    fn edge(source: &str, target: &str, weight: f64, sources: &[&str]) -> TenantEdge {
+        TenantEdge {
+            source: source.into(),
+            target: target.into(),
+            relation: "uses".into(),
+            weight,
+            tenant: "acme".into(),
+            sources: sources.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn read(path: &Path) -> RecordBatch {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let mut batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 1, "small tables fit one batch");
+        batches.remove(0)
+    }
+
+    #[test]
+    fn test_parquet_typed_columns() {
+        let nodes = vec![
+            node("rust", Some(0), &["rust-lang", "rs"]),
+            node("tokio", None, &[]),
+            node("cargo", Some(0), &[]),
+        ];
+        let edges = vec![
+            edge("tokio", "rust", 2.5, &["README.md", "guide.pdf p.12"]),
+            edge("cargo", "rust", 1.0, &[]),
+        ];
+        let dir = tempdir().unwrap();
+        let np = dir.path().join("nodes.parquet");
+        let ep = dir.path().join("edges.parquet");
+        export_parquet(&nodes, &edges, &np, &ep).unwrap();
+
+        let batch = read(&np);
+        assert_eq!(batch.num_rows(), 3);
+        let schema = batch.schema();
+        assert_eq!(
+            schema.field_with_name("degree").unwrap().data_type(),
+            &DataType::Int64
+        );
+        assert_eq!(
+            schema.field_with_name("pagerank").unwrap().data_type(),
+            &DataType::Float64
+        );
+
+        let community = batch
+            .column_by_name("community")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(community.value(0), 0);
+        assert!(community.is_null(1));
+
+        let aliases = batch
+            .column_by_name("aliases")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(aliases.value_length(0), 2);
+        assert_eq!(aliases.value_length(1), 0);
+
+        let in_degree = batch
+            .column_by_name("in_degree")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(in_degree.value(0), 2);
+
+        let pagerank = batch
+            .column_by_name("pagerank")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert!(pagerank.value(0) > pagerank.value(1));
+
+        let batch = read(&ep);
+        assert_eq!(batch.num_rows(), 2);
+        let weight = batch
+            .column_by_name("weight")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(weight.value(0), 2.5);
+        let tenant = batch
+            .column_by_name("tenant")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(tenant.value(1), "acme");
+        let sources = batch
+            .column_by_name("sources")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        let first = sources.value(0);
+        let first = first.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(first.value(0), "README.md");
+        assert_eq!(first.value(1), "guide.pdf p.12");
+        assert_eq!(sources.value_length(1), 0);
+    }
+
+    #[test]
+    fn test_parquet_keeps_tenants_apart() {
+        // `rust` exists in two tenants; only acme's copy has an incoming edge
+        let mut other = node("rust", None, &[]);
+        other.tenant = "zeta".into();
+        let nodes = vec![node("rust", None, &[]), node("tokio", None, &[]), other];
+        let mut edges = vec![edge("tokio", "rust", 1.0, &[])];
+        let mut zeta = edge("rust", "rust", 1.0, &[]);
+        zeta.tenant = "zeta".into();
+        edges.push(zeta);
+        let dir = tempdir().unwrap();
+        let np = dir.path().join("nodes.parquet");
+        let ep = dir.path().join("edges.parquet");
+        export_parquet(&nodes, &edges, &np, &ep).unwrap();
+
+        let batch = read(&np);
+        let in_degree = batch
+            .column_by_name("in_degree")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(
+            (in_degree.value(0), in_degree.value(1), in_degree.value(2)),
+            (1, 0, 1)
+        );
+
+        let batch = read(&ep);
+        let tenant = batch
+            .column_by_name("tenant")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!((tenant.value(0), tenant.value(1)), ("acme", "zeta"));
+    }
+
+    #[test]
+    fn test_parquet_empty_graph() {
+        let dir = tempdir().unwrap();
+        let np = dir.path().join("nodes.parquet");
+        let ep = dir.path().join("edges.parquet");
+        export_parquet(&[], &[], &np, &ep).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&np).unwrap()).unwrap();
+        assert_eq!(reader.schema().fields().len(), 11);
+    }
+}
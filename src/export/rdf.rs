@@ -0,0 +1,327 @@
+//! RDF exports: Turtle and JSON-LD.
+//!
+//! Both formats describe the same model. Every concept is an `rk:Concept`
+//! whose IRI is `<base>concept/<tenant>/<id>`, so tenants that share a
+//! concept id stay apart. Node fields become literal properties.
+//! Each edge becomes a direct `rk:relatesTo` triple, plus an `rk:Relation`
+//! blank node that keeps the relation text and weight.
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::graph::neo4j::{GraphNode, TenantEdge};
+
+const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Export to RDF Turtle, minting IRIs under `base_iri`
+pub fn export_turtle(
+    nodes: &[GraphNode],
+    edges: &[TenantEdge],
+    base_iri: &str,
+    path: &Path,
+) -> Result<()> {
+    let base = normalize_base(base_iri)?;
+    let file =
+        File::create(path).with_context(|| format!("Failed to create file: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, "@prefix rk: <{}vocab#> .", base)?;
+    writeln!(writer, "@prefix rdfs: <{}> .", RDFS)?;
+    writeln!(writer, "@prefix xsd: <{}> .", XSD)?;
+    writeln!(writer)?;
+
+    let targets = targets_by_source(edges);
+    for node in nodes {
+        let mut props = vec![
+            "a rk:Concept".to_string(),
+            format!("rdfs:label {}", turtle_string(&node.label)),
+            format!("rk:tenant {}", turtle_string(&node.tenant)),
+        ];
+        if let Some(degree) = node.degree {
+            props.push(format!("rk:degree {}", degree));
+        }
+        if let Some(community) = node.community {
+            props.push(format!("rk:community {}", community));
+        }
+        if let Some(entity_type) = &node.entity_type {
+            props.push(format!("rk:entityType {}", turtle_string(entity_type)));
+        }
+        for alias in &node.aliases {
+            props.push(format!("rk:alias {}", turtle_string(alias)));
+        }
+        for target in targets
+            .get(&(node.tenant.as_str(), node.id.as_str()))
+            .into_iter()
+            .flatten()
+        {
+            props.push(format!(
+                "rk:relatesTo <{}>",
+                concept_iri(&base, &node.tenant, target)
+            ));
+        }
+        writeln!(
+            writer,
+            "<{}> {} .",
+            concept_iri(&base, &node.tenant, &node.id),
+            props.join(" ;\n    ")
+        )?;
+        writeln!(writer)?;
+    }
+
+    for (i, edge) in edges.iter().enumerate() {
+        writeln!(
+            writer,
+            "_:e{} a rk:Relation ;\n    rk:source <{}> ;\n    rk:target <{}> ;\n    rk:relation {} ;\n    rk:weight \"{}\"^^xsd:double .",
+            i,
+            concept_iri(&base, &edge.tenant, &edge.source),
+            concept_iri(&base, &edge.tenant, &edge.target),
+            turtle_string(&edge.relation),
+            edge.weight
+        )?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+/// Export to JSON-LD, minting IRIs under `base_iri`
+pub fn export_jsonld(
+    nodes: &[GraphNode],
+    edges: &[TenantEdge],
+    base_iri: &str,
+    path: &Path,
+) -> Result<()> {
+    let base = normalize_base(base_iri)?;
+    let targets = targets_by_source(edges);
+
+    let mut graph = Vec::with_capacity(nodes.len() + edges.len());
+    for node in nodes {
+        let mut obj = Map::new();
+        obj.insert(
+            "@id".into(),
+            json!(concept_iri(&base, &node.tenant, &node.id)),
+        );
+        obj.insert("@type".into(), json!("Concept"));
+        obj.insert("label".into(), json!(node.label));
+        obj.insert("tenant".into(), json!(node.tenant));
+        if let Some(degree) = node.degree {
+            obj.insert("degree".into(), json!(degree));
+        }
+        if let Some(community) = node.community {
+            obj.insert("community".into(), json!(community));
+        }
+        if let Some(entity_type) = &node.entity_type {
+            obj.insert("entityType".into(), json!(entity_type));
+        }
+        if !node.aliases.is_empty() {
+            obj.insert("alias".into(), json!(node.aliases));
+        }
+        if let Some(targets) = targets.get(&(node.tenant.as_str(), node.id.as_str())) {
+            let iris: Vec<String> = targets
+                .iter()
+                .map(|t| concept_iri(&base, &node.tenant, t))
+                .collect();
+            obj.insert("relatesTo".into(), json!(iris));
+        }
+        graph.push(Value::Object(obj));
+    }
+    for (i, edge) in edges.iter().enumerate() {
+        graph.push(json!({
+            "@id": format!("_:e{}", i),
+            "@type": "Relation",
+            "source": concept_iri(&base, &edge.tenant, &edge.source),
+            "target": concept_iri(&base, &edge.tenant, &edge.target),
+            "relation": edge.relation,
+            "weight": edge.weight,
+        }));
+    }
+
+    let document = json!({
+        "@context": {
+            "@vocab": format!("{}vocab#", base),
+            "rdfs": RDFS,
+            "xsd": XSD,
+            "label": "rdfs:label",
+            "alias": { "@container": "@set" },
+            "relatesTo": { "@type": "@id", "@container": "@set" },
+            "source": { "@type": "@id" },
+            "target": { "@type": "@id" },
+            "degree": { "@type": "xsd:integer" },
+            "community": { "@type": "xsd:integer" },
+            "weight": { "@type": "xsd:double" },
+        },
+        "@graph": graph,
+    });
+
+    let file =
+        File::create(path).with_context(|| format!("Failed to create file: {}", path.display()))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &document)
+        .context("Failed to write JSON-LD")?;
+
+    Ok(())
+}
+
+/// Check the base IRI and make sure further segments can be appended to it
+fn normalize_base(base_iri: &str) -> Result<String> {
+    let base = base_iri.trim();
+    let has_scheme = base.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    if !has_scheme
+        || base
+            .chars()
+            .any(|c| c.is_whitespace() || "<>\"{}|^`\\".contains(c))
+    {
+        bail!(
+            "Invalid base IRI '{}'. Expected an absolute IRI such as 'http://example.org/kg/'",
+            base_iri
+        );
+    }
+
+    if base.ends_with(['/', '#', ':']) {
+        Ok(base.to_string())
+    } else {
+        Ok(format!("{}/", base))
+    }
+}
+
+fn concept_iri(base: &str, tenant: &str, id: &str) -> String {
+    format!(
+        "{}concept/{}/{}",
+        base,
+        percent_encode(tenant),
+        percent_encode(id)
+    )
+}
+
+/// Percent-encode everything outside the RFC 3986 unreserved set
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn turtle_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// Targets of each `(tenant, source)` concept
+fn targets_by_source(edges: &[TenantEdge]) -> HashMap<(&str, &str), Vec<&str>> {
+    let mut targets: HashMap<(&str, &str), Vec<&str>> = HashMap::new();
+    for edge in edges {
+        let list = targets
+            .entry((edge.tenant.as_str(), edge.source.as_str()))
+            .or_default();
+        if !list.contains(&edge.target.as_str()) {
+            list.push(edge.target.as_str());
+        }
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample() -> (Vec<GraphNode>, Vec<TenantEdge>) {
+        let nodes = vec![
+            GraphNode {
+                id: "rust lang".into(),
+                label: "Rust \"lang\"".into(),
+                community: Some(2),
+                degree: Some(1),
+                entity_type: Some("technology".into()),
+                tenant: "acme".into(),
+                aliases: vec!["rs".into()],
+            },
+            GraphNode {
+                id: "tokio".into(),
+                label: "Tokio".into(),
+                community: None,
+                degree: None,
+                entity_type: None,
+                tenant: "acme".into(),
+                aliases: Vec::new(),
+            },
+        ];
+        let edges = vec![TenantEdge {
+            source: "rust lang".into(),
+            target: "tokio".into(),
+            relation: "has runtime".into(),
+            weight: 2.5,
+            tenant: "acme".into(),
+            sources: Vec::new(),
+        }];
+        (nodes, edges)
+    }
+
+    #[test]
+    fn test_turtle_contains_all_fields() {
+        let (nodes, edges) = sample();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("graph.ttl");
+        export_turtle(&nodes, &edges, "http://example.org/kg", &path).unwrap();
+
+        let ttl = std::fs::read_to_string(&path).unwrap();
+        assert!(ttl.contains("@prefix rk: <http://example.org/kg/vocab#> ."));
+        assert!(ttl.contains("<http://example.org/kg/concept/acme/rust%20lang> a rk:Concept"));
+        assert!(ttl.contains(r#"rdfs:label "Rust \"lang\"""#));
+        assert!(ttl.contains(r#"rk:tenant "acme""#));
+        assert!(ttl.contains("rk:community 2"));
+        assert!(ttl.contains(r#"rk:entityType "technology""#));
+        assert!(ttl.contains(r#"rk:alias "rs""#));
+        assert!(ttl.contains("rk:relatesTo <http://example.org/kg/concept/acme/tokio>"));
+        assert!(ttl.contains(r#"rk:weight "2.5"^^xsd:double"#));
+    }
+
+    #[test]
+    fn test_jsonld_document() {
+        let (nodes, edges) = sample();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("graph.jsonld");
+        export_jsonld(&nodes, &edges, "urn:kg:", &path).unwrap();
+
+        let doc: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(doc["@context"]["@vocab"], "urn:kg:vocab#");
+        let graph = doc["@graph"].as_array().unwrap();
+        assert_eq!(graph.len(), 3);
+        assert_eq!(graph[0]["@id"], "urn:kg:concept/acme/rust%20lang");
+        assert_eq!(graph[0]["entityType"], "technology");
+        assert_eq!(graph[0]["relatesTo"][0], "urn:kg:concept/acme/tokio");
+        assert!(graph[1].get("community").is_none());
+        assert_eq!(graph[2]["@type"], "Relation");
+        assert_eq!(graph[2]["weight"], 2.5);
+    }
+
+    #[test]
+    fn test_invalid_base_iri() {
+        assert!(normalize_base("not an iri").is_err());
+        assert!(normalize_base("example.org/kg").is_err());
+        assert_eq!(normalize_base("urn:kg:").unwrap(), "urn:kg:");
+        assert_eq!(
+            normalize_base("https://example.org/kg#").unwrap(),
+            "https://example.org/kg#"
+        );
+    }
+}
@@ -0,0 +1,814 @@
+//! Self-contained HTML report: graph, analytics, communities and a node index.
+//!
+//! Everything is inlined (styles, data and a small canvas renderer), so the
+//! file opens offline and can be handed to people without rknowledge. Node
+//! positions are computed here with a force-directed layout, which keeps the
+//! page script down to drawing and interaction.
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use super::escape_xml as escape_html;
+use crate::graph::analytics::{self, GraphStats};
+use crate::graph::builder::GraphBuilder;
+use crate::graph::community;
+use crate::graph::neo4j::{GraphEdge, GraphNode};
+
+/// Concepts drawn in the graph view; the index always lists every concept
+const MAX_DRAWN_NODES: usize = 400;
+
+/// Relations quoted per concept in the node index
+const MAX_SNIPPETS: usize = 6;
+
+/// Citations shown per relation before "+N more"
+const MAX_CITATIONS: usize = 3;
+
+/// Members listed per community before "+N more"
+const MAX_COMMUNITY_MEMBERS: usize = 30;
+
+const LAYOUT_ITERATIONS: usize = 150;
+
+/// Render the full report page
+///
+/// `tenant` is the tenant the export was limited to, if any; the title
+/// otherwise names the nodes' single tenant or says "all tenants".
+pub fn render(nodes: &[GraphNode], edges: &[GraphEdge], tenant: Option<&str>) -> Result<String> {
+    let graph = GraphBuilder::from_graph_data(nodes, edges).build();
+    let stats = analytics::compute_stats(&graph);
+    let communities =
+        community::community_summary(&graph, &community::label_propagation(&graph, 50));
+
+    let mut degree: HashMap<&str, usize> = HashMap::new();
+    for edge in edges {
+        *degree.entry(edge.source.as_str()).or_default() += 1;
+        *degree.entry(edge.target.as_str()).or_default() += 1;
+    }
+    let degree_of = |id: &str| degree.get(id).copied().unwrap_or(0);
+
+    // Analytics graphs are keyed by lowercased id; index by label too for lookups
+    let mut community_of: HashMap<String, usize> = HashMap::new();
+    for (id, members) in &communities {
+        for member in members {
+            community_of.insert(member.clone(), *id);
+        }
+    }
+    for node in nodes {
+        if let Some(&c) = community_of.get(&node.id.to_lowercase()) {
+            community_of.insert(node.label.to_lowercase(), c);
+        }
+    }
+
+    let tenant = match tenant {
+        Some(tenant) => tenant,
+        None => match nodes.first() {
+            Some(first) if nodes.iter().all(|n| n.tenant == first.tenant) => &first.tenant,
+            Some(_) => "all tenants",
+            None => "default",
+        },
+    };
+
+    let html = fill(
+        TEMPLATE,
+        &[
+            ("__TENANT__", escape_html(tenant)),
+            ("__OVERVIEW__", overview_cards(&stats)),
+            ("__GRAPH_NOTE__", graph_note(nodes.len())),
+            ("__PAGERANK__", pagerank_table(&stats)),
+            ("__DEGREE__", degree_table(&stats)),
+            ("__TYPES__", type_table(nodes)),
+            ("__COMMUNITIES__", community_list(&communities)),
+            (
+                "__INDEX__",
+                node_index(nodes, edges, &community_of, &degree_of),
+            ),
+            (
+                "__DATA__",
+                script_json(&graph_data(nodes, edges, &community_of, &degree_of))?,
+            ),
+        ],
+    );
+
+    Ok(html)
+}
+
+/// Replace `__NAME__` placeholders in one pass, so filled-in content is never
+/// searched for placeholders again
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("__") {
+        out.push_str(&rest[..open]);
+        let after = &rest[open..];
+        match values.iter().find(|(name, _)| after.starts_with(name)) {
+            Some((name, value)) => {
+                out.push_str(value);
+                rest = &after[name.len()..];
+            }
+            None => {
+                out.push('_');
+                rest = &after[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn overview_cards(stats: &GraphStats) -> String {
+    let cards = [
+        ("Concepts", stats.node_count.to_string()),
+        ("Relations", stats.edge_count.to_string()),
+        ("Components", stats.connected_components.to_string()),
+        ("Communities", stats.community_count.to_string()),
+        ("Density", format!("{:.4}", stats.density)),
+        ("Avg degree", format!("{:.1}", stats.avg_degree)),
+        ("Max degree", stats.max_degree.to_string()),
+    ];
+    cards
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                r#"<div class="card"><div class="val">{}</div><div class="name">{}</div></div>"#,
+                value, name
+            )
+        })
+        .collect()
+}
+
+fn graph_note(total: usize) -> String {
+    if total > MAX_DRAWN_NODES {
+        format!(
+            "Showing the {} best-connected of {} concepts. Drag to pan, scroll to zoom, click a concept for details.",
+            MAX_DRAWN_NODES, total
+        )
+    } else {
+        "Drag to pan, scroll to zoom, click a concept for details.".to_string()
+    }
+}
+
+fn pagerank_table(stats: &GraphStats) -> String {
+    ranked_table(
+        "PageRank",
+        stats
+            .top_pagerank
+            .iter()
+            .map(|(label, score)| (label.as_str(), format!("{:.4}", score))),
+    )
+}
+
+fn degree_table(stats: &GraphStats) -> String {
+    ranked_table(
+        "Degree",
+        stats
+            .top_degree
+            .iter()
+            .map(|(label, degree)| (label.as_str(), degree.to_string())),
+    )
+}
+
+fn ranked_table<'a>(metric: &str, rows: impl Iterator<Item = (&'a str, String)>) -> String {
+    let rows: String = rows
+        .enumerate()
+        .map(|(i, (label, value))| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td></tr>",
+                i + 1,
+                escape_html(label),
+                value
+            )
+        })
+        .collect();
+    format!(
+        "<table><tr><th>#</th><th>Concept</th><th class=\"num\">{}</th></tr>{}</table>",
+        metric, rows
+    )
+}
+
+fn type_table(nodes: &[GraphNode]) -> String {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for node in nodes {
+        *counts
+            .entry(node.entity_type.as_deref().unwrap_or("untyped"))
+            .or_default() += 1;
+    }
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_by_key(|(t, c)| (std::cmp::Reverse(*c), *t));
+
+    let rows: String = counts
+        .iter()
+        .map(|(t, c)| {
+            format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td></tr>",
+                escape_html(t),
+                c
+            )
+        })
+        .collect();
+    format!(
+        "<table><tr><th>Entity type</th><th class=\"num\">Concepts</th></tr>{}</table>",
+        rows
+    )
+}
+
+fn community_list(communities: &[(usize, Vec<String>)]) -> String {
+    if communities.is_empty() {
+        return "<p class=\"muted\">No communities detected.</p>".to_string();
+    }
+    communities
+        .iter()
+        .map(|(id, members)| {
+            let shown: Vec<String> = members
+                .iter()
+                .take(MAX_COMMUNITY_MEMBERS)
+                .map(|m| format!("<span class=\"chip\">{}</span>", escape_html(m)))
+                .collect();
+            let more = if members.len() > MAX_COMMUNITY_MEMBERS {
+                format!(
+                    "<span class=\"muted\"> +{} more</span>",
+                    members.len() - MAX_COMMUNITY_MEMBERS
+                )
+            } else {
+                String::new()
+            };
+            format!(
+                "<div class=\"community\"><h3>Community {} <span class=\"muted\">({} members)</span></h3>{}{}</div>",
+                id,
+                members.len(),
+                shown.join(""),
+                more
+            )
+        })
+        .collect()
+}
+
+/// Searchable table of every concept, quoting the relations it takes part in
+/// together with where each was read
+fn node_index(
+    nodes: &[GraphNode],
+    edges: &[GraphEdge],
+    community_of: &HashMap<String, usize>,
+    degree_of: &dyn Fn(&str) -> usize,
+) -> String {
+    let labels: HashMap<&str, &str> = nodes
+        .iter()
+        .map(|n| (n.id.as_str(), n.label.as_str()))
+        .collect();
+    let label_of = |id: &'_ str| -> String { labels.get(id).copied().unwrap_or(id).to_string() };
+
+    let mut snippets: HashMap<&str, Vec<(String, &[String])>> = HashMap::new();
+    let mut explicit: Vec<&GraphEdge> = edges
+        .iter()
+        .filter(|e| e.relation != "contextual proximity")
+        .collect();
+    explicit.sort_by(|a, b| {
+        b.weight
+            .partial_cmp(&a.weight)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for edge in explicit {
+        let snippet = format!(
+            "{} → {} → {}",
+            label_of(&edge.source),
+            edge.relation,
+            label_of(&edge.target)
+        );
+        for id in [edge.source.as_str(), edge.target.as_str()] {
+            let list = snippets.entry(id).or_default();
+            if list.len() < MAX_SNIPPETS {
+                list.push((snippet.clone(), edge.sources.as_slice()));
+            }
+        }
+    }
+
+    let mut sorted: Vec<&GraphNode> = nodes.iter().collect();
+    sorted.sort_by_key(|n| n.label.to_lowercase());
+
+    let rows: String = sorted
+        .iter()
+        .map(|n| {
+            let community = community_of
+                .get(&n.label.to_lowercase())
+                .map(|c| c.to_string())
+                .unwrap_or_default();
+            let quotes: String = snippets
+                .get(n.id.as_str())
+                .map(|list| {
+                    list.iter()
+                        .map(|(s, sources)| {
+                            format!("<div>{}{}</div>", escape_html(s), citations(sources))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let aliases = if n.aliases.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "<div class=\"muted\">aka {}</div>",
+                    escape_html(&n.aliases.join(", "))
+                )
+            };
+            format!(
+                "<tr data-search=\"{}\"><td><a href=\"#\" data-node=\"{}\">{}</a>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"snippets\">{}</td></tr>",
+                escape_html(&format!("{} {}", n.label, n.aliases.join(" ")).to_lowercase()),
+                escape_html(&n.id),
+                escape_html(&n.label),
+                aliases,
+                escape_html(n.entity_type.as_deref().unwrap_or("concept")),
+                community,
+                degree_of(&n.id),
+                quotes
+            )
+        })
+        .collect();
+
+    format!(
+        "<table id=\"index\"><tr><th>Concept</th><th>Type</th><th class=\"num\">Community</th><th class=\"num\">Degree</th><th>Relations</th></tr>{}</table>",
+        rows
+    )
+}
+
+/// The documents a relation was read from (`file.pdf p.12`, `notes.md#char=…`)
+fn citations(sources: &[String]) -> String {
+    if sources.is_empty() {
+        return String::new();
+    }
+    let shown: Vec<String> = sources
+        .iter()
+        .take(MAX_CITATIONS)
+        .map(|s| escape_html(s))
+        .collect();
+    let more = if sources.len() > MAX_CITATIONS {
+        format!(" +{} more", sources.len() - MAX_CITATIONS)
+    } else {
+        String::new()
+    };
+    format!(
+        " <span class=\"cite\">[{}{}]</span>",
+        shown.join("; "),
+        more
+    )
+}
+
+/// Drawn nodes (with precomputed positions) and the edges between them
+fn graph_data(
+    nodes: &[GraphNode],
+    edges: &[GraphEdge],
+    community_of: &HashMap<String, usize>,
+    degree_of: &dyn Fn(&str) -> usize,
+) -> serde_json::Value {
+    let mut drawn: Vec<&GraphNode> = nodes.iter().collect();
+    drawn.sort_by_key(|n| std::cmp::Reverse(degree_of(&n.id)));
+    drawn.truncate(MAX_DRAWN_NODES);
+
+    let index: HashMap<&str, usize> = drawn
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), i))
+        .collect();
+    let drawn_edges: Vec<(usize, usize, &GraphEdge)> = edges
+        .iter()
+        .filter_map(|e| {
+            Some((
+                *index.get(e.source.as_str())?,
+                *index.get(e.target.as_str())?,
+                e,
+            ))
+        })
+        .filter(|(s, t, _)| s != t)
+        .collect();
+
+    let links: Vec<(usize, usize)> = drawn_edges.iter().map(|(s, t, _)| (*s, *t)).collect();
+    let positions = layout(drawn.len(), &links, LAYOUT_ITERATIONS);
+
+    let nodes_json: Vec<serde_json::Value> = drawn
+        .iter()
+        .zip(&positions)
+        .map(|(n, (x, y))| {
+            serde_json::json!({
+                "id": n.id,
+                "label": n.label,
+                "type": n.entity_type.as_deref().unwrap_or("concept"),
+                "community": community_of.get(&n.label.to_lowercase()),
+                "degree": degree_of(&n.id),
+                "x": (x * 10.0).round() / 10.0,
+                "y": (y * 10.0).round() / 10.0,
+            })
+        })
+        .collect();
+    let edges_json: Vec<serde_json::Value> = drawn_edges
+        .iter()
+        .map(|(s, t, e)| {
+            serde_json::json!({
+                "s": s,
+                "t": t,
+                "relation": e.relation,
+                "proximity": e.relation == "contextual proximity",
+            })
+        })
+        .collect();
+
+    serde_json::json!({ "nodes": nodes_json, "edges": edges_json })
+}
+
+/// JSON that is safe to embed inside a `<script>` element
+fn script_json(value: &serde_json::Value) -> Result<String> {
+    Ok(serde_json::to_string(value)?.replace("</", "<\\/"))
+}
+
+/// Fruchterman–Reingold layout, deterministic for a given graph.
+///
+/// Returns positions roughly within a 1000×1000 box centred on the origin.
+fn layout(n: usize, links: &[(usize, usize)], iterations: usize) -> Vec<(f64, f64)> {
+    const SIZE: f64 = 1000.0;
+    if n == 0 {
+        return Vec::new();
+    }
+
+    // Start on a sunflower spiral so the result doesn't depend on randomness
+    let golden = std::f64::consts::PI * (3.0 - 5f64.sqrt());
+    let mut pos: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let r = SIZE / 2.0 * ((i as f64 + 0.5) / n as f64).sqrt();
+            let a = i as f64 * golden;
+            (r * a.cos(), r * a.sin())
+        })
+        .collect();
+
+    let k = (SIZE * SIZE / n as f64).sqrt() * 0.5;
+    let mut temperature = SIZE / 10.0;
+    let cooling = temperature / (iterations as f64 + 1.0);
+
+    for _ in 0..iterations {
+        let mut disp = vec![(0.0f64, 0.0f64); n];
+
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let dx = pos[i].0 - pos[j].0;
+                let dy = pos[i].1 - pos[j].1;
+                let dist = (dx * dx + dy * dy).sqrt().max(0.01);
+                let force = k * k / dist;
+                disp[i].0 += dx / dist * force;
+                disp[i].1 += dy / dist * force;
+                disp[j].0 -= dx / dist * force;
+                disp[j].1 -= dy / dist * force;
+            }
+        }
+
+        for &(s, t) in links {
+            let dx = pos[s].0 - pos[t].0;
+            let dy = pos[s].1 - pos[t].1;
+            let dist = (dx * dx + dy * dy).sqrt().max(0.01);
+            let force = dist * dist / k;
+            disp[s].0 -= dx / dist * force;
+            disp[s].1 -= dy / dist * force;
+            disp[t].0 += dx / dist * force;
+            disp[t].1 += dy / dist * force;
+        }
+
+        for (p, d) in pos.iter_mut().zip(&disp) {
+            // Mild gravity keeps disconnected components on screen
+            let dx = d.0 - p.0 * 0.05 * k / SIZE * 10.0;
+            let dy = d.1 - p.1 * 0.05 * k / SIZE * 10.0;
+            let len = (dx * dx + dy * dy).sqrt().max(0.01);
+            let step = len.min(temperature);
+            p.0 += dx / len * step;
+            p.1 += dy / len * step;
+        }
+
+        temperature = (temperature - cooling).max(1.0);
+    }
+
+    pos
+}
+
+const TEMPLATE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>RKnowledge Report - __TENANT__</title>
+<style>
+* { box-sizing: border-box; }
+body { margin: 0; font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; background: #050510; color: #c8c8e0; }
+header { padding: 20px 32px; background: rgba(18,18,42,0.9); border-bottom: 1px solid rgba(255,255,255,0.08); }
+header h1 { margin: 0; font-size: 1.4em; background: linear-gradient(135deg, #ff6b8a, #a855f7); -webkit-background-clip: text; -webkit-text-fill-color: transparent; }
+header .muted { margin-top: 4px; }
+main { padding: 24px 32px; max-width: 1400px; margin: 0 auto; }
+section { margin-bottom: 36px; }
+h2 { font-size: 0.8em; text-transform: uppercase; letter-spacing: 1.5px; color: #a855f7; border-bottom: 1px solid rgba(255,255,255,0.06); padding-bottom: 8px; }
+h3 { font-size: 0.95em; color: #e0e0f8; margin: 0 0 8px; }
+.muted { color: #666688; font-size: 0.85em; }
+.cards { display: flex; flex-wrap: wrap; gap: 12px; }
+.card { background: rgba(255,255,255,0.03); border: 1px solid rgba(255,255,255,0.06); border-radius: 12px; padding: 14px 20px; min-width: 130px; }
+.card .val { font-size: 1.6em; font-weight: 700; color: #fff; }
+.card .name { font-size: 0.75em; color: #666688; text-transform: uppercase; letter-spacing: 1px; }
+.graph-wrap { position: relative; height: 620px; border: 1px solid rgba(255,255,255,0.06); border-radius: 12px; overflow: hidden; background: #07071a; }
+canvas { width: 100%; height: 100%; display: block; cursor: grab; }
+#detail { position: absolute; top: 12px; right: 12px; width: 320px; max-height: 596px; overflow-y: auto; background: rgba(8,8,20,0.95); border: 1px solid rgba(255,255,255,0.08); border-radius: 12px; padding: 16px; display: none; font-size: 0.85em; }
+#detail h3 { font-size: 1.2em; }
+#detail .rel { padding: 4px 0; border-bottom: 1px solid rgba(255,255,255,0.04); }
+.tables { display: grid; grid-template-columns: repeat(auto-fit, minmax(320px, 1fr)); gap: 24px; }
+table { width: 100%; border-collapse: collapse; font-size: 0.85em; }
+th { text-align: left; color: #666688; font-weight: 600; padding: 6px 8px; border-bottom: 1px solid rgba(255,255,255,0.08); }
+td { padding: 6px 8px; border-bottom: 1px solid rgba(255,255,255,0.03); vertical-align: top; }
+.num { text-align: right; }
+.community { margin-bottom: 18px; }
+.chip { display: inline-block; background: rgba(168,85,247,0.12); color: #d0c0f8; border-radius: 100px; padding: 2px 10px; margin: 2px; font-size: 0.8em; }
+#search { width: 100%; max-width: 420px; background: rgba(10,10,30,0.6); border: 1px solid rgba(255,255,255,0.1); border-radius: 10px; color: #f0f0ff; padding: 9px 14px; margin-bottom: 12px; outline: none; }
+#search:focus { border-color: #a855f7; }
+.snippets { color: #8888aa; font-size: 0.9em; }
+.cite { color: #666688; font-size: 0.85em; }
+a { color: #c4a5ff; text-decoration: none; }
+a:hover { text-decoration: underline; }
+</style>
+</head>
+<body>
+<header>
+<h1>RKnowledge Report</h1>
+<div class="muted">Tenant: __TENANT__</div>
+</header>
+<main>
+<section>
+<h2>Overview</h2>
+<div class="cards">__OVERVIEW__</div>
+</section>
+
+<section>
+<h2>Graph</h2>
+<p class="muted">__GRAPH_NOTE__</p>
+<div class="graph-wrap"><canvas id="graph"></canvas><div id="detail"></div></div>
+</section>
+
+<section class="tables">
+<div><h2>Top concepts by PageRank</h2>__PAGERANK__</div>
+<div><h2>Most connected concepts</h2>__DEGREE__</div>
+<div><h2>Entity types</h2>__TYPES__</div>
+</section>
+
+<section>
+<h2>Communities</h2>
+__COMMUNITIES__
+</section>
+
+<section>
+<h2>Concept index</h2>
+<input id="search" type="text" placeholder="Search concepts...">
+__INDEX__
+</section>
+</main>
+
+<script>
+const DATA = __DATA__;
+(function () {
+    const colors = ['#6366f1', '#ec4899', '#14b8a6', '#f59e0b', '#8b5cf6', '#06b6d4', '#ef4444', '#22c55e', '#f97316'];
+    const typeColor = {};
+    DATA.nodes.forEach(n => {
+        if (!(n.type in typeColor)) typeColor[n.type] = colors[Object.keys(typeColor).length % colors.length];
+    });
+
+    const canvas = document.getElementById('graph');
+    const ctx = canvas.getContext('2d');
+    const detail = document.getElementById('detail');
+    let scale = 1, ox = 0, oy = 0, selected = -1, highlight = new Set();
+
+    function fit() {
+        const rect = canvas.getBoundingClientRect();
+        canvas.width = rect.width * devicePixelRatio;
+        canvas.height = rect.height * devicePixelRatio;
+        if (!DATA.nodes.length) return;
+        const xs = DATA.nodes.map(n => n.x), ys = DATA.nodes.map(n => n.y);
+        const w = Math.max(...xs) - Math.min(...xs) + 100, h = Math.max(...ys) - Math.min(...ys) + 100;
+        scale = Math.min(rect.width / w, rect.height / h);
+        ox = rect.width / 2 - scale * (Math.min(...xs) + Math.max(...xs)) / 2;
+        oy = rect.height / 2 - scale * (Math.min(...ys) + Math.max(...ys)) / 2;
+    }
+
+    const radius = n => 3 + Math.sqrt(n.degree) * 1.5;
+    const screen = n => [n.x * scale + ox, n.y * scale + oy];
+
+    function draw() {
+        ctx.setTransform(devicePixelRatio, 0, 0, devicePixelRatio, 0, 0);
+        ctx.clearRect(0, 0, canvas.width, canvas.height);
+        DATA.edges.forEach(e => {
+            const [x1, y1] = screen(DATA.nodes[e.s]), [x2, y2] = screen(DATA.nodes[e.t]);
+            const active = e.s === selected || e.t === selected;
+            ctx.strokeStyle = active ? '#a855f7' : e.proximity ? 'rgba(60,60,100,0.2)' : 'rgba(168,85,247,0.35)';
+            ctx.lineWidth = active ? 2 : e.proximity ? 0.5 : 1;
+            ctx.beginPath(); ctx.moveTo(x1, y1); ctx.lineTo(x2, y2); ctx.stroke();
+        });
+        DATA.nodes.forEach((n, i) => {
+            const [x, y] = screen(n);
+            const dim = highlight.size && !highlight.has(i);
+            ctx.globalAlpha = dim ? 0.15 : 1;
+            ctx.fillStyle = typeColor[n.type];
+            ctx.beginPath(); ctx.arc(x, y, radius(n) * (i === selected ? 1.6 : 1), 0, 2 * Math.PI); ctx.fill();
+            if (i === selected || highlight.has(i) || radius(n) * scale > 4 || scale > 1.5) {
+                ctx.fillStyle = '#c8c8e0';
+                ctx.font = '11px sans-serif';
+                ctx.fillText(n.label, x + radius(n) + 3, y + 4);
+            }
+        });
+        ctx.globalAlpha = 1;
+    }
+
+    function select(i) {
+        selected = i;
+        if (i < 0) { detail.style.display = 'none'; draw(); return; }
+        const n = DATA.nodes[i];
+        const rels = DATA.edges.filter(e => e.s === i || e.t === i);
+        detail.innerHTML = '';
+        const title = document.createElement('h3');
+        title.textContent = n.label;
+        const meta = document.createElement('div');
+        meta.className = 'muted';
+        meta.textContent = n.type + (n.community != null ? ' · community ' + n.community : '') + ' · degree ' + n.degree;
+        detail.append(title, meta);
+        rels.forEach(e => {
+            const row = document.createElement('div');
+            row.className = 'rel';
+            row.textContent = DATA.nodes[e.s].label + ' → ' + e.relation + ' → ' + DATA.nodes[e.t].label;
+            detail.appendChild(row);
+        });
+        detail.style.display = 'block';
+        draw();
+    }
+
+    function nodeAt(mx, my) {
+        let best = -1, bestDist = Infinity;
+        DATA.nodes.forEach((n, i) => {
+            const [x, y] = screen(n);
+            const d = Math.hypot(x - mx, y - my);
+            if (d < Math.max(radius(n), 6) && d < bestDist) { best = i; bestDist = d; }
+        });
+        return best;
+    }
+
+    let drag = null, moved = false;
+    canvas.addEventListener('mousedown', e => { drag = [e.offsetX, e.offsetY]; moved = false; });
+    canvas.addEventListener('mousemove', e => {
+        if (!drag) return;
+        ox += e.offsetX - drag[0]; oy += e.offsetY - drag[1];
+        moved = moved || Math.abs(e.offsetX - drag[0]) + Math.abs(e.offsetY - drag[1]) > 2;
+        drag = [e.offsetX, e.offsetY];
+        draw();
+    });
+    canvas.addEventListener('mouseup', e => { if (!moved) select(nodeAt(e.offsetX, e.offsetY)); drag = null; });
+    canvas.addEventListener('mouseleave', () => { drag = null; });
+    canvas.addEventListener('wheel', e => {
+        e.preventDefault();
+        const factor = e.deltaY < 0 ? 1.15 : 1 / 1.15;
+        ox = e.offsetX - (e.offsetX - ox) * factor;
+        oy = e.offsetY - (e.offsetY - oy) * factor;
+        scale *= factor;
+        draw();
+    }, { passive: false });
+    window.addEventListener('resize', () => { fit(); draw(); });
+
+    // Index search filters the table and highlights matches in the graph
+    const search = document.getElementById('search');
+    const rows = Array.from(document.querySelectorAll('#index tr[data-search]'));
+    search.addEventListener('input', () => {
+        const q = search.value.trim().toLowerCase();
+        rows.forEach(r => { r.style.display = !q || r.dataset.search.includes(q) ? '' : 'none'; });
+        highlight = new Set();
+        if (q) DATA.nodes.forEach((n, i) => { if (n.label.toLowerCase().includes(q)) highlight.add(i); });
+        draw();
+    });
+
+    const byId = new Map(DATA.nodes.map((n, i) => [n.id, i]));
+    document.querySelectorAll('#index a[data-node]').forEach(a => a.addEventListener('click', e => {
+        e.preventDefault();
+        const i = byId.get(a.dataset.node);
+        if (i === undefined) return;
+        canvas.scrollIntoView({ behavior: 'smooth', block: 'center' });
+        const rect = canvas.getBoundingClientRect();
+        ox = rect.width / 2 - DATA.nodes[i].x * scale;
+        oy = rect.height / 2 - DATA.nodes[i].y * scale;
+        select(i);
+    }));
+
+    fit();
+    draw();
+})();
+</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, entity_type: &str) -> GraphNode {
+        GraphNode {
+            id: id.into(),
+            label: id.into(),
+            community: None,
+            degree: None,
+            entity_type: Some(entity_type.into()),
+            tenant: "default".into(),
+            aliases: Vec::new(),
+        }
+    }
+
+    fn edge(source: &str, target: &str, relation: &str) -> GraphEdge {
+        GraphEdge {
+            source: source.into(),
+            target: target.into(),
+            relation: relation.into(),
+            weight: 1.0,
+            sources: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_report_is_self_contained() {
+        let nodes = vec![node("rust", "language"), node("tokio", "library")];
+        let edges = vec![edge("tokio", "rust", "written in")];
+        let html = render(&nodes, &edges, None).unwrap();
+
+        assert!(!html.contains("<script src"));
+        assert!(!html.contains("https://"));
+        assert!(!html.contains("__DATA__"));
+        assert!(html.contains("Top concepts by PageRank"));
+        assert!(html.contains("Community "));
+        assert!(html.contains("tokio → written in → rust"));
+    }
+
+    #[test]
+    fn test_report_cites_relation_sources() {
+        let nodes = vec![node("rust", "language"), node("tokio", "library")];
+        let mut cited = edge("tokio", "rust", "written in");
+        cited.sources = vec![
+            "guide.pdf p.12".into(),
+            "notes.md#char=10,80".into(),
+            "a.md".into(),
+            "b.md".into(),
+        ];
+        let html = render(&nodes, &[cited], None).unwrap();
+
+        assert!(html.contains(
+            "tokio → written in → rust <span class=\"cite\">[guide.pdf p.12; notes.md#char=10,80; a.md +1 more]</span>"
+        ));
+    }
+
+    #[test]
+    fn test_report_escapes_labels() {
+        let nodes = vec![node("<b>bold</b>", "concept"), node("a&b", "concept")];
+        let edges = vec![edge("<b>bold</b>", "a&b", "</script><script>alert(1)")];
+        let html = render(&nodes, &edges, None).unwrap();
+
+        assert!(!html.contains("<b>bold</b>"));
+        assert!(html.contains("&lt;b&gt;bold&lt;/b&gt;"));
+        assert!(!html.contains("</script><script>alert"));
+    }
+
+    #[test]
+    fn test_report_does_not_expand_placeholders_in_labels() {
+        let nodes = vec![node("__INDEX__", "__DATA__"), node("__TENANT__", "concept")];
+        let edges = vec![edge("__INDEX__", "__TENANT__", "__DATA__")];
+        let html = render(&nodes, &edges, None).unwrap();
+
+        assert_eq!(html.matches("\"nodes\":[").count(), 1);
+        assert_eq!(html.matches("<title>").count(), 1);
+        assert!(html.contains("__INDEX__ → __DATA__ → __TENANT__"));
+    }
+
+    #[test]
+    fn test_report_titles_its_tenants() {
+        let title = |html: &str| {
+            let start = html.find("<title>").unwrap();
+            html[start..html.find("</title>").unwrap()].to_string()
+        };
+        let mut nodes = vec![node("rust", "language"), node("tokio", "library")];
+        nodes[0].tenant = "acme".into();
+        nodes[1].tenant = "acme".into();
+        assert!(title(&render(&nodes, &[], None).unwrap()).ends_with("- acme"));
+
+        nodes[1].tenant = "globex".into();
+        assert!(title(&render(&nodes, &[], None).unwrap()).ends_with("- all tenants"));
+        assert!(title(&render(&nodes, &[], Some("globex")).unwrap()).ends_with("- globex"));
+    }
+
+    #[test]
+    fn test_empty_graph_renders() {
+        let html = render(&[], &[], None).unwrap();
+        assert!(html.contains("No communities detected"));
+        assert!(html.contains("\"nodes\":[]"));
+    }
+
+    #[test]
+    fn test_layout_is_deterministic_and_finite() {
+        let links = vec![(0, 1), (1, 2), (2, 0), (3, 4)];
+        let a = layout(5, &links, 50);
+        let b = layout(5, &links, 50);
+        assert_eq!(a, b);
+        assert!(a.iter().all(|(x, y)| x.is_finite() && y.is_finite()));
+        assert!(layout(0, &[], 10).is_empty());
+
+        // Linked nodes end up closer than unlinked ones
+        let dist =
+            |i: usize, j: usize| ((a[i].0 - a[j].0).powi(2) + (a[i].1 - a[j].1).powi(2)).sqrt();
+        assert!(dist(3, 4) < dist(0, 3));
+    }
+}
@@ -1,5 +1,5 @@
-use petgraph::algo::{connected_components, dijkstra};
-use petgraph::graph::{DiGraph, NodeIndex, UnGraph};
+use petgraph::algo::connected_components;
+use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::visit::EdgeRef;
 use std::collections::HashMap;
 
@@ -35,20 +35,19 @@ pub fn pagerank(
         graph.node_indices().map(|ni| (ni, 1.0 / n_f)).collect();
 
     for _ in 0..iterations {
-        let mut new_scores: HashMap<NodeIndex, f64> = graph
+        // Dangling nodes distribute their score evenly; sum it once per iteration
+        let dangling: f64 = graph
             .node_indices()
-            .map(|ni| (ni, (1.0 - damping) / n_f))
-            .collect();
+            .filter(|&ni| graph.edges(ni).next().is_none())
+            .map(|ni| scores[&ni])
+            .sum();
+        let base = (1.0 - damping) / n_f + dangling * damping / n_f;
+        let mut new_scores: HashMap<NodeIndex, f64> =
+            graph.node_indices().map(|ni| (ni, base)).collect();
 
         for ni in graph.node_indices() {
             let out_degree = graph.edges(ni).count();
-            if out_degree == 0 {
-                // Dangling node: distribute evenly
-                let share = scores[&ni] * damping / n_f;
-                for nj in graph.node_indices() {
-                    *new_scores.get_mut(&nj).unwrap() += share;
-                }
-            } else {
+            if out_degree > 0 {
                 let share = scores[&ni] * damping / out_degree as f64;
                 for edge in graph.edges(ni) {
                     *new_scores.get_mut(&edge.target()).unwrap() += share;
@@ -78,116 +77,6 @@ pub fn node_degrees(graph: &DiGraph<String, f64>) -> HashMap<NodeIndex, usize> {
         .collect()
 }
 
-/// Find the shortest path between two nodes by label.
-/// Uses an undirected view of the graph since edges are normalized by alphabetical order.
-/// Returns (total_weight, list_of_node_labels) or None if no path exists.
-pub fn shortest_path(
-    graph: &DiGraph<String, f64>,
-    from_label: &str,
-    to_label: &str,
-) -> Option<(f64, Vec<String>)> {
-    let from_label_lower = from_label.to_lowercase();
-    let to_label_lower = to_label.to_lowercase();
-
-    if from_label_lower == to_label_lower {
-        let idx = graph
-            .node_indices()
-            .find(|&ni| graph[ni].to_lowercase() == from_label_lower)?;
-        return Some((0.0, vec![graph[idx].clone()]));
-    }
-
-    // Build undirected graph for path finding
-    let mut undirected: UnGraph<String, f64> = UnGraph::new_undirected();
-    let mut dir_to_undir: HashMap<NodeIndex, petgraph::graph::NodeIndex> = HashMap::new();
-    let mut undir_to_dir: HashMap<petgraph::graph::NodeIndex, NodeIndex> = HashMap::new();
-
-    for ni in graph.node_indices() {
-        let new_idx = undirected.add_node(graph[ni].clone());
-        dir_to_undir.insert(ni, new_idx);
-        undir_to_dir.insert(new_idx, ni);
-    }
-
-    for edge in graph.edge_references() {
-        let src = dir_to_undir[&edge.source()];
-        let tgt = dir_to_undir[&edge.target()];
-        if undirected.find_edge(src, tgt).is_none() {
-            undirected.add_edge(src, tgt, *edge.weight());
-        }
-    }
-
-    let from_dir = graph
-        .node_indices()
-        .find(|&ni| graph[ni].to_lowercase() == from_label_lower)?;
-    let to_dir = graph
-        .node_indices()
-        .find(|&ni| graph[ni].to_lowercase() == to_label_lower)?;
-    let from_idx = dir_to_undir[&from_dir];
-    let to_idx = dir_to_undir[&to_dir];
-
-    // Dijkstra with inverted weights (higher weight = stronger connection = shorter path)
-    let costs = dijkstra(&undirected, from_idx, Some(to_idx), |e| {
-        1.0 / e.weight().max(0.001)
-    });
-    let costs_std: HashMap<petgraph::graph::NodeIndex, f64> = costs.into_iter().collect();
-
-    let cost = *costs_std.get(&to_idx)?;
-
-    // BFS path reconstruction on undirected graph
-    let path = reconstruct_path_undirected(&undirected, from_idx, to_idx, &costs_std)?;
-    let labels: Vec<String> = path.iter().map(|&ni| undirected[ni].clone()).collect();
-
-    Some((cost, labels))
-}
-
-/// Reconstruct shortest path on an undirected graph from dijkstra costs.
-fn reconstruct_path_undirected(
-    graph: &UnGraph<String, f64>,
-    from: petgraph::graph::NodeIndex,
-    to: petgraph::graph::NodeIndex,
-    costs: &HashMap<petgraph::graph::NodeIndex, f64>,
-) -> Option<Vec<petgraph::graph::NodeIndex>> {
-    if from == to {
-        return Some(vec![from]);
-    }
-
-    let mut path = vec![to];
-    let mut current = to;
-
-    for _ in 0..graph.node_count() {
-        if current == from {
-            path.reverse();
-            return Some(path);
-        }
-
-        let current_cost = *costs.get(&current)?;
-        let mut best_prev: Option<(petgraph::graph::NodeIndex, f64)> = None;
-
-        for edge in graph.edges(current) {
-            let neighbor = edge.target();
-            if neighbor == current {
-                continue;
-            }
-            if let Some(&neighbor_cost) = costs.get(&neighbor) {
-                let edge_cost = 1.0 / edge.weight().max(0.001);
-                let diff = (neighbor_cost + edge_cost - current_cost).abs();
-                if diff < 1e-6 && (best_prev.is_none() || neighbor_cost < best_prev.unwrap().1) {
-                    best_prev = Some((neighbor, neighbor_cost));
-                }
-            }
-        }
-
-        match best_prev {
-            Some((prev, _)) => {
-                path.push(prev);
-                current = prev;
-            }
-            None => return None,
-        }
-    }
-
-    None
-}
-
 /// Compute full graph statistics.
 pub fn compute_stats(graph: &DiGraph<String, f64>) -> GraphStats {
     let node_count = graph.node_count();
@@ -214,7 +103,7 @@ pub fn compute_stats(graph: &DiGraph<String, f64>) -> GraphStats {
         .iter()
         .map(|(&ni, &d)| (graph[ni].clone(), d))
         .collect();
-    degree_vec.sort_by(|a, b| b.1.cmp(&a.1));
+    degree_vec.sort_by_key(|d| std::cmp::Reverse(d.1));
     degree_vec.truncate(10);
 
     // PageRank
@@ -301,42 +190,6 @@ mod tests {
         assert_eq!(deg[&a_idx], 2); // a -> b, a -> c (out edges only for 'a')
     }
 
-    #[test]
-    fn test_shortest_path_exists() {
-        let g = build_simple_graph();
-        let result = shortest_path(&g, "a", "c");
-        assert!(result.is_some());
-        let (cost, path) = result.unwrap();
-        assert!(cost > 0.0);
-        assert_eq!(path.first().unwrap(), "a");
-        assert_eq!(path.last().unwrap(), "c");
-    }
-
-    #[test]
-    fn test_shortest_path_no_path() {
-        let mut g = DiGraph::new();
-        g.add_node("isolated_a".into());
-        g.add_node("isolated_b".into());
-        let result = shortest_path(&g, "isolated_a", "isolated_b");
-        assert!(result.is_none());
-    }
-
-    #[test]
-    fn test_shortest_path_same_node() {
-        let g = build_simple_graph();
-        let result = shortest_path(&g, "a", "a");
-        assert!(result.is_some());
-        let (_, path) = result.unwrap();
-        assert_eq!(path.len(), 1);
-    }
-
-    #[test]
-    fn test_shortest_path_case_insensitive() {
-        let g = build_simple_graph();
-        let result = shortest_path(&g, "A", "C");
-        assert!(result.is_some());
-    }
-
     #[test]
     fn test_compute_stats() {
         let g = build_simple_graph();
@@ -1,6 +1,6 @@
 use petgraph::graph::{DiGraph, NodeIndex};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::llm::Relation;
 
@@ -16,6 +16,9 @@ pub struct GraphNode {
     /// Tenant namespace for isolation
     #[serde(default = "default_tenant")]
     pub tenant: String,
+    /// Other names the concept was found under
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
 }
 
 fn default_tenant() -> String {
@@ -35,15 +38,17 @@ pub struct GraphEdge {
 /// Builder for constructing knowledge graphs
 pub struct GraphBuilder {
     /// Map from node label to node index
-    node_indices: HashMap<String, NodeIndex>,
+    node_indices: BTreeMap<String, NodeIndex>,
     /// The underlying graph
     graph: DiGraph<String, EdgeData>,
     /// Track which nodes appear in which chunks
-    node_chunks: HashMap<String, HashSet<String>>,
+    node_chunks: BTreeMap<String, BTreeSet<String>>,
     /// Edge data aggregated by (source, target) pair
-    edges: HashMap<(String, String), EdgeData>,
+    edges: BTreeMap<(String, String), EdgeData>,
     /// Entity types per node label (most recently seen type wins)
-    node_types: HashMap<String, String>,
+    node_types: BTreeMap<String, String>,
+    /// Other names per node label
+    node_aliases: BTreeMap<String, BTreeSet<String>>,
     /// Tenant namespace for this graph
     tenant: String,
 }
@@ -52,17 +57,21 @@ pub struct GraphBuilder {
 struct EdgeData {
     relations: Vec<String>,
     weight: f64,
-    chunk_ids: HashSet<String>,
+    chunk_ids: BTreeSet<String>,
+    /// True when the first explicit relation ran from the alphabetically later
+    /// node to the earlier one, so exports keep the extracted direction.
+    reversed: bool,
 }
 
 impl GraphBuilder {
     pub fn new() -> Self {
         Self {
-            node_indices: HashMap::new(),
+            node_indices: BTreeMap::new(),
             graph: DiGraph::new(),
-            node_chunks: HashMap::new(),
-            edges: HashMap::new(),
-            node_types: HashMap::new(),
+            node_chunks: BTreeMap::new(),
+            edges: BTreeMap::new(),
+            node_types: BTreeMap::new(),
+            node_aliases: BTreeMap::new(),
             tenant: "default".to_string(),
         }
     }
@@ -84,9 +93,43 @@ impl GraphBuilder {
         &self.tenant
     }
 
-    /// Add a node to the graph if it doesn't exist (public accessor)
-    pub fn ensure_node_public(&mut self, label: &str) -> NodeIndex {
-        self.ensure_node(label)
+    /// Rebuild a builder from fetched nodes and edges, e.g. to run analytics on Neo4j data
+    pub fn from_graph_data(
+        nodes: &[super::neo4j::GraphNode],
+        edges: &[super::neo4j::GraphEdge],
+    ) -> Self {
+        let mut builder = Self::new();
+        for edge in edges {
+            builder.add_relations(
+                vec![Relation {
+                    node_1: edge.source.clone(),
+                    node_1_type: None,
+                    node_2: edge.target.clone(),
+                    node_2_type: None,
+                    edge: edge.relation.clone(),
+                }],
+                "neo4j",
+            );
+        }
+        // Also add isolated nodes
+        for node in nodes {
+            builder.ensure_node(&node.label);
+            builder.add_aliases(&node.label, &node.aliases);
+        }
+        builder
+    }
+
+    /// Record other names of a concept; they are kept only if the concept
+    /// ends up in the graph
+    pub fn add_aliases(&mut self, label: &str, aliases: impl IntoIterator<Item = impl AsRef<str>>) {
+        let label = label.to_lowercase().trim().to_string();
+        let known = self.node_aliases.entry(label.clone()).or_default();
+        for alias in aliases {
+            let alias = alias.as_ref().trim();
+            if !alias.is_empty() && !alias.eq_ignore_ascii_case(&label) {
+                known.insert(alias.to_string());
+            }
+        }
     }
 
     /// Add a node to the graph if it doesn't exist
@@ -150,9 +193,19 @@ impl GraphBuilder {
             let edge_data = self.edges.entry(key).or_insert_with(|| EdgeData {
                 relations: Vec::new(),
                 weight: 0.0,
-                chunk_ids: HashSet::new(),
+                chunk_ids: BTreeSet::new(),
+                reversed: false,
             });
 
+            // The first explicit relation decides the edge's direction
+            if edge_data
+                .relations
+                .iter()
+                .all(|r| r == "contextual proximity")
+            {
+                edge_data.reversed = node_1 > node_2;
+            }
+
             edge_data.relations.push(relation.edge);
             edge_data.weight += 4.0; // Weight for explicit relation
             edge_data.chunk_ids.insert(chunk_id.to_string());
@@ -163,7 +216,7 @@ impl GraphBuilder {
     /// Nodes that appear in the same chunk are related by contextual proximity
     pub fn calculate_contextual_proximity(&mut self) {
         // Group nodes by chunk
-        let mut chunk_nodes: HashMap<String, Vec<String>> = HashMap::new();
+        let mut chunk_nodes: BTreeMap<String, Vec<String>> = BTreeMap::new();
 
         for (node, chunks) in &self.node_chunks {
             for chunk_id in chunks {
@@ -190,7 +243,8 @@ impl GraphBuilder {
                     let edge_data = self.edges.entry(key).or_insert_with(|| EdgeData {
                         relations: Vec::new(),
                         weight: 0.0,
-                        chunk_ids: HashSet::new(),
+                        chunk_ids: BTreeSet::new(),
+                        reversed: false,
                     });
 
                     // Only add contextual proximity if no explicit relation exists
@@ -215,7 +269,7 @@ impl GraphBuilder {
     /// Build and return the final graph
     pub fn build(&self) -> DiGraph<String, f64> {
         let mut graph = DiGraph::new();
-        let mut indices: HashMap<String, NodeIndex> = HashMap::new();
+        let mut indices: BTreeMap<String, NodeIndex> = BTreeMap::new();
 
         // Add all nodes
         for label in self.node_indices.keys() {
@@ -223,8 +277,9 @@ impl GraphBuilder {
             indices.insert(label.clone(), idx);
         }
 
-        // Add all edges
-        for ((source, target), edge_data) in &self.edges {
+        // Add all edges, in the direction they were extracted
+        for ((a, b), edge_data) in &self.edges {
+            let (source, target) = if edge_data.reversed { (b, a) } else { (a, b) };
             if let (Some(&src_idx), Some(&tgt_idx)) = (indices.get(source), indices.get(target)) {
                 graph.add_edge(src_idx, tgt_idx, edge_data.weight);
             }
@@ -254,6 +309,11 @@ impl GraphBuilder {
                     .and_then(|ni| communities.get(&ni).copied());
 
                 let entity_type = self.node_types.get(label).cloned();
+                let aliases = self
+                    .node_aliases
+                    .get(label)
+                    .map(|a| a.iter().cloned().collect())
+                    .unwrap_or_default();
 
                 GraphNode {
                     id: label.clone(),
@@ -262,6 +322,7 @@ impl GraphBuilder {
                     community,
                     entity_type,
                     tenant: self.tenant.clone(),
+                    aliases,
                 }
             })
             .collect()
@@ -271,7 +332,8 @@ impl GraphBuilder {
     pub fn get_edges(&self) -> Vec<GraphEdge> {
         self.edges
             .iter()
-            .map(|((source, target), data)| {
+            .map(|((a, b), data)| {
+                let (source, target) = if data.reversed { (b, a) } else { (a, b) };
                 let relation = data
                     .relations
                     .iter()
@@ -397,6 +459,24 @@ mod tests {
         assert!((edges[0].weight - 8.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_edge_keeps_extracted_direction() {
+        let mut builder = GraphBuilder::new();
+        builder.add_relations(vec![rel("zookeeper", "kafka", "coordinates")], "c1");
+        builder.add_relations(vec![rel("kafka", "zookeeper", "depends on")], "c2");
+
+        let edges = builder.get_edges();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].source, "zookeeper");
+        assert_eq!(edges[0].target, "kafka");
+
+        let graph = builder.build();
+        let edge = graph.edge_indices().next().unwrap();
+        let (src, tgt) = graph.edge_endpoints(edge).unwrap();
+        assert_eq!(graph[src], "zookeeper");
+        assert_eq!(graph[tgt], "kafka");
+    }
+
     #[test]
     fn test_contextual_proximity_creates_edges() {
         let mut builder = GraphBuilder::new();
@@ -456,6 +536,20 @@ mod tests {
         assert_eq!(hub.degree, 3); // connected to 3 edges
     }
 
+    #[test]
+    fn test_aliases_attach_to_known_nodes() {
+        let mut builder = GraphBuilder::new();
+        builder.add_relations(vec![rel("payment service", "ledger", "calls")], "c1");
+        builder.add_aliases("Payment Service", ["PaySvc", "payment service", " "]);
+        builder.add_aliases("unused", ["nothing"]);
+
+        let nodes = builder.get_nodes();
+        assert_eq!(nodes.len(), 2);
+        let service = nodes.iter().find(|n| n.id == "payment service").unwrap();
+        assert_eq!(service.aliases, ["PaySvc"]);
+        assert!(nodes.iter().all(|n| n.id != "unused"));
+    }
+
     #[test]
     fn test_get_edges_prefers_explicit_over_proximity() {
         let mut builder = GraphBuilder::new();
@@ -0,0 +1,948 @@
+//! Declarative mappings from structured files to relations, without an LLM.
+//!
+//! A mapping file is TOML with one `[[source]]` table per input file:
+//!
+//! ```toml
+//! [[source]]
+//! path = "services.yaml"   # relative to the mapping file
+//! records = "services"     # dot path to the record list (JSON/YAML)
+//!
+//! [[source.relation]]
+//! from = "{name}"
+//! from_type = "service"
+//! to = "{depends_on}"      # list fields fan out into one relation per item
+//! to_type = "service"
+//! relation = "depends on"
+//! ```
+//!
+//! CSV, JSON, JSON Lines and YAML records go through the `[[source.relation]]`
+//! rules. SQL files need no rules: tables, columns, foreign keys and views are
+//! mapped by a built-in schema reader. Tables keep their schema (`audit.orders`)
+//! unless it is a database's default one (`public`, `dbo`, `main`).
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use super::builder::GraphBuilder;
+use crate::llm::Relation;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MappingFile {
+    #[serde(default, rename = "source")]
+    sources: Vec<SourceMapping>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SourceMapping {
+    path: PathBuf,
+    /// Overrides the format inferred from the file extension
+    format: Option<SourceFormat>,
+    /// Dot path to the records inside a JSON or YAML document
+    records: Option<String>,
+    /// CSV field delimiter (default `,`, or tab for `.tsv`)
+    delimiter: Option<char>,
+    /// SQL only: add a node per column (default true)
+    #[serde(default = "default_true")]
+    columns: bool,
+    #[serde(default, rename = "relation")]
+    relations: Vec<RelationRule>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SourceFormat {
+    Csv,
+    Json,
+    Jsonl,
+    Yaml,
+    Sql,
+}
+
+impl SourceFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        Some(match ext.as_str() {
+            "csv" | "tsv" => Self::Csv,
+            "json" => Self::Json,
+            "jsonl" | "ndjson" => Self::Jsonl,
+            "yaml" | "yml" => Self::Yaml,
+            "sql" | "ddl" => Self::Sql,
+            _ => return None,
+        })
+    }
+}
+
+/// One relation per record (or more, when a placeholder holds a list)
+///
+/// Every field is a template: `{field}` is replaced by the record's value,
+/// `{a.b}` reaches into nested objects and `{_key}` is the key of a record
+/// taken from a map. A record missing any placeholder yields no relation.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RelationRule {
+    from: String,
+    from_type: Option<String>,
+    to: String,
+    to_type: Option<String>,
+    relation: String,
+    /// Split string values on this separator, e.g. `;` for multi-valued CSV cells
+    split: Option<String>,
+}
+
+/// What one mapped source contributed to the graph
+#[derive(Debug)]
+pub struct SourceSummary {
+    pub path: PathBuf,
+    pub records: usize,
+    pub relations: usize,
+    /// Records that matched no rule, usually because a field was missing
+    pub unmatched: usize,
+}
+
+/// Read every source in a mapping file and add its relations to `builder`
+///
+/// Relations from the same record share a chunk id, so contextual proximity
+/// links the values of one row just like concepts of one text chunk.
+pub fn apply_mapping(
+    mapping_path: &Path,
+    builder: &mut GraphBuilder,
+) -> Result<Vec<SourceSummary>> {
+    let content = std::fs::read_to_string(mapping_path)
+        .with_context(|| format!("Failed to read mapping file: {}", mapping_path.display()))?;
+    let mapping: MappingFile = toml::from_str(&content)
+        .with_context(|| format!("Invalid mapping file: {}", mapping_path.display()))?;
+    if mapping.sources.is_empty() {
+        bail!(
+            "Mapping file {} has no [[source]] entries",
+            mapping_path.display()
+        );
+    }
+
+    let base = mapping_path.parent().unwrap_or(Path::new(""));
+    let mut summaries = Vec::with_capacity(mapping.sources.len());
+    for source in &mapping.sources {
+        let path = base.join(&source.path);
+        let groups = map_source(source, &path)
+            .with_context(|| format!("Failed to map source: {}", path.display()))?;
+
+        let mut summary = SourceSummary {
+            path: path.clone(),
+            records: groups.len(),
+            relations: 0,
+            unmatched: 0,
+        };
+        for (chunk_id, relations) in groups {
+            if relations.is_empty() {
+                summary.unmatched += 1;
+                continue;
+            }
+            summary.relations += relations.len();
+            builder.add_relations(relations, &format!("{}#{}", path.display(), chunk_id));
+        }
+        summaries.push(summary);
+    }
+
+    Ok(summaries)
+}
+
+/// Relations of one source, grouped by record (chunk id suffix, relations)
+fn map_source(source: &SourceMapping, path: &Path) -> Result<Vec<(String, Vec<Relation>)>> {
+    let format = source
+        .format
+        .or_else(|| SourceFormat::from_path(path))
+        .with_context(|| {
+            format!(
+                "Cannot infer the format of {}; set format = \"csv\", \"json\", \"jsonl\", \"yaml\" or \"sql\"",
+                path.display()
+            )
+        })?;
+
+    if format == SourceFormat::Sql {
+        if !source.relations.is_empty() {
+            bail!(
+                "SQL sources use the built-in schema mapping and take no [[source.relation]] rules"
+            );
+        }
+        let sql = std::fs::read_to_string(path).context("Failed to read file")?;
+        return Ok(sql_relations(&sql, source.columns));
+    }
+
+    if source.relations.is_empty() {
+        bail!("No [[source.relation]] rules for this source");
+    }
+    let records = read_records(source, format, path)?;
+    Ok(records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let relations = source
+                .relations
+                .iter()
+                .flat_map(|rule| apply_rule(rule, record))
+                .collect();
+            (i.to_string(), relations)
+        })
+        .collect())
+}
+
+fn read_records(source: &SourceMapping, format: SourceFormat, path: &Path) -> Result<Vec<Value>> {
+    let file = std::fs::File::open(path).context("Failed to open file")?;
+
+    let document: Value = match format {
+        SourceFormat::Csv => {
+            let tsv = path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("tsv"));
+            let delimiter = source.delimiter.unwrap_or(if tsv { '\t' } else { ',' });
+            if !delimiter.is_ascii() {
+                bail!("CSV delimiter must be a single ASCII character");
+            }
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(delimiter as u8)
+                .flexible(true)
+                .from_reader(file);
+            let headers = reader
+                .headers()
+                .context("Failed to read CSV header")?
+                .clone();
+            let mut rows = Vec::new();
+            for (i, row) in reader.records().enumerate() {
+                let row = row.with_context(|| format!("Invalid CSV row {}", i + 2))?;
+                let record: Map<String, Value> = headers
+                    .iter()
+                    .zip(row.iter())
+                    .filter(|(_, value)| !value.trim().is_empty())
+                    .map(|(name, value)| (name.trim().to_string(), Value::from(value)))
+                    .collect();
+                rows.push(Value::Object(record));
+            }
+            return Ok(rows);
+        }
+        SourceFormat::Jsonl => {
+            let content = std::io::read_to_string(file)?;
+            let rows = content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(i, line)| {
+                    serde_json::from_str(line)
+                        .with_context(|| format!("Invalid JSON on line {}", i + 1))
+                })
+                .collect::<Result<Vec<Value>>>()?;
+            return Ok(rows);
+        }
+        SourceFormat::Json => {
+            serde_json::from_reader(std::io::BufReader::new(file)).context("Invalid JSON")?
+        }
+        SourceFormat::Yaml => serde_yaml::from_reader(file).context("Invalid YAML")?,
+        SourceFormat::Sql => unreachable!("SQL is mapped without records"),
+    };
+
+    let mut node = &document;
+    if let Some(records) = source.records.as_deref().filter(|r| !r.is_empty()) {
+        for key in records.split('.') {
+            node = node
+                .get(key)
+                .with_context(|| format!("No '{}' in records path '{}'", key, records))?;
+        }
+    }
+
+    Ok(match node {
+        Value::Array(items) => items.clone(),
+        // A map of named records; the name is available as {_key}
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| {
+                let mut record = match value {
+                    Value::Object(fields) => fields.clone(),
+                    other => Map::from_iter([("_value".to_string(), other.clone())]),
+                };
+                record.insert("_key".into(), Value::from(key.as_str()));
+                Value::Object(record)
+            })
+            .collect(),
+        _ => bail!("Records must be a list or a map"),
+    })
+}
+
+fn apply_rule(rule: &RelationRule, record: &Value) -> Vec<Relation> {
+    let split = rule.split.as_deref();
+    let from = render(&rule.from, record, split);
+    let to = render(&rule.to, record, split);
+    let edges = render(&rule.relation, record, split);
+    let from_type = rule
+        .from_type
+        .as_ref()
+        .and_then(|t| render(t, record, None).into_iter().next());
+    let to_type = rule
+        .to_type
+        .as_ref()
+        .and_then(|t| render(t, record, None).into_iter().next());
+
+    let mut relations = Vec::new();
+    for node_1 in &from {
+        for node_2 in &to {
+            for edge in &edges {
+                relations.push(Relation {
+                    node_1: node_1.clone(),
+                    node_1_type: from_type.clone(),
+                    node_2: node_2.clone(),
+                    node_2_type: to_type.clone(),
+                    edge: edge.clone(),
+                });
+            }
+        }
+    }
+    relations
+}
+
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{([^{}]+)\}").expect("valid placeholder regex"));
+
+/// Every rendering of `template` for a record: one per combination of list values
+fn render(template: &str, record: &Value, split: Option<&str>) -> Vec<String> {
+    let mut results = vec![String::new()];
+    let mut last = 0;
+
+    for caps in PLACEHOLDER.captures_iter(template) {
+        let whole = caps.get(0).expect("match");
+        let literal = &template[last..whole.start()];
+        last = whole.end();
+
+        let path: Vec<&str> = caps[1].trim().split('.').collect();
+        let mut found = Vec::new();
+        lookup(record, &path, &mut found);
+        let values: Vec<String> = found
+            .into_iter()
+            .filter_map(scalar)
+            .flat_map(|value| match split {
+                Some(sep) => value.split(sep).map(|v| v.trim().to_string()).collect(),
+                None => vec![value],
+            })
+            .filter(|v| !v.is_empty())
+            .collect();
+        if values.is_empty() {
+            return Vec::new();
+        }
+
+        results = results
+            .iter()
+            .flat_map(|prefix| {
+                values
+                    .iter()
+                    .map(move |value| format!("{}{}{}", prefix, literal, value))
+            })
+            .collect();
+    }
+
+    let tail = &template[last..];
+    results
+        .into_iter()
+        .map(|r| format!("{}{}", r, tail).trim().to_string())
+        .filter(|r| !r.is_empty())
+        .collect()
+}
+
+/// Collect the values at `path`, flattening arrays along the way
+fn lookup<'v>(value: &'v Value, path: &[&str], out: &mut Vec<&'v Value>) {
+    match (value, path.split_first()) {
+        (Value::Array(items), _) => {
+            for item in items {
+                lookup(item, path, out);
+            }
+        }
+        (Value::Null, _) => {}
+        (_, None) => out.push(value),
+        (Value::Object(map), Some((key, rest))) => {
+            if let Some(child) = map.get(*key) {
+                lookup(child, rest, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// ── SQL schemas ──
+
+static CREATE_TABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^CREATE\s+(?:OR\s+REPLACE\s+)?(?:(?:GLOBAL|LOCAL)\s+)?(?:TEMP(?:ORARY)?\s+)?(?:UNLOGGED\s+)?TABLE\s+(?:IF\s+NOT\s+EXISTS\s+)?([^\s(]+)\s*\("#)
+        .expect("valid regex")
+});
+static CREATE_VIEW: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^CREATE\s+(?:OR\s+REPLACE\s+)?(?:MATERIALIZED\s+)?VIEW\s+(?:IF\s+NOT\s+EXISTS\s+)?(\S+).*?\bAS\b(.*)$"#)
+        .expect("valid regex")
+});
+static ALTER_TABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)^ALTER\s+TABLE\s+(?:ONLY\s+)?(?:IF\s+EXISTS\s+)?(\S+)\s+(.*)$"#)
+        .expect("valid regex")
+});
+static FOREIGN_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)FOREIGN\s+KEY\s*\(([^)]*)\)\s*REFERENCES\s+([^\s(]+)\s*(?:\(([^)]*)\))?"#)
+        .expect("valid regex")
+});
+static INLINE_REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)\bREFERENCES\s+([^\s(]+)\s*(?:\(([^)]*)\))?"#).expect("valid regex")
+});
+/// Schemas that unqualified names resolve to, left out of table names
+const DEFAULT_SCHEMAS: &[&str] = &["public", "dbo", "main"];
+
+static VIEW_SOURCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(?:FROM|JOIN)\s+([\w."`\[\]]+)"#).expect("valid regex"));
+
+/// Map SQL DDL to relations, grouped by table or view (empty groups are left out)
+///
+/// Tables have columns (as `table.column` nodes when `columns` is set),
+/// foreign keys become `references` edges and views read from the tables
+/// named in their FROM and JOIN clauses.
+fn sql_relations(sql: &str, columns: bool) -> Vec<(String, Vec<Relation>)> {
+    let mut groups: Vec<(String, Vec<Relation>)> = Vec::new();
+
+    for statement in sql_statements(sql) {
+        let statement = statement.trim();
+        if let Some(caps) = CREATE_TABLE.captures(statement) {
+            let table = table_name(&caps[1]);
+            let open = caps.get(0).expect("match").end();
+            let body = matching_paren(&statement[open..]);
+            let mut relations = Vec::new();
+            for definition in split_top_level(body) {
+                table_definition(&table, definition.trim(), columns, &mut relations);
+            }
+            if !relations.is_empty() {
+                groups.push((table, relations));
+            }
+        } else if let Some(caps) = ALTER_TABLE.captures(statement) {
+            let table = table_name(&caps[1]);
+            let mut relations = Vec::new();
+            for fk in FOREIGN_KEY.captures_iter(&caps[2]) {
+                foreign_key(
+                    &table,
+                    &fk[1],
+                    &fk[2],
+                    fk.get(3).map(|m| m.as_str()),
+                    columns,
+                    &mut relations,
+                );
+            }
+            if !relations.is_empty() {
+                groups.push((table, relations));
+            }
+        } else if let Some(caps) = CREATE_VIEW.captures(statement) {
+            let view = table_name(&caps[1]);
+            let mut relations: Vec<Relation> = Vec::new();
+            for source in VIEW_SOURCE.captures_iter(&caps[2]) {
+                let table = table_name(&source[1]);
+                if relations.iter().any(|r| r.node_2 == table) {
+                    continue;
+                }
+                relations.push(relation(&view, "view", "reads from", &table, "table"));
+            }
+            if !relations.is_empty() {
+                groups.push((view, relations));
+            }
+        }
+    }
+
+    groups
+}
+
+fn table_definition(table: &str, definition: &str, columns: bool, out: &mut Vec<Relation>) {
+    let upper = definition.to_uppercase();
+    let upper = upper.trim_start();
+    let definition = if upper.starts_with("CONSTRAINT") {
+        // CONSTRAINT <name> <constraint>
+        let mut parts = definition.trim_start().splitn(3, char::is_whitespace);
+        parts.nth(2).unwrap_or("").trim_start()
+    } else {
+        definition
+    };
+
+    if let Some(fk) = FOREIGN_KEY.captures(definition) {
+        foreign_key(
+            table,
+            &fk[1],
+            &fk[2],
+            fk.get(3).map(|m| m.as_str()),
+            columns,
+            out,
+        );
+        return;
+    }
+
+    let keyword = definition
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .to_uppercase();
+    if matches!(
+        keyword.as_str(),
+        "" | "PRIMARY"
+            | "UNIQUE"
+            | "KEY"
+            | "INDEX"
+            | "CHECK"
+            | "EXCLUDE"
+            | "FULLTEXT"
+            | "SPATIAL"
+            | "CONSTRAINT"
+            | "PERIOD"
+            | "LIKE"
+    ) {
+        return;
+    }
+
+    // Column: <name> <type> [constraints...]
+    let mut words = definition.split_whitespace();
+    let column = identifier(words.next().unwrap_or(""));
+    let data_type = words
+        .next()
+        .map(|t| t.split('(').next().unwrap_or(t).to_lowercase())
+        .unwrap_or_default();
+    if column.is_empty() {
+        return;
+    }
+    if columns {
+        let edge = if data_type.is_empty() {
+            "has column".to_string()
+        } else {
+            format!("has column of type {}", data_type)
+        };
+        out.push(relation(
+            table,
+            "table",
+            &edge,
+            &format!("{}.{}", table, column),
+            "column",
+        ));
+    }
+    if let Some(reference) = INLINE_REFERENCE.captures(definition) {
+        foreign_key(
+            table,
+            &column,
+            &reference[1],
+            reference.get(2).map(|m| m.as_str()),
+            columns,
+            out,
+        );
+    }
+}
+
+fn foreign_key(
+    table: &str,
+    local: &str,
+    target: &str,
+    target_columns: Option<&str>,
+    columns: bool,
+    out: &mut Vec<Relation>,
+) {
+    let target = table_name(target);
+    let local: Vec<String> = local.split(',').map(identifier).collect();
+    out.push(relation(
+        table,
+        "table",
+        &format!("references via {}", local.join(", ")),
+        &target,
+        "table",
+    ));
+
+    if columns && let Some(target_columns) = target_columns {
+        for (from, to) in local.iter().zip(target_columns.split(',').map(identifier)) {
+            out.push(relation(
+                &format!("{}.{}", table, from),
+                "column",
+                "references",
+                &format!("{}.{}", target, to),
+                "column",
+            ));
+        }
+    }
+}
+
+fn relation(node_1: &str, type_1: &str, edge: &str, node_2: &str, type_2: &str) -> Relation {
+    Relation {
+        node_1: node_1.to_string(),
+        node_1_type: Some(type_1.to_string()),
+        node_2: node_2.to_string(),
+        node_2_type: Some(type_2.to_string()),
+        edge: edge.to_string(),
+    }
+}
+
+/// Table or view name with its schema, unless that is a default schema:
+/// `"audit"."Orders"` → `audit.Orders`, `public.orders` → `orders`
+fn table_name(raw: &str) -> String {
+    let parts: Vec<String> = raw.trim().split('.').map(identifier).collect();
+    match parts.as_slice() {
+        [.., schema, table] if !DEFAULT_SCHEMAS.contains(&schema.to_lowercase().as_str()) => {
+            format!("{}.{}", schema, table)
+        }
+        [.., table] => table.clone(),
+        [] => String::new(),
+    }
+}
+
+/// Unquoted, unqualified identifier: `"public"."Orders"` → `Orders`
+fn identifier(raw: &str) -> String {
+    let unqualified = raw.trim().rsplit('.').next().unwrap_or("");
+    unqualified
+        .trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'))
+        .trim()
+        .to_string()
+}
+
+/// Statements separated by `;`, with comments removed
+fn sql_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = sql.chars().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            current.push(c);
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' | '`' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        current.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                current.push(' ');
+            }
+            ';' => statements.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        statements.push(current);
+    }
+    statements
+}
+
+/// Text up to the parenthesis that closes an already opened one
+fn matching_paren(text: &str) -> &str {
+    let mut depth = 1;
+    let mut quote: Option<char> = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return &text[..i];
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Split on commas that are not inside parentheses or quotes
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn edges(builder: &GraphBuilder) -> Vec<(String, String, String)> {
+        let mut edges: Vec<_> = builder
+            .get_edges()
+            .into_iter()
+            .filter(|e| e.relation != "contextual proximity")
+            .map(|e| (e.source, e.relation, e.target))
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    #[test]
+    fn test_yaml_map_and_list_fan_out() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("catalog.yaml"),
+            "services:\n  auth:\n    team: identity\n    depends_on: [postgres, redis]\n  billing:\n    team: payments\n    depends_on: []\n",
+        )
+        .unwrap();
+        let mapping = dir.path().join("mapping.toml");
+        std::fs::write(
+            &mapping,
+            r#"
+[[source]]
+path = "catalog.yaml"
+records = "services"
+
+[[source.relation]]
+from = "{_key}"
+from_type = "service"
+to = "{depends_on}"
+to_type = "datastore"
+relation = "depends on"
+
+[[source.relation]]
+from = "{_key}"
+to = "team {team}"
+relation = "owned by"
+"#,
+        )
+        .unwrap();
+
+        let mut builder = GraphBuilder::new();
+        let summaries = apply_mapping(&mapping, &mut builder).unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].records, 2);
+        assert_eq!(summaries[0].relations, 4);
+        assert_eq!(summaries[0].unmatched, 0);
+
+        assert_eq!(
+            edges(&builder),
+            vec![
+                ("auth".into(), "depends on".into(), "postgres".into()),
+                ("auth".into(), "depends on".into(), "redis".into()),
+                ("auth".into(), "owned by".into(), "team identity".into()),
+                ("billing".into(), "owned by".into(), "team payments".into()),
+            ]
+        );
+        let nodes = builder.get_nodes();
+        let redis = nodes.iter().find(|n| n.label == "redis").unwrap();
+        assert_eq!(redis.entity_type.as_deref(), Some("datastore"));
+    }
+
+    #[test]
+    fn test_csv_split_and_missing_fields() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("hosts.csv"),
+            "host,role,runs\nweb-1,frontend,nginx; node\ndb-1,,postgres\n",
+        )
+        .unwrap();
+        let mapping = dir.path().join("mapping.toml");
+        std::fs::write(
+            &mapping,
+            r#"
+[[source]]
+path = "hosts.csv"
+
+[[source.relation]]
+from = "{host}"
+to = "{runs}"
+relation = "runs"
+split = ";"
+
+[[source.relation]]
+from = "{host}"
+to = "{role}"
+relation = "has role"
+"#,
+        )
+        .unwrap();
+
+        let mut builder = GraphBuilder::new();
+        let summary = &apply_mapping(&mapping, &mut builder).unwrap()[0];
+        assert_eq!(summary.records, 2);
+        assert_eq!(summary.relations, 4);
+        assert_eq!(
+            edges(&builder),
+            vec![
+                ("db-1".into(), "runs".into(), "postgres".into()),
+                ("web-1".into(), "has role".into(), "frontend".into()),
+                ("web-1".into(), "runs".into(), "nginx".into()),
+                ("web-1".into(), "runs".into(), "node".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_nested_paths() {
+        let record = serde_json::json!({
+            "name": "api",
+            "owners": [{"email": "a@x.io"}, {"email": "b@x.io"}],
+            "port": 8080
+        });
+        assert_eq!(
+            render("{owners.email}", &record, None),
+            vec!["a@x.io", "b@x.io"]
+        );
+        assert_eq!(render("{name}:{port}", &record, None), vec!["api:8080"]);
+        assert!(render("{missing}", &record, None).is_empty());
+    }
+
+    #[test]
+    fn test_sql_schema() {
+        let sql = r#"
+-- customers and their orders
+CREATE TABLE IF NOT EXISTS "public"."customers" (
+    id SERIAL PRIMARY KEY,
+    email VARCHAR(255) NOT NULL, /* unique; per tenant */
+    UNIQUE (email)
+);
+CREATE TABLE orders (
+    id BIGINT PRIMARY KEY,
+    customer_id INTEGER REFERENCES customers(id),
+    total NUMERIC(10, 2) DEFAULT 0
+);
+CREATE TABLE order_items (
+    order_id BIGINT,
+    sku TEXT,
+    CONSTRAINT fk_order FOREIGN KEY (order_id) REFERENCES orders (id)
+);
+CREATE VIEW order_totals AS
+    SELECT o.id, c.email FROM orders o JOIN customers c ON c.id = o.customer_id;
+CREATE TABLE audit.orders (
+    changed_at TIMESTAMP,
+    order_id BIGINT REFERENCES public.orders (id)
+);
+"#;
+        let groups = sql_relations(sql, true);
+        let names: Vec<&str> = groups.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "customers",
+                "orders",
+                "order_items",
+                "order_totals",
+                "audit.orders"
+            ]
+        );
+
+        let orders = &groups[1].1;
+        let edges: Vec<(&str, &str, &str)> = orders
+            .iter()
+            .map(|r| (r.node_1.as_str(), r.edge.as_str(), r.node_2.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("orders", "has column of type bigint", "orders.id"),
+                ("orders", "has column of type integer", "orders.customer_id"),
+                ("orders", "references via customer_id", "customers"),
+                ("orders.customer_id", "references", "customers.id"),
+                ("orders", "has column of type numeric", "orders.total"),
+            ]
+        );
+        assert_eq!(groups[0].1.len(), 2);
+        assert!(
+            groups[2]
+                .1
+                .iter()
+                .any(|r| r.edge == "references via order_id")
+        );
+        let view_sources: Vec<&str> = groups[3].1.iter().map(|r| r.node_2.as_str()).collect();
+        assert_eq!(view_sources, vec!["orders", "customers"]);
+
+        // A second schema's table stays apart from the default schema's
+        let audit: Vec<(&str, &str, &str)> = groups[4]
+            .1
+            .iter()
+            .map(|r| (r.node_1.as_str(), r.edge.as_str(), r.node_2.as_str()))
+            .collect();
+        assert_eq!(
+            audit,
+            vec![
+                (
+                    "audit.orders",
+                    "has column of type timestamp",
+                    "audit.orders.changed_at"
+                ),
+                (
+                    "audit.orders",
+                    "has column of type bigint",
+                    "audit.orders.order_id"
+                ),
+                ("audit.orders", "references via order_id", "orders"),
+                ("audit.orders.order_id", "references", "orders.id"),
+            ]
+        );
+
+        // Without columns, only tables with foreign keys remain
+        let tables_only = sql_relations(sql, false);
+        let names: Vec<&str> = tables_only.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["orders", "order_items", "order_totals", "audit.orders"]
+        );
+        assert_eq!(tables_only[0].1.len(), 1);
+    }
+
+    #[test]
+    fn test_mapping_errors() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("data.csv"), "a,b\n1,2\n").unwrap();
+        let mapping = dir.path().join("mapping.toml");
+
+        std::fs::write(&mapping, "[[source]]\npath = \"data.csv\"\n").unwrap();
+        let err = apply_mapping(&mapping, &mut GraphBuilder::new()).unwrap_err();
+        assert!(format!("{:#}", err).contains("No [[source.relation]] rules"));
+
+        std::fs::write(&mapping, "[[source]]\npath = \"data.txt\"\n").unwrap();
+        let err = apply_mapping(&mapping, &mut GraphBuilder::new()).unwrap_err();
+        assert!(format!("{:#}", err).contains("Cannot infer the format"));
+
+        std::fs::write(&mapping, "[[source]]\npth = \"data.csv\"\n").unwrap();
+        assert!(apply_mapping(&mapping, &mut GraphBuilder::new()).is_err());
+    }
+}
@@ -1,4 +1,8 @@
 pub mod analytics;
 pub mod builder;
 pub mod community;
+pub mod mapping;
 pub mod neo4j;
+pub mod paths;
+pub mod resolver;
+pub mod subgraph;
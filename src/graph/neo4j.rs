@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
-use neo4rs::{Graph, Row, query};
+use neo4rs::{BoltType, Graph, Row, query};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use super::builder::GraphBuilder;
 use crate::config::Neo4jConfig;
@@ -17,6 +18,9 @@ pub struct GraphNode {
     /// Tenant namespace for isolation
     #[serde(default = "default_tenant")]
     pub tenant: String,
+    /// Alternative names the concept is known by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
 }
 
 fn default_tenant() -> String {
@@ -30,6 +34,88 @@ pub struct GraphEdge {
     pub target: String,
     pub relation: String,
     pub weight: f64,
+    /// Where the relation was read: a file, `file.pdf p.12`, or a mapped record
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+}
+
+/// An edge together with the tenant of its endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantEdge {
+    pub source: String,
+    pub target: String,
+    pub relation: String,
+    pub weight: f64,
+    #[serde(default = "default_tenant")]
+    pub tenant: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+}
+
+impl From<TenantEdge> for GraphEdge {
+    fn from(edge: TenantEdge) -> Self {
+        GraphEdge {
+            source: edge.source,
+            target: edge.target,
+            relation: edge.relation,
+            weight: edge.weight,
+            sources: edge.sources,
+        }
+    }
+}
+
+impl GraphEdge {
+    /// Attach the tenant of the edge's endpoints
+    pub fn with_tenant(self, tenant: &str) -> TenantEdge {
+        TenantEdge {
+            source: self.source,
+            target: self.target,
+            relation: self.relation,
+            weight: self.weight,
+            tenant: tenant.to_string(),
+            sources: self.sources,
+        }
+    }
+}
+
+/// What an import wrote
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ImportCounts {
+    pub nodes: usize,
+    pub edges: usize,
+    /// Edges dropped because an endpoint was not in the edge's tenant
+    pub skipped_edges: usize,
+}
+
+/// One record of a streamed graph, as written to and read from JSON Lines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GraphRecord {
+    Node(GraphNode),
+    Edge(TenantEdge),
+}
+
+fn node_row(node: GraphNode) -> BoltType {
+    let mut row: HashMap<&str, BoltType> = HashMap::new();
+    row.insert("id", node.id.into());
+    row.insert("label", node.label.into());
+    row.insert("degree", node.degree.map(|d| d as i64).into());
+    row.insert("community", node.community.map(|c| c as i64).into());
+    row.insert("entity_type", node.entity_type.into());
+    row.insert("tenant", node.tenant.into());
+    row.insert("aliases", node.aliases.into());
+    row.into()
+}
+
+fn edge_row(edge: TenantEdge) -> BoltType {
+    let mut row: HashMap<&str, BoltType> = HashMap::new();
+    row.insert("source", edge.source.into());
+    row.insert("target", edge.target.into());
+    row.insert("relation", edge.relation.into());
+    row.insert("weight", edge.weight.into());
+    row.insert("tenant", edge.tenant.into());
+    row.insert("sources", edge.sources.into());
+    row.into()
 }
 
 /// Neo4j client for storing and querying knowledge graphs
@@ -72,14 +158,15 @@ impl Neo4jClient {
         let nodes = builder.get_nodes();
         for node in &nodes {
             let q = query(
-                "CREATE (n:Concept {id: $id, label: $label, degree: $degree, community: $community, entity_type: $entity_type, tenant: $tenant})"
+                "CREATE (n:Concept {id: $id, label: $label, degree: $degree, community: $community, entity_type: $entity_type, tenant: $tenant, aliases: $aliases})"
             )
             .param("id", node.id.clone())
             .param("label", node.label.clone())
             .param("degree", node.degree as i64)
             .param("community", node.community.unwrap_or(0) as i64)
             .param("entity_type", node.entity_type.clone().unwrap_or_else(|| "concept".to_string()))
-            .param("tenant", node.tenant.clone());
+            .param("tenant", node.tenant.clone())
+            .param("aliases", node.aliases.clone());
 
             self.graph.run(q).await.context("Failed to create node")?;
         }
@@ -89,12 +176,13 @@ impl Neo4jClient {
         for edge in &edges {
             let q = query(
                 "MATCH (a:Concept {id: $source, tenant: $tenant}), (b:Concept {id: $target, tenant: $tenant}) \
-                 CREATE (a)-[r:RELATES_TO {relation: $relation, weight: $weight}]->(b)",
+                 CREATE (a)-[r:RELATES_TO {relation: $relation, weight: $weight, sources: $sources}]->(b)",
             )
             .param("source", edge.source.clone())
             .param("target", edge.target.clone())
             .param("relation", edge.relation.clone())
             .param("weight", edge.weight)
+            .param("sources", edge.chunk_ids.clone())
             .param("tenant", tenant);
 
             self.graph.run(q).await.context("Failed to create edge")?;
@@ -115,20 +203,23 @@ impl Neo4jClient {
             .await
             .ok();
 
-        // MERGE nodes (create if not exists, update if exists) - scoped by tenant
+        // MERGE nodes (create if not exists, update if exists) - scoped by tenant;
+        // aliases are added to the ones already stored
         let nodes = builder.get_nodes();
         for node in &nodes {
             let q = query(
                 "MERGE (n:Concept {id: $id, tenant: $tenant}) \
-                 ON CREATE SET n.label = $label, n.degree = $degree, n.community = $community, n.entity_type = $entity_type \
-                 ON MATCH SET n.degree = n.degree + $degree, n.community = $community, n.entity_type = $entity_type"
+                 ON CREATE SET n.label = $label, n.degree = $degree, n.community = $community, n.entity_type = $entity_type, n.aliases = $aliases \
+                 ON MATCH SET n.degree = n.degree + $degree, n.community = $community, n.entity_type = $entity_type, \
+                     n.aliases = coalesce(n.aliases, []) + [a IN $aliases WHERE NOT a IN coalesce(n.aliases, [])]"
             )
             .param("id", node.id.clone())
             .param("label", node.label.clone())
             .param("degree", node.degree as i64)
             .param("community", node.community.unwrap_or(0) as i64)
             .param("entity_type", node.entity_type.clone().unwrap_or_else(|| "concept".to_string()))
-            .param("tenant", node.tenant.clone());
+            .param("tenant", node.tenant.clone())
+            .param("aliases", node.aliases.clone());
 
             self.graph.run(q).await.context("Failed to merge node")?;
         }
@@ -139,13 +230,15 @@ impl Neo4jClient {
             let q = query(
                 "MATCH (a:Concept {id: $source, tenant: $tenant}), (b:Concept {id: $target, tenant: $tenant}) \
                  MERGE (a)-[r:RELATES_TO {relation: $relation}]->(b) \
-                 ON CREATE SET r.weight = $weight \
-                 ON MATCH SET r.weight = r.weight + $weight",
+                 ON CREATE SET r.weight = $weight, r.sources = $sources \
+                 ON MATCH SET r.weight = r.weight + $weight, \
+                     r.sources = coalesce(r.sources, []) + [s IN $sources WHERE NOT s IN coalesce(r.sources, [])]",
             )
             .param("source", edge.source.clone())
             .param("target", edge.target.clone())
             .param("relation", edge.relation.clone())
             .param("weight", edge.weight)
+            .param("sources", edge.chunk_ids.clone())
             .param("tenant", tenant);
 
             self.graph.run(q).await.context("Failed to merge edge")?;
@@ -159,11 +252,43 @@ impl Neo4jClient {
         &self,
         tenant: Option<&str>,
     ) -> Result<(Vec<GraphNode>, Vec<GraphEdge>)> {
+        let (nodes, edges) = self.fetch_tenant_graph(tenant).await?;
+        Ok((nodes, edges.into_iter().map(GraphEdge::from).collect()))
+    }
+
+    /// Fetch all nodes and edges, keeping the tenant of each edge
+    pub async fn fetch_tenant_graph(
+        &self,
+        tenant: Option<&str>,
+    ) -> Result<(Vec<GraphNode>, Vec<TenantEdge>)> {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        self.stream_graph(tenant, |record| {
+            match record {
+                GraphRecord::Node(node) => nodes.push(node),
+                GraphRecord::Edge(edge) => edges.push(edge),
+            }
+            Ok(())
+        })
+        .await?;
+
+        Ok((nodes, edges))
+    }
+
+    /// Read the graph row by row from the Neo4j cursor, handing each record to `sink`
+    ///
+    /// All nodes are delivered before any edge, so a consumer replaying the
+    /// records in order always sees both endpoints before the edge between them.
+    pub async fn stream_graph(
+        &self,
+        tenant: Option<&str>,
+        mut sink: impl FnMut(GraphRecord) -> Result<()>,
+    ) -> Result<()> {
         // Fetch nodes - filter by tenant if specified
         let nodes_query = match tenant {
-            Some(t) => query("MATCH (n:Concept {tenant: $tenant}) RETURN n.id AS id, n.label AS label, n.degree AS degree, n.community AS community, n.entity_type AS entity_type, n.tenant AS tenant")
+            Some(t) => query("MATCH (n:Concept {tenant: $tenant}) RETURN n.id AS id, n.label AS label, n.degree AS degree, n.community AS community, n.entity_type AS entity_type, n.tenant AS tenant, n.aliases AS aliases")
                 .param("tenant", t),
-            None => query("MATCH (n:Concept) RETURN n.id AS id, n.label AS label, n.degree AS degree, n.community AS community, n.entity_type AS entity_type, n.tenant AS tenant"),
+            None => query("MATCH (n:Concept) RETURN n.id AS id, n.label AS label, n.degree AS degree, n.community AS community, n.entity_type AS entity_type, n.tenant AS tenant, n.aliases AS aliases"),
         };
 
         let mut result = self
@@ -172,16 +297,16 @@ impl Neo4jClient {
             .await
             .context("Failed to fetch nodes")?;
 
-        let mut nodes = Vec::new();
-        while let Ok(Some(row)) = result.next().await {
+        while let Some(row) = result.next().await.context("Failed to read node row")? {
             let id: String = row.get("id").unwrap_or_default();
             let label: String = row.get("label").unwrap_or_else(|_| id.clone());
             let degree: i64 = row.get("degree").unwrap_or(0);
             let community: i64 = row.get("community").unwrap_or(-1);
             let entity_type: Option<String> = row.get("entity_type").ok();
-            let node_tenant: String = row.get("tenant").unwrap_or_else(|_| "default".to_string());
+            let node_tenant: String = row.get("tenant").unwrap_or_else(|_| default_tenant());
+            let aliases: Vec<String> = row.get("aliases").unwrap_or_default();
 
-            nodes.push(GraphNode {
+            sink(GraphRecord::Node(GraphNode {
                 id,
                 label,
                 community: if community >= 0 {
@@ -192,19 +317,20 @@ impl Neo4jClient {
                 degree: Some(degree as usize),
                 entity_type,
                 tenant: node_tenant,
-            });
+                aliases,
+            }))?;
         }
 
         // Fetch edges - filter by tenant if specified
         let edges_query = match tenant {
             Some(t) => query(
                 "MATCH (a:Concept {tenant: $tenant})-[r:RELATES_TO]->(b:Concept {tenant: $tenant}) \
-                 RETURN a.id AS source, b.id AS target, r.relation AS relation, r.weight AS weight",
+                 RETURN a.id AS source, b.id AS target, r.relation AS relation, r.weight AS weight, a.tenant AS tenant, r.sources AS sources",
             )
             .param("tenant", t),
             None => query(
                 "MATCH (a:Concept)-[r:RELATES_TO]->(b:Concept) \
-                 RETURN a.id AS source, b.id AS target, r.relation AS relation, r.weight AS weight",
+                 RETURN a.id AS source, b.id AS target, r.relation AS relation, r.weight AS weight, a.tenant AS tenant, r.sources AS sources",
             ),
         };
 
@@ -214,24 +340,131 @@ impl Neo4jClient {
             .await
             .context("Failed to fetch edges")?;
 
-        let mut edges = Vec::new();
-        while let Ok(Some(row)) = result.next().await {
-            let source: String = row.get("source").unwrap_or_default();
-            let target: String = row.get("target").unwrap_or_default();
-            let relation: String = row
-                .get("relation")
-                .unwrap_or_else(|_| "related".to_string());
-            let weight: f64 = row.get("weight").unwrap_or(1.0);
-
-            edges.push(GraphEdge {
-                source,
-                target,
-                relation,
-                weight,
-            });
+        while let Some(row) = result.next().await.context("Failed to read edge row")? {
+            sink(GraphRecord::Edge(TenantEdge {
+                source: row.get("source").unwrap_or_default(),
+                target: row.get("target").unwrap_or_default(),
+                relation: row
+                    .get("relation")
+                    .unwrap_or_else(|_| "related".to_string()),
+                weight: row.get("weight").unwrap_or(1.0),
+                tenant: row.get("tenant").unwrap_or_else(|_| default_tenant()),
+                sources: row.get("sources").unwrap_or_default(),
+            }))?;
         }
 
-        Ok((nodes, edges))
+        Ok(())
+    }
+
+    /// Write streamed records into Neo4j in batches of `batch_size`
+    ///
+    /// Nodes are merged on `(id, tenant)` and take the record's properties;
+    /// edges are merged on their relation and take the record's weight, so
+    /// importing the same file twice leaves the graph unchanged. Pending nodes
+    /// are always flushed before pending edges. `progress` receives the running
+    /// node and edge counts after every batch.
+    ///
+    /// An edge whose endpoints are not both in its tenant cannot be created;
+    /// such edges are counted in [`ImportCounts::skipped_edges`].
+    pub async fn import_records(
+        &self,
+        records: impl Iterator<Item = Result<GraphRecord>>,
+        batch_size: usize,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<ImportCounts> {
+        self.graph
+            .run(query(
+                "CREATE INDEX concept_id_tenant IF NOT EXISTS FOR (n:Concept) ON (n.id, n.tenant)",
+            ))
+            .await
+            .ok();
+
+        let batch_size = batch_size.max(1);
+        let mut node_batch: Vec<BoltType> = Vec::with_capacity(batch_size);
+        let mut edge_batch: Vec<BoltType> = Vec::with_capacity(batch_size);
+        let mut counts = ImportCounts::default();
+
+        for record in records {
+            match record? {
+                GraphRecord::Node(node) => {
+                    node_batch.push(node_row(node));
+                    if node_batch.len() >= batch_size {
+                        counts.nodes += self.flush_nodes(&mut node_batch).await?;
+                        progress(counts.nodes, counts.edges);
+                    }
+                }
+                GraphRecord::Edge(edge) => {
+                    edge_batch.push(edge_row(edge));
+                    if edge_batch.len() >= batch_size {
+                        counts.nodes += self.flush_nodes(&mut node_batch).await?;
+                        self.flush_edges(&mut edge_batch, &mut counts).await?;
+                        progress(counts.nodes, counts.edges);
+                    }
+                }
+            }
+        }
+        counts.nodes += self.flush_nodes(&mut node_batch).await?;
+        self.flush_edges(&mut edge_batch, &mut counts).await?;
+        progress(counts.nodes, counts.edges);
+
+        Ok(counts)
+    }
+
+    async fn flush_nodes(&self, batch: &mut Vec<BoltType>) -> Result<usize> {
+        if batch.is_empty() {
+            return Ok(0);
+        }
+        let rows = std::mem::take(batch);
+        let count = rows.len();
+        self.graph
+            .run(
+                query(
+                    "UNWIND $rows AS row \
+                     MERGE (n:Concept {id: row.id, tenant: row.tenant}) \
+                     SET n.label = row.label, n.degree = row.degree, n.community = row.community, \
+                         n.entity_type = row.entity_type, n.aliases = row.aliases",
+                )
+                .param("rows", rows),
+            )
+            .await
+            .context("Failed to import nodes")?;
+        Ok(count)
+    }
+
+    /// Write a batch of edges, counting the ones written and the ones whose
+    /// endpoints were missing
+    async fn flush_edges(
+        &self,
+        batch: &mut Vec<BoltType>,
+        counts: &mut ImportCounts,
+    ) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(batch);
+        let sent = rows.len();
+        let mut result = self
+            .graph
+            .execute(
+                query(
+                    "UNWIND $rows AS row \
+                     MATCH (a:Concept {id: row.source, tenant: row.tenant}), (b:Concept {id: row.target, tenant: row.tenant}) \
+                     MERGE (a)-[r:RELATES_TO {relation: row.relation}]->(b) \
+                     SET r.weight = row.weight, r.sources = row.sources \
+                     RETURN count(r) AS written",
+                )
+                .param("rows", rows),
+            )
+            .await
+            .context("Failed to import edges")?;
+        let written: i64 = match result.next().await.context("Failed to import edges")? {
+            Some(row) => row.get("written").unwrap_or(0),
+            None => 0,
+        };
+        let written = written.max(0) as usize;
+        counts.edges += written;
+        counts.skipped_edges += sent.saturating_sub(written);
+        Ok(())
     }
 
     /// Execute a raw Cypher query
@@ -252,20 +485,53 @@ impl Neo4jClient {
         Ok(results)
     }
 
+    /// Fetch concept labels and their aliases, for resolving user-typed names
+    pub async fn fetch_concept_names(
+        &self,
+        tenant: Option<&str>,
+    ) -> Result<Vec<(String, Vec<String>)>> {
+        let names_query = match tenant {
+            Some(t) => query(
+                "MATCH (n:Concept {tenant: $tenant}) RETURN n.label AS label, n.aliases AS aliases",
+            )
+            .param("tenant", t),
+            None => query("MATCH (n:Concept) RETURN n.label AS label, n.aliases AS aliases"),
+        };
+
+        let mut result = self
+            .graph
+            .execute(names_query)
+            .await
+            .context("Failed to fetch concept names")?;
+
+        let mut names = Vec::new();
+        while let Ok(Some(row)) = result.next().await {
+            let label: String = row.get("label").unwrap_or_default();
+            let aliases: Vec<String> = row.get("aliases").unwrap_or_default();
+            if !label.is_empty() {
+                names.push((label, aliases));
+            }
+        }
+
+        Ok(names)
+    }
+
     /// Search for concepts by name or relation
     pub async fn search_concepts(
         &self,
         search_term: &str,
+        tenant: Option<&str>,
     ) -> Result<Vec<(String, Vec<(String, String)>)>> {
         let search_pattern = format!("(?i).*{}.*", regex::escape(search_term));
 
         let mut result = self.graph
             .execute(query(
                 "MATCH (n:Concept)-[r:RELATES_TO]-(m:Concept) \
-                 WHERE n.label =~ $pattern OR r.relation =~ $pattern \
+                 WHERE (n.label =~ $pattern OR r.relation =~ $pattern) \
+                   AND ($tenant IS NULL OR (n.tenant = $tenant AND m.tenant = $tenant)) \
                  RETURN n.label AS concept, collect({related: m.label, edge: r.relation}) AS relations \
                  LIMIT 20"
-            ).param("pattern", search_pattern))
+            ).param("pattern", search_pattern).param("tenant", tenant))
             .await
             .context("Failed to search concepts")?;
 
@@ -297,12 +563,14 @@ impl Neo4jClient {
         &self,
         search_term: &str,
         depth: usize,
+        tenant: Option<&str>,
     ) -> Result<Vec<(String, Vec<(String, String)>)>> {
         let search_pattern = format!("(?i).*{}.*", regex::escape(search_term));
         let depth_val = depth.clamp(1, 10) as i64; // Clamp to reasonable range
 
+        // Concepts of one tenant only link to each other, so scoping the start is enough
         let cypher = format!(
-            "MATCH (n:Concept) WHERE n.label =~ $pattern \
+            "MATCH (n:Concept) WHERE n.label =~ $pattern AND ($tenant IS NULL OR n.tenant = $tenant) \
              WITH n \
              MATCH path = (n)-[r:RELATES_TO*1..{}]-(m:Concept) \
              UNWIND relationships(path) AS rel \
@@ -314,7 +582,11 @@ impl Neo4jClient {
 
         let mut result = self
             .graph
-            .execute(query(&cypher).param("pattern", search_pattern))
+            .execute(
+                query(&cypher)
+                    .param("pattern", search_pattern)
+                    .param("tenant", tenant),
+            )
             .await
             .context("Failed to search concepts with depth")?;
 
@@ -415,6 +687,7 @@ mod tests {
             degree: Some(5),
             entity_type: Some("concept".into()),
             tenant: "default".into(),
+            aliases: Vec::new(),
         };
         let json = serde_json::to_string(&node).unwrap();
         assert!(json.contains("Test Node"));
@@ -437,6 +710,7 @@ mod tests {
             target: "b".into(),
             relation: "knows".into(),
             weight: 4.5,
+            sources: Vec::new(),
         };
         let json = serde_json::to_string(&edge).unwrap();
         assert!(json.contains("\"source\":\"a\""));
@@ -468,6 +742,7 @@ mod tests {
             degree: Some(10),
             entity_type: Some("location".into()),
             tenant: "default".into(),
+            aliases: Vec::new(),
         };
         let json = serde_json::to_string(&original).unwrap();
         let back: GraphNode = serde_json::from_str(&json).unwrap();
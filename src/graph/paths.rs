@@ -0,0 +1,753 @@
+//! Relation-aware path finding over a fetched graph.
+//!
+//! Unlike the label-only petgraph used for analytics, [`PathGraph`] keeps
+//! the relation text and entity types so paths can be filtered by them and
+//! explained hop by hop. It supports:
+//!
+//! - weighted shortest paths (Dijkstra, cost = 1 / weight)
+//! - k-shortest loopless paths (Yen's algorithm)
+//! - all simple paths up to a hop limit
+//! - directed or undirected traversal
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::neo4j::{GraphEdge, GraphNode};
+
+/// Upper bound on paths returned by [`PathGraph::all_simple_paths`]
+pub const MAX_SIMPLE_PATHS: usize = 1000;
+
+/// Options that constrain which edges and nodes a path may use
+#[derive(Debug, Clone, Default)]
+pub struct PathOptions {
+    /// Only follow edges in their stored direction (source → target)
+    pub directed: bool,
+    /// Only follow edges whose relation contains one of these (case-insensitive)
+    pub include_relations: Vec<String>,
+    /// Never follow edges whose relation contains one of these (case-insensitive)
+    pub exclude_relations: Vec<String>,
+    /// Intermediate nodes must have one of these entity types
+    pub include_types: Vec<String>,
+    /// Intermediate nodes must not have any of these entity types
+    pub exclude_types: Vec<String>,
+}
+
+/// A single step of a path
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub from: String,
+    pub to: String,
+    pub relation: String,
+    pub weight: f64,
+    /// False when the hop walks an edge against its stored direction
+    pub forward: bool,
+}
+
+/// A path between two concepts, with the relation used on every hop
+#[derive(Debug, Clone)]
+pub struct GraphPath {
+    pub nodes: Vec<String>,
+    pub hops: Vec<Hop>,
+    pub cost: f64,
+}
+
+impl GraphPath {
+    /// Number of edges traversed
+    pub fn hop_count(&self) -> usize {
+        self.hops.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PathEdge {
+    source: usize,
+    target: usize,
+    relation: String,
+    weight: f64,
+}
+
+impl PathEdge {
+    fn cost(&self) -> f64 {
+        1.0 / self.weight.max(0.001)
+    }
+}
+
+/// Adjacency entry: (edge index, neighbor, forward)
+type Step = (usize, usize, bool);
+
+/// Graph used for path queries, built from fetched nodes and edges
+pub struct PathGraph {
+    labels: Vec<String>,
+    types: Vec<Option<String>>,
+    index: HashMap<String, usize>,
+    edges: Vec<PathEdge>,
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+}
+
+impl PathGraph {
+    /// Build a path graph from nodes and edges as returned by `fetch_graph`.
+    ///
+    /// Edges may reference ids without a matching node; those nodes are
+    /// created on the fly with no entity type.
+    pub fn from_data(nodes: &[GraphNode], edges: &[GraphEdge]) -> Self {
+        let mut graph = Self {
+            labels: Vec::new(),
+            types: Vec::new(),
+            index: HashMap::new(),
+            edges: Vec::new(),
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
+        };
+
+        for node in nodes {
+            let idx = graph.ensure_node(&node.id, &node.label);
+            if graph.types[idx].is_none() {
+                graph.types[idx] = node
+                    .entity_type
+                    .as_ref()
+                    .map(|t| t.to_lowercase().trim().to_string());
+            }
+        }
+
+        for edge in edges {
+            let source = graph.ensure_node(&edge.source, &edge.source);
+            let target = graph.ensure_node(&edge.target, &edge.target);
+            if source == target {
+                continue;
+            }
+            let idx = graph.edges.len();
+            graph.edges.push(PathEdge {
+                source,
+                target,
+                relation: edge.relation.clone(),
+                weight: edge.weight,
+            });
+            graph.outgoing[source].push(idx);
+            graph.incoming[target].push(idx);
+        }
+
+        graph
+    }
+
+    fn ensure_node(&mut self, id: &str, label: &str) -> usize {
+        let key = id.to_lowercase().trim().to_string();
+        if let Some(&idx) = self.index.get(&key) {
+            return idx;
+        }
+        let idx = self.labels.len();
+        self.labels.push(label.to_string());
+        self.types.push(None);
+        self.outgoing.push(Vec::new());
+        self.incoming.push(Vec::new());
+        self.index.insert(key, idx);
+        idx
+    }
+
+    /// Look up a node by id or label (case-insensitive)
+    pub fn find_node(&self, name: &str) -> Option<usize> {
+        let key = name.to_lowercase().trim().to_string();
+        self.index.get(&key).copied().or_else(|| {
+            self.labels
+                .iter()
+                .position(|label| label.to_lowercase() == key)
+        })
+    }
+
+    /// Whether a concept with this id or label exists
+    pub fn contains(&self, name: &str) -> bool {
+        self.find_node(name).is_some()
+    }
+
+    /// Weighted shortest path honoring the given options
+    pub fn shortest_path(&self, from: &str, to: &str, opts: &PathOptions) -> Option<GraphPath> {
+        self.k_shortest_paths(from, to, 1, opts).into_iter().next()
+    }
+
+    /// Up to `k` loopless shortest paths, cheapest first (Yen's algorithm)
+    pub fn k_shortest_paths(
+        &self,
+        from: &str,
+        to: &str,
+        k: usize,
+        opts: &PathOptions,
+    ) -> Vec<GraphPath> {
+        let (Some(start), Some(goal)) = (self.find_node(from), self.find_node(to)) else {
+            return Vec::new();
+        };
+        if k == 0 {
+            return Vec::new();
+        }
+        if start == goal {
+            return vec![self.to_graph_path(start, &[])];
+        }
+
+        let edge_ok = self.edge_filter(opts);
+        let node_ok = self.node_filter(opts, start, goal);
+
+        let no_nodes = HashSet::new();
+        let no_edges = HashSet::new();
+        let Some(first) =
+            self.dijkstra(start, goal, opts, &edge_ok, &node_ok, &no_nodes, &no_edges)
+        else {
+            return Vec::new();
+        };
+
+        let mut accepted: Vec<(f64, Vec<Step>)> = vec![first];
+        let mut candidates: Vec<(f64, Vec<Step>)> = Vec::new();
+
+        while accepted.len() < k {
+            let (_, last) = accepted.last().unwrap().clone();
+
+            for spur_pos in 0..last.len() {
+                let root = &last[..spur_pos];
+                let spur_node = if spur_pos == 0 {
+                    start
+                } else {
+                    root[spur_pos - 1].1
+                };
+
+                // Ban the next edge of every accepted path sharing this root
+                let mut banned_edges = HashSet::new();
+                for (_, path) in &accepted {
+                    if path.len() > spur_pos && path[..spur_pos] == *root {
+                        banned_edges.insert(path[spur_pos].0);
+                    }
+                }
+
+                // Ban root nodes so the spur path stays loopless
+                let mut banned_nodes: HashSet<usize> = HashSet::new();
+                banned_nodes.insert(start);
+                for step in root {
+                    banned_nodes.insert(step.1);
+                }
+                banned_nodes.remove(&spur_node);
+
+                if let Some((spur_cost, spur_path)) = self.dijkstra(
+                    spur_node,
+                    goal,
+                    opts,
+                    &edge_ok,
+                    &node_ok,
+                    &banned_nodes,
+                    &banned_edges,
+                ) {
+                    let root_cost: f64 = root.iter().map(|s| self.edges[s.0].cost()).sum();
+                    let mut total: Vec<Step> = root.to_vec();
+                    total.extend(spur_path);
+                    let already_known = accepted.iter().any(|(_, p)| *p == total)
+                        || candidates.iter().any(|(_, p)| *p == total);
+                    if !already_known {
+                        candidates.push((root_cost + spur_cost, total));
+                    }
+                }
+            }
+
+            if candidates.is_empty() {
+                break;
+            }
+
+            // Take the cheapest candidate (fewest hops on ties)
+            let best = candidates
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    a.0.partial_cmp(&b.0)
+                        .unwrap_or(Ordering::Equal)
+                        .then(a.1.len().cmp(&b.1.len()))
+                })
+                .map(|(i, _)| i)
+                .unwrap();
+            accepted.push(candidates.swap_remove(best));
+        }
+
+        accepted
+            .into_iter()
+            .map(|(_, steps)| self.to_graph_path(start, &steps))
+            .collect()
+    }
+
+    /// All simple paths with at most `max_hops` edges, shortest first.
+    ///
+    /// Paths are searched one length at a time, so when the result stops at
+    /// [`MAX_SIMPLE_PATHS`] (to keep dense graphs tractable) it still holds
+    /// every path shorter than the longest one returned.
+    pub fn all_simple_paths(
+        &self,
+        from: &str,
+        to: &str,
+        max_hops: usize,
+        opts: &PathOptions,
+    ) -> Vec<GraphPath> {
+        let (Some(start), Some(goal)) = (self.find_node(from), self.find_node(to)) else {
+            return Vec::new();
+        };
+        if start == goal {
+            return vec![self.to_graph_path(start, &[])];
+        }
+
+        let edge_ok = self.edge_filter(opts);
+        let node_ok = self.node_filter(opts, start, goal);
+
+        let mut found: Vec<Vec<Step>> = Vec::new();
+        let mut visited = vec![false; self.labels.len()];
+        visited[start] = true;
+        let mut stack: Vec<Step> = Vec::new();
+
+        for hops in 1..=max_hops {
+            if found.len() >= MAX_SIMPLE_PATHS {
+                break;
+            }
+            self.dfs(
+                start,
+                goal,
+                hops,
+                opts,
+                &edge_ok,
+                &node_ok,
+                &mut visited,
+                &mut stack,
+                &mut found,
+            );
+        }
+
+        let mut paths: Vec<GraphPath> = found
+            .iter()
+            .map(|steps| self.to_graph_path(start, steps))
+            .collect();
+        paths.sort_by(|a, b| {
+            a.hop_count()
+                .cmp(&b.hop_count())
+                .then(a.cost.partial_cmp(&b.cost).unwrap_or(Ordering::Equal))
+        });
+        paths
+    }
+
+    /// Collect the simple paths from `current` that reach `goal` in exactly `hops_left` hops
+    #[allow(clippy::too_many_arguments)]
+    fn dfs(
+        &self,
+        current: usize,
+        goal: usize,
+        hops_left: usize,
+        opts: &PathOptions,
+        edge_ok: &[bool],
+        node_ok: &[bool],
+        visited: &mut [bool],
+        stack: &mut Vec<Step>,
+        found: &mut Vec<Vec<Step>>,
+    ) {
+        if found.len() >= MAX_SIMPLE_PATHS || hops_left == 0 {
+            return;
+        }
+        for step in self.neighbors(current, opts) {
+            let (edge, next, _) = step;
+            if !edge_ok[edge] || visited[next] {
+                continue;
+            }
+            if next == goal {
+                if hops_left > 1 {
+                    continue;
+                }
+                stack.push(step);
+                found.push(stack.clone());
+                stack.pop();
+                if found.len() >= MAX_SIMPLE_PATHS {
+                    return;
+                }
+                continue;
+            }
+            if !node_ok[next] || hops_left == 1 {
+                continue;
+            }
+            visited[next] = true;
+            stack.push(step);
+            self.dfs(
+                next,
+                goal,
+                hops_left - 1,
+                opts,
+                edge_ok,
+                node_ok,
+                visited,
+                stack,
+                found,
+            );
+            stack.pop();
+            visited[next] = false;
+        }
+    }
+
+    /// Dijkstra from `start` to `goal`, returning the cost and the steps taken
+    #[allow(clippy::too_many_arguments)]
+    fn dijkstra(
+        &self,
+        start: usize,
+        goal: usize,
+        opts: &PathOptions,
+        edge_ok: &[bool],
+        node_ok: &[bool],
+        banned_nodes: &HashSet<usize>,
+        banned_edges: &HashSet<usize>,
+    ) -> Option<(f64, Vec<Step>)> {
+        let n = self.labels.len();
+        let mut dist = vec![f64::INFINITY; n];
+        let mut prev: Vec<Option<Step>> = vec![None; n];
+        let mut heap = BinaryHeap::new();
+
+        dist[start] = 0.0;
+        heap.push(QueueEntry {
+            cost: 0.0,
+            node: start,
+        });
+
+        while let Some(QueueEntry { cost, node }) = heap.pop() {
+            if node == goal {
+                break;
+            }
+            if cost > dist[node] {
+                continue;
+            }
+            for step in self.neighbors(node, opts) {
+                let (edge, next, _) = step;
+                if !edge_ok[edge]
+                    || banned_edges.contains(&edge)
+                    || banned_nodes.contains(&next)
+                    || (next != goal && !node_ok[next])
+                {
+                    continue;
+                }
+                let next_cost = cost + self.edges[edge].cost();
+                if next_cost < dist[next] {
+                    dist[next] = next_cost;
+                    prev[next] = Some((edge, node, step.2));
+                    heap.push(QueueEntry {
+                        cost: next_cost,
+                        node: next,
+                    });
+                }
+            }
+        }
+
+        if dist[goal].is_infinite() {
+            return None;
+        }
+
+        // Walk predecessors back to the start
+        let mut steps = Vec::new();
+        let mut current = goal;
+        while current != start {
+            let (edge, from, forward) = prev[current]?;
+            steps.push((edge, current, forward));
+            current = from;
+        }
+        steps.reverse();
+        Some((dist[goal], steps))
+    }
+
+    /// Edges leaving `node` under the traversal mode
+    fn neighbors(&self, node: usize, opts: &PathOptions) -> Vec<Step> {
+        let mut steps: Vec<Step> = self.outgoing[node]
+            .iter()
+            .map(|&e| (e, self.edges[e].target, true))
+            .collect();
+        if !opts.directed {
+            steps.extend(
+                self.incoming[node]
+                    .iter()
+                    .map(|&e| (e, self.edges[e].source, false)),
+            );
+        }
+        steps
+    }
+
+    fn edge_filter(&self, opts: &PathOptions) -> Vec<bool> {
+        let include: Vec<String> = opts
+            .include_relations
+            .iter()
+            .map(|r| r.to_lowercase())
+            .collect();
+        let exclude: Vec<String> = opts
+            .exclude_relations
+            .iter()
+            .map(|r| r.to_lowercase())
+            .collect();
+
+        self.edges
+            .iter()
+            .map(|e| {
+                let relation = e.relation.to_lowercase();
+                (include.is_empty() || include.iter().any(|r| relation.contains(r.as_str())))
+                    && !exclude.iter().any(|r| relation.contains(r.as_str()))
+            })
+            .collect()
+    }
+
+    /// Type filters apply to intermediate nodes only; the endpoints always pass
+    fn node_filter(&self, opts: &PathOptions, start: usize, goal: usize) -> Vec<bool> {
+        let include: Vec<String> = opts
+            .include_types
+            .iter()
+            .map(|t| t.to_lowercase())
+            .collect();
+        let exclude: Vec<String> = opts
+            .exclude_types
+            .iter()
+            .map(|t| t.to_lowercase())
+            .collect();
+
+        self.types
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                if i == start || i == goal {
+                    return true;
+                }
+                let t = t.as_deref().unwrap_or("concept");
+                (include.is_empty() || include.iter().any(|x| x == t))
+                    && !exclude.iter().any(|x| x == t)
+            })
+            .collect()
+    }
+
+    fn to_graph_path(&self, start: usize, steps: &[Step]) -> GraphPath {
+        let mut nodes = vec![self.labels[start].clone()];
+        let mut hops = Vec::with_capacity(steps.len());
+        let mut current = start;
+        let mut cost = 0.0;
+
+        for &(edge, next, forward) in steps {
+            let e = &self.edges[edge];
+            hops.push(Hop {
+                from: self.labels[current].clone(),
+                to: self.labels[next].clone(),
+                relation: e.relation.clone(),
+                weight: e.weight,
+                forward,
+            });
+            nodes.push(self.labels[next].clone());
+            cost += e.cost();
+            current = next;
+        }
+
+        GraphPath { nodes, hops, cost }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct QueueEntry {
+    cost: f64,
+    node: usize,
+}
+
+impl Eq for QueueEntry {}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed for a min-heap
+        other
+            .cost
+            .partial_cmp(&self.cost)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, entity_type: &str) -> GraphNode {
+        GraphNode {
+            id: id.into(),
+            label: id.into(),
+            community: None,
+            degree: None,
+            entity_type: Some(entity_type.into()),
+            tenant: "default".into(),
+            aliases: Vec::new(),
+        }
+    }
+
+    fn edge(source: &str, target: &str, relation: &str, weight: f64) -> GraphEdge {
+        GraphEdge {
+            source: source.into(),
+            target: target.into(),
+            relation: relation.into(),
+            weight,
+            sources: Vec::new(),
+        }
+    }
+
+    /// producer → kafka → consumer, plus a weaker detour through zookeeper
+    fn sample_graph() -> PathGraph {
+        let nodes = vec![
+            node("producer", "service"),
+            node("kafka", "message broker"),
+            node("consumer", "service"),
+            node("zookeeper", "coordinator"),
+        ];
+        let edges = vec![
+            edge("producer", "kafka", "publishes to", 8.0),
+            edge("kafka", "consumer", "delivers to", 8.0),
+            edge("producer", "zookeeper", "contextual proximity", 1.0),
+            edge("zookeeper", "consumer", "contextual proximity", 1.0),
+        ];
+        PathGraph::from_data(&nodes, &edges)
+    }
+
+    #[test]
+    fn test_shortest_path_reports_relations() {
+        let g = sample_graph();
+        let path = g
+            .shortest_path("producer", "consumer", &PathOptions::default())
+            .unwrap();
+        assert_eq!(path.nodes, vec!["producer", "kafka", "consumer"]);
+        assert_eq!(path.hops[0].relation, "publishes to");
+        assert_eq!(path.hops[1].relation, "delivers to");
+        assert!(path.hops.iter().all(|h| h.forward));
+    }
+
+    #[test]
+    fn test_k_shortest_returns_alternatives_in_cost_order() {
+        let g = sample_graph();
+        let paths = g.k_shortest_paths("producer", "consumer", 3, &PathOptions::default());
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].nodes[1], "kafka");
+        assert_eq!(paths[1].nodes[1], "zookeeper");
+        assert!(paths[0].cost <= paths[1].cost);
+    }
+
+    #[test]
+    fn test_directed_mode_respects_edge_direction() {
+        let g = sample_graph();
+        let opts = PathOptions {
+            directed: true,
+            ..Default::default()
+        };
+        assert!(g.shortest_path("producer", "consumer", &opts).is_some());
+        assert!(g.shortest_path("consumer", "producer", &opts).is_none());
+
+        let back = g
+            .shortest_path("consumer", "producer", &PathOptions::default())
+            .unwrap();
+        assert!(back.hops.iter().all(|h| !h.forward));
+    }
+
+    #[test]
+    fn test_exclude_relation_filter() {
+        let g = sample_graph();
+        let opts = PathOptions {
+            exclude_relations: vec!["publishes".into()],
+            ..Default::default()
+        };
+        let path = g.shortest_path("producer", "consumer", &opts).unwrap();
+        assert_eq!(path.nodes[1], "zookeeper");
+    }
+
+    #[test]
+    fn test_include_relation_filter() {
+        let g = sample_graph();
+        let opts = PathOptions {
+            include_relations: vec!["Contextual".into()],
+            ..Default::default()
+        };
+        let path = g.shortest_path("producer", "consumer", &opts).unwrap();
+        assert!(
+            path.hops
+                .iter()
+                .all(|h| h.relation == "contextual proximity")
+        );
+    }
+
+    #[test]
+    fn test_entity_type_filters_apply_to_intermediate_nodes() {
+        let g = sample_graph();
+        let opts = PathOptions {
+            exclude_types: vec!["message broker".into()],
+            ..Default::default()
+        };
+        let path = g.shortest_path("producer", "consumer", &opts).unwrap();
+        assert_eq!(path.nodes[1], "zookeeper");
+
+        // Endpoints are never filtered out
+        let opts = PathOptions {
+            include_types: vec!["coordinator".into()],
+            ..Default::default()
+        };
+        let path = g.shortest_path("producer", "consumer", &opts).unwrap();
+        assert_eq!(path.nodes, vec!["producer", "zookeeper", "consumer"]);
+    }
+
+    #[test]
+    fn test_all_simple_paths_respects_max_hops() {
+        let g = sample_graph();
+        let paths = g.all_simple_paths("producer", "consumer", 2, &PathOptions::default());
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|p| p.hop_count() == 2));
+
+        let paths = g.all_simple_paths("producer", "consumer", 1, &PathOptions::default());
+        assert!(paths.is_empty());
+    }
+
+    #[test]
+    fn test_all_simple_paths_keeps_shortest_when_truncated() {
+        // 1000 four-hop paths through three layers of ten, found by DFS before
+        // the direct edge and the two-hop detour added last
+        let mut nodes = vec![node("start", "x"), node("goal", "x"), node("detour", "x")];
+        let mut edges = Vec::new();
+        for layer in ["a", "b", "c"] {
+            for i in 0..10 {
+                nodes.push(node(&format!("{}{}", layer, i), "x"));
+            }
+        }
+        for i in 0..10 {
+            edges.push(edge("start", &format!("a{}", i), "to", 1.0));
+            for j in 0..10 {
+                edges.push(edge(&format!("a{}", i), &format!("b{}", j), "to", 1.0));
+                edges.push(edge(&format!("b{}", i), &format!("c{}", j), "to", 1.0));
+            }
+            edges.push(edge(&format!("c{}", i), "goal", "to", 1.0));
+        }
+        edges.push(edge("start", "detour", "to", 1.0));
+        edges.push(edge("detour", "goal", "to", 1.0));
+        edges.push(edge("start", "goal", "to", 1.0));
+        let g = PathGraph::from_data(&nodes, &edges);
+
+        let opts = PathOptions {
+            directed: true,
+            ..Default::default()
+        };
+        let paths = g.all_simple_paths("start", "goal", 4, &opts);
+        assert_eq!(paths.len(), MAX_SIMPLE_PATHS);
+        assert_eq!(paths[0].nodes, vec!["start", "goal"]);
+        assert_eq!(paths[1].nodes, vec!["start", "detour", "goal"]);
+        assert!(paths[2..].iter().all(|p| p.hop_count() == 4));
+    }
+
+    #[test]
+    fn test_case_insensitive_lookup_and_same_node() {
+        let g = sample_graph();
+        assert!(g.contains("KAFKA"));
+        let path = g
+            .shortest_path("Kafka", "kafka", &PathOptions::default())
+            .unwrap();
+        assert_eq!(path.hop_count(), 0);
+        assert_eq!(path.nodes, vec!["kafka"]);
+    }
+
+    #[test]
+    fn test_missing_node_returns_nothing() {
+        let g = sample_graph();
+        assert!(
+            g.k_shortest_paths("producer", "nope", 2, &PathOptions::default())
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Fuzzy resolution of user-typed concept names against graph labels.
+//!
+//! Concepts are stored lowercased, and LLM extraction rarely agrees on exact
+//! spelling, so `path`, `query` and `add` resolve their arguments through
+//! [`ConceptResolver`] instead of requiring an exact label. Matching tries,
+//! in order: exact label, exact alias, prefix, and finally a fuzzy score that
+//! takes the better of normalized edit distance and trigram similarity.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use super::neo4j::GraphNode;
+
+/// Candidates scoring below this are not suggested
+const MIN_SCORE: f64 = 0.55;
+
+/// A fuzzy match is used without asking only when it is this good...
+const CONFIDENT_SCORE: f64 = 0.85;
+
+/// ...and leads the runner-up by at least this much
+const CONFIDENT_MARGIN: f64 = 0.15;
+
+/// How a candidate matched the query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    Exact,
+    Alias,
+    Prefix,
+    Fuzzy,
+}
+
+impl std::fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchKind::Exact => write!(f, "exact"),
+            MatchKind::Alias => write!(f, "alias"),
+            MatchKind::Prefix => write!(f, "prefix"),
+            MatchKind::Fuzzy => write!(f, "fuzzy"),
+        }
+    }
+}
+
+/// A concept that matched a query
+#[derive(Debug, Clone)]
+pub struct ConceptMatch {
+    /// Canonical label of the concept
+    pub label: String,
+    /// The label or alias that produced the match
+    pub matched: String,
+    pub kind: MatchKind,
+    /// Similarity in 0.0..=1.0
+    pub score: f64,
+}
+
+/// Outcome of resolving a query
+#[derive(Debug, Clone)]
+pub enum Resolution {
+    /// An exact hit, or a fuzzy hit that clearly beats every alternative
+    Found(ConceptMatch),
+    /// Several plausible candidates, best first
+    Ambiguous(Vec<ConceptMatch>),
+    NotFound,
+}
+
+struct Candidate {
+    label: String,
+    aliases: Vec<String>,
+}
+
+/// Resolves free-text concept names against known labels and aliases
+pub struct ConceptResolver {
+    candidates: Vec<Candidate>,
+}
+
+impl ConceptResolver {
+    /// Build a resolver from `(label, aliases)` pairs
+    pub fn new(concepts: impl IntoIterator<Item = (String, Vec<String>)>) -> Self {
+        let mut seen = HashSet::new();
+        let candidates = concepts
+            .into_iter()
+            .filter(|(label, _)| seen.insert(normalize(label)))
+            .map(|(label, aliases)| Candidate { label, aliases })
+            .collect();
+        Self { candidates }
+    }
+
+    /// Build a resolver from fetched graph nodes
+    pub fn from_nodes(nodes: &[GraphNode]) -> Self {
+        Self::new(nodes.iter().map(|n| (n.label.clone(), n.aliases.clone())))
+    }
+
+    /// Best matches for `query`, highest score first
+    pub fn candidates(&self, query: &str, limit: usize) -> Vec<ConceptMatch> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<ConceptMatch> = self
+            .candidates
+            .iter()
+            .filter_map(|c| {
+                let label_match =
+                    score(&query, &normalize(&c.label)).map(|(s, kind)| (s, kind, &c.label));
+                let alias_match = c
+                    .aliases
+                    .iter()
+                    .filter_map(|a| {
+                        score(&query, &normalize(a)).map(|(s, kind)| {
+                            let kind = if kind == MatchKind::Exact {
+                                MatchKind::Alias
+                            } else {
+                                kind
+                            };
+                            // Prefer the label when it scores as well as an alias
+                            (s * 0.99, kind, a)
+                        })
+                    })
+                    .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+                [label_match, alias_match]
+                    .into_iter()
+                    .flatten()
+                    .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+                    .map(|(score, kind, matched)| ConceptMatch {
+                        label: c.label.clone(),
+                        matched: matched.clone(),
+                        kind,
+                        score,
+                    })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.label.len().cmp(&b.label.len()))
+                .then_with(|| a.label.cmp(&b.label))
+        });
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Resolve `query` to a single concept if that can be done safely
+    pub fn resolve(&self, query: &str) -> Resolution {
+        let matches = self.candidates(query, 10);
+
+        let exact: Vec<&ConceptMatch> = matches
+            .iter()
+            .filter(|m| matches!(m.kind, MatchKind::Exact | MatchKind::Alias))
+            .collect();
+        if let Some(m) = exact.iter().find(|m| m.kind == MatchKind::Exact) {
+            return Resolution::Found((*m).clone());
+        }
+        if exact.len() == 1 {
+            return Resolution::Found(exact[0].clone());
+        }
+        if exact.len() > 1 {
+            return Resolution::Ambiguous(exact.into_iter().cloned().collect());
+        }
+
+        match matches.as_slice() {
+            [] => Resolution::NotFound,
+            [only] if only.score >= CONFIDENT_SCORE => Resolution::Found(only.clone()),
+            [best, second, ..]
+                if best.score >= CONFIDENT_SCORE
+                    && best.score - second.score >= CONFIDENT_MARGIN =>
+            {
+                Resolution::Found(best.clone())
+            }
+            _ => Resolution::Ambiguous(matches),
+        }
+    }
+}
+
+/// How alike two concept names are, in 0.0..=1.0, scored as the resolver
+/// scores a query against a label (either name may be the shorter one)
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize(a), normalize(b));
+    let forward = score(&a, &b).map_or(0.0, |(s, _)| s);
+    let backward = score(&b, &a).map_or(0.0, |(s, _)| s);
+    forward.max(backward)
+}
+
+/// Lowercase, trim and fold separators so "Apache_Kafka" equals "apache kafka"
+fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .replace(['_', '-'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Score a normalized query against a normalized candidate
+fn score(query: &str, candidate: &str) -> Option<(f64, MatchKind)> {
+    if candidate.is_empty() {
+        return None;
+    }
+    if query == candidate {
+        return Some((1.0, MatchKind::Exact));
+    }
+
+    let ratio = query.chars().count() as f64 / candidate.chars().count() as f64;
+    if candidate.starts_with(query) {
+        return Some((0.8 + 0.15 * ratio.min(1.0), MatchKind::Prefix));
+    }
+    if candidate
+        .split(' ')
+        .any(|word| word.starts_with(query) && query.len() >= 3)
+    {
+        return Some((0.7 + 0.15 * ratio.min(1.0), MatchKind::Prefix));
+    }
+
+    let fuzzy = levenshtein_similarity(query, candidate).max(trigram_similarity(query, candidate));
+    (fuzzy >= MIN_SCORE).then_some((fuzzy, MatchKind::Fuzzy))
+}
+
+/// 1 - edit_distance / max_len
+fn levenshtein_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    1.0 - prev[b.len()] as f64 / max_len as f64
+}
+
+/// Dice coefficient over padded character trigrams
+fn trigram_similarity(a: &str, b: &str) -> f64 {
+    let ta = trigrams(a);
+    let tb = trigrams(b);
+    if ta.is_empty() || tb.is_empty() {
+        return 0.0;
+    }
+    let shared = ta.intersection(&tb).count();
+    2.0 * shared as f64 / (ta.len() + tb.len()) as f64
+}
+
+fn trigrams(s: &str) -> HashSet<[char; 3]> {
+    let padded: Vec<char> = format!("  {} ", s).chars().collect();
+    padded.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver() -> ConceptResolver {
+        ConceptResolver::new(vec![
+            ("apache kafka".to_string(), vec!["kafka".to_string()]),
+            ("kafka streams".to_string(), vec![]),
+            ("kubernetes".to_string(), vec!["k8s".to_string()]),
+            ("postgresql".to_string(), vec!["postgres".to_string()]),
+            ("redis".to_string(), vec![]),
+        ])
+    }
+
+    #[test]
+    fn test_exact_match_ignores_case_and_separators() {
+        match resolver().resolve("Apache_Kafka") {
+            Resolution::Found(m) => {
+                assert_eq!(m.label, "apache kafka");
+                assert_eq!(m.kind, MatchKind::Exact);
+            }
+            other => panic!("expected exact match, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_alias_match() {
+        match resolver().resolve("K8S") {
+            Resolution::Found(m) => {
+                assert_eq!(m.label, "kubernetes");
+                assert_eq!(m.kind, MatchKind::Alias);
+            }
+            other => panic!("expected alias match, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_typo_resolves_to_closest() {
+        match resolver().resolve("kubernets") {
+            Resolution::Found(m) => {
+                assert_eq!(m.label, "kubernetes");
+                assert_eq!(m.kind, MatchKind::Fuzzy);
+            }
+            other => panic!("expected fuzzy match, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_shared_prefix_is_ambiguous() {
+        match resolver().resolve("kaf") {
+            Resolution::Ambiguous(matches) => {
+                let labels: Vec<&str> = matches.iter().map(|m| m.label.as_str()).collect();
+                assert!(labels.contains(&"apache kafka"));
+                assert!(labels.contains(&"kafka streams"));
+            }
+            other => panic!("expected ambiguity, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unrelated_query_not_found() {
+        assert!(matches!(
+            resolver().resolve("quantum chromodynamics"),
+            Resolution::NotFound
+        ));
+    }
+
+    #[test]
+    fn test_candidates_sorted_and_limited() {
+        let matches = resolver().candidates("postgre", 1);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].label, "postgresql");
+    }
+
+    #[test]
+    fn test_similarity_measures() {
+        assert!((levenshtein_similarity("kitten", "kitten") - 1.0).abs() < f64::EPSILON);
+        assert!(levenshtein_similarity("kitten", "sitting") > 0.5);
+        assert!(trigram_similarity("postgres", "postgresql") > 0.7);
+        assert_eq!(trigram_similarity("abc", ""), 0.0);
+        assert_eq!(similarity("Apache_Kafka", "apache kafka"), 1.0);
+        assert_eq!(similarity("kafka", "redis"), 0.0);
+        assert_eq!(
+            similarity("postgres", "postgresql"),
+            similarity("postgresql", "postgres")
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Ego-network extraction: everything within N hops of a concept.
+//!
+//! Works on already-fetched nodes and edges, so the same code serves Neo4j
+//! and JSON graph files, and the result can go straight to any exporter.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::neo4j::{GraphEdge, GraphNode, TenantEdge};
+
+/// Options that limit which edges an ego network follows
+#[derive(Debug, Clone, Default)]
+pub struct SubgraphOptions {
+    /// Maximum number of hops from the center
+    pub depth: usize,
+    /// Ignore edges lighter than this
+    pub min_weight: f64,
+    /// Only follow edges whose relation contains one of these (case-insensitive)
+    pub include_relations: Vec<String>,
+    /// Never follow edges whose relation contains one of these (case-insensitive)
+    pub exclude_relations: Vec<String>,
+}
+
+impl SubgraphOptions {
+    fn allows(&self, edge: &GraphEdge) -> bool {
+        let relation = edge.relation.to_lowercase();
+        edge.weight >= self.min_weight
+            && (self.include_relations.is_empty()
+                || self
+                    .include_relations
+                    .iter()
+                    .any(|r| relation.contains(&r.to_lowercase())))
+            && !self
+                .exclude_relations
+                .iter()
+                .any(|r| relation.contains(&r.to_lowercase()))
+    }
+}
+
+/// Extract the ego network around `center` (matched by id or label, ignoring case).
+///
+/// Edges are followed in both directions. The result holds every node reached
+/// within `depth` hops and every allowed edge between those nodes. Returns
+/// `None` if the center is not in the graph.
+pub fn ego_network(
+    nodes: &[GraphNode],
+    edges: &[GraphEdge],
+    center: &str,
+    opts: &SubgraphOptions,
+) -> Option<(Vec<GraphNode>, Vec<GraphEdge>)> {
+    let wanted = center.to_lowercase();
+    let center_id = nodes
+        .iter()
+        .find(|n| n.id.to_lowercase() == wanted || n.label.to_lowercase() == wanted)?
+        .id
+        .clone();
+
+    let allowed: Vec<&GraphEdge> = edges.iter().filter(|e| opts.allows(e)).collect();
+
+    let mut adjacency: HashMap<&str, Vec<&str>> = HashMap::new();
+    for edge in &allowed {
+        adjacency
+            .entry(edge.source.as_str())
+            .or_default()
+            .push(edge.target.as_str());
+        adjacency
+            .entry(edge.target.as_str())
+            .or_default()
+            .push(edge.source.as_str());
+    }
+
+    let mut reached: HashSet<&str> = HashSet::from([center_id.as_str()]);
+    let mut queue = VecDeque::from([(center_id.as_str(), 0)]);
+    while let Some((id, dist)) = queue.pop_front() {
+        if dist == opts.depth {
+            continue;
+        }
+        for &next in adjacency.get(id).into_iter().flatten() {
+            if reached.insert(next) {
+                queue.push_back((next, dist + 1));
+            }
+        }
+    }
+
+    let sub_nodes = nodes
+        .iter()
+        .filter(|n| reached.contains(n.id.as_str()))
+        .cloned()
+        .collect();
+    let sub_edges = allowed
+        .into_iter()
+        .filter(|e| reached.contains(e.source.as_str()) && reached.contains(e.target.as_str()))
+        .cloned()
+        .collect();
+
+    Some((sub_nodes, sub_edges))
+}
+
+/// Keep only the nodes and edges of one tenant, so an ego network cannot cross
+/// into another tenant through a shared concept id.
+///
+/// The tenant is `tenant` when given, else that of the node matching `center`.
+/// Returns `None` if no node matches `center`.
+pub fn tenant_slice(
+    nodes: Vec<GraphNode>,
+    edges: Vec<TenantEdge>,
+    center: &str,
+    tenant: Option<&str>,
+) -> Option<(String, Vec<GraphNode>, Vec<GraphEdge>)> {
+    let wanted = center.to_lowercase();
+    let tenant = match tenant {
+        Some(tenant) => tenant.to_string(),
+        None => nodes
+            .iter()
+            .find(|n| n.id.to_lowercase() == wanted || n.label.to_lowercase() == wanted)?
+            .tenant
+            .clone(),
+    };
+
+    let nodes = nodes.into_iter().filter(|n| n.tenant == tenant).collect();
+    let edges = edges
+        .into_iter()
+        .filter(|e| e.tenant == tenant)
+        .map(GraphEdge::from)
+        .collect();
+
+    Some((tenant, nodes, edges))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str) -> GraphNode {
+        GraphNode {
+            id: id.into(),
+            label: id.into(),
+            community: None,
+            degree: None,
+            entity_type: None,
+            tenant: "default".into(),
+            aliases: Vec::new(),
+        }
+    }
+
+    fn edge(source: &str, target: &str, relation: &str, weight: f64) -> GraphEdge {
+        GraphEdge {
+            source: source.into(),
+            target: target.into(),
+            relation: relation.into(),
+            weight,
+            sources: Vec::new(),
+        }
+    }
+
+    fn sample() -> (Vec<GraphNode>, Vec<GraphEdge>) {
+        let nodes = [
+            "kafka",
+            "producer",
+            "consumer",
+            "zookeeper",
+            "broker",
+            "java",
+        ]
+        .into_iter()
+        .map(node)
+        .collect();
+        let edges = vec![
+            edge("producer", "kafka", "writes to", 2.0),
+            edge("consumer", "kafka", "reads from", 2.0),
+            edge("kafka", "zookeeper", "contextual proximity", 0.5),
+            edge("zookeeper", "broker", "coordinates", 1.0),
+            edge("broker", "java", "runs on", 1.0),
+        ];
+        (nodes, edges)
+    }
+
+    fn ids(nodes: &[GraphNode]) -> Vec<&str> {
+        let mut ids: Vec<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_depth_limits_reach() {
+        let (nodes, edges) = sample();
+        let opts = SubgraphOptions {
+            depth: 1,
+            ..Default::default()
+        };
+        let (sub_nodes, sub_edges) = ego_network(&nodes, &edges, "kafka", &opts).unwrap();
+        assert_eq!(
+            ids(&sub_nodes),
+            vec!["consumer", "kafka", "producer", "zookeeper"]
+        );
+        assert_eq!(sub_edges.len(), 3);
+
+        let opts = SubgraphOptions {
+            depth: 2,
+            ..Default::default()
+        };
+        let (sub_nodes, _) = ego_network(&nodes, &edges, "KAFKA", &opts).unwrap();
+        assert!(ids(&sub_nodes).contains(&"broker"));
+        assert!(!ids(&sub_nodes).contains(&"java"));
+    }
+
+    #[test]
+    fn test_excluded_relations_are_not_followed() {
+        let (nodes, edges) = sample();
+        let opts = SubgraphOptions {
+            depth: 3,
+            exclude_relations: vec!["contextual proximity".into()],
+            ..Default::default()
+        };
+        let (sub_nodes, sub_edges) = ego_network(&nodes, &edges, "kafka", &opts).unwrap();
+        assert_eq!(ids(&sub_nodes), vec!["consumer", "kafka", "producer"]);
+        assert!(
+            sub_edges
+                .iter()
+                .all(|e| e.relation != "contextual proximity")
+        );
+    }
+
+    #[test]
+    fn test_min_weight_and_include_filter() {
+        let (nodes, edges) = sample();
+        let opts = SubgraphOptions {
+            depth: 3,
+            min_weight: 1.0,
+            include_relations: vec!["WRITES".into()],
+            ..Default::default()
+        };
+        let (sub_nodes, sub_edges) = ego_network(&nodes, &edges, "kafka", &opts).unwrap();
+        assert_eq!(ids(&sub_nodes), vec!["kafka", "producer"]);
+        assert_eq!(sub_edges.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_center() {
+        let (nodes, edges) = sample();
+        assert!(ego_network(&nodes, &edges, "redis", &SubgraphOptions::default()).is_none());
+    }
+
+    #[test]
+    fn test_tenant_slice_does_not_cross_tenants() {
+        let in_tenant = |id: &str, tenant: &str| GraphNode {
+            tenant: tenant.into(),
+            ..node(id)
+        };
+        let nodes = vec![
+            in_tenant("kafka", "acme"),
+            in_tenant("producer", "acme"),
+            in_tenant("kafka", "globex"),
+            in_tenant("spark", "globex"),
+        ];
+        let edges = vec![
+            edge("producer", "kafka", "writes to", 1.0).with_tenant("acme"),
+            edge("kafka", "spark", "feeds", 1.0).with_tenant("globex"),
+        ];
+        let opts = SubgraphOptions {
+            depth: 2,
+            ..Default::default()
+        };
+
+        let (tenant, nodes_a, edges_a) =
+            tenant_slice(nodes.clone(), edges.clone(), "kafka", Some("acme")).unwrap();
+        assert_eq!(tenant, "acme");
+        let (sub_nodes, sub_edges) = ego_network(&nodes_a, &edges_a, "kafka", &opts).unwrap();
+        assert_eq!(ids(&sub_nodes), vec!["kafka", "producer"]);
+        assert!(sub_nodes.iter().all(|n| n.tenant == "acme"));
+        assert_eq!(sub_edges.len(), 1);
+        assert_eq!(sub_edges[0].source, "producer");
+
+        // Without a tenant, the center's own tenant is used
+        let (tenant, nodes_b, edges_b) = tenant_slice(nodes, edges, "spark", None).unwrap();
+        assert_eq!(tenant, "globex");
+        let (sub_nodes, sub_edges) = ego_network(&nodes_b, &edges_b, "kafka", &opts).unwrap();
+        assert_eq!(ids(&sub_nodes), vec!["kafka", "spark"]);
+        assert_eq!(sub_edges.len(), 1);
+        assert_eq!(sub_edges[0].target, "spark");
+    }
+
+    #[test]
+    fn test_depth_zero_is_center_only() {
+        let (nodes, edges) = sample();
+        let (sub_nodes, sub_edges) =
+            ego_network(&nodes, &edges, "kafka", &SubgraphOptions::default()).unwrap();
+        assert_eq!(ids(&sub_nodes), vec!["kafka"]);
+        assert!(sub_edges.is_empty());
+    }
+}
@@ -4,8 +4,8 @@ use tokio::sync::Semaphore;
 use tracing::{debug, info, warn};
 
 use crate::config::DomainConfig;
-use crate::llm::{LlmClient, Relation};
-use crate::parser::{AdaptiveChunker, Chunk, ModelContextLimits};
+use crate::llm::{ContentKind, LlmClient, MentionTable, Relation};
+use crate::parser::{AdaptiveChunker, Chunk, ChunkPlan, ModelProfile};
 
 /// Processor that handles context overflow with automatic retry
 pub struct AdaptiveProcessor {
@@ -20,12 +20,10 @@ pub struct AdaptiveProcessor {
 #[allow(dead_code)]
 impl AdaptiveProcessor {
     /// Create a new adaptive processor for a specific model
-    pub fn new(llm_client: LlmClient, model: &str, concurrency: usize) -> Self {
-        let chunker = ModelContextLimits::create_chunker(model);
-
+    pub fn new(llm_client: LlmClient, model: &ModelProfile, concurrency: usize) -> Self {
         Self {
             llm_client: Arc::new(llm_client),
-            chunker,
+            chunker: model.chunker(&ChunkPlan::for_model(model)),
             max_retries: 3,
             concurrency,
             domain_config: None,
@@ -121,7 +119,15 @@ impl AdaptiveProcessor {
                 attempt + 1
             );
 
-            match client.extract_relations(&current_chunk.text, domain).await {
+            match client
+                .extract_relations(
+                    &current_chunk.text,
+                    domain,
+                    ContentKind::Text,
+                    &MentionTable::default(),
+                )
+                .await
+            {
                 Ok(relations) => {
                     return Ok(relations);
                 }
@@ -169,6 +175,7 @@ impl AdaptiveProcessor {
                                     "{}-retry-{}",
                                     current_chunk.chunk_index, attempt
                                 )),
+                                span: None,
                             };
                         }
 
@@ -3,16 +3,14 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use super::parsing::parse_relations_json;
-use super::prompts::{domain_aware_extraction_prompt, graph_extraction_user_prompt};
-use super::{LlmProviderTrait, Relation};
-use crate::config::DomainConfig;
+use super::LlmProviderTrait;
 
 pub struct AnthropicProvider {
     client: Client,
     api_key: String,
     model: String,
     base_url: String,
+    max_tokens: u32,
 }
 
 #[derive(Serialize)]
@@ -55,13 +53,23 @@ impl AnthropicProvider {
                 .unwrap_or("https://api.anthropic.com")
                 .trim_end_matches('/')
                 .to_string(),
+            max_tokens: 4096,
         })
     }
 
+    /// Cap the response length, e.g. at the model's output limit
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+}
+
+#[async_trait]
+impl LlmProviderTrait for AnthropicProvider {
     async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
         let request = AnthropicRequest {
             model: self.model.clone(),
-            max_tokens: 4096,
+            max_tokens: self.max_tokens,
             system: system.to_string(),
             messages: vec![Message {
                 role: "user".to_string(),
@@ -97,22 +105,6 @@ impl AnthropicProvider {
             .and_then(|c| c.text.clone())
             .context("No text content in Anthropic response")
     }
-}
-
-#[async_trait]
-impl LlmProviderTrait for AnthropicProvider {
-    async fn extract_relations(
-        &self,
-        text: &str,
-        domain: Option<&DomainConfig>,
-    ) -> Result<Vec<Relation>> {
-        let system_prompt = domain_aware_extraction_prompt(domain);
-        let user_prompt = graph_extraction_user_prompt(text);
-        let response = self.complete(&system_prompt, &user_prompt).await?;
-
-        // Parse JSON response
-        parse_relations_json(&response)
-    }
 
     fn name(&self) -> &'static str {
         "anthropic"
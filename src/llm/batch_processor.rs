@@ -1,13 +1,22 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::Arc;
 use tokio::fs;
 use tracing::{debug, info, warn};
 
-use crate::config::DomainConfig;
-use crate::llm::{LlmClient, Relation};
-use crate::parser::{AdaptiveChunker, Chunk, ModelContextLimits};
+use super::coreference::{COREFERENCE_SYSTEM_PROMPT, coreference_user_prompt};
+use super::prompts::graph_extraction_user_prompt;
+use crate::config::{DomainConfig, SelectionConfig};
+use crate::llm::{ContentKind, LlmClient, MentionTable, Relation};
+use crate::parser::{
+    Chunk, ChunkPlan, Chunker, CodeChunker, Language, PageTracker, Tokenizer, cite_pages,
+};
+
+/// Tokens the batch markers add around each chunk
+const CHUNK_MARKER_TOKENS: usize = 8;
 
 /// Batch processor for efficient large codebase processing
 ///
@@ -18,13 +27,17 @@ use crate::parser::{AdaptiveChunker, Chunk, ModelContextLimits};
 /// 4. Deduplicates similar content
 pub struct BatchProcessor {
     llm_client: LlmClient,
-    chunker: AdaptiveChunker,
+    chunker: Box<dyn Chunker>,
+    tokenizer: Arc<Tokenizer>,
+    plan: ChunkPlan,
     #[allow(dead_code)]
     concurrency: usize,
-    batch_size: usize, // Number of chunks per LLM call
     progress_file: Option<String>,
     processed_hashes: HashMap<String, ProcessedDoc>,
     domain_config: Option<DomainConfig>,
+    coreference: bool,
+    /// Names of entities found by the coreference pre-pass, by canonical name
+    aliases: BTreeMap<String, BTreeSet<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +46,10 @@ struct ProcessedDoc {
     chunks_processed: usize,
     relations_count: usize,
     timestamp: String,
+    /// Version of the prompt templates the relations came from; progress
+    /// files written before prompts were versioned have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prompt_version: Option<String>,
 }
 
 impl BatchProcessor {
@@ -40,23 +57,40 @@ impl BatchProcessor {
     ///
     /// # Arguments
     /// * `llm_client` - The LLM client to use
-    /// * `model` - Model name for context window detection
+    /// * `chunker` - Splits prose into chunks
+    /// * `tokenizer` - Counts prompt and code chunk tokens
+    /// * `plan` - Chunk size, chunks per LLM call and tokens per LLM call
     /// * `concurrency` - Number of concurrent batch operations
-    /// * `batch_size` - Number of chunks to process per LLM call (default: 5)
-    pub fn new(llm_client: LlmClient, model: &str, concurrency: usize, batch_size: usize) -> Self {
-        let chunker = ModelContextLimits::create_chunker(model);
-
+    ///
+    /// Batches stop growing at `plan.batch_size` chunks, or once they would
+    /// overflow `plan.batch_budget`.
+    pub fn new(
+        llm_client: LlmClient,
+        chunker: Box<dyn Chunker>,
+        tokenizer: Arc<Tokenizer>,
+        plan: ChunkPlan,
+        concurrency: usize,
+    ) -> Self {
         Self {
             llm_client,
             chunker,
+            tokenizer,
+            plan,
             concurrency,
-            batch_size: batch_size.max(1),
             progress_file: None,
             processed_hashes: HashMap::new(),
             domain_config: None,
+            coreference: false,
+            aliases: BTreeMap::new(),
         }
     }
 
+    /// Resolve each prose document's entity mentions before extraction
+    pub fn with_coreference(mut self, enabled: bool) -> Self {
+        self.coreference = enabled;
+        self
+    }
+
     /// Set domain configuration for specialized extraction
     pub fn with_domain_config(mut self, domain_config: Option<DomainConfig>) -> Self {
         self.domain_config = domain_config;
@@ -108,19 +142,23 @@ impl BatchProcessor {
         format!("{:x}", hasher.finish())
     }
 
-    /// Check if document was already processed
+    /// Check if document was already processed, with the current prompts
     fn is_already_processed(&self, source: &str, hash: &str) -> bool {
         if let Some(doc) = self.processed_hashes.get(source) {
-            return doc.hash == hash;
+            let version = self.llm_client.prompts().version();
+            return doc.hash == hash && doc.prompt_version.as_deref().is_none_or(|v| v == version);
         }
         false
     }
 
     /// Process multiple documents efficiently
+    ///
+    /// Relations come back grouped by provenance: the source path, or
+    /// `file.pdf p.12` when the text carries page markers.
     pub async fn process_documents(
         &mut self,
         documents: Vec<(String, String)>, // (source, text) pairs
-    ) -> Result<Vec<Relation>> {
+    ) -> Result<Vec<(String, Vec<Relation>)>> {
         let mut all_relations = Vec::new();
         let mut total_chunks = 0;
         let mut skipped_docs = 0;
@@ -129,7 +167,7 @@ impl BatchProcessor {
         let total_docs = documents.len();
         info!(
             "Processing {} documents with batch size {}",
-            total_docs, self.batch_size
+            total_docs, self.plan.batch_size
         );
 
         for (source, text) in documents {
@@ -142,16 +180,34 @@ impl BatchProcessor {
                 continue;
             }
 
-            // Split into chunks
-            let chunks = self.chunker.split(&text);
+            // Split into chunks; source code is cut at declarations and gets symbol hints
+            let (chunks, kind) = match Language::from_path(Path::new(&source)) {
+                Some(language) => (self.split_code(&text, &source, language), ContentKind::Code),
+                None => (self.chunker.chunk(&text).await?, ContentKind::Text),
+            };
             let chunk_count = chunks.len();
             total_chunks += chunk_count;
 
             info!("Processing {} ({} chunks)", source, chunk_count);
 
+            // One mention table per document, shared by all of its chunks
+            let mentions = if self.coreference && kind == ContentKind::Text {
+                self.resolve_mentions(&chunks, &source).await
+            } else {
+                MentionTable::default()
+            };
+            for (canonical, names) in mentions.aliases() {
+                self.aliases
+                    .entry(canonical.to_string())
+                    .or_default()
+                    .extend(names.into_iter().map(String::from));
+            }
+
             // Process chunks in batches
-            let doc_relations = self.process_chunks_in_batches(&chunks, &source).await?;
-            let relation_count = doc_relations.len();
+            let doc_relations = self
+                .process_chunks_in_batches(&chunks, &source, kind, &mentions)
+                .await?;
+            let relation_count = doc_relations.iter().map(|(_, r)| r.len()).sum();
             all_relations.extend(doc_relations);
 
             // Mark as processed
@@ -166,6 +222,7 @@ impl BatchProcessor {
                         .unwrap_or_default()
                         .as_secs()
                         .to_string(),
+                    prompt_version: Some(self.llm_client.prompts().version().to_string()),
                 },
             );
 
@@ -189,24 +246,91 @@ impl BatchProcessor {
             processed_docs,
             skipped_docs,
             total_chunks,
-            all_relations.len()
+            all_relations.iter().map(|(_, r)| r.len()).sum::<usize>()
         );
 
         Ok(all_relations)
     }
 
+    /// Split source code at declaration boundaries, prefixing each chunk with its symbol hints
+    fn split_code(&self, text: &str, source: &str, language: Language) -> Vec<Chunk> {
+        CodeChunker::new(self.plan.chunk_tokens)
+            .with_tokenizer(Arc::clone(&self.tokenizer))
+            .split(text, language)
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let text = chunk.with_hints(source, language);
+                Chunk {
+                    estimated_tokens: self.tokenizer.count(&text),
+                    text,
+                    chunk_index: i,
+                    parent_id: None,
+                    span: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Build a document's mention table, reading as many chunks per call as
+    /// the budget allows and passing the entities found so far along
+    ///
+    /// The pre-pass is optional: a call that fails leaves the table as it is.
+    async fn resolve_mentions(&self, chunks: &[Chunk], source: &str) -> MentionTable {
+        // A tenth of the room is kept for the growing table of known entities
+        let prompt_tokens = self.tokenizer.count(COREFERENCE_SYSTEM_PROMPT)
+            + self
+                .tokenizer
+                .count(&coreference_user_prompt("", &MentionTable::default()));
+        let room = self.plan.batch_budget.saturating_sub(prompt_tokens) * 9 / 10;
+
+        let mut table = MentionTable::default();
+        for range in Self::plan_batches(chunks, usize::MAX, room) {
+            let text = chunks[range]
+                .iter()
+                .map(|c| c.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            match self.llm_client.resolve_mentions(&text, &table).await {
+                Ok(found) => table.merge(found),
+                Err(e) => warn!("Coreference pre-pass failed for {}: {}", source, e),
+            }
+        }
+        debug!(
+            "{} entities in the mention table of {}",
+            table.len(),
+            source
+        );
+        table
+    }
+
     /// Process chunks in batches (multiple chunks per LLM call)
     async fn process_chunks_in_batches(
         &self,
         chunks: &[Chunk],
         source: &str,
-    ) -> Result<Vec<Relation>> {
+        kind: ContentKind,
+        mentions: &MentionTable,
+    ) -> Result<Vec<(String, Vec<Relation>)>> {
         let mut all_relations = Vec::new();
 
-        // Group chunks into batches
-        let batches: Vec<Vec<&Chunk>> = chunks
-            .chunks(self.batch_size)
-            .map(|c| c.iter().collect())
+        // Pages each chunk spans, for citing relations as "file.pdf p.12"
+        let mut tracker = PageTracker::default();
+        let pages: Vec<Vec<u32>> = chunks.iter().map(|c| tracker.pages(&c.text)).collect();
+
+        // Group chunks into batches that fit next to the prompts
+        let domain = self.domain_config.as_ref();
+        let prompt_tokens = self.tokenizer.count(
+            &self
+                .llm_client
+                .prompts()
+                .system_prompt(domain, kind, mentions),
+        ) + self.tokenizer.count(&graph_extraction_user_prompt(""));
+        let room = self.plan.batch_budget.saturating_sub(prompt_tokens);
+        let ranges = Self::plan_batches(chunks, self.plan.batch_size, room);
+        let batches: Vec<Vec<&Chunk>> = ranges
+            .iter()
+            .map(|range| chunks[range.clone()].iter().collect())
             .collect();
 
         info!(
@@ -216,6 +340,8 @@ impl BatchProcessor {
         );
 
         for (batch_idx, batch) in batches.iter().enumerate() {
+            let first = ranges[batch_idx].start;
+            let batch_pages: Vec<u32> = pages[ranges[batch_idx].clone()].concat();
             debug!(
                 "Processing batch {}/{} ({} chunks)",
                 batch_idx + 1,
@@ -228,11 +354,11 @@ impl BatchProcessor {
 
             // Process with retry logic
             match self
-                .process_batch_with_retry(&batch_text, source, batch_idx)
+                .process_batch_with_retry(&batch_text, source, batch_idx, kind, mentions)
                 .await
             {
                 Ok(relations) => {
-                    all_relations.extend(relations);
+                    all_relations.push((cite(source, &batch_pages, batch), relations));
                 }
                 Err(e) => {
                     warn!(
@@ -240,9 +366,13 @@ impl BatchProcessor {
                         batch_idx, e
                     );
                     // Fallback: process chunks individually
-                    for chunk in batch.iter() {
-                        match self.process_single_chunk(chunk, source).await {
-                            Ok(relations) => all_relations.extend(relations),
+                    for (offset, chunk) in batch.iter().enumerate() {
+                        match self
+                            .process_single_chunk(chunk, source, kind, mentions)
+                            .await
+                        {
+                            Ok(relations) => all_relations
+                                .push((cite(source, &pages[first + offset], &[chunk]), relations)),
                             Err(e) => warn!("Failed to process chunk {}: {}", chunk.chunk_index, e),
                         }
                     }
@@ -253,6 +383,28 @@ impl BatchProcessor {
         Ok(all_relations)
     }
 
+    /// Split chunks into consecutive batches of at most `batch_size` chunks and
+    /// `room` tokens; a chunk that fills the room alone gets a batch of its own
+    fn plan_batches(chunks: &[Chunk], batch_size: usize, room: usize) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        let mut used = 0;
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let cost = chunk.estimated_tokens + CHUNK_MARKER_TOKENS;
+            if i > start && (i - start == batch_size || used + cost > room) {
+                ranges.push(start..i);
+                start = i;
+                used = 0;
+            }
+            used += cost;
+        }
+        if start < chunks.len() {
+            ranges.push(start..chunks.len());
+        }
+        ranges
+    }
+
     /// Format multiple chunks for batch LLM processing
     fn format_batch_for_processing(
         &self,
@@ -278,10 +430,12 @@ impl BatchProcessor {
         batch_text: &str,
         _source: &str,
         batch_idx: usize,
+        kind: ContentKind,
+        mentions: &MentionTable,
     ) -> Result<Vec<Relation>> {
         match self
             .llm_client
-            .extract_relations(batch_text, self.domain_config.as_ref())
+            .extract_relations(batch_text, self.domain_config.as_ref(), kind, mentions)
             .await
         {
             Ok(relations) => {
@@ -310,10 +464,16 @@ impl BatchProcessor {
     }
 
     /// Process a single chunk (fallback method)
-    async fn process_single_chunk(&self, chunk: &Chunk, _source: &str) -> Result<Vec<Relation>> {
+    async fn process_single_chunk(
+        &self,
+        chunk: &Chunk,
+        _source: &str,
+        kind: ContentKind,
+        mentions: &MentionTable,
+    ) -> Result<Vec<Relation>> {
         debug!("Processing single chunk {}", chunk.chunk_index);
         self.llm_client
-            .extract_relations(&chunk.text, self.domain_config.as_ref())
+            .extract_relations(&chunk.text, self.domain_config.as_ref(), kind, mentions)
             .await
     }
 
@@ -338,6 +498,11 @@ impl BatchProcessor {
             .any(|indicator| error_lower.contains(indicator))
     }
 
+    /// Other names of the entities the coreference pre-pass found, by canonical name
+    pub fn aliases(&self) -> &BTreeMap<String, BTreeSet<String>> {
+        &self.aliases
+    }
+
     /// Get processing statistics
     pub fn get_stats(&self) -> ProcessingStats {
         ProcessingStats {
@@ -356,6 +521,19 @@ impl BatchProcessor {
     }
 }
 
+/// Where a batch was read: its PDF pages, else its character range
+/// (`notes.md#char=120,940`, after RFC 5147) when the chunker tracked one
+fn cite(source: &str, pages: &[u32], chunks: &[&Chunk]) -> String {
+    let spans: Option<Vec<&Range<usize>>> = chunks.iter().map(|c| c.span.as_ref()).collect();
+    match spans.as_deref() {
+        Some([first, .., last]) if pages.is_empty() => {
+            format!("{}#char={},{}", source, first.start, last.end)
+        }
+        Some([only]) if pages.is_empty() => format!("{}#char={},{}", source, only.start, only.end),
+        _ => cite_pages(source, pages),
+    }
+}
+
 /// Processing statistics
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -365,10 +543,26 @@ pub struct ProcessingStats {
     pub total_chunks: usize,
 }
 
+/// (source, text) pairs
+type Documents = Vec<(String, String)>;
+
 /// Smart document selector to avoid processing duplicates/similar docs
-pub struct DocumentSelector;
+///
+/// The heuristics come from the `[selection]` section of the config.
+pub struct DocumentSelector {
+    config: SelectionConfig,
+}
 
 impl DocumentSelector {
+    pub fn new(config: SelectionConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether a set of this many documents should be thinned out
+    pub fn applies_to(&self, count: usize) -> bool {
+        count > self.config.threshold
+    }
+
     /// Select representative documents from a large codebase
     ///
     /// Strategy:
@@ -376,20 +570,19 @@ impl DocumentSelector {
     /// 2. Limit files per directory (avoiding duplication)
     /// 3. Skip auto-generated files
     /// 4. Prioritize recent/modified files
+    ///
+    /// Returns the selected documents and, for each dropped one, the reason.
     pub fn select_representative_docs(
+        &self,
         all_docs: &[(String, String)],
-        max_per_dir: usize,
-    ) -> Vec<(String, String)> {
+    ) -> (Documents, Vec<(String, String)>) {
         let mut selected = Vec::new();
+        let mut dropped = Vec::new();
         let mut dir_counts: HashMap<String, usize> = HashMap::new();
 
-        // Sort to prioritize important files
+        // Sort to prioritize important files (higher score first)
         let mut sorted_docs = all_docs.to_vec();
-        sorted_docs.sort_by(|(path_a, _), (path_b, _)| {
-            let score_a = Self::document_priority(path_a);
-            let score_b = Self::document_priority(path_b);
-            score_b.cmp(&score_a) // Higher score first
-        });
+        sorted_docs.sort_by_key(|(path, _)| std::cmp::Reverse(self.document_priority(path)));
 
         for (source, text) in sorted_docs {
             // Get directory
@@ -400,13 +593,21 @@ impl DocumentSelector {
                 .to_string();
 
             // Check if we should skip
-            if Self::should_skip(&source, &text) {
+            if let Some(reason) = self.skip_reason(&source, &text) {
+                dropped.push((source, reason));
                 continue;
             }
 
             // Check directory limit
             let count = dir_counts.entry(dir.clone()).or_insert(0);
-            if *count >= max_per_dir {
+            if *count >= self.config.max_per_dir {
+                dropped.push((
+                    source,
+                    format!(
+                        "directory already has {} selected documents",
+                        self.config.max_per_dir
+                    ),
+                ));
                 continue;
             }
 
@@ -419,62 +620,54 @@ impl DocumentSelector {
             selected.len(),
             all_docs.len()
         );
-        selected
+        (selected, dropped)
     }
 
     /// Calculate document priority (higher = more important)
-    fn document_priority(path: &str) -> i32 {
+    fn document_priority(&self, path: &str) -> i32 {
         let lower = path.to_lowercase();
-        let mut score = 0;
-
-        // High priority files
-        if lower.contains("readme") || lower.contains("skill") || lower.contains("toc") {
-            score += 100;
-        }
-        if lower.contains("overview") || lower.contains("getting-started") {
-            score += 50;
-        }
-        if lower.contains("example") || lower.contains("guide") {
-            score += 30;
-        }
-
-        // Penalize generated files
-        if lower.contains("generated") || lower.contains("auto") {
-            score -= 50;
-        }
-
-        // Prefer smaller, focused files
-        if lower.ends_with(".md") {
-            score += 10;
-        }
-
-        score
+        self.config
+            .priorities
+            .iter()
+            .filter(|(pattern, _)| lower.contains(&pattern.to_lowercase()))
+            .map(|(_, score)| score)
+            .sum()
     }
 
-    /// Check if document should be skipped
-    fn should_skip(source: &str, text: &str) -> bool {
+    /// Why a document should be skipped, if it should
+    fn skip_reason(&self, source: &str, text: &str) -> Option<String> {
         let lower = source.to_lowercase();
 
-        // Skip generated files
-        if lower.contains("generated")
-            || lower.contains("auto-generated")
-            || lower.contains("broken-links")
-            || lower.contains("source-reference-map")
+        // Skip generated files and other configured patterns
+        if let Some(pattern) = self
+            .config
+            .skip_patterns
+            .iter()
+            .find(|p| lower.contains(&p.to_lowercase()))
         {
-            return true;
+            return Some(format!("path contains '{}'", pattern));
         }
 
         // Skip very small files
-        if text.len() < 100 {
-            return true;
+        if text.len() < self.config.min_chars {
+            return Some(format!("shorter than {} characters", self.config.min_chars));
         }
 
-        // Skip JSON data files
-        if lower.ends_with(".json") {
-            return true;
+        // Skip data files
+        let extension = Path::new(&lower)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
+        if self
+            .config
+            .skip_extensions
+            .iter()
+            .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(extension))
+        {
+            return Some(format!(".{} files are skipped", extension));
         }
 
-        false
+        None
     }
 }
 
@@ -482,6 +675,34 @@ impl DocumentSelector {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_batches_respect_size_and_budget() {
+        let chunk = |tokens| Chunk {
+            text: String::new(),
+            estimated_tokens: tokens,
+            chunk_index: 0,
+            parent_id: None,
+            span: None,
+        };
+        let chunks: Vec<Chunk> = [100, 100, 100, 100, 100, 100, 100].map(chunk).to_vec();
+        assert_eq!(
+            BatchProcessor::plan_batches(&chunks, 3, 10_000),
+            vec![0..3, 3..6, 6..7]
+        );
+        assert_eq!(
+            BatchProcessor::plan_batches(&chunks, 5, 250),
+            vec![0..2, 2..4, 4..6, 6..7]
+        );
+
+        // Oversized chunks still go out, one per call
+        let chunks: Vec<Chunk> = [500, 50, 500].map(chunk).to_vec();
+        assert_eq!(
+            BatchProcessor::plan_batches(&chunks, 5, 300),
+            vec![0..1, 1..2, 2..3]
+        );
+        assert!(BatchProcessor::plan_batches(&[], 5, 300).is_empty());
+    }
+
     #[test]
     fn test_context_overflow_detection() {
         assert!(BatchProcessor::is_context_overflow(
@@ -495,23 +716,47 @@ mod tests {
 
     #[test]
     fn test_document_priority() {
+        let selector = DocumentSelector::new(SelectionConfig::default());
         assert!(
-            DocumentSelector::document_priority("README.md")
-                > DocumentSelector::document_priority("generated-file.md")
-        );
-        assert!(
-            DocumentSelector::document_priority("SKILL.md")
-                > DocumentSelector::document_priority("random.md")
+            selector.document_priority("README.md")
+                > selector.document_priority("generated-file.md")
         );
+        assert!(selector.document_priority("SKILL.md") > selector.document_priority("random.md"));
     }
 
     #[test]
     fn test_should_skip() {
-        assert!(DocumentSelector::should_skip("broken-links.json", "{}"));
-        assert!(DocumentSelector::should_skip("generated.md", "content"));
-        assert!(!DocumentSelector::should_skip(
+        let selector = DocumentSelector::new(SelectionConfig::default());
+        assert!(selector.skip_reason("broken-links.json", "{}").is_some());
+        assert!(selector.skip_reason("generated.md", "content").is_some());
+        assert!(selector.skip_reason(
             "readme.md",
             "# Title\n\nThis is a long content that should not be skipped because it has more than one hundred characters to pass the minimum length check."
-        ));
+        ).is_none());
+    }
+
+    #[test]
+    fn test_selection_is_configurable() {
+        let long = "x".repeat(200);
+        let docs: Vec<(String, String)> = ["a/readme.md", "a/one.md", "a/two.md", "a/CHANGELOG.md"]
+            .iter()
+            .map(|s| (s.to_string(), long.clone()))
+            .collect();
+        let selector = DocumentSelector::new(SelectionConfig {
+            threshold: 2,
+            max_per_dir: 2,
+            skip_patterns: vec!["changelog".to_string()],
+            ..Default::default()
+        });
+        assert!(selector.applies_to(4));
+        let (selected, dropped) = selector.select_representative_docs(&docs);
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].0, "a/readme.md");
+        assert_eq!(dropped.len(), 2);
+        assert!(
+            dropped
+                .iter()
+                .any(|(s, r)| s == "a/CHANGELOG.md" && r.contains("changelog"))
+        );
     }
 }
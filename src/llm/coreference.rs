@@ -0,0 +1,280 @@
+//! Coreference pre-pass: one table of the entities a document mentions and
+//! the names, pronouns and descriptions it uses for each of them.
+//!
+//! The table goes into every chunk's extraction prompt, so relations read from
+//! a late chunk ("it calls the ledger") name the same nodes as the chunk that
+//! introduced them ("The payment service calls the ledger").
+
+use serde::Deserialize;
+use std::fmt;
+
+use super::parsing::extract_json_array;
+
+/// System prompt for building the mention table
+pub const COREFERENCE_SYSTEM_PROMPT: &str = r#"You build an entity mention table for a document, so that every part of it can refer to the same entity by the same name.
+You are provided with a part of the document (delimited by ```) and the entities already found in earlier parts.
+
+Thought 1: Find the entities the text refers to: systems, services, components, organizations, people, products, places, documents and key concepts.
+
+Thought 2: For each entity, collect every surface form the text uses for it: full and short names, acronyms, and phrases such as "the service", "the library", "this approach". Include pronouns ("it", "they") that clearly refer to it.
+
+Thought 3: Choose one canonical name per entity: the most specific full name, lowercase, 1-4 words. Keep the canonical name of an entity that is already known and add the new surface forms to it.
+
+Format your output as a JSON array:
+[
+    {
+        "canonical": "payment service",
+        "mentions": ["PaymentService", "the service", "it"]
+    }
+]
+
+Rules:
+- Only list entities that are mentioned more than once or under more than one form
+- Never give the same surface form to two entities
+- Return an empty array [] if there is nothing to resolve
+- Output ONLY valid JSON, no other text"#;
+
+/// User prompt for one part of a document, given the entities known so far
+pub fn coreference_user_prompt(text: &str, known: &MentionTable) -> String {
+    if known.is_empty() {
+        format!("context: ```{}```\n\noutput: ", text)
+    } else {
+        format!(
+            "known entities:\n{}\ncontext: ```{}```\n\noutput: ",
+            known, text
+        )
+    }
+}
+
+/// An entity and the forms a document uses for it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EntityMentions {
+    pub canonical: String,
+    #[serde(default)]
+    pub mentions: Vec<String>,
+}
+
+/// Canonical entity names with their surface forms, in order of first mention
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MentionTable {
+    entities: Vec<EntityMentions>,
+}
+
+impl MentionTable {
+    /// Parse the pre-pass response; anything that is not a mention table is ignored
+    pub fn parse(response: &str) -> Self {
+        let json = extract_json_array(response);
+        let mut table = Self::default();
+        match serde_json::from_str::<Vec<EntityMentions>>(&json) {
+            Ok(entities) => {
+                for entity in entities {
+                    table.add(entity);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to parse mention table: {}", e),
+        }
+        table
+    }
+
+    /// Merge another table into this one, keeping known canonical names
+    pub fn merge(&mut self, other: MentionTable) {
+        for entity in other.entities {
+            self.add(entity);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Canonical names with the forms that name the entity outside this
+    /// document: pronouns and phrases such as "the service" are left out
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, Vec<&str>)> {
+        self.entities.iter().map(|entity| {
+            let names = entity
+                .mentions
+                .iter()
+                .map(String::as_str)
+                .filter(|m| is_name(m))
+                .collect();
+            (entity.canonical.as_str(), names)
+        })
+    }
+
+    /// Add an entity, or its new forms when the entity is already known.
+    /// A form that already belongs to another entity is dropped.
+    fn add(&mut self, entity: EntityMentions) {
+        let canonical = entity.canonical.trim().to_lowercase();
+        if canonical.is_empty() {
+            return;
+        }
+        let index = match self.entities.iter().position(|e| e.canonical == canonical) {
+            Some(index) => index,
+            None => {
+                self.entities.push(EntityMentions {
+                    canonical,
+                    mentions: Vec::new(),
+                });
+                self.entities.len() - 1
+            }
+        };
+
+        for mention in entity.mentions {
+            let mention = mention.trim();
+            let taken = self.entities.iter().enumerate().any(|(i, e)| {
+                e.mentions.iter().any(|m| m.eq_ignore_ascii_case(mention))
+                    || (i != index && e.canonical.eq_ignore_ascii_case(mention))
+            });
+            if !mention.is_empty()
+                && !taken
+                && !mention.eq_ignore_ascii_case(&self.entities[index].canonical)
+            {
+                self.entities[index].mentions.push(mention.to_string());
+            }
+        }
+    }
+}
+
+/// Words that make a surface form a pronoun or a description rather than a name
+const NON_NAME_WORDS: &[&str] = &[
+    "a", "an", "the", "this", "that", "these", "those", "it", "its", "they", "them", "their", "he",
+    "him", "his", "she", "her", "we", "us", "our", "you", "your",
+];
+
+fn is_name(mention: &str) -> bool {
+    mention
+        .split_whitespace()
+        .next()
+        .is_some_and(|first| !NON_NAME_WORDS.contains(&first.to_lowercase().as_str()))
+}
+
+/// One line per entity: `- canonical: "form", "form"`
+impl fmt::Display for MentionTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entity in &self.entities {
+            write!(f, "- {}", entity.canonical)?;
+            for (i, mention) in entity.mentions.iter().enumerate() {
+                let separator = if i == 0 { ":" } else { "," };
+                write!(f, "{} \"{}\"", separator, mention)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{ContentKind, LlmClient, LlmProviderTrait};
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_parse_normalizes_and_deduplicates() {
+        let response = r#"Here is the table:
+```json
+[
+  {"canonical": "Payment Service", "mentions": ["PaymentService", "the service", "it", "payment service"]},
+  {"canonical": "ledger", "mentions": ["the ledger", "it"]},
+  {"canonical": "  ", "mentions": ["nothing"]}
+]
+```"#;
+        let table = MentionTable::parse(response);
+        assert_eq!(table.len(), 2);
+        assert_eq!(
+            table.to_string(),
+            "- payment service: \"PaymentService\", \"the service\", \"it\"\n- ledger: \"the ledger\"\n"
+        );
+        let aliases: Vec<_> = table.aliases().collect();
+        assert_eq!(
+            aliases,
+            [
+                ("payment service", vec!["PaymentService"]),
+                ("ledger", vec![])
+            ]
+        );
+        assert!(MentionTable::parse("no table today").is_empty());
+    }
+
+    #[test]
+    fn test_merge_keeps_known_canonical_names() {
+        let mut table = MentionTable::parse(
+            r#"[{"canonical": "payment service", "mentions": ["the service"]}]"#,
+        );
+        table.merge(MentionTable::parse(
+            r#"[{"canonical": "payment service", "mentions": ["PaySvc", "the service"]},
+                {"canonical": "fraud check", "mentions": ["payment service", "the check"]}]"#,
+        ));
+        assert_eq!(
+            table.to_string(),
+            "- payment service: \"the service\", \"PaySvc\"\n- fraud check: \"the check\"\n"
+        );
+    }
+
+    /// Answers the pre-pass with a table and records every prompt
+    struct Recorder {
+        prompts: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    #[async_trait]
+    impl LlmProviderTrait for Recorder {
+        async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
+            self.prompts
+                .lock()
+                .unwrap()
+                .push((system.to_string(), user_message.to_string()));
+            if system == COREFERENCE_SYSTEM_PROMPT {
+                Ok(
+                    r#"[{"canonical": "payment service", "mentions": ["the service", "it"]}]"#
+                        .to_string(),
+                )
+            } else {
+                Ok(
+                    r#"[{"node_1": "payment service", "node_2": "ledger", "edge": "writes to"}]"#
+                        .to_string(),
+                )
+            }
+        }
+
+        fn name(&self) -> &'static str {
+            "recorder"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mention_table_reaches_extraction_prompt() {
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let client = LlmClient::from_provider(Box::new(Recorder {
+            prompts: Arc::clone(&prompts),
+        }));
+        let known = MentionTable::parse(r#"[{"canonical": "ledger", "mentions": ["the db"]}]"#);
+        let table = client
+            .resolve_mentions("The payment service is fast. It writes to the db.", &known)
+            .await
+            .unwrap();
+        assert_eq!(table.len(), 1);
+
+        client
+            .extract_relations("It writes to the ledger.", None, ContentKind::Text, &table)
+            .await
+            .unwrap();
+
+        let prompts = prompts.lock().unwrap();
+        assert!(
+            prompts[0]
+                .1
+                .starts_with("known entities:\n- ledger: \"the db\"\n")
+        );
+        assert!(
+            prompts[1]
+                .0
+                .contains("- payment service: \"the service\", \"it\"")
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config::Config;
+use crate::parser::Embedder;
+
+/// Texts sent per embedding request
+const BATCH_SIZE: usize = 64;
+
+/// Embedding API flavours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Api {
+    OpenAI,
+    Ollama,
+    Google,
+}
+
+/// Client for the embedding endpoint of a configured provider
+pub struct EmbeddingClient {
+    client: Client,
+    api: Api,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct OpenAIEmbeddings {
+    data: Vec<OpenAIEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAIEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbeddings {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+struct GoogleEmbeddings {
+    embeddings: Vec<GoogleEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct GoogleEmbedding {
+    values: Vec<f32>,
+}
+
+impl EmbeddingClient {
+    /// Create a client from the `[embeddings]` section, falling back to the default provider
+    pub fn new(config: &Config) -> Result<Self> {
+        let provider = config
+            .embeddings
+            .provider
+            .as_deref()
+            .unwrap_or(&config.default_provider)
+            .to_lowercase();
+
+        let (api, default_model, default_url) = match provider.as_str() {
+            "openai" => (
+                Api::OpenAI,
+                "text-embedding-3-small",
+                "https://api.openai.com/v1",
+            ),
+            "ollama" => (Api::Ollama, "nomic-embed-text", "http://localhost:11434"),
+            "google" => (
+                Api::Google,
+                "text-embedding-004",
+                "https://generativelanguage.googleapis.com",
+            ),
+            other => anyhow::bail!(
+                "Provider '{}' has no embedding API. Set [embeddings] provider to openai, ollama or google.",
+                other
+            ),
+        };
+
+        let provider_config = config
+            .get_provider(&provider)
+            .with_context(|| format!("{} provider not configured", provider))?;
+        if api != Api::Ollama && provider_config.api_key.is_empty() {
+            anyhow::bail!("An API key is required for {} embeddings", provider);
+        }
+
+        Ok(Self {
+            client: Client::new(),
+            api,
+            api_key: provider_config.api_key.clone(),
+            base_url: provider_config
+                .base_url
+                .as_deref()
+                .unwrap_or(default_url)
+                .trim_end_matches('/')
+                .to_string(),
+            model: config
+                .embeddings
+                .model
+                .clone()
+                .unwrap_or_else(|| default_model.to_string()),
+        })
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let request = match self.api {
+            Api::OpenAI => self
+                .client
+                .post(format!("{}/embeddings", self.base_url))
+                .bearer_auth(&self.api_key)
+                .json(&json!({ "model": self.model, "input": texts })),
+            Api::Ollama => self
+                .client
+                .post(format!("{}/api/embed", self.base_url))
+                .json(&json!({ "model": self.model, "input": texts })),
+            Api::Google => {
+                let model = format!("models/{}", self.model);
+                let requests: Vec<_> = texts
+                    .iter()
+                    .map(|text| json!({ "model": model, "content": { "parts": [{ "text": text }] } }))
+                    .collect();
+                self.client
+                    .post(format!(
+                        "{}/v1beta/{}:batchEmbedContents?key={}",
+                        self.base_url, model, self.api_key
+                    ))
+                    .json(&json!({ "requests": requests }))
+            }
+        };
+
+        let response = request
+            .send()
+            .await
+            .context("Failed to send embedding request")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Embedding API error ({}): {}", status, error_text);
+        }
+
+        let vectors = match self.api {
+            Api::OpenAI => {
+                let mut data = response
+                    .json::<OpenAIEmbeddings>()
+                    .await
+                    .context("Failed to parse embedding response")?
+                    .data;
+                data.sort_by_key(|d| d.index);
+                data.into_iter().map(|d| d.embedding).collect()
+            }
+            Api::Ollama => {
+                response
+                    .json::<OllamaEmbeddings>()
+                    .await
+                    .context("Failed to parse embedding response")?
+                    .embeddings
+            }
+            Api::Google => response
+                .json::<GoogleEmbeddings>()
+                .await
+                .context("Failed to parse embedding response")?
+                .embeddings
+                .into_iter()
+                .map(|e| e.values)
+                .collect(),
+        };
+        Ok(vectors)
+    }
+}
+
+#[async_trait]
+impl Embedder for EmbeddingClient {
+    /// Embed texts in order, one vector per text
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            let embedded = self.embed_batch(batch).await?;
+            if embedded.len() != batch.len() {
+                anyhow::bail!(
+                    "Embedding API returned {} vectors for {} texts",
+                    embedded.len(),
+                    batch.len()
+                );
+            }
+            vectors.extend(embedded);
+        }
+        Ok(vectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::Value;
+
+    fn config(provider: &str, base_url: &str) -> Config {
+        toml::from_str(&format!(
+            r#"
+            [providers.openai]
+            api_key = "sk-test"
+            base_url = "{base_url}"
+            [providers.ollama]
+            base_url = "{base_url}"
+            [neo4j]
+            uri = "bolt://localhost:7687"
+            user = "neo4j"
+            password = "test"
+            [embeddings]
+            provider = "{provider}"
+        "#
+        ))
+        .unwrap()
+    }
+
+    /// Embed each input as [length, 1.0]
+    async fn stub_server() -> String {
+        fn lengths(body: &Value) -> Vec<f32> {
+            body["input"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t.as_str().unwrap().len() as f32)
+                .collect()
+        }
+        let app = Router::new()
+            .route(
+                "/embeddings",
+                post(|Json(body): Json<Value>| async move {
+                    // Out of order, as the API allows
+                    let data: Vec<Value> = lengths(&body)
+                        .into_iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(i, len)| json!({ "index": i, "embedding": [len, 1.0] }))
+                        .collect();
+                    Json(json!({ "data": data }))
+                }),
+            )
+            .route(
+                "/api/embed",
+                post(|Json(body): Json<Value>| async move {
+                    let embeddings: Vec<Value> = lengths(&body)
+                        .into_iter()
+                        .map(|len| json!([len, 1.0]))
+                        .collect();
+                    Json(json!({ "embeddings": embeddings }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        base
+    }
+
+    #[tokio::test]
+    async fn test_openai_and_ollama_embeddings_keep_order() {
+        let base = stub_server().await;
+        let texts: Vec<String> = (1..=70).map(|n| "x".repeat(n)).collect();
+
+        for provider in ["openai", "ollama"] {
+            let client = EmbeddingClient::new(&config(provider, &base)).unwrap();
+            let vectors = client.embed(&texts).await.unwrap();
+            assert_eq!(vectors.len(), 70, "{}", provider);
+            assert_eq!(vectors[0], vec![1.0, 1.0]);
+            assert_eq!(vectors[69], vec![70.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn test_provider_without_embeddings_is_rejected() {
+        let err = EmbeddingClient::new(&config("anthropic", "http://localhost"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("no embedding API"));
+    }
+}
@@ -3,16 +3,14 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use super::parsing::parse_relations_json;
-use super::prompts::{domain_aware_extraction_prompt, graph_extraction_user_prompt};
-use super::{LlmProviderTrait, Relation};
-use crate::config::DomainConfig;
+use super::LlmProviderTrait;
 
 pub struct GoogleProvider {
     client: Client,
     api_key: String,
     model: String,
     base_url: String,
+    max_tokens: u32,
 }
 
 #[derive(Serialize)]
@@ -86,9 +84,19 @@ impl GoogleProvider {
                 .unwrap_or("https://generativelanguage.googleapis.com")
                 .trim_end_matches('/')
                 .to_string(),
+            max_tokens: 4096,
         })
     }
 
+    /// Cap the response length, e.g. at the model's output limit
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+}
+
+#[async_trait]
+impl LlmProviderTrait for GoogleProvider {
     async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
         let request = GoogleRequest {
             contents: vec![Content {
@@ -103,7 +111,7 @@ impl GoogleProvider {
             }),
             generation_config: GenerationConfig {
                 temperature: 0.0,
-                max_output_tokens: 4096,
+                max_output_tokens: self.max_tokens,
             },
         };
 
@@ -148,24 +156,6 @@ impl GoogleProvider {
 
         Ok(text)
     }
-}
-
-#[async_trait]
-impl LlmProviderTrait for GoogleProvider {
-    async fn extract_relations(
-        &self,
-        text: &str,
-        domain: Option<&DomainConfig>,
-    ) -> Result<Vec<Relation>> {
-        let system_prompt = domain_aware_extraction_prompt(domain);
-        let user_prompt = graph_extraction_user_prompt(text);
-        let response = self.complete(&system_prompt, &user_prompt).await?;
-
-        tracing::debug!(raw_len = response.len(), "Parsing Google response");
-
-        // Parse JSON response
-        parse_relations_json(&response)
-    }
 
     fn name(&self) -> &'static str {
         "google"
@@ -0,0 +1,356 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+
+use super::LlmProviderTrait;
+use crate::config::HttpProviderConfig;
+
+/// Provider for an endpoint described by request templates in `[providers.http.<name>]`
+pub struct HttpProvider {
+    client: Client,
+    name: String,
+    config: HttpProviderConfig,
+    model: String,
+    max_tokens: u32,
+    response_path: Vec<PathStep>,
+}
+
+/// One step of a JSONPath: `.key`, `['key']` or `[index]`
+#[derive(Debug, Clone, PartialEq)]
+enum PathStep {
+    Key(String),
+    Index(i64),
+}
+
+impl HttpProvider {
+    pub fn new(name: &str, config: &HttpProviderConfig, model: &str) -> Result<Self> {
+        if config.url.trim().is_empty() {
+            anyhow::bail!("[providers.http.{}] needs a url", name);
+        }
+        let response_path = parse_json_path(&config.response_path).with_context(|| {
+            format!(
+                "Invalid response_path '{}' in [providers.http.{}]",
+                config.response_path, name
+            )
+        })?;
+
+        Ok(Self {
+            client: Client::new(),
+            name: name.to_string(),
+            config: config.clone(),
+            model: model.to_string(),
+            max_tokens: 4096,
+            response_path,
+        })
+    }
+
+    /// Cap the response length, e.g. at the model's output limit
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Placeholder values for one request
+    fn variables(&self, system: &str, user_message: &str) -> [(&'static str, Value); 5] {
+        [
+            ("model", Value::from(self.model.as_str())),
+            ("api_key", Value::from(self.config.api_key.as_str())),
+            ("max_tokens", Value::from(self.max_tokens)),
+            ("system", Value::from(system)),
+            ("user", Value::from(user_message)),
+        ]
+    }
+}
+
+#[async_trait]
+impl LlmProviderTrait for HttpProvider {
+    async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
+        let variables = self.variables(system, user_message);
+
+        let mut request = self
+            .client
+            .post(render(&self.config.url, &variables))
+            .json(&render_json(&self.config.body, &variables));
+
+        if !self.config.api_key.is_empty() {
+            let header = self
+                .config
+                .auth_header
+                .as_deref()
+                .unwrap_or("Authorization");
+            let scheme = match &self.config.auth_scheme {
+                Some(scheme) => Some(scheme.as_str()),
+                None if header.eq_ignore_ascii_case("authorization") => Some("Bearer"),
+                None => None,
+            };
+            let value = match scheme.filter(|s| !s.is_empty()) {
+                Some(scheme) => format!("{} {}", scheme, self.config.api_key),
+                None => self.config.api_key.clone(),
+            };
+            request = request.header(header, value);
+        }
+        for (header, value) in &self.config.headers {
+            request = request.header(header, render(value, &variables));
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to {}", self.name))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("{} API error ({}): {}", self.name, status, error_text);
+        }
+
+        let response: Value = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse {} response", self.name))?;
+
+        match select(&response, &self.response_path) {
+            Some(Value::String(text)) => Ok(text.clone()),
+            Some(Value::Null) | None => anyhow::bail!(
+                "No value at {} in {} response",
+                self.config.response_path,
+                self.name
+            ),
+            Some(other) => Ok(other.to_string()),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "http"
+    }
+}
+
+/// Fill `{name}` placeholders in one pass, so values are never expanded again
+fn render(template: &str, variables: &[(&str, Value)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| {
+            let name = &after[..close];
+            variables
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| (close, v))
+        });
+        match value {
+            Some((close, value)) => {
+                match value {
+                    Value::String(s) => out.push_str(s),
+                    other => out.push_str(&other.to_string()),
+                }
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Fill placeholders in every string of a JSON template; a string that is
+/// exactly one placeholder is replaced by the value itself
+fn render_json(template: &Value, variables: &[(&str, Value)]) -> Value {
+    match template {
+        Value::String(s) => {
+            let whole = s
+                .strip_prefix('{')
+                .and_then(|s| s.strip_suffix('}'))
+                .and_then(|name| variables.iter().find(|(n, _)| *n == name));
+            match whole {
+                Some((_, value)) => value.clone(),
+                None => Value::String(render(s, variables)),
+            }
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render_json(item, variables))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), render_json(value, variables)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Parse the JSONPath subset `$.key[0]['other key'][-1]`
+fn parse_json_path(path: &str) -> Result<Vec<PathStep>> {
+    let mut rest = path
+        .trim()
+        .strip_prefix('$')
+        .context("a JSONPath starts with $")?;
+    let mut steps = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                anyhow::bail!("empty key after '.'");
+            }
+            steps.push(PathStep::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let close = after.find(']').context("unclosed '['")?;
+            let inner = after[..close].trim();
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+            steps.push(match quoted {
+                Some(key) => PathStep::Key(key.to_string()),
+                None => PathStep::Index(
+                    inner
+                        .parse()
+                        .with_context(|| format!("'{}' is not an index", inner))?,
+                ),
+            });
+            rest = &after[close + 1..];
+        } else {
+            anyhow::bail!("expected '.' or '[' at '{}'", rest);
+        }
+    }
+    Ok(steps)
+}
+
+/// Follow a parsed JSONPath; negative indexes count from the end
+fn select<'a>(value: &'a Value, path: &[PathStep]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, step| match step {
+        PathStep::Key(key) => value.get(key),
+        PathStep::Index(index) => {
+            let items = value.as_array()?;
+            let index = if *index < 0 {
+                items.len().checked_sub(index.unsigned_abs() as usize)?
+            } else {
+                *index as usize
+            };
+            items.get(index)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Path;
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::json;
+
+    #[test]
+    fn test_json_path() {
+        let value = json!({ "choices": [{ "message": { "content": "a" } }, { "text": "b" }], "odd key": 1 });
+        let path = |p| parse_json_path(p).unwrap();
+        assert_eq!(
+            select(&value, &path("$.choices[0].message.content")),
+            Some(&json!("a"))
+        );
+        assert_eq!(
+            select(&value, &path("$.choices[-1]['text']")),
+            Some(&json!("b"))
+        );
+        assert_eq!(select(&value, &path("$[\"odd key\"]")), Some(&json!(1)));
+        assert_eq!(select(&value, &path("$.choices[5]")), None);
+        assert!(parse_json_path("choices[0]").is_err());
+        assert!(parse_json_path("$.choices[first]").is_err());
+    }
+
+    #[test]
+    fn test_templates_fill_each_placeholder_once() {
+        let variables = [
+            ("user", Value::from("say {system}")),
+            ("system", Value::from("be brief")),
+            ("max_tokens", Value::from(512)),
+        ];
+        assert_eq!(
+            render("{user} / {system} / {other}", &variables),
+            "say {system} / be brief / {other}"
+        );
+        assert_eq!(
+            render_json(
+                &json!({ "prompt": "{system}\n\n{user}", "max_tokens": "{max_tokens}", "n": 1 }),
+                &variables
+            ),
+            json!({ "prompt": "be brief\n\nsay {system}", "max_tokens": 512, "n": 1 })
+        );
+    }
+
+    fn azure_config(base: &str) -> HttpProviderConfig {
+        toml::from_str(&format!(
+            r#"
+            url = "{base}/openai/deployments/{{model}}/chat/completions?api-version=2024-06-01"
+            api_key = "azure-key"
+            auth_header = "api-key"
+            response_path = "$.choices[0].message.content"
+            headers = {{ "x-client" = "rknowledge {{model}}" }}
+
+            [body]
+            max_tokens = "{{max_tokens}}"
+            messages = [
+                {{ role = "system", content = "{{system}}" }},
+                {{ role = "user", content = "{{user}}" }},
+            ]
+            "#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_azure_style_endpoint() {
+        let app =
+            Router::new().route(
+                "/openai/deployments/{deployment}/chat/completions",
+                post(
+                    |Path(deployment): Path<String>,
+                     headers: HeaderMap,
+                     Json(body): Json<Value>| async move {
+                        let reply = format!(
+                            "{} {} {} {} {}",
+                            deployment,
+                            headers["api-key"].to_str().unwrap(),
+                            headers["x-client"].to_str().unwrap(),
+                            body["max_tokens"],
+                            body["messages"][1]["content"].as_str().unwrap(),
+                        );
+                        Json(json!({ "choices": [{ "message": { "content": reply } }] }))
+                    },
+                ),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let provider = HttpProvider::new("azure", &azure_config(&base), "gpt4o-prod")
+            .unwrap()
+            .with_max_tokens(1000);
+        let reply = provider.complete("system", "hello").await.unwrap();
+        assert_eq!(
+            reply,
+            "gpt4o-prod azure-key rknowledge gpt4o-prod 1000 hello"
+        );
+
+        let mut missing = azure_config(&base);
+        missing.response_path = "$.output.text".to_string();
+        let err = HttpProvider::new("azure", &missing, "gpt4o-prod")
+            .unwrap()
+            .complete("system", "hello")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No value at $.output.text"));
+    }
+}
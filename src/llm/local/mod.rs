@@ -0,0 +1,456 @@
+//! In-process inference over a quantized GGUF model, on the CPU
+//!
+//! Llama-family (Llama 2 and 3, Mistral, TinyLlama) and Qwen 2 architectures
+//! are supported. The tokenizer and chat format come from the file itself, so
+//! no network access is needed at any point.
+
+mod vocab;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use candle_core::quantized::gguf_file;
+use candle_core::{Device, Tensor};
+use candle_transformers::generation::LogitsProcessor;
+use candle_transformers::models::{quantized_llama, quantized_qwen2};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::LlmProviderTrait;
+use crate::config::{LocalProviderConfig, ModelSpec};
+use vocab::{Vocab, integer};
+
+/// Context window when neither the config nor the file gives one
+const DEFAULT_CONTEXT_SIZE: usize = 4096;
+
+/// Response length when the config gives none
+const DEFAULT_MAX_TOKENS: usize = 1024;
+
+/// The model's limits from the file's metadata and the `[providers.local]` section
+///
+/// The window is the smallest of the configured size, the size the model was
+/// trained with and what the runtime supports for the architecture.
+pub fn model_spec(path: &Path, config: &LocalProviderConfig) -> Result<ModelSpec> {
+    let (content, _) = read_content(path)?;
+    let architecture = architecture(&content.metadata)?;
+    Ok(ModelSpec {
+        context_size: context_size(&content.metadata, &architecture, config),
+        max_output_tokens: config.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        tokenizer: None,
+    })
+}
+
+/// Provider that runs a GGUF model inside the process
+pub struct LocalProvider {
+    runtime: Arc<Mutex<Runtime>>,
+    max_tokens: usize,
+    temperature: f64,
+    seed: u64,
+}
+
+/// The loaded weights, one forward pass at a time
+struct Runtime {
+    weights: Weights,
+    vocab: Vocab,
+    format: ChatFormat,
+    context_size: usize,
+    device: Device,
+}
+
+enum Weights {
+    Llama(quantized_llama::ModelWeights),
+    Qwen2(quantized_qwen2::ModelWeights),
+}
+
+impl Weights {
+    /// Logits for the token after `input`; position 0 starts a new sequence
+    fn forward(&mut self, input: &Tensor, position: usize) -> candle_core::Result<Tensor> {
+        match self {
+            Self::Llama(model) => model.forward(input, position),
+            Self::Qwen2(model) => model.forward(input, position),
+        }
+    }
+}
+
+impl LocalProvider {
+    /// Load the weights and tokenizer from a `.gguf` file
+    pub fn load(path: &Path, config: &LocalProviderConfig) -> Result<Self> {
+        let (content, mut reader) = read_content(path)?;
+        let architecture = architecture(&content.metadata)?;
+        let context_size = context_size(&content.metadata, &architecture, config);
+        let vocab = Vocab::from_metadata(&content.metadata)?;
+        let format = ChatFormat::detect(&vocab, &architecture);
+
+        let device = Device::Cpu;
+        let weights = match architecture.as_str() {
+            "llama" => Weights::Llama(quantized_llama::ModelWeights::from_gguf(
+                content,
+                &mut reader,
+                &device,
+            )?),
+            "qwen2" => Weights::Qwen2(quantized_qwen2::ModelWeights::from_gguf(
+                content,
+                &mut reader,
+                &device,
+            )?),
+            other => anyhow::bail!(
+                "Unsupported model architecture '{}'. Use a Llama, Mistral or Qwen 2 GGUF.",
+                other
+            ),
+        };
+
+        Ok(Self {
+            runtime: Arc::new(Mutex::new(Runtime {
+                weights,
+                vocab,
+                format,
+                context_size,
+                device,
+            })),
+            max_tokens: config.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            temperature: config.temperature,
+            seed: config.seed,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProviderTrait for LocalProvider {
+    async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
+        let runtime = Arc::clone(&self.runtime);
+        let prompt = (system.to_string(), user_message.to_string());
+        let mut sampler = LogitsProcessor::new(
+            self.seed,
+            (self.temperature > 0.0).then_some(self.temperature),
+            None,
+        );
+        let max_tokens = self.max_tokens;
+
+        // Inference holds a core for seconds; keep it off the async workers
+        tokio::task::spawn_blocking(move || {
+            let mut runtime = runtime
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Local model is unusable after a failed run"))?;
+            runtime.generate(&prompt.0, &prompt.1, max_tokens, &mut sampler)
+        })
+        .await
+        .context("Local inference task failed")?
+    }
+
+    fn name(&self) -> &'static str {
+        "local"
+    }
+}
+
+impl Runtime {
+    fn generate(
+        &mut self,
+        system: &str,
+        user_message: &str,
+        max_tokens: usize,
+        sampler: &mut LogitsProcessor,
+    ) -> Result<String> {
+        let prompt = self.format.encode(&self.vocab, system, user_message);
+        // Leave room for at least a short answer
+        let room = self.context_size.saturating_sub(prompt.len());
+        if room < max_tokens.min(256) {
+            anyhow::bail!(
+                "Prompt of {} tokens exceeds the model's context size ({} tokens)",
+                prompt.len(),
+                self.context_size
+            );
+        }
+
+        let input = Tensor::new(prompt.as_slice(), &self.device)?.unsqueeze(0)?;
+        let mut logits = self.weights.forward(&input, 0)?.squeeze(0)?;
+        let mut output = Vec::new();
+        for position in prompt.len()..prompt.len() + max_tokens.min(room) {
+            let next = sampler.sample(&logits)?;
+            if self.vocab.is_stop(next) {
+                break;
+            }
+            output.push(next);
+            let input = Tensor::new(&[next], &self.device)?.unsqueeze(0)?;
+            logits = self.weights.forward(&input, position)?.squeeze(0)?;
+        }
+        Ok(self.vocab.decode(&output))
+    }
+}
+
+/// How a model expects a conversation to be laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChatFormat {
+    /// `<|start_header_id|>role<|end_header_id|>` turns (Llama 3)
+    Llama3,
+    /// `<|im_start|>role` turns (Qwen, many fine-tunes)
+    ChatMl,
+    /// `[INST] ... [/INST]` (Llama 2, Mistral)
+    Instruct,
+    /// System and user text one after the other
+    Plain,
+}
+
+/// A piece of a prompt: a marker encoded as its own token where the
+/// vocabulary has one, or ordinary text
+enum Part<'a> {
+    Special(&'a str),
+    Text(&'a str),
+}
+
+impl ChatFormat {
+    /// Pick the format from the markers the vocabulary knows
+    fn detect(vocab: &Vocab, architecture: &str) -> Self {
+        if vocab.token_id("<|start_header_id|>").is_some() {
+            Self::Llama3
+        } else if vocab.token_id("<|im_start|>").is_some() {
+            Self::ChatMl
+        } else if architecture == "llama" {
+            Self::Instruct
+        } else {
+            Self::Plain
+        }
+    }
+
+    fn parts<'a>(self, system: &'a str, user_message: &'a str) -> Vec<Part<'a>> {
+        use Part::{Special, Text};
+        match self {
+            Self::Llama3 => vec![
+                Special("<|start_header_id|>"),
+                Text("system"),
+                Special("<|end_header_id|>"),
+                Text("\n\n"),
+                Text(system),
+                Special("<|eot_id|>"),
+                Special("<|start_header_id|>"),
+                Text("user"),
+                Special("<|end_header_id|>"),
+                Text("\n\n"),
+                Text(user_message),
+                Special("<|eot_id|>"),
+                Special("<|start_header_id|>"),
+                Text("assistant"),
+                Special("<|end_header_id|>"),
+                Text("\n\n"),
+            ],
+            Self::ChatMl => vec![
+                Special("<|im_start|>"),
+                Text("system\n"),
+                Text(system),
+                Special("<|im_end|>"),
+                Text("\n"),
+                Special("<|im_start|>"),
+                Text("user\n"),
+                Text(user_message),
+                Special("<|im_end|>"),
+                Text("\n"),
+                Special("<|im_start|>"),
+                Text("assistant\n"),
+            ],
+            Self::Instruct => vec![
+                Special("[INST]"),
+                Text(system),
+                Text("\n\n"),
+                Text(user_message),
+                Special("[/INST]"),
+            ],
+            Self::Plain => vec![Text(system), Text("\n\n"), Text(user_message), Text("\n\n")],
+        }
+    }
+
+    /// Token ids for a system prompt and user message, after the BOS token
+    fn encode(self, vocab: &Vocab, system: &str, user_message: &str) -> Vec<u32> {
+        let mut ids: Vec<u32> = vocab.bos.into_iter().collect();
+        // Adjacent text parts are encoded together, so merges span them
+        let mut text = String::new();
+        for part in self.parts(system, user_message) {
+            match part {
+                Part::Special(marker) => match vocab.token_id(marker) {
+                    Some(id) => {
+                        ids.extend(flush(vocab, &mut text));
+                        ids.push(id);
+                    }
+                    None => text.push_str(marker),
+                },
+                Part::Text(part) => text.push_str(part),
+            }
+        }
+        ids.extend(flush(vocab, &mut text));
+        ids
+    }
+}
+
+fn flush(vocab: &Vocab, text: &mut String) -> Vec<u32> {
+    let ids = if text.is_empty() {
+        Vec::new()
+    } else {
+        vocab.encode(text)
+    };
+    text.clear();
+    ids
+}
+
+/// The file's metadata, and a reader positioned to load its tensors
+fn read_content(path: &Path) -> Result<(gguf_file::Content, BufReader<File>)> {
+    let mut reader = BufReader::new(
+        File::open(path).with_context(|| format!("Failed to open model {}", path.display()))?,
+    );
+    let content = gguf_file::Content::read(&mut reader)
+        .with_context(|| format!("{} is not a GGUF file", path.display()))?;
+    Ok((content, reader))
+}
+
+fn architecture(metadata: &HashMap<String, gguf_file::Value>) -> Result<String> {
+    Ok(metadata
+        .get("general.architecture")
+        .context("GGUF file has no general.architecture")?
+        .to_string()?
+        .clone())
+}
+
+fn context_size(
+    metadata: &HashMap<String, gguf_file::Value>,
+    architecture: &str,
+    config: &LocalProviderConfig,
+) -> usize {
+    let trained = metadata
+        .get(&format!("{}.context_length", architecture))
+        .and_then(integer)
+        .map_or(usize::MAX, |n| n as usize);
+    // The llama runtime precomputes rotary embeddings for a fixed length
+    let supported = match architecture {
+        "llama" => quantized_llama::MAX_SEQ_LEN,
+        _ => usize::MAX,
+    };
+    config
+        .context_size
+        .unwrap_or(DEFAULT_CONTEXT_SIZE)
+        .min(trained)
+        .min(supported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gguf_file::Value;
+
+    #[test]
+    fn test_chat_formats() {
+        let vocab = Vocab::from_metadata(&vocab::tests::sentencepiece_metadata()).unwrap();
+        let format = ChatFormat::detect(&vocab, "llama");
+        assert_eq!(format, ChatFormat::Instruct);
+
+        let ids = format.encode(&vocab, "hello", "world");
+        // BOS, the [INST] token, then [/INST] spelled out as text
+        assert_eq!(&ids[..2], &[1, 3]);
+        assert_eq!(vocab.decode(&ids[2..]), "hello\n\nworld[/INST]");
+        assert_eq!(ChatFormat::detect(&vocab, "phi3"), ChatFormat::Plain);
+    }
+
+    #[test]
+    fn test_model_spec_reads_metadata() {
+        let mut metadata = vocab::tests::sentencepiece_metadata();
+        metadata.insert(
+            "general.architecture".to_string(),
+            Value::String("qwen2".to_string()),
+        );
+        metadata.insert("qwen2.context_length".to_string(), Value::U32(32_768));
+        let entries: Vec<(&str, &Value)> = metadata.iter().map(|(k, v)| (k.as_str(), v)).collect();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiny.gguf");
+        let mut file = File::create(&path).unwrap();
+        gguf_file::write(&mut file, &entries, &[]).unwrap();
+
+        let spec = model_spec(&path, &LocalProviderConfig::default()).unwrap();
+        assert_eq!(spec.context_size, DEFAULT_CONTEXT_SIZE);
+        assert_eq!(spec.max_output_tokens, DEFAULT_MAX_TOKENS);
+
+        let config = LocalProviderConfig {
+            context_size: Some(65_536),
+            max_tokens: Some(512),
+            ..Default::default()
+        };
+        let spec = model_spec(&path, &config).unwrap();
+        assert_eq!(spec.context_size, 32_768);
+        assert_eq!(spec.max_output_tokens, 512);
+
+        let err = model_spec(&dir.path().join("missing.gguf"), &config).unwrap_err();
+        assert!(err.to_string().contains("Failed to open model"));
+    }
+
+    /// A one-layer llama with random weights over the test vocabulary
+    fn tiny_llama(path: &Path) {
+        use candle_core::quantized::{GgmlDType, QTensor};
+
+        let mut metadata = vocab::tests::sentencepiece_metadata();
+        let vocab_size = metadata["tokenizer.ggml.tokens"].to_vec().unwrap().len();
+        for (key, value) in [
+            ("general.architecture", Value::String("llama".to_string())),
+            ("llama.context_length", Value::U32(2048)),
+            ("llama.attention.head_count", Value::U32(2)),
+            ("llama.attention.head_count_kv", Value::U32(2)),
+            ("llama.block_count", Value::U32(1)),
+            ("llama.embedding_length", Value::U32(8)),
+            ("llama.rope.dimension_count", Value::U32(4)),
+            ("llama.attention.layer_norm_rms_epsilon", Value::F32(1e-5)),
+        ] {
+            metadata.insert(key.to_string(), value);
+        }
+
+        let (dim, ffn) = (8, 16);
+        let shapes = [
+            ("token_embd.weight", vec![vocab_size, dim]),
+            ("output_norm.weight", vec![dim]),
+            ("output.weight", vec![vocab_size, dim]),
+            ("blk.0.attn_q.weight", vec![dim, dim]),
+            ("blk.0.attn_k.weight", vec![dim, dim]),
+            ("blk.0.attn_v.weight", vec![dim, dim]),
+            ("blk.0.attn_output.weight", vec![dim, dim]),
+            ("blk.0.ffn_gate.weight", vec![ffn, dim]),
+            ("blk.0.ffn_down.weight", vec![dim, ffn]),
+            ("blk.0.ffn_up.weight", vec![ffn, dim]),
+            ("blk.0.attn_norm.weight", vec![dim]),
+            ("blk.0.ffn_norm.weight", vec![dim]),
+        ];
+        let tensors: Vec<(&str, QTensor)> = shapes
+            .iter()
+            .map(|(name, shape)| {
+                let weights = Tensor::randn(0f32, 1.0, shape.as_slice(), &Device::Cpu).unwrap();
+                (*name, QTensor::quantize(&weights, GgmlDType::F32).unwrap())
+            })
+            .collect();
+
+        let entries: Vec<(&str, &Value)> = metadata.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        let tensors: Vec<(&str, &QTensor)> = tensors.iter().map(|(n, t)| (*n, t)).collect();
+        let mut file = File::create(path).unwrap();
+        gguf_file::write(&mut file, &entries, &tensors).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_generates_within_the_context_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiny-llama.gguf");
+        tiny_llama(&path);
+
+        let config = LocalProviderConfig {
+            context_size: Some(64),
+            max_tokens: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(model_spec(&path, &config).unwrap().context_size, 64);
+        let provider = LocalProvider::load(&path, &config).unwrap();
+
+        let reply = provider.complete("hello", "world").await.unwrap();
+        assert!(reply.len() <= 8 * 4);
+        // Greedy decoding gives the same reply every time
+        assert_eq!(provider.complete("hello", "world").await.unwrap(), reply);
+
+        let err = provider
+            .complete("hello", &"world ".repeat(80))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("context size"));
+    }
+}
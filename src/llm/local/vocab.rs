@@ -0,0 +1,412 @@
+//! The tokenizer a GGUF file carries in its `tokenizer.ggml.*` metadata
+//!
+//! Two families are understood:
+//! - `llama`: SentencePiece pieces with scores and `<0xAB>` byte fallback
+//!   (Llama 2, Mistral, TinyLlama)
+//! - `gpt2`: byte-level BPE with a merge list (Llama 3, Qwen 2)
+
+use anyhow::{Context, Result, bail};
+use candle_core::quantized::gguf_file::Value;
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::parser::BPE_PATTERN;
+
+/// SentencePiece word-boundary marker
+const SPACE_MARKER: char = '\u{2581}';
+
+/// `tokenizer.ggml.token_type` values
+const TYPE_CONTROL: i32 = 3;
+const TYPE_BYTE: i32 = 6;
+
+/// How text becomes pieces
+enum Model {
+    SentencePiece {
+        scores: Vec<f32>,
+        byte_tokens: HashMap<u8, u32>,
+    },
+    Bpe {
+        merges: HashMap<(String, String), usize>,
+        pattern: Regex,
+        byte_chars: Box<[char; 256]>,
+    },
+}
+
+/// A model's vocabulary, for encoding prompts and decoding responses
+pub struct Vocab {
+    tokens: Vec<String>,
+    types: Vec<i32>,
+    ids: HashMap<String, u32>,
+    model: Model,
+    pub bos: Option<u32>,
+    pub eos: Option<u32>,
+    unknown: Option<u32>,
+}
+
+impl Vocab {
+    /// Read the vocabulary from GGUF metadata
+    pub fn from_metadata(metadata: &HashMap<String, Value>) -> Result<Self> {
+        let kind = string(metadata, "tokenizer.ggml.model")?;
+        let tokens: Vec<String> = array(metadata, "tokenizer.ggml.tokens")?
+            .iter()
+            .map(|v| v.to_string().cloned())
+            .collect::<candle_core::Result<_>>()?;
+        let types: Vec<i32> = match metadata.get("tokenizer.ggml.token_type") {
+            Some(value) => value
+                .to_vec()?
+                .iter()
+                .map(|v| integer(v).map_or(1, |t| t as i32))
+                .collect(),
+            None => vec![1; tokens.len()],
+        };
+        let ids: HashMap<String, u32> = tokens
+            .iter()
+            .enumerate()
+            .map(|(id, token)| (token.clone(), id as u32))
+            .collect();
+
+        let model = match kind.as_str() {
+            "llama" => {
+                let scores = match metadata.get("tokenizer.ggml.scores") {
+                    Some(value) => value
+                        .to_vec()?
+                        .iter()
+                        .map(|v| v.to_f32())
+                        .collect::<candle_core::Result<_>>()?,
+                    None => vec![0.0; tokens.len()],
+                };
+                let byte_tokens = (0..=255u8)
+                    .filter_map(|b| ids.get(&format!("<0x{:02X}>", b)).map(|id| (b, *id)))
+                    .collect();
+                Model::SentencePiece {
+                    scores,
+                    byte_tokens,
+                }
+            }
+            "gpt2" => {
+                let merges = array(metadata, "tokenizer.ggml.merges")?
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(rank, merge)| {
+                        let (left, right) = merge.to_string().ok()?.split_once(' ')?;
+                        Some(((left.to_string(), right.to_string()), rank))
+                    })
+                    .collect();
+                Model::Bpe {
+                    merges,
+                    pattern: Regex::new(BPE_PATTERN).expect("valid BPE pattern"),
+                    byte_chars: Box::new(byte_chars()),
+                }
+            }
+            other => bail!("Unsupported GGUF tokenizer '{}'", other),
+        };
+
+        let id = |key: &str| metadata.get(key).and_then(integer).map(|id| id as u32);
+        Ok(Self {
+            bos: id("tokenizer.ggml.bos_token_id"),
+            eos: id("tokenizer.ggml.eos_token_id"),
+            unknown: id("tokenizer.ggml.unknown_token_id"),
+            tokens,
+            types,
+            ids,
+            model,
+        })
+    }
+
+    /// Id of a token spelled exactly `token`, such as `<|im_start|>`
+    pub fn token_id(&self, token: &str) -> Option<u32> {
+        self.ids.get(token).copied()
+    }
+
+    /// Whether generation ends at this token: end of sequence or any control token
+    pub fn is_stop(&self, id: u32) -> bool {
+        Some(id) == self.eos || self.types.get(id as usize) == Some(&TYPE_CONTROL)
+    }
+
+    /// Token ids for plain text; special tokens are never produced
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        match &self.model {
+            Model::SentencePiece {
+                scores,
+                byte_tokens,
+            } => {
+                // Dummy prefix and spaces as markers, as llama.cpp does
+                let normalized: String = std::iter::once(' ')
+                    .chain(text.chars())
+                    .map(|c| if c == ' ' { SPACE_MARKER } else { c })
+                    .collect();
+                let mut ids = Vec::new();
+                for word in split_before_marker(&normalized) {
+                    let symbols = self.merge(word.chars().map(String::from).collect(), |l, r| {
+                        let id = self.ids.get(&format!("{}{}", l, r))?;
+                        // Highest score first; negate so that min() picks it
+                        Some(-scores.get(*id as usize).copied().unwrap_or(0.0))
+                    });
+                    for symbol in symbols {
+                        match self.ids.get(&symbol) {
+                            Some(id) => ids.push(*id),
+                            None => ids.extend(
+                                symbol
+                                    .bytes()
+                                    .filter_map(|b| byte_tokens.get(&b).copied().or(self.unknown)),
+                            ),
+                        }
+                    }
+                }
+                ids
+            }
+            Model::Bpe {
+                merges,
+                pattern,
+                byte_chars,
+            } => {
+                let mut ids = Vec::new();
+                for piece in pattern.find_iter(text) {
+                    let symbols = piece
+                        .as_str()
+                        .bytes()
+                        .map(|b| byte_chars[b as usize].to_string())
+                        .collect();
+                    let symbols = self.merge(symbols, |l, r| {
+                        merges
+                            .get(&(l.to_string(), r.to_string()))
+                            .map(|rank| *rank as f32)
+                    });
+                    for symbol in symbols {
+                        match self.ids.get(&symbol) {
+                            Some(id) => ids.push(*id),
+                            None => ids.extend(
+                                symbol
+                                    .chars()
+                                    .filter_map(|c| self.ids.get(&c.to_string()).copied()),
+                            ),
+                        }
+                    }
+                }
+                ids
+            }
+        }
+    }
+
+    /// Text for generated ids; control tokens are left out
+    pub fn decode(&self, ids: &[u32]) -> String {
+        let mut bytes = Vec::new();
+        for &id in ids {
+            let (Some(token), Some(&kind)) =
+                (self.tokens.get(id as usize), self.types.get(id as usize))
+            else {
+                continue;
+            };
+            if kind == TYPE_CONTROL {
+                continue;
+            }
+            match &self.model {
+                Model::SentencePiece { .. } => {
+                    let byte = token
+                        .strip_prefix("<0x")
+                        .and_then(|hex| hex.strip_suffix('>'))
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                    match byte {
+                        Some(byte) if kind == TYPE_BYTE => bytes.push(byte),
+                        _ => bytes.extend(token.replace(SPACE_MARKER, " ").bytes()),
+                    }
+                }
+                Model::Bpe { byte_chars, .. } => {
+                    for c in token.chars() {
+                        match byte_chars.iter().position(|b| *b == c) {
+                            Some(byte) => bytes.push(byte as u8),
+                            None => bytes.extend(c.to_string().bytes()),
+                        }
+                    }
+                }
+            }
+        }
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+        match self.model {
+            // Undo the dummy prefix
+            Model::SentencePiece { .. } => text.strip_prefix(' ').map(String::from).unwrap_or(text),
+            Model::Bpe { .. } => text,
+        }
+    }
+
+    /// Merge the adjacent pair with the lowest priority until no pair merges
+    fn merge(
+        &self,
+        mut symbols: Vec<String>,
+        priority: impl Fn(&str, &str) -> Option<f32>,
+    ) -> Vec<String> {
+        loop {
+            let best = symbols
+                .windows(2)
+                .enumerate()
+                .filter_map(|(i, pair)| priority(&pair[0], &pair[1]).map(|p| (p, i)))
+                .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+            match best {
+                Some((_, i)) => {
+                    let right = symbols.remove(i + 1);
+                    symbols[i].push_str(&right);
+                }
+                None => return symbols,
+            }
+        }
+    }
+}
+
+/// An integer of any width; GGUF writers differ in which they use
+pub(super) fn integer(value: &Value) -> Option<u64> {
+    match value {
+        Value::U8(v) => Some(*v as u64),
+        Value::U16(v) => Some(*v as u64),
+        Value::U32(v) => Some(*v as u64),
+        Value::U64(v) => Some(*v),
+        Value::I8(v) => u64::try_from(*v).ok(),
+        Value::I16(v) => u64::try_from(*v).ok(),
+        Value::I32(v) => u64::try_from(*v).ok(),
+        Value::I64(v) => u64::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+fn string(metadata: &HashMap<String, Value>, key: &str) -> Result<String> {
+    Ok(metadata
+        .get(key)
+        .with_context(|| format!("GGUF file has no {}", key))?
+        .to_string()?
+        .clone())
+}
+
+fn array<'a>(metadata: &'a HashMap<String, Value>, key: &str) -> Result<&'a Vec<Value>> {
+    Ok(metadata
+        .get(key)
+        .with_context(|| format!("GGUF file has no {}", key))?
+        .to_vec()?)
+}
+
+/// Split before every word-boundary marker, so merges stay within words
+fn split_before_marker(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if c == SPACE_MARKER && i > start {
+            words.push(&text[start..i]);
+            start = i;
+        }
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// GPT-2's printable stand-in for each byte
+fn byte_chars() -> [char; 256] {
+    let mut chars = ['\0'; 256];
+    let mut next = 256u32;
+    for (byte, slot) in chars.iter_mut().enumerate() {
+        let printable = matches!(byte, 0x21..=0x7E | 0xA1..=0xAC | 0xAE..=0xFF);
+        let code = if printable {
+            byte as u32
+        } else {
+            next += 1;
+            next - 1
+        };
+        *slot = char::from_u32(code).expect("valid code point");
+    }
+    chars
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Value {
+        Value::Array(items.iter().map(|s| Value::String(s.to_string())).collect())
+    }
+
+    /// A SentencePiece vocabulary with byte fallback and chat markers
+    pub(in crate::llm::local) fn sentencepiece_metadata() -> HashMap<String, Value> {
+        let mut tokens = vec!["<unk>", "<s>", "</s>", "[INST]"];
+        let bytes: Vec<String> = (0..=255u8).map(|b| format!("<0x{:02X}>", b)).collect();
+        tokens.extend(bytes.iter().map(String::as_str));
+        tokens.extend([
+            "▁", "h", "e", "l", "o", "w", "r", "d", "he", "ll", "▁he", "▁hell", "▁hello", "▁w",
+            "or", "▁wor", "ld", "▁world",
+        ]);
+        let types: Vec<Value> = tokens
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                Value::I32(match (i, t.starts_with("<0x")) {
+                    (1..=3, _) => TYPE_CONTROL,
+                    (_, true) => TYPE_BYTE,
+                    _ => 1,
+                })
+            })
+            .collect();
+        let scores = (0..tokens.len()).map(|i| Value::F32(i as f32)).collect();
+        HashMap::from([
+            (
+                "tokenizer.ggml.model".to_string(),
+                Value::String("llama".to_string()),
+            ),
+            ("tokenizer.ggml.tokens".to_string(), strings(&tokens)),
+            ("tokenizer.ggml.token_type".to_string(), Value::Array(types)),
+            ("tokenizer.ggml.scores".to_string(), Value::Array(scores)),
+            ("tokenizer.ggml.bos_token_id".to_string(), Value::U32(1)),
+            ("tokenizer.ggml.eos_token_id".to_string(), Value::U32(2)),
+        ])
+    }
+
+    #[test]
+    fn test_sentencepiece_round_trip_with_byte_fallback() {
+        let vocab = Vocab::from_metadata(&sentencepiece_metadata()).unwrap();
+        let ids = vocab.encode("hello world é");
+        let pieces: Vec<&str> = ids
+            .iter()
+            .map(|id| vocab.tokens[*id as usize].as_str())
+            .collect();
+        assert_eq!(pieces, ["▁hello", "▁world", "▁", "<0xC3>", "<0xA9>"]);
+        assert_eq!(vocab.decode(&ids), "hello world é");
+        assert!(vocab.is_stop(2) && vocab.is_stop(3) && !vocab.is_stop(4));
+    }
+
+    #[test]
+    fn test_byte_level_bpe_round_trip() {
+        let chars = byte_chars();
+        assert_eq!(chars[b'a' as usize], 'a');
+        assert_eq!(chars[b' ' as usize], 'Ġ');
+        assert_eq!(chars[b'\n' as usize], 'Ċ');
+
+        let mut tokens: Vec<String> = chars.iter().map(char::to_string).collect();
+        tokens.extend(["Ġw", "or", "ld", "Ġwor", "Ġworld", "<|im_end|>"].map(String::from));
+        let mut types = vec![Value::I32(1); tokens.len() - 1];
+        types.push(Value::I32(TYPE_CONTROL));
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        let metadata = HashMap::from([
+            (
+                "tokenizer.ggml.model".to_string(),
+                Value::String("gpt2".to_string()),
+            ),
+            ("tokenizer.ggml.tokens".to_string(), strings(&tokens)),
+            ("tokenizer.ggml.token_type".to_string(), Value::Array(types)),
+            (
+                "tokenizer.ggml.merges".to_string(),
+                strings(&["Ġ w", "o r", "l d", "Ġw or", "Ġwor ld"]),
+            ),
+        ]);
+        let vocab = Vocab::from_metadata(&metadata).unwrap();
+
+        let ids = vocab.encode("hi world\n");
+        let pieces: Vec<&str> = ids
+            .iter()
+            .map(|id| vocab.tokens[*id as usize].as_str())
+            .collect();
+        assert_eq!(pieces, ["h", "i", "Ġworld", "Ċ"]);
+        let end = vocab.token_id("<|im_end|>").unwrap();
+        assert!(vocab.is_stop(end));
+        assert_eq!(
+            vocab.decode(&[ids.as_slice(), &[end]].concat()),
+            "hi world\n"
+        );
+    }
+}
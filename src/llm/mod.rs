@@ -1,58 +1,135 @@
 pub mod adaptive_processor;
 mod anthropic;
 pub mod batch_processor;
+mod coreference;
+mod embeddings;
 mod google;
+mod http;
+#[cfg(feature = "local")]
+mod local;
 mod ollama;
 mod openai;
 pub(crate) mod parsing;
 mod prompts;
+mod replay;
+mod template;
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 use crate::cli::LlmProvider;
-use crate::config::{Config, DomainConfig};
+#[cfg(feature = "local")]
+use crate::config::LocalProviderConfig;
+use crate::config::{Config, DomainConfig, HttpProviderConfig, ModelSpec};
+use crate::parser::ModelRegistry;
+
+pub use coreference::MentionTable;
+pub use embeddings::EmbeddingClient;
+pub use prompts::PromptTemplates;
+pub use replay::FixtureManifest;
+
+use coreference::{COREFERENCE_SYSTEM_PROMPT, coreference_user_prompt};
+use parsing::{parse_relations_json, try_parse_relations_json};
+use prompts::graph_extraction_user_prompt;
 
 /// A relation extracted from text
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Relation {
     pub node_1: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_1_type: Option<String>,
     pub node_2: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub node_2_type: Option<String>,
     pub edge: String,
 }
 
+/// What kind of text is being extracted from, which selects the system prompt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentKind {
+    /// Documents and prose
+    #[default]
+    Text,
+    /// Source code, with symbol hints in front of each chunk
+    Code,
+}
+
 /// Trait for LLM providers
 #[async_trait]
 pub trait LlmProviderTrait: Send + Sync {
-    /// Extract relations from text using the LLM
-    async fn extract_relations(
-        &self,
-        text: &str,
-        domain: Option<&DomainConfig>,
-    ) -> Result<Vec<Relation>>;
+    /// Send a system prompt and a user message, and return the model's reply
+    async fn complete(&self, system: &str, user_message: &str) -> Result<String>;
 
     /// Get the provider name
     #[allow(dead_code)]
     fn name(&self) -> &'static str;
 }
 
+/// The `[providers.http.<name>]` section behind a provider name
+pub fn http_provider_config<'a>(config: &'a Config, name: &str) -> Result<&'a HttpProviderConfig> {
+    config.providers.http.get(name).with_context(|| {
+        format!(
+            "Unknown provider '{}'. Use anthropic, openai, google, ollama or local, or describe the endpoint in [providers.http.{}]",
+            name, name
+        )
+    })
+}
+
+/// The `.gguf` file for the local provider: `--model`, else `[providers.local] model`
+#[cfg(feature = "local")]
+fn local_model<'a>(
+    config: &'a Config,
+    model_override: Option<&'a str>,
+) -> Result<(&'a str, LocalProviderConfig)> {
+    let local = config.providers.local.clone().unwrap_or_default();
+    let path = model_override
+        .or(config.providers.local.as_ref().and_then(|l| l.model.as_deref()))
+        .context(
+            "The local provider needs a model. Set model = \"/path/to/model.gguf\" under [providers.local] or pass --model.",
+        )?;
+    Ok((path, local))
+}
+
+/// Context window and output limit of the local provider's model, read from the file
+#[cfg(feature = "local")]
+pub fn local_model_spec(config: &Config, model_override: Option<&str>) -> Result<ModelSpec> {
+    let (path, local) = local_model(config, model_override)?;
+    local::model_spec(Path::new(path), &local)
+}
+
+/// Context window and output limit of the local provider's model, read from the file
+#[cfg(not(feature = "local"))]
+pub fn local_model_spec(_config: &Config, _model_override: Option<&str>) -> Result<ModelSpec> {
+    anyhow::bail!("{}", LOCAL_FEATURE_MISSING)
+}
+
+#[cfg(not(feature = "local"))]
+const LOCAL_FEATURE_MISSING: &str =
+    "This build has no local provider. Rebuild with: cargo build --release --features local";
+
 /// Main LLM client that abstracts over providers
 pub struct LlmClient {
     provider: Box<dyn LlmProviderTrait>,
+    prompts: PromptTemplates,
 }
 
 impl LlmClient {
     /// Create a new LLM client for the specified provider
+    ///
+    /// Models found in the registry get their output limit as the response cap.
     pub fn new(
         provider: LlmProvider,
         config: &Config,
         model_override: Option<&str>,
     ) -> Result<Self> {
+        let registry = ModelRegistry::from_config(config);
+        let max_tokens = |model: &str| {
+            registry
+                .lookup(model)
+                .map_or(4096, |spec| spec.max_output_tokens as u32)
+        };
         let provider_impl: Box<dyn LlmProviderTrait> = match provider {
             LlmProvider::Anthropic => {
                 let provider_config = config
@@ -62,11 +139,14 @@ impl LlmClient {
                     .map(String::from)
                     .or_else(|| provider_config.model.clone())
                     .unwrap_or_else(|| "claude-sonnet-4-20250514".to_string());
-                Box::new(anthropic::AnthropicProvider::new(
-                    &provider_config.api_key,
-                    &model,
-                    provider_config.base_url.as_deref(),
-                )?)
+                Box::new(
+                    anthropic::AnthropicProvider::new(
+                        &provider_config.api_key,
+                        &model,
+                        provider_config.base_url.as_deref(),
+                    )?
+                    .with_max_tokens(max_tokens(&model)),
+                )
             }
             LlmProvider::OpenAI => {
                 let provider_config = config
@@ -76,11 +156,14 @@ impl LlmClient {
                     .map(String::from)
                     .or_else(|| provider_config.model.clone())
                     .unwrap_or_else(|| "gpt-4o".to_string());
-                Box::new(openai::OpenAIProvider::new(
-                    &provider_config.api_key,
-                    &model,
-                    provider_config.base_url.as_deref(),
-                )?)
+                Box::new(
+                    openai::OpenAIProvider::new(
+                        &provider_config.api_key,
+                        &model,
+                        provider_config.base_url.as_deref(),
+                    )?
+                    .with_max_tokens(max_tokens(&model)),
+                )
             }
             LlmProvider::Ollama => {
                 let provider_config = config
@@ -94,7 +177,11 @@ impl LlmClient {
                     .base_url
                     .as_deref()
                     .unwrap_or("http://localhost:11434");
-                Box::new(ollama::OllamaProvider::new(base_url, &model))
+                let mut ollama = ollama::OllamaProvider::new(base_url, &model);
+                if let Some(spec) = registry.lookup(&model) {
+                    ollama = ollama.with_context_size(spec.context_size);
+                }
+                Box::new(ollama)
             }
             LlmProvider::Google => {
                 let provider_config = config
@@ -104,26 +191,138 @@ impl LlmClient {
                     .map(String::from)
                     .or_else(|| provider_config.model.clone())
                     .unwrap_or_else(|| "gemini-2.0-flash".to_string());
-                Box::new(google::GoogleProvider::new(
-                    &provider_config.api_key,
-                    &model,
-                    provider_config.base_url.as_deref(),
+                Box::new(
+                    google::GoogleProvider::new(
+                        &provider_config.api_key,
+                        &model,
+                        provider_config.base_url.as_deref(),
+                    )?
+                    .with_max_tokens(max_tokens(&model)),
+                )
+            }
+            #[cfg(feature = "local")]
+            LlmProvider::Local => {
+                let (path, local) = local_model(config, model_override)?;
+                Box::new(local::LocalProvider::load(
+                    std::path::Path::new(path),
+                    &local,
                 )?)
             }
+            #[cfg(not(feature = "local"))]
+            LlmProvider::Local => anyhow::bail!("{}", LOCAL_FEATURE_MISSING),
+            LlmProvider::Replay => {
+                anyhow::bail!("The replay provider answers from a recording; pass --fixtures <DIR>")
+            }
+            LlmProvider::Http(name) => {
+                let provider_config = http_provider_config(config, &name)?;
+                let model = model_override
+                    .map(String::from)
+                    .or_else(|| provider_config.model.clone())
+                    .unwrap_or_default();
+                Box::new(
+                    http::HttpProvider::new(&name, provider_config, &model)?
+                        .with_max_tokens(max_tokens(&model)),
+                )
+            }
         };
 
         Ok(Self {
             provider: provider_impl,
+            prompts: PromptTemplates::default(),
         })
     }
 
+    /// Answer every prompt from a fixture directory written by [`recording`](Self::recording)
+    pub fn replay(dir: &Path) -> Self {
+        Self {
+            provider: Box::new(replay::ReplayProvider::new(dir)),
+            prompts: PromptTemplates::default(),
+        }
+    }
+
+    /// Save every response to `dir`, keyed by its prompts
+    pub fn recording(self, dir: &Path) -> Result<Self> {
+        Ok(Self {
+            provider: Box::new(replay::RecordingProvider::new(self.provider, dir)?),
+            prompts: self.prompts,
+        })
+    }
+
+    /// Extract with these system prompt templates instead of the built-in ones
+    pub fn with_prompts(mut self, prompts: PromptTemplates) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// The system prompt templates used for extraction
+    pub fn prompts(&self) -> &PromptTemplates {
+        &self.prompts
+    }
+
+    /// Wrap an existing provider
+    #[cfg(test)]
+    pub(crate) fn from_provider(provider: Box<dyn LlmProviderTrait>) -> Self {
+        Self {
+            provider,
+            prompts: PromptTemplates::default(),
+        }
+    }
+
     /// Extract relations from text
+    ///
+    /// Entities in `mentions` are named by their canonical names, whatever
+    /// form the text uses for them.
     pub async fn extract_relations(
         &self,
         text: &str,
         domain: Option<&DomainConfig>,
+        kind: ContentKind,
+        mentions: &MentionTable,
     ) -> Result<Vec<Relation>> {
-        self.provider.extract_relations(text, domain).await
+        let response = self
+            .complete_extraction(text, domain, kind, mentions)
+            .await?;
+        parse_relations_json(&response)
+    }
+
+    /// Extract relations from text, with `None` when the reply is not a
+    /// relation array (rather than treating it as no relations)
+    pub async fn try_extract_relations(
+        &self,
+        text: &str,
+        domain: Option<&DomainConfig>,
+        kind: ContentKind,
+        mentions: &MentionTable,
+    ) -> Result<Option<Vec<Relation>>> {
+        let response = self
+            .complete_extraction(text, domain, kind, mentions)
+            .await?;
+        Ok(try_parse_relations_json(&response).ok())
+    }
+
+    async fn complete_extraction(
+        &self,
+        text: &str,
+        domain: Option<&DomainConfig>,
+        kind: ContentKind,
+        mentions: &MentionTable,
+    ) -> Result<String> {
+        let system_prompt = self.prompts.system_prompt(domain, kind, mentions);
+        let user_prompt = graph_extraction_user_prompt(text);
+        self.provider.complete(&system_prompt, &user_prompt).await
+    }
+
+    /// Find the entities in a part of a document and the forms used for them,
+    /// keeping the canonical names already in `known`
+    pub async fn resolve_mentions(&self, text: &str, known: &MentionTable) -> Result<MentionTable> {
+        let response = self
+            .provider
+            .complete(
+                COREFERENCE_SYSTEM_PROMPT,
+                &coreference_user_prompt(text, known),
+            )
+            .await?;
+        Ok(MentionTable::parse(&response))
     }
 
     /// Get the provider name
@@ -4,15 +4,13 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-use super::parsing::parse_relations_json;
-use super::prompts::{domain_aware_extraction_prompt, graph_extraction_user_prompt};
-use super::{LlmProviderTrait, Relation};
-use crate::config::DomainConfig;
+use super::LlmProviderTrait;
 
 pub struct OllamaProvider {
     client: Client,
     base_url: String,
     model: String,
+    num_ctx: Option<usize>,
 }
 
 #[derive(Serialize)]
@@ -33,6 +31,8 @@ struct Message {
 struct OllamaOptions {
     temperature: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     num_predict: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     top_p: Option<f32>,
@@ -65,9 +65,19 @@ impl OllamaProvider {
             client,
             base_url: base_url.trim_end_matches('/').to_string(),
             model: model.to_string(),
+            num_ctx: None,
         }
     }
 
+    /// Ask Ollama for the model's full context window instead of its small default
+    pub fn with_context_size(mut self, num_ctx: usize) -> Self {
+        self.num_ctx = Some(num_ctx);
+        self
+    }
+}
+
+#[async_trait]
+impl LlmProviderTrait for OllamaProvider {
     async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
         let request = OllamaChatRequest {
             model: self.model.clone(),
@@ -85,6 +95,8 @@ impl OllamaProvider {
             options: OllamaOptions {
                 // Small temperature for more focused output while avoiding repetition
                 temperature: 0.1,
+                // Window the chunks were sized for, when the model is known
+                num_ctx: self.num_ctx,
                 // Let model decide when to stop (no forced token limit)
                 num_predict: None,
                 // Nucleus sampling for better quality
@@ -118,22 +130,6 @@ impl OllamaProvider {
 
         Ok(response.message.content)
     }
-}
-
-#[async_trait]
-impl LlmProviderTrait for OllamaProvider {
-    async fn extract_relations(
-        &self,
-        text: &str,
-        domain: Option<&DomainConfig>,
-    ) -> Result<Vec<Relation>> {
-        let system_prompt = domain_aware_extraction_prompt(domain);
-        let user_prompt = graph_extraction_user_prompt(text);
-        let response = self.complete(&system_prompt, &user_prompt).await?;
-
-        // Parse JSON response
-        parse_relations_json(&response)
-    }
 
     fn name(&self) -> &'static str {
         "ollama"
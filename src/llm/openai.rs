@@ -3,16 +3,14 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use super::parsing::parse_relations_json;
-use super::prompts::{domain_aware_extraction_prompt, graph_extraction_user_prompt};
-use super::{LlmProviderTrait, Relation};
-use crate::config::DomainConfig;
+use super::LlmProviderTrait;
 
 pub struct OpenAIProvider {
     client: Client,
     api_key: String,
     model: String,
     base_url: String,
+    max_tokens: u32,
 }
 
 #[derive(Serialize)]
@@ -55,9 +53,19 @@ impl OpenAIProvider {
             api_key: api_key.to_string(),
             model: model.to_string(),
             base_url: base_url.unwrap_or("https://api.openai.com/v1").to_string(),
+            max_tokens: 4096,
         })
     }
 
+    /// Cap the response length, e.g. at the model's output limit
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+}
+
+#[async_trait]
+impl LlmProviderTrait for OpenAIProvider {
     async fn complete(&self, system: &str, user_message: &str) -> Result<String> {
         let request = OpenAIRequest {
             model: self.model.clone(),
@@ -71,7 +79,7 @@ impl OpenAIProvider {
                     content: user_message.to_string(),
                 },
             ],
-            max_tokens: 4096,
+            max_tokens: self.max_tokens,
             temperature: 0.0,
         };
 
@@ -102,22 +110,6 @@ impl OpenAIProvider {
             .and_then(|c| c.message.content.clone())
             .context("No content in OpenAI response")
     }
-}
-
-#[async_trait]
-impl LlmProviderTrait for OpenAIProvider {
-    async fn extract_relations(
-        &self,
-        text: &str,
-        domain: Option<&DomainConfig>,
-    ) -> Result<Vec<Relation>> {
-        let system_prompt = domain_aware_extraction_prompt(domain);
-        let user_prompt = graph_extraction_user_prompt(text);
-        let response = self.complete(&system_prompt, &user_prompt).await?;
-
-        // Parse JSON response
-        parse_relations_json(&response)
-    }
 
     fn name(&self) -> &'static str {
         "openai"
@@ -19,19 +19,8 @@ use super::Relation;
 ///
 /// Node names are lowercased and trimmed. Empty relations are discarded.
 pub fn parse_relations_json(response: &str) -> Result<Vec<Relation>> {
-    let json_str = extract_json_array(response);
-
-    match serde_json::from_str::<Vec<Relation>>(&json_str) {
-        Ok(relations) => Ok(relations
-            .into_iter()
-            .map(|mut r| {
-                r.node_1 = r.node_1.to_lowercase().trim().to_string();
-                r.node_2 = r.node_2.to_lowercase().trim().to_string();
-                r.edge = r.edge.trim().to_string();
-                r
-            })
-            .filter(|r| !r.node_1.is_empty() && !r.node_2.is_empty() && r.node_1 != r.node_2)
-            .collect()),
+    match try_parse_relations_json(response) {
+        Ok(relations) => Ok(relations),
         Err(e) => {
             tracing::warn!(
                 "Failed to parse relations JSON: {}. Response: {}",
@@ -43,6 +32,23 @@ pub fn parse_relations_json(response: &str) -> Result<Vec<Relation>> {
     }
 }
 
+/// Like [`parse_relations_json`], but a response that is not a relation
+/// array is an error instead of an empty list
+pub fn try_parse_relations_json(response: &str) -> Result<Vec<Relation>> {
+    let json_str = extract_json_array(response);
+    let relations = serde_json::from_str::<Vec<Relation>>(&json_str)?;
+    Ok(relations
+        .into_iter()
+        .map(|mut r| {
+            r.node_1 = r.node_1.to_lowercase().trim().to_string();
+            r.node_2 = r.node_2.to_lowercase().trim().to_string();
+            r.edge = r.edge.trim().to_string();
+            r
+        })
+        .filter(|r| !r.node_1.is_empty() && !r.node_2.is_empty() && r.node_1 != r.node_2)
+        .collect())
+}
+
 /// Extract a JSON array from a response that may contain extra text.
 ///
 /// Tries the following strategies in order:
@@ -1,40 +1,3 @@
-/// System prompt for extracting relations from text
-pub const GRAPH_EXTRACTION_SYSTEM_PROMPT: &str = r#"You are a network graph maker who extracts terms and their relations from a given context.
-You are provided with a context chunk (delimited by ```). Your task is to extract the ontology of terms mentioned in the given context. These terms should represent the key concepts as per the context.
-
-Thought 1: While traversing through each sentence, think about the key terms mentioned in it.
-    Terms may include object, entity, location, organization, person, condition, acronym, documents, service, concept, etc.
-    Terms should be as atomistic as possible.
-
-Thought 2: Think about how these terms can have one on one relation with other terms.
-    Terms that are mentioned in the same sentence or the same paragraph are typically related to each other.
-    Terms can be related to many other terms.
-
-Thought 3: Find out the relation between each such related pair of terms.
-
-Thought 4: Classify each term with a short descriptive type that best captures what it is (e.g. "programming language", "database", "design pattern", "medical condition", "company", "algorithm", "city", "person", "framework", etc.). Use whatever type naturally fits — do not constrain yourself to a fixed list.
-
-Format your output as a JSON array. Each element of the array contains a pair of terms and the relation between them:
-[
-    {
-        "node_1": "A concept from extracted ontology",
-        "node_1_type": "descriptive type for node_1",
-        "node_2": "A related concept from extracted ontology",
-        "node_2_type": "descriptive type for node_2",
-        "edge": "relationship between the two concepts, node_1 and node_2 in one or two sentences"
-    }
-]
-
-Rules:
-- Extract only the most important and meaningful relationships
-- Keep node names concise (1-4 words)
-- Node names should be lowercase
-- Entity types should be short (1-3 words), lowercase, and descriptive of what the entity actually is
-- Edge descriptions should be brief but descriptive
-- Return at least 3-5 relationships if the text is substantial
-- Return an empty array [] if no meaningful relationships can be extracted
-- Output ONLY valid JSON, no other text"#;
-
 /// User prompt template for extracting relations
 pub fn graph_extraction_user_prompt(text: &str) -> String {
     format!("context: ```{}```\n\noutput: ", text)
@@ -62,90 +25,317 @@ Rules:
 - Entity names should be lowercase
 - Output ONLY valid JSON, no other text"#;
 
-use crate::config::DomainConfig;
+use anyhow::{Context, Result};
+use std::path::Path;
 
-/// Generate domain-aware extraction system prompt
+use super::replay::stable_hash;
+use super::template::Template;
+use super::{ContentKind, MentionTable};
+use crate::config::{Config, DomainConfig, DomainExample, PromptsConfig};
+use crate::parser::{ChunkPlan, Tokenizer};
+
+/// Built-in system prompt templates
+const EXTRACTION_TEMPLATE: &str = include_str!("templates/extraction.txt");
+const CODE_TEMPLATE: &str = include_str!("templates/code.txt");
+
+/// Variables each template may use
+const EXTRACTION_VARIABLES: &[&str] = &[
+    "domain",
+    "context",
+    "entity_types",
+    "focus",
+    "mentions",
+    "examples",
+];
+const CODE_VARIABLES: &[&str] = &["domain", "context", "entity_types", "focus"];
+
+/// The system prompt templates for prose and code, and the version a build
+/// records for them
 ///
-/// This function templates the base extraction prompt with domain-specific context,
-/// entity type hints, and focus areas to improve extraction quality for specialized domains.
-pub fn domain_aware_extraction_prompt(domain: Option<&DomainConfig>) -> String {
-    let domain = match domain {
-        Some(d) => d,
-        None => return GRAPH_EXTRACTION_SYSTEM_PROMPT.to_string(),
-    };
-
-    let mut prompt = String::from(
-        r#"You are a network graph maker who extracts terms and their relations from a given context.
-You are provided with a context chunk (delimited by ```). Your task is to extract the ontology of terms mentioned in the given context. These terms should represent the key concepts as per the context.
-"#,
-    );
-
-    // Add domain context if provided
-    if let Some(context) = &domain.context {
-        prompt.push_str(&format!("\n**Domain Context**: {}\n", context));
+/// The version is a hash of both template sources, so any edit to a prompt
+/// file gives a new version.
+#[derive(Debug, Clone)]
+pub struct PromptTemplates {
+    name: String,
+    version: String,
+    extraction: Template,
+    code: Template,
+}
+
+impl Default for PromptTemplates {
+    fn default() -> Self {
+        Self::from_sources("built-in".to_string(), EXTRACTION_TEMPLATE, CODE_TEMPLATE)
+            .expect("built-in prompt templates are valid")
     }
+}
 
-    // Add domain name context
-    if let Some(name) = &domain.name {
-        prompt.push_str(&format!(
-            "You are analyzing content from the **{}** domain.\n",
-            name
-        ));
+impl PromptTemplates {
+    /// Templates from `[prompts]`, with `extraction` replacing the prose
+    /// template when given
+    ///
+    /// Paths in the config are relative to the configuration directory.
+    pub fn from_config(config: &PromptsConfig, extraction: Option<&Path>) -> Result<Self> {
+        let config_dir = Config::config_dir()?;
+        let extraction = extraction
+            .map(Path::to_path_buf)
+            .or_else(|| config.extraction.as_ref().map(|p| config_dir.join(p)));
+        let code = config.code.as_ref().map(|p| config_dir.join(p));
+
+        let read = |path: &Path| {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read prompt template {}", path.display()))
+        };
+        let extraction_source = match &extraction {
+            Some(path) => read(path)?,
+            None => EXTRACTION_TEMPLATE.to_string(),
+        };
+        let code_source = match &code {
+            Some(path) => read(path)?,
+            None => CODE_TEMPLATE.to_string(),
+        };
+
+        let files: Vec<String> = [&extraction, &code]
+            .into_iter()
+            .flatten()
+            .map(|p| p.display().to_string())
+            .collect();
+        let name = if files.is_empty() {
+            "built-in".to_string()
+        } else {
+            files.join(" + ")
+        };
+        Self::from_sources(name.clone(), &extraction_source, &code_source)
+            .with_context(|| format!("Invalid prompt templates: {}", name))
     }
 
-    prompt.push_str(r#"
-Thought 1: While traversing through each sentence, think about the key terms mentioned in it.
-    Terms may include object, entity, location, organization, person, condition, acronym, documents, service, concept, etc.
-    Terms should be as atomistic as possible.
+    fn from_sources(name: String, extraction: &str, code: &str) -> Result<Self> {
+        let hash = stable_hash(&[extraction, code]);
+        Ok(Self {
+            name,
+            version: hash[..12].to_string(),
+            extraction: Template::parse(extraction, EXTRACTION_VARIABLES)
+                .context("Extraction template")?,
+            code: Template::parse(code, CODE_VARIABLES).context("Code template")?,
+        })
+    }
 
-Thought 2: Think about how these terms can have one on one relation with other terms.
-    Terms that are mentioned in the same sentence or the same paragraph are typically related to each other.
-    Terms can be related to many other terms.
+    /// Where the templates came from: file paths, or `built-in`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 
-Thought 3: Find out the relation between each such related pair of terms.
-"#);
+    /// Short hash of the template sources
+    pub fn version(&self) -> &str {
+        &self.version
+    }
 
-    // Add entity type hints if provided
-    if !domain.entity_types.is_empty() {
-        prompt.push_str(&format!(
-            "\nThought 4: Classify each term with a descriptive type. Common types in this domain include: {}.\n",
-            domain.entity_types.join(", ")
-        ));
-    } else {
-        prompt.push_str(r#"
-Thought 4: Classify each term with a short descriptive type that best captures what it is (e.g. "programming language", "database", "design pattern", "medical condition", "company", "algorithm", "city", "person", "framework", etc.). Use whatever type naturally fits — do not constrain yourself to a fixed list.
-"#);
+    /// System prompt for a chunk of the given kind
+    ///
+    /// A document's mention table asks for its entities by their canonical names.
+    pub fn system_prompt(
+        &self,
+        domain: Option<&DomainConfig>,
+        kind: ContentKind,
+        mentions: &MentionTable,
+    ) -> String {
+        let default_domain = DomainConfig::default();
+        let domain = domain.unwrap_or(&default_domain);
+        let entity_types = domain.entity_types.join(", ");
+        let mentions = mentions.to_string();
+        let examples = render_examples(&domain.examples);
+        let values = [
+            ("domain", domain.name.as_deref().unwrap_or("")),
+            ("context", domain.context.as_deref().unwrap_or("")),
+            ("entity_types", entity_types.as_str()),
+            ("focus", domain.focus.as_deref().unwrap_or("")),
+            ("mentions", mentions.trim_end()),
+            ("examples", examples.as_str()),
+        ];
+        let template = match kind {
+            ContentKind::Text => &self.extraction,
+            ContentKind::Code => &self.code,
+        };
+        template.render(&values).trim_end().to_string()
     }
 
-    // Add focus area if provided
-    if let Some(focus) = &domain.focus {
-        prompt.push_str(&format!(
-            "\n**Primary Focus**: Pay special attention to {}.\n",
-            focus
-        ));
+    /// Drop the domain's examples that would crowd out a full chunk
+    ///
+    /// Examples are kept in order while the prose system prompt and the user
+    /// prompt fit in the plan's room beside one chunk; one too long to fit is
+    /// skipped for shorter ones after it. Returns how many were dropped.
+    pub fn fit_examples(
+        &self,
+        domain: &mut DomainConfig,
+        plan: &ChunkPlan,
+        tokenizer: &Tokenizer,
+    ) -> usize {
+        let limit = plan
+            .prompt_budget()
+            .saturating_sub(tokenizer.count(&graph_extraction_user_prompt("")));
+        let mentions = MentionTable::default();
+        let candidates = std::mem::take(&mut domain.examples);
+        let total = candidates.len();
+        for example in candidates {
+            domain.examples.push(example);
+            let prompt = self.system_prompt(Some(domain), ContentKind::Text, &mentions);
+            if tokenizer.count(&prompt) > limit {
+                domain.examples.pop();
+            }
+        }
+        total - domain.examples.len()
     }
+}
 
-    prompt.push_str(r#"
-Format your output as a JSON array. Each element of the array contains a pair of terms and the relation between them:
-[
-    {
-        "node_1": "A concept from extracted ontology",
-        "node_1_type": "descriptive type for node_1",
-        "node_2": "A related concept from extracted ontology",
-        "node_2_type": "descriptive type for node_2",
-        "edge": "relationship between the two concepts, node_1 and node_2 in one or two sentences"
+/// Examples as the exchanges the model sees: the user prompt, then the answer
+fn render_examples(examples: &[DomainExample]) -> String {
+    examples
+        .iter()
+        .map(|example| {
+            let relations = serde_json::to_string_pretty(&example.relations)
+                .expect("relations serialize to JSON");
+            format!(
+                "{}{}",
+                graph_extraction_user_prompt(example.text.trim()),
+                relations
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelSpec;
+    use crate::llm::Relation;
+    use crate::parser::ModelProfile;
+
+    #[test]
+    fn test_builtin_prompts_fill_the_domain() {
+        let prompts = PromptTemplates::default();
+        let mentions = MentionTable::default();
+        let plain = prompts.system_prompt(None, ContentKind::Text, &mentions);
+        assert!(plain.starts_with("You are a network graph maker"));
+        assert!(plain.ends_with("Output ONLY valid JSON, no other text"));
+        assert!(!plain.contains("{{"));
+        assert!(!plain.contains("Domain Context"));
+
+        let domain = DomainConfig {
+            name: Some("medical".to_string()),
+            entity_types: vec!["drug".to_string(), "disease".to_string()],
+            ..Default::default()
+        };
+        let text = prompts.system_prompt(Some(&domain), ContentKind::Text, &mentions);
+        assert!(text.contains("content from the **medical** domain.\n"));
+        assert!(text.contains("in this domain include: drug, disease.\n"));
+        let code = prompts.system_prompt(Some(&domain), ContentKind::Code, &mentions);
+        assert!(code.contains("in this codebase include: drug, disease.\n"));
     }
-]
 
-Rules:
-- Extract only the most important and meaningful relationships
-- Keep node names concise (1-4 words)
-- Node names should be lowercase
-- Entity types should be short (1-3 words), lowercase, and descriptive of what the entity actually is
-- Edge descriptions should be brief but descriptive
-- Return at least 3-5 relationships if the text is substantial
-- Return an empty array [] if no meaningful relationships can be extracted
-- Output ONLY valid JSON, no other text"#);
-
-    prompt
+    #[test]
+    fn test_template_files_get_their_own_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("short.txt");
+        std::fs::write(
+            &path,
+            "Extract relations{{#domain}} about {{domain}}{{/domain}}.\n",
+        )
+        .unwrap();
+
+        let builtin = PromptTemplates::default();
+        let custom = PromptTemplates::from_config(&PromptsConfig::default(), Some(&path)).unwrap();
+        assert_eq!(builtin.name(), "built-in");
+        assert_eq!(custom.name(), path.display().to_string());
+        assert_eq!(builtin.version().len(), 12);
+        assert_ne!(custom.version(), builtin.version());
+
+        let domain = DomainConfig {
+            name: Some("law".to_string()),
+            ..Default::default()
+        };
+        let mentions = MentionTable::default();
+        assert_eq!(
+            custom.system_prompt(Some(&domain), ContentKind::Text, &mentions),
+            "Extract relations about law."
+        );
+        // Code chunks keep the built-in code template
+        assert_eq!(
+            custom.system_prompt(Some(&domain), ContentKind::Code, &mentions),
+            builtin.system_prompt(Some(&domain), ContentKind::Code, &mentions)
+        );
+
+        std::fs::write(&path, "{{domian}}").unwrap();
+        let err = PromptTemplates::from_config(&PromptsConfig::default(), Some(&path)).unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown variable 'domian'"));
+    }
+
+    fn example(text: &str) -> DomainExample {
+        DomainExample {
+            text: text.to_string(),
+            relations: vec![Relation {
+                node_1: "serde".to_string(),
+                node_1_type: Some("library".to_string()),
+                node_2: "rust".to_string(),
+                node_2_type: None,
+                edge: "serializes data of".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_examples_are_shown_as_exchanges() {
+        let prompts = PromptTemplates::default();
+        let mentions = MentionTable::default();
+        let domain = DomainConfig {
+            examples: vec![example("Serde serializes Rust data.")],
+            ..Default::default()
+        };
+        let text = prompts.system_prompt(Some(&domain), ContentKind::Text, &mentions);
+        assert!(text.contains(
+            "output expected for them:\n\ncontext: ```Serde serializes Rust data.```\n\noutput: [\n"
+        ));
+        assert!(text.contains(r#""node_1_type": "library""#));
+        assert!(!text.contains("null"));
+        assert!(text.ends_with("]"));
+        // Code chunks get no prose examples
+        let code = prompts.system_prompt(Some(&domain), ContentKind::Code, &mentions);
+        assert!(!code.contains("Serde"));
+    }
+
+    #[test]
+    fn test_examples_fit_beside_a_chunk() {
+        let prompts = PromptTemplates::default();
+        let profile = ModelProfile::heuristic(ModelSpec {
+            context_size: 8192,
+            max_output_tokens: 1024,
+            tokenizer: None,
+        });
+        let tokenizer = &profile.tokenizer;
+        let long = "Serde serializes Rust data. ".repeat(600);
+        let mut domain = DomainConfig {
+            examples: vec![
+                example("Serde serializes Rust data."),
+                example(&long),
+                example("Tokio runs async Rust."),
+            ],
+            ..Default::default()
+        };
+
+        // A large window keeps every example
+        let roomy = ChunkPlan::new(500, 50, 1, &profile);
+        assert_eq!(
+            prompts.fit_examples(&mut domain.clone(), &roomy, tokenizer),
+            0
+        );
+
+        // The long one is skipped, the short one after it kept
+        let tight = ChunkPlan::new(2000, 200, 1, &profile);
+        let dropped = prompts.fit_examples(&mut domain, &tight, tokenizer);
+        assert_eq!(dropped, 1);
+        assert_eq!(domain.examples.len(), 2);
+        assert_eq!(domain.examples[1].text, "Tokio runs async Rust.");
+        let prompt =
+            prompts.system_prompt(Some(&domain), ContentKind::Text, &MentionTable::default());
+        assert!(tokenizer.count(&prompt) <= tight.prompt_budget());
+    }
 }
@@ -0,0 +1,213 @@
+//! A small Mustache subset for prompt files
+//!
+//! - `{{name}}` inserts a variable
+//! - `{{#name}}...{{/name}}` keeps its content when the variable is non-empty
+//! - `{{^name}}...{{/name}}` keeps its content when the variable is empty
+//! - `{{! comment }}` is dropped
+//!
+//! Section and comment tags alone on a line take the line with them, so
+//! templates can put them on lines of their own.
+
+use anyhow::{Result, bail};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Var(String),
+    Section {
+        name: String,
+        inverted: bool,
+        body: Vec<Node>,
+    },
+}
+
+/// A parsed template; rendering cannot fail
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// Parse `source`, allowing only the variables in `variables`
+    pub fn parse(source: &str, variables: &[&str]) -> Result<Self> {
+        // Open sections: (name, inverted, nodes before it)
+        let mut stack: Vec<(String, bool, Vec<Node>)> = Vec::new();
+        let mut nodes = Vec::new();
+        let mut rest = source;
+        let mut line = 1;
+        // Whether `rest` starts at the beginning of a line
+        let mut line_start = true;
+
+        while let Some(open) = rest.find("{{") {
+            let mut text = &rest[..open];
+            let tag_line = line + count_lines(text);
+            let after = &rest[open + 2..];
+            let close = after
+                .find("}}")
+                .ok_or_else(|| anyhow::anyhow!("line {}: unclosed {{{{", tag_line))?;
+            let tag = after[..close].trim();
+            let mut next = &after[close + 2..];
+
+            let sigil = tag
+                .chars()
+                .next()
+                .filter(|c| matches!(c, '#' | '^' | '/' | '!'));
+            let alone = sigil.is_some() && standalone(text, next, line_start);
+            if alone {
+                text = text.trim_end_matches([' ', '\t']);
+                next = skip_line(next);
+            }
+            line += count_lines(&rest[..rest.len() - next.len()]);
+            line_start = alone;
+            if !text.is_empty() {
+                nodes.push(Node::Text(text.to_string()));
+            }
+
+            let name = tag[sigil.map_or(0, char::len_utf8)..].trim();
+            if sigil != Some('!') {
+                if name.is_empty() {
+                    bail!("line {}: empty tag", tag_line);
+                }
+                if !variables.contains(&name) {
+                    bail!(
+                        "line {}: unknown variable '{}' (available: {})",
+                        tag_line,
+                        name,
+                        variables.join(", ")
+                    );
+                }
+            }
+            match sigil {
+                Some('!') => {}
+                Some(c @ ('#' | '^')) => {
+                    stack.push((name.to_string(), c == '^', std::mem::take(&mut nodes)));
+                }
+                Some(_) => {
+                    let Some((open_name, inverted, outer)) = stack.pop() else {
+                        bail!("line {}: {{{{/{}}}}} closes nothing", tag_line, name);
+                    };
+                    if open_name != name {
+                        bail!(
+                            "line {}: {{{{/{}}}}} closes {{{{#{}}}}}",
+                            tag_line,
+                            name,
+                            open_name
+                        );
+                    }
+                    let body = std::mem::replace(&mut nodes, outer);
+                    nodes.push(Node::Section {
+                        name: open_name,
+                        inverted,
+                        body,
+                    });
+                }
+                None => nodes.push(Node::Var(name.to_string())),
+            }
+            rest = next;
+        }
+
+        if let Some((name, _, _)) = stack.pop() {
+            bail!("{{{{#{}}}}} is never closed", name);
+        }
+        if !rest.is_empty() {
+            nodes.push(Node::Text(rest.to_string()));
+        }
+        Ok(Self { nodes })
+    }
+
+    /// Fill the template; variables not in `values` are empty
+    pub fn render(&self, values: &[(&str, &str)]) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, values, &mut out);
+        out
+    }
+}
+
+fn render_nodes(nodes: &[Node], values: &[(&str, &str)], out: &mut String) {
+    let value = |name: &str| {
+        values
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map_or("", |(_, v)| *v)
+    };
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(name) => out.push_str(value(name)),
+            Node::Section {
+                name,
+                inverted,
+                body,
+            } => {
+                if value(name).is_empty() == *inverted {
+                    render_nodes(body, values, out);
+                }
+            }
+        }
+    }
+}
+
+fn count_lines(text: &str) -> usize {
+    text.matches('\n').count()
+}
+
+/// Whether a tag has only blanks between it and the surrounding line breaks
+///
+/// `before` is the text since the previous tag, which ended a line when
+/// `line_start` is set.
+fn standalone(before: &str, after: &str, line_start: bool) -> bool {
+    let (starts_line, indent) = match before.rfind('\n') {
+        Some(i) => (true, &before[i + 1..]),
+        None => (line_start, before),
+    };
+    let trailing = after.split('\n').next().unwrap_or("");
+    starts_line
+        && indent.trim_matches([' ', '\t']).is_empty()
+        && trailing.trim_matches([' ', '\t', '\r']).is_empty()
+}
+
+/// Drop the rest of the line, line break included
+fn skip_line(text: &str) -> &str {
+    match text.find('\n') {
+        Some(end) => &text[end + 1..],
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS: &[&str] = &["name", "items"];
+
+    #[test]
+    fn test_sections_and_standalone_lines() {
+        let template = Template::parse(
+            "Hello {{name}}.\n{{! greeting only }}\n{{#items}}\nItems: {{items}}\n{{/items}}\n{{^items}}\nNo items.\n{{/items}}\nBye",
+            VARS,
+        )
+        .unwrap();
+        assert_eq!(
+            template.render(&[("name", "Ada"), ("items", "a, b")]),
+            "Hello Ada.\nItems: a, b\nBye"
+        );
+        assert_eq!(
+            template.render(&[("name", "Ada")]),
+            "Hello Ada.\nNo items.\nBye"
+        );
+
+        // Inline sections keep the text around them
+        let inline = Template::parse("a {{#name}}[{{name}}]{{/name}} b", VARS).unwrap();
+        assert_eq!(inline.render(&[("name", "x")]), "a [x] b");
+        assert_eq!(inline.render(&[]), "a  b");
+    }
+
+    #[test]
+    fn test_errors_name_the_line() {
+        let err = |source| Template::parse(source, VARS).unwrap_err().to_string();
+        assert!(err("a\n{{nmae}}").contains("line 2: unknown variable 'nmae'"));
+        assert!(err("{{#name}}x{{/items}}").contains("closes {{#name}}"));
+        assert!(err("{{#name}}x").contains("never closed"));
+        assert!(err("x {{name").contains("unclosed"));
+    }
+}
@@ -5,6 +5,8 @@ mod export;
 mod graph;
 mod llm;
 mod parser;
+mod server;
+mod source;
 
 use anyhow::Result;
 use clap::Parser;
@@ -40,12 +42,40 @@ async fn main() -> Result<()> {
             output,
             chunk_size,
             chunk_overlap,
+            chunker,
+            coref,
+            fixtures,
+            record,
             concurrency,
             append,
             domain,
             context,
             context_file,
+            examples,
+            prompt,
+            mappings,
+            git_ref,
+            depth,
+            max_pages,
+            allow_domains,
+            include,
+            exclude,
+            max_file_size,
+            no_ignore,
+            show_skipped,
         } => {
+            let crawl = source::CrawlOptions {
+                depth,
+                max_pages,
+                domains: allow_domains,
+                ..Default::default()
+            };
+            let collect = parser::CollectOptions {
+                include,
+                exclude,
+                max_file_size,
+                use_ignore_files: !no_ignore,
+            };
             cli::commands::build::run(
                 path,
                 provider,
@@ -53,23 +83,135 @@ async fn main() -> Result<()> {
                 output,
                 chunk_size,
                 chunk_overlap,
+                chunker,
+                coref,
+                fixtures,
+                record,
                 concurrency,
                 append,
                 domain,
                 context,
                 context_file,
+                examples,
+                prompt,
+                mappings,
+                git_ref,
+                crawl,
+                collect,
+                show_skipped,
                 cli.tenant.as_deref(),
             )
             .await?;
         }
-        Commands::Export { format, output } => {
-            cli::commands::export::run(format, output, cli.tenant.as_deref()).await?;
+        Commands::Eval {
+            path,
+            gold,
+            prompt_a,
+            prompt_b,
+            sample,
+            provider,
+            model,
+            domain,
+            context,
+            examples,
+            fixtures,
+            record,
+            match_threshold,
+            json,
+        } => {
+            cli::commands::eval::run(
+                path,
+                gold,
+                prompt_a,
+                prompt_b,
+                sample,
+                provider,
+                model,
+                domain,
+                context,
+                examples,
+                fixtures,
+                record,
+                match_threshold,
+                json,
+            )
+            .await?;
         }
-        Commands::Query { query, depth } => {
-            cli::commands::query::run(query, depth).await?;
+        Commands::Export {
+            format,
+            output,
+            base_iri,
+        } => {
+            cli::commands::export::run(format, output, &base_iri, cli.tenant.as_deref()).await?;
         }
-        Commands::Path { from, to } => {
-            cli::commands::path::run(from, to, cli.tenant.as_deref()).await?;
+        Commands::Import { file, batch_size } => {
+            cli::commands::import::run(file, batch_size, cli.tenant.as_deref()).await?;
+        }
+        Commands::Query {
+            query,
+            depth,
+            best_match,
+        } => {
+            cli::commands::query::run(query, depth, best_match, cli.tenant.as_deref()).await?;
+        }
+        Commands::Path {
+            from,
+            to,
+            k_paths,
+            all_paths,
+            max_hops,
+            directed,
+            relations,
+            exclude_relations,
+            entity_types,
+            exclude_entity_types,
+            best_match,
+        } => {
+            let options = graph::paths::PathOptions {
+                directed,
+                include_relations: relations,
+                exclude_relations,
+                include_types: entity_types,
+                exclude_types: exclude_entity_types,
+            };
+            cli::commands::path::run(
+                from,
+                to,
+                k_paths,
+                all_paths.then_some(max_hops),
+                options,
+                best_match,
+                cli.tenant.as_deref(),
+            )
+            .await?;
+        }
+        Commands::Subgraph {
+            concept,
+            depth,
+            min_weight,
+            relation_filters,
+            exclude_relations,
+            format,
+            output,
+            input,
+            best_match,
+        } => {
+            let options = graph::subgraph::SubgraphOptions {
+                depth,
+                min_weight,
+                include_relations: relation_filters,
+                exclude_relations,
+            };
+            cli::commands::subgraph::run(
+                concept,
+                options,
+                format,
+                output,
+                input,
+                best_match,
+                cli.tenant.as_deref(),
+            )
+            .await?;
         }
         Commands::Stats => {
             cli::commands::stats::run(cli.tenant.as_deref()).await?;
@@ -77,8 +219,22 @@ async fn main() -> Result<()> {
         Commands::Communities => {
             cli::commands::communities::run(cli.tenant.as_deref()).await?;
         }
-        Commands::Viz { port } => {
-            cli::commands::viz::run(port, cli.tenant.as_deref()).await?;
+        Commands::Viz {
+            port,
+            host,
+            input,
+            max_nodes,
+            no_open,
+        } => {
+            cli::commands::viz::run(
+                port,
+                host,
+                input,
+                max_nodes,
+                !no_open,
+                cli.tenant.as_deref(),
+            )
+            .await?;
         }
         Commands::Doctor => {
             cli::commands::doctor::run().await?;
@@ -91,6 +247,7 @@ async fn main() -> Result<()> {
             type2,
             interactive,
             from_file,
+            best_match,
         } => {
             cli::commands::add::run(
                 node1,
@@ -100,6 +257,7 @@ async fn main() -> Result<()> {
                 type2,
                 interactive,
                 from_file,
+                best_match,
                 cli.tenant.as_deref(),
             )
             .await?;
@@ -1,7 +1,18 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::chunker::{ChunkPlan, Chunker};
+use super::semantic::SemanticChunker;
+use super::tokenizer::Tokenizer;
+use crate::config::{Config, ModelSpec};
+
 /// Token estimation for context-aware chunking
 ///
 /// Rough approximation: 1 token ≈ 4 characters for English text
-/// This is a simple estimation - for production, use tiktoken or similar
+/// Used when no tokenizer is configured for the model, see [`Tokenizer`]
 pub fn estimate_tokens(text: &str) -> usize {
     // Simple character-based estimation: ~4 chars per token
     // This is conservative but works for most English text
@@ -15,6 +26,7 @@ pub struct AdaptiveChunker {
     overlap_tokens: usize,
     separators: Vec<&'static str>,
     max_retries: u32,
+    tokenizer: Arc<Tokenizer>,
 }
 
 impl AdaptiveChunker {
@@ -29,21 +41,28 @@ impl AdaptiveChunker {
             overlap_tokens,
             separators: vec!["\n\n", "\n", ". ", "! ", "? ", "; ", ", ", " ", ""],
             max_retries: 3,
+            tokenizer: Arc::default(),
         }
     }
 
-    /// Create a chunker optimized for a specific model context size
-    ///
-    /// Reserves space for:
-    /// - System prompt (~200 tokens)
-    /// - User prompt + text
-    /// - Response buffer (~500 tokens)
-    pub fn for_context_window(context_size: usize) -> Self {
-        let reserved = 700; // System prompt + response buffer
-        let safe_target = (context_size.saturating_sub(reserved)) / 2;
-        let overlap = safe_target / 10;
+    /// Count tokens with a model's vocabulary instead of the heuristic
+    pub fn with_tokenizer(mut self, tokenizer: Arc<Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Tokens in `text` according to this chunker's tokenizer
+    pub fn count_tokens(&self, text: &str) -> usize {
+        self.tokenizer.count(text)
+    }
 
-        Self::new(safe_target, overlap)
+    /// Word cost when splitting at whitespace; the heuristic errs on the high side
+    fn word_tokens(&self, word: &str) -> usize {
+        if self.tokenizer.is_exact() {
+            self.tokenizer.count(word)
+        } else {
+            (word.len() as f32 * 0.5) as usize + 1
+        }
     }
 
     /// Split text into token-aware chunks
@@ -53,13 +72,14 @@ impl AdaptiveChunker {
             return vec![];
         }
 
-        let estimated_tokens = estimate_tokens(text);
+        let estimated_tokens = self.count_tokens(text);
         if estimated_tokens <= self.target_tokens {
             return vec![Chunk {
                 text: text.to_string(),
                 estimated_tokens,
                 chunk_index: 0,
                 parent_id: None,
+                span: None,
             }];
         }
 
@@ -69,7 +89,8 @@ impl AdaptiveChunker {
     /// Split with a specific target (used for retry with smaller size)
     #[allow(dead_code)]
     pub fn split_with_target(&self, text: &str, target_tokens: usize) -> Vec<Chunk> {
-        let mut temp = Self::new(target_tokens, target_tokens / 10);
+        let mut temp = Self::new(target_tokens, target_tokens / 10)
+            .with_tokenizer(Arc::clone(&self.tokenizer));
         temp.max_retries = self.max_retries;
         temp.split(text)
     }
@@ -87,7 +108,9 @@ impl AdaptiveChunker {
 
         let separator = self.separators[separator_idx];
         let splits: Vec<&str> = if separator.is_empty() {
-            text.chars().map(|c| &text[..c.len_utf8()]).collect()
+            text.char_indices()
+                .map(|(i, c)| &text[i..i + c.len_utf8()])
+                .collect()
         } else {
             text.split(separator).collect()
         };
@@ -104,11 +127,11 @@ impl AdaptiveChunker {
                 split.to_string()
             };
 
-            let split_tokens = estimate_tokens(&split_with_sep);
+            let split_tokens = self.count_tokens(&split_with_sep);
 
             if current_tokens + split_tokens > self.target_tokens {
                 if !current_chunk.is_empty() {
-                    let chunk_tokens = estimate_tokens(&current_chunk);
+                    let chunk_tokens = self.count_tokens(&current_chunk);
 
                     // If still too big, recurse with next separator
                     if chunk_tokens > self.target_tokens {
@@ -119,11 +142,13 @@ impl AdaptiveChunker {
                             parent_id.clone(),
                         ));
                     } else {
+                        let text = current_chunk.trim().to_string();
                         chunks.push(Chunk {
-                            text: current_chunk.trim().to_string(),
-                            estimated_tokens: chunk_tokens,
+                            estimated_tokens: self.count_tokens(&text),
+                            text,
                             chunk_index: idx,
                             parent_id: parent_id.clone(),
+                            span: None,
                         });
                         idx += 1;
                     }
@@ -137,7 +162,7 @@ impl AdaptiveChunker {
                 };
 
                 current_chunk = format!("{}{}", overlap_text, split_with_sep);
-                current_tokens = estimate_tokens(&current_chunk);
+                current_tokens = self.count_tokens(&current_chunk);
             } else {
                 current_chunk.push_str(&split_with_sep);
                 current_tokens += split_tokens;
@@ -148,7 +173,7 @@ impl AdaptiveChunker {
         if !current_chunk.is_empty() {
             let trimmed = current_chunk.trim().to_string();
             if !trimmed.is_empty() {
-                let chunk_tokens = estimate_tokens(&trimmed);
+                let chunk_tokens = self.count_tokens(&trimmed);
 
                 if chunk_tokens > self.target_tokens {
                     chunks.extend(self.recursive_split(
@@ -163,6 +188,7 @@ impl AdaptiveChunker {
                         estimated_tokens: chunk_tokens,
                         chunk_index: idx,
                         parent_id,
+                        span: None,
                     });
                 }
             }
@@ -184,16 +210,17 @@ impl AdaptiveChunker {
         let mut idx = chunk_index;
 
         for word in words {
-            let word_tokens = (word.len() as f32 * 0.5) as usize + 1;
+            let word_tokens = self.word_tokens(word);
 
             if current_tokens + word_tokens > self.target_tokens {
                 if !current_words.is_empty() {
                     let text = current_words.join(" ");
                     chunks.push(Chunk {
                         text: text.clone(),
-                        estimated_tokens: estimate_tokens(&text),
+                        estimated_tokens: self.count_tokens(&text),
                         chunk_index: idx,
                         parent_id: parent_id.clone(),
+                        span: None,
                     });
                     idx += 1;
                 }
@@ -208,10 +235,7 @@ impl AdaptiveChunker {
 
                 current_words = overlap_words;
                 current_words.push(word);
-                current_tokens = current_words
-                    .iter()
-                    .map(|w| (w.len() as f32 * 0.5) as usize + 1)
-                    .sum();
+                current_tokens = current_words.iter().map(|w| self.word_tokens(w)).sum();
             } else {
                 current_words.push(word);
                 current_tokens += word_tokens;
@@ -222,10 +246,11 @@ impl AdaptiveChunker {
         if !current_words.is_empty() {
             let text = current_words.join(" ");
             chunks.push(Chunk {
+                estimated_tokens: self.count_tokens(&text),
                 text,
-                estimated_tokens: current_tokens,
                 chunk_index: idx,
                 parent_id,
+                span: None,
             });
         }
 
@@ -240,6 +265,17 @@ impl AdaptiveChunker {
     }
 }
 
+#[async_trait]
+impl Chunker for AdaptiveChunker {
+    async fn chunk(&self, text: &str) -> Result<Vec<Chunk>> {
+        Ok(self.split(text))
+    }
+
+    fn name(&self) -> &'static str {
+        "adaptive"
+    }
+}
+
 /// A chunk with metadata
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -248,48 +284,137 @@ pub struct Chunk {
     pub estimated_tokens: usize,
     pub chunk_index: usize,
     pub parent_id: Option<String>,
+    /// Character range of the chunk in the document text, when the chunker tracks it
+    pub span: Option<Range<usize>>,
 }
 
-/// Model context limits database
-pub struct ModelContextLimits;
-
-impl ModelContextLimits {
-    /// Get context window size for known models
-    pub fn get_context_size(model: &str) -> usize {
-        let model_lower = model.to_lowercase();
-
-        match model_lower.as_str() {
-            // Small local models (Ollama)
-            m if m.contains("llama3.2") || m.contains("llama-3.2") => 8192,
-            m if m.contains("phi3:mini") || m.contains("phi-3-mini") => 4096,
-            m if m.contains("mistral") => 32768,
-            m if m.contains("qwen2.5:3b") => 8192,
-            m if m.contains("qwen2.5:7b") => 32768,
-            m if m.contains("gemma2:2b") => 4096,
-            m if m.contains("gemma2:9b") => 8192,
-
-            // Larger local models
-            m if m.contains("llama3.3") || m.contains("llama-3.3") => 128000,
-            m if m.contains("qwen2.5:72b") => 32768,
-
-            // Cloud models
-            m if m.contains("claude-3-opus") => 200000,
-            m if m.contains("claude-3-sonnet") => 200000,
-            m if m.contains("claude-3-haiku") => 200000,
-            m if m.contains("gpt-4") => 8192,
-            m if m.contains("gpt-4o") => 128000,
-            m if m.contains("gpt-3.5") => 16385,
-            m if m.contains("gemini") => 1048576,
-
-            // Default for unknown models (conservative)
-            _ => 4096,
+/// Model context windows, output limits and tokenizers
+///
+/// Built from `models.toml` plus the `[models]` section of the config. A model
+/// name is matched against every key as a case-insensitive substring and the
+/// longest key wins, so `gpt-4o-mini` resolves to `gpt-4o` rather than `gpt-4`.
+pub struct ModelRegistry {
+    entries: BTreeMap<String, ModelSpec>,
+}
+
+/// Tokens kept free for the extraction prompts when sizing chunks
+const PROMPT_RESERVE: usize = 1000;
+
+impl ModelRegistry {
+    /// The registry shipped with rknowledge
+    pub fn builtin() -> Self {
+        let entries: BTreeMap<String, ModelSpec> =
+            toml::from_str(BUILTIN_MODELS).expect("valid built-in model registry");
+        Self {
+            entries: entries
+                .into_iter()
+                .map(|(key, spec)| (key.to_lowercase(), spec))
+                .collect(),
+        }
+    }
+
+    /// The built-in registry with the config's `[models]` entries on top
+    ///
+    /// Relative tokenizer paths are resolved against the config directory.
+    pub fn from_config(config: &Config) -> Self {
+        let config_dir = Config::config_dir().ok();
+        let mut registry = Self::builtin();
+        for (key, spec) in &config.models {
+            let mut spec = spec.clone();
+            if let (Some(path), Some(dir)) = (&spec.tokenizer, &config_dir)
+                && path.is_relative()
+            {
+                spec.tokenizer = Some(dir.join(path));
+            }
+            registry.entries.insert(key.to_lowercase(), spec);
         }
+        registry
+    }
+
+    /// The most specific entry for `model`, if any key matches
+    pub fn lookup(&self, model: &str) -> Option<&ModelSpec> {
+        let model = model.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|(key, _)| model.contains(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, spec)| spec)
+    }
+
+    /// Like [`lookup`](Self::lookup), with a conservative 4K window for unknown models
+    pub fn spec(&self, model: &str) -> ModelSpec {
+        self.lookup(model).cloned().unwrap_or_default()
     }
 
-    /// Create an adaptive chunker for a specific model
-    pub fn create_chunker(model: &str) -> AdaptiveChunker {
-        let context_size = Self::get_context_size(model);
-        AdaptiveChunker::for_context_window(context_size)
+    /// Resolve `model` and load its tokenizer, if one is configured
+    pub fn profile(&self, model: &str) -> Result<ModelProfile> {
+        ModelProfile::load(self.spec(model))
+            .with_context(|| format!("Failed to load tokenizer for {}", model))
+    }
+}
+
+const BUILTIN_MODELS: &str = include_str!("models.toml");
+
+/// A resolved model: its limits and the tokenizer to count with
+pub struct ModelProfile {
+    pub spec: ModelSpec,
+    pub tokenizer: Arc<Tokenizer>,
+}
+
+impl ModelProfile {
+    /// A profile that counts with the spec's tokenizer, if it names one
+    pub fn load(spec: ModelSpec) -> Result<Self> {
+        let Some(path) = &spec.tokenizer else {
+            return Ok(Self::heuristic(spec));
+        };
+        let tokenizer = Tokenizer::load(path)?;
+        Ok(Self {
+            spec,
+            tokenizer: Arc::new(tokenizer),
+        })
+    }
+
+    /// A profile that counts with the heuristic
+    pub fn heuristic(spec: ModelSpec) -> Self {
+        Self {
+            spec,
+            tokenizer: Arc::default(),
+        }
+    }
+
+    /// Tokens left for prompt and text once the response is reserved
+    pub fn input_budget(&self) -> usize {
+        self.spec
+            .context_size
+            .saturating_sub(self.spec.max_output_tokens)
+    }
+
+    /// Chunk size at which one chunk plus the prompts fills the input budget
+    ///
+    /// Heuristic counts can run short of the real tokenizer, so without a
+    /// vocabulary chunks only plan for half the budget.
+    pub fn target_tokens(&self) -> usize {
+        let mut target = self.input_budget().saturating_sub(PROMPT_RESERVE);
+        if !self.tokenizer.is_exact() {
+            target /= 2;
+        }
+        target.max(100)
+    }
+
+    /// A size-based chunker that follows `plan`, counting with this model's tokenizer
+    pub fn chunker(&self, plan: &ChunkPlan) -> AdaptiveChunker {
+        AdaptiveChunker::new(plan.chunk_tokens, plan.overlap_tokens)
+            .with_tokenizer(Arc::clone(&self.tokenizer))
+    }
+
+    /// A topic-based chunker that follows `plan`, see [`SemanticChunker`]
+    pub fn semantic_chunker(
+        &self,
+        plan: &ChunkPlan,
+        breakpoint_percentile: f32,
+    ) -> SemanticChunker {
+        SemanticChunker::new(plan.chunk_tokens, breakpoint_percentile)
+            .with_tokenizer(Arc::clone(&self.tokenizer))
     }
 }
 
@@ -323,7 +448,8 @@ mod tests {
 
     #[test]
     fn test_context_window_chunker() {
-        let chunker = AdaptiveChunker::for_context_window(4096);
+        let model = ModelProfile::heuristic(ModelSpec::default());
+        let chunker = model.chunker(&ChunkPlan::for_model(&model));
         // Create enough text to definitely require multiple chunks
         // "Word " = 5 chars, ~1.25 tokens each.
         // 8000 repetitions = 40000 chars = ~10000 tokens
@@ -349,10 +475,122 @@ mod tests {
 
     #[test]
     fn test_model_context_limits() {
-        assert_eq!(ModelContextLimits::get_context_size("mistral"), 32768);
-        assert_eq!(ModelContextLimits::get_context_size("llama3.2"), 8192);
-        assert_eq!(ModelContextLimits::get_context_size("phi3:mini"), 4096);
-        assert_eq!(ModelContextLimits::get_context_size("unknown-model"), 4096);
+        let registry = ModelRegistry::builtin();
+        assert_eq!(registry.spec("mistral").context_size, 32768);
+        assert_eq!(registry.spec("llama3.2").context_size, 8192);
+        assert_eq!(registry.spec("phi3:mini").context_size, 4096);
+        assert_eq!(registry.spec("unknown-model").context_size, 4096);
+        assert!(registry.lookup("unknown-model").is_none());
+    }
+
+    #[test]
+    fn test_longest_key_wins() {
+        let registry = ModelRegistry::builtin();
+        assert_eq!(registry.spec("gpt-4").context_size, 8192);
+        assert_eq!(registry.spec("gpt-4o").context_size, 128000);
+        assert_eq!(registry.spec("GPT-4o-mini").max_output_tokens, 16384);
+        assert_eq!(
+            registry.spec("claude-3-haiku-20240307").max_output_tokens,
+            4096
+        );
+        assert_eq!(
+            registry.spec("claude-sonnet-4-20250514").context_size,
+            200000
+        );
+    }
+
+    #[test]
+    fn test_config_models_override_builtin() {
+        let config: Config = toml::from_str(
+            r#"
+            [providers]
+            [neo4j]
+            uri = "bolt://localhost:7687"
+            user = "neo4j"
+            password = "test"
+
+            [models."gpt-4o"]
+            context_size = 64000
+
+            [models."my-finetune"]
+            context_size = 16000
+            max_output_tokens = 2000
+            tokenizer = "/opt/tokenizers/o200k_base.tiktoken"
+        "#,
+        )
+        .unwrap();
+
+        // Config entries replace built-in ones whole
+        let registry = ModelRegistry::from_config(&config);
+        assert_eq!(registry.spec("gpt-4o").context_size, 64000);
+        assert_eq!(registry.spec("gpt-4o").max_output_tokens, 1024);
+        let custom = registry.spec("org/my-finetune:latest");
+        assert_eq!(custom.context_size, 16000);
+        assert_eq!(
+            custom.tokenizer.as_deref(),
+            Some(std::path::Path::new("/opt/tokenizers/o200k_base.tiktoken"))
+        );
+        assert!(registry.profile("my-finetune").is_err());
+    }
+
+    #[test]
+    fn test_exact_tokenizer_fills_budget() {
+        let spec = ModelSpec {
+            context_size: 8192,
+            max_output_tokens: 2048,
+            tokenizer: None,
+        };
+        let heuristic = ModelProfile::heuristic(spec.clone());
+        assert_eq!(heuristic.input_budget(), 6144);
+        assert_eq!(heuristic.target_tokens(), (6144 - PROMPT_RESERVE) / 2);
+
+        // A one-token-per-character vocabulary counts every byte
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("bytes.tiktoken");
+        let ranks: String = (b'a'..=b'z')
+            .chain([b' '])
+            .enumerate()
+            .map(|(rank, byte)| {
+                use base64::Engine;
+                let token = base64::engine::general_purpose::STANDARD.encode([byte]);
+                format!("{} {}\n", token, rank)
+            })
+            .collect();
+        std::fs::write(&path, ranks).unwrap();
+        let exact = ModelProfile {
+            spec,
+            tokenizer: Arc::new(Tokenizer::load(&path).unwrap()),
+        };
+        let chunker = exact.chunker(&ChunkPlan::for_model(&exact));
+        assert_eq!(exact.target_tokens(), 6144 - PROMPT_RESERVE);
+
+        let text = "abcd ".repeat(3000);
+        let chunks = chunker.split(&text);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert_eq!(chunk.estimated_tokens, chunker.count_tokens(&chunk.text));
+            assert!(chunk.estimated_tokens <= exact.target_tokens());
+        }
+    }
+
+    #[test]
+    fn test_small_text_fits_one_chunk() {
+        let chunker = AdaptiveChunker::new(1000, 100);
+        let text = "This is a small text that fits in one chunk.";
+        let chunks = chunker.split(text);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].text, text);
+        assert!(chunker.split("   \n\n\t  ").is_empty());
+    }
+
+    #[test]
+    fn test_text_without_separators_splits_by_character() {
+        let chunker = AdaptiveChunker::new(5, 0);
+        let text = "日本語のテスト文章です。これは二番目の文です。";
+        let chunks = chunker.split(text);
+        assert!(chunks.len() > 1);
+        let joined: String = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(joined, text);
     }
 
     #[test]
@@ -0,0 +1,42 @@
+//! Helpers shared by the ZIP + XML document formats (DOCX, ODT, EPUB).
+
+use anyhow::{Context, Result};
+use quick_xml::events::BytesStart;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Open a ZIP-based document container (DOCX, ODT, EPUB)
+pub fn open(path: &Path) -> Result<ZipArchive<File>> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    ZipArchive::new(file).with_context(|| format!("Not a valid ZIP container: {}", path.display()))
+}
+
+/// Read one archive entry as UTF-8 text, or `None` if the entry does not exist
+pub fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<Option<String>> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", name)),
+    };
+    let mut content = String::new();
+    entry
+        .read_to_string(&mut content)
+        .with_context(|| format!("{} is not valid UTF-8", name))?;
+    Ok(Some(content))
+}
+
+/// Value of an XML attribute by its qualified name (e.g. `w:val`)
+pub fn attr(element: &BytesStart, name: &str) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == name.as_bytes())
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
@@ -0,0 +1,97 @@
+/// A structural unit of a rich-text document, with styling already stripped
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    /// Heading with its outline level (1 = top)
+    Heading(usize, String),
+    Paragraph(String),
+    ListItem(String),
+}
+
+/// Render blocks as Markdown-like plain text
+///
+/// Headings become `#` lines so the outline survives chunking. Paragraphs are
+/// separated by blank lines, and consecutive list items stay together.
+pub fn render(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    let mut prev_item = false;
+
+    for block in blocks {
+        let (line, is_item) = match block {
+            Block::Heading(level, text) => {
+                let text = normalize(text);
+                if text.is_empty() {
+                    continue;
+                }
+                (
+                    format!("{} {}", "#".repeat((*level).clamp(1, 6)), text),
+                    false,
+                )
+            }
+            Block::Paragraph(text) => (normalize(text), false),
+            Block::ListItem(text) => (format!("- {}", normalize(text)), true),
+        };
+        if line.is_empty() || line == "- " {
+            continue;
+        }
+
+        if !out.is_empty() {
+            out.push_str(if is_item && prev_item { "\n" } else { "\n\n" });
+        }
+        out.push_str(&line);
+        prev_item = is_item;
+    }
+
+    out
+}
+
+/// Heading level implied by a style id or name such as `Heading2`, `heading 2` or `Title`
+pub fn heading_level(style: &str) -> Option<usize> {
+    let style = style.to_lowercase().replace(' ', "");
+    if style == "title" {
+        return Some(1);
+    }
+    style
+        .strip_prefix("heading")?
+        .parse::<usize>()
+        .ok()
+        .filter(|level| (1..=9).contains(level))
+}
+
+/// Collapse runs of spaces and tabs, trim each line and drop empty lines
+fn normalize(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_structure() {
+        let blocks = vec![
+            Block::Heading(1, "Guide".into()),
+            Block::Paragraph("  Intro   text\there ".into()),
+            Block::ListItem("one".into()),
+            Block::ListItem("two".into()),
+            Block::Paragraph("   ".into()),
+            Block::Heading(9, "Deep".into()),
+        ];
+        assert_eq!(
+            render(&blocks),
+            "# Guide\n\nIntro text here\n\n- one\n- two\n\n###### Deep"
+        );
+    }
+
+    #[test]
+    fn test_heading_level_names() {
+        assert_eq!(heading_level("Heading1"), Some(1));
+        assert_eq!(heading_level("heading 3"), Some(3));
+        assert_eq!(heading_level("Title"), Some(1));
+        assert_eq!(heading_level("Normal"), None);
+        assert_eq!(heading_level("Heading10"), None);
+    }
+}
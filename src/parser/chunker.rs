@@ -1,245 +1,183 @@
-/// Text chunker that splits text into overlapping chunks
-pub struct TextChunker {
-    pub chunk_size: usize,
-    pub chunk_overlap: usize,
-    separators: Vec<&'static str>,
-}
+//! The chunking pipeline: every strategy behind one trait, sized by one plan
 
-impl TextChunker {
-    pub fn new(chunk_size: usize, chunk_overlap: usize) -> Self {
-        Self {
-            chunk_size,
-            chunk_overlap,
-            // Separators in order of preference (most to least specific)
-            separators: vec!["\n\n", "\n", ". ", "! ", "? ", "; ", ", ", " ", ""],
-        }
-    }
+use anyhow::Result;
+use async_trait::async_trait;
+use std::fmt;
 
-    /// Split text into chunks with overlap
-    pub fn split(&self, text: &str) -> Vec<String> {
-        let text = text.trim();
-        if text.is_empty() {
-            return vec![];
-        }
+use super::adaptive_chunker::{Chunk, ModelProfile};
 
-        if text.len() <= self.chunk_size {
-            return vec![text.to_string()];
-        }
+/// Splits a document's text into the chunks the LLM sees
+#[async_trait]
+pub trait Chunker: Send + Sync {
+    /// Chunks of `text`, in document order
+    async fn chunk(&self, text: &str) -> Result<Vec<Chunk>>;
 
-        self.recursive_split(text, 0)
-    }
+    /// Strategy name for reports
+    fn name(&self) -> &'static str;
+}
 
-    fn recursive_split(&self, text: &str, separator_idx: usize) -> Vec<String> {
-        if separator_idx >= self.separators.len() {
-            // No more separators, just split by character count
-            return self.split_by_chars(text);
-        }
+/// Chunk and batch sizes, from the user's settings within the model's limits
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkPlan {
+    /// Tokens per chunk the user asked for
+    pub requested_tokens: usize,
+    /// Tokens per chunk actually used
+    pub chunk_tokens: usize,
+    /// Tokens repeated between consecutive chunks
+    pub overlap_tokens: usize,
+    /// Most chunks sent in one LLM call
+    pub batch_size: usize,
+    /// Tokens per LLM call for prompts and chunks
+    pub batch_budget: usize,
+    /// Tokens reserved for the response
+    pub output_tokens: usize,
+    /// Whether counts come from the model's tokenizer
+    pub exact: bool,
+}
 
-        let separator = self.separators[separator_idx];
-        let splits: Vec<&str> = if separator.is_empty() {
-            text.chars().map(|c| &text[..c.len_utf8()]).collect()
+impl ChunkPlan {
+    /// Combine the requested chunk size and overlap with the model's limits
+    ///
+    /// The chunk size is capped where one chunk plus the prompts fills the
+    /// model's input budget, and overlap to half a chunk. Heuristic counts
+    /// plan batches for half the budget, as they can run short.
+    pub fn new(
+        requested_tokens: usize,
+        overlap_tokens: usize,
+        batch_size: usize,
+        model: &ModelProfile,
+    ) -> Self {
+        let exact = model.tokenizer.is_exact();
+        let chunk_tokens = requested_tokens.clamp(1, model.target_tokens());
+        let batch_budget = if exact {
+            model.input_budget()
         } else {
-            text.split(separator).collect()
+            model.input_budget() / 2
         };
 
-        let mut chunks = Vec::new();
-        let mut current_chunk = String::new();
-
-        for (i, split) in splits.iter().enumerate() {
-            let split_with_sep = if i < splits.len() - 1 && !separator.is_empty() {
-                format!("{}{}", split, separator)
-            } else {
-                split.to_string()
-            };
-
-            if current_chunk.len() + split_with_sep.len() > self.chunk_size {
-                if !current_chunk.is_empty() {
-                    // Check if current chunk needs further splitting
-                    if current_chunk.len() > self.chunk_size {
-                        chunks.extend(self.recursive_split(&current_chunk, separator_idx + 1));
-                    } else {
-                        chunks.push(current_chunk.trim().to_string());
-                    }
-                }
-
-                // Start new chunk, potentially with overlap
-                current_chunk = if !chunks.is_empty() && self.chunk_overlap > 0 {
-                    let last_chunk = chunks.last().unwrap();
-                    // Use char boundary-safe overlap
-                    let overlap_chars: String = last_chunk
-                        .chars()
-                        .rev()
-                        .take(self.chunk_overlap)
-                        .collect::<Vec<_>>()
-                        .into_iter()
-                        .rev()
-                        .collect();
-                    format!("{}{}", overlap_chars, split_with_sep)
-                } else {
-                    split_with_sep
-                };
-            } else {
-                current_chunk.push_str(&split_with_sep);
-            }
+        Self {
+            requested_tokens,
+            chunk_tokens,
+            overlap_tokens: overlap_tokens.min(chunk_tokens / 2),
+            batch_size: batch_size.max(1),
+            batch_budget,
+            output_tokens: model.spec.max_output_tokens,
+            exact,
         }
+    }
 
-        // Don't forget the last chunk
-        if !current_chunk.is_empty() {
-            let trimmed = current_chunk.trim().to_string();
-            if !trimmed.is_empty() {
-                if trimmed.len() > self.chunk_size {
-                    chunks.extend(self.recursive_split(&trimmed, separator_idx + 1));
-                } else {
-                    chunks.push(trimmed);
-                }
-            }
-        }
+    /// A plan that fills the model's window, with 10% overlap and five chunks per call
+    pub fn for_model(model: &ModelProfile) -> Self {
+        let target = model.target_tokens();
+        Self::new(target, target / 10, 5, model)
+    }
 
-        // Filter out empty chunks and very small chunks
-        chunks.into_iter().filter(|c| c.len() > 10).collect()
+    /// Tokens left for the prompts beside one full chunk
+    ///
+    /// Heuristic counts keep the chunks' margin: the chunk counts twice
+    /// against the input budget, which their batch budget is half of.
+    pub fn prompt_budget(&self) -> usize {
+        let room = self.batch_budget.saturating_sub(self.chunk_tokens);
+        if self.exact { room } else { room * 2 }
     }
 
-    fn split_by_chars(&self, text: &str) -> Vec<String> {
-        let chars: Vec<char> = text.chars().collect();
-        let mut chunks = Vec::new();
-        let mut start = 0;
-
-        while start < chars.len() {
-            let end = (start + self.chunk_size).min(chars.len());
-            let chunk: String = chars[start..end].iter().collect();
-            chunks.push(chunk.trim().to_string());
-
-            start = if self.chunk_overlap > 0 {
-                end.saturating_sub(self.chunk_overlap)
-            } else {
-                end
-            };
-
-            // Prevent infinite loop
-            if start >= end {
-                break;
-            }
-        }
+    /// Whether the model's window cut the requested chunk size
+    pub fn is_capped(&self) -> bool {
+        self.chunk_tokens < self.requested_tokens
+    }
+}
 
-        chunks
+impl fmt::Display for ChunkPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} tokens per chunk, {} overlap",
+            self.chunk_tokens, self.overlap_tokens
+        )?;
+        if self.is_capped() {
+            write!(
+                f,
+                " ({} requested, capped by the model's window)",
+                self.requested_tokens
+            )?;
+        }
+        write!(
+            f,
+            "; up to {} chunks and {} tokens per call, {} reserved for output; {} token counts",
+            self.batch_size,
+            self.batch_budget,
+            self.output_tokens,
+            if self.exact { "exact" } else { "estimated" }
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[test]
-    fn test_basic_chunking() {
-        let chunker = TextChunker::new(50, 10);
-        let text =
-            "This is a test. Another sentence here. And one more sentence to make it longer.";
-        let chunks = chunker.split(text);
-
-        assert!(!chunks.is_empty());
-        for chunk in &chunks {
-            assert!(chunk.len() <= 70); // Allow flexibility due to overlap
+    use crate::config::ModelSpec;
+    use crate::parser::AdaptiveChunker;
+    use crate::parser::semantic::{Embedder, SemanticChunker};
+    use std::sync::Arc;
+
+    /// Gives every sentence the same vector, so only size splits chunks
+    struct FlatEmbedder;
+
+    #[async_trait]
+    impl Embedder for FlatEmbedder {
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            Ok(vec![vec![1.0]; texts.len()])
         }
     }
 
-    #[test]
-    fn test_empty_text() {
-        let chunker = TextChunker::new(100, 10);
-        assert!(chunker.split("").is_empty());
+    fn profile(context_size: usize, max_output_tokens: usize) -> ModelProfile {
+        ModelProfile::heuristic(ModelSpec {
+            context_size,
+            max_output_tokens,
+            tokenizer: None,
+        })
     }
 
     #[test]
-    fn test_whitespace_only() {
-        let chunker = TextChunker::new(100, 10);
-        assert!(chunker.split("   \n\n\t  ").is_empty());
+    fn test_plan_honours_request_within_model_limit() {
+        let model = profile(128_000, 16_384);
+        let plan = ChunkPlan::new(1500, 150, 5, &model);
+        assert_eq!(plan.chunk_tokens, 1500);
+        assert_eq!(plan.overlap_tokens, 150);
+        assert!(!plan.is_capped());
+        assert_eq!(plan.batch_budget, (128_000 - 16_384) / 2);
+        assert_eq!(plan.prompt_budget(), 128_000 - 16_384 - 2 * 1500);
+        assert!(
+            plan.to_string()
+                .starts_with("1500 tokens per chunk, 150 overlap;")
+        );
     }
 
     #[test]
-    fn test_small_text_fits_one_chunk() {
-        let chunker = TextChunker::new(1000, 100);
-        let text = "This is a small text that fits in one chunk.";
-        let chunks = chunker.split(text);
-        assert_eq!(chunks.len(), 1);
-        assert_eq!(chunks[0], text);
+    fn test_plan_caps_to_small_window() {
+        let model = profile(4096, 1024);
+        let plan = ChunkPlan::new(1500, 1200, 0, &model);
+        assert_eq!(plan.chunk_tokens, model.target_tokens());
+        assert!(plan.is_capped());
+        assert_eq!(plan.overlap_tokens, plan.chunk_tokens / 2);
+        assert_eq!(plan.batch_size, 1);
+        assert!(plan.to_string().contains("1500 requested, capped"));
     }
 
-    #[test]
-    fn test_paragraph_boundary_splits() {
-        let chunker = TextChunker::new(100, 10);
+    #[tokio::test]
+    async fn test_chunkers_share_the_trait() {
+        let chunkers: Vec<Box<dyn Chunker>> = vec![
+            Box::new(AdaptiveChunker::new(20, 0)),
+            Box::new(SemanticChunker::new(20, 95.0).with_embedder(Arc::new(FlatEmbedder))),
+        ];
         let text = "First paragraph with some text.\n\nSecond paragraph with more.\n\nThird paragraph final.";
-        let chunks = chunker.split(text);
-        assert!(!chunks.is_empty());
-    }
-
-    #[test]
-    fn test_no_content_lost() {
-        let chunker = TextChunker::new(200, 20);
-        let text = "Artificial intelligence is changing the world. \
-                    Machine learning enables computers to learn from data. \
-                    Deep learning uses neural networks with many layers. \
-                    Natural language processing handles human language. \
-                    Computer vision interprets visual information.";
-        let chunks = chunker.split(text);
-
-        // Every word in the original should appear in at least one chunk
-        for word in text.split_whitespace() {
-            assert!(
-                chunks.iter().any(|c| c.contains(word)),
-                "Word '{}' missing from chunks",
-                word
-            );
-        }
-    }
-
-    #[test]
-    fn test_overlap_present() {
-        let chunker = TextChunker::new(50, 20);
-        let text = "First sentence is here. Second sentence follows. Third sentence ends it. Fourth comes after.";
-        let chunks = chunker.split(text);
-
-        if chunks.len() >= 2 {
-            // The end of chunk[0] should overlap with the start of chunk[1]
-            let end_of_first = &chunks[0][chunks[0].len().saturating_sub(15)..];
-            let found_overlap = chunks[1].contains(end_of_first);
-            // Overlap is best-effort due to separator logic, so just check chunks exist
-            assert!(chunks.len() >= 2);
-            let _ = found_overlap; // just verify no panic
-        }
-    }
-
-    #[test]
-    fn test_chunks_non_empty() {
-        let chunker = TextChunker::new(100, 10);
-        let text = "Word. ".repeat(50);
-        let chunks = chunker.split(&text);
-        for chunk in &chunks {
-            assert!(chunk.len() > 10, "Chunk too small: '{}'", chunk);
+        for chunker in &chunkers {
+            let chunks = chunker.chunk(text).await.unwrap();
+            assert!(chunks.len() > 1, "{}", chunker.name());
+            for word in text.split_whitespace() {
+                assert!(chunks.iter().any(|c| c.text.contains(word)));
+            }
+            assert!(chunker.chunk("   \n\n\t  ").await.unwrap().is_empty());
         }
     }
-
-    #[test]
-    fn test_zero_overlap() {
-        let chunker = TextChunker::new(50, 0);
-        let text =
-            "First part of text. Second part of text. Third part of text. Fourth part ends here.";
-        let chunks = chunker.split(text);
-        assert!(!chunks.is_empty());
-    }
-
-    #[test]
-    fn test_unicode_text() {
-        let chunker = TextChunker::new(50, 5);
-        let text = "日本語のテスト文章です。これは二番目の文です。三番目の文章もあります。";
-        let chunks = chunker.split(text);
-        assert!(!chunks.is_empty());
-    }
-
-    #[test]
-    fn test_very_long_single_word() {
-        let chunker = TextChunker::new(20, 5);
-        let text = "a".repeat(100);
-        let chunks = chunker.split(&text);
-        assert!(!chunks.is_empty());
-        // Should be able to handle splitting by characters
-    }
 }
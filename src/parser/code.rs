@@ -0,0 +1,1245 @@
+//! Source code ingestion.
+//!
+//! Code is split at declaration boundaries (functions, impl blocks, classes,
+//! ...) instead of paragraph separators. Each piece keeps the symbols it
+//! declares, their doc comments and the file's imports, so the extraction
+//! prompt can use them as node hints.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use super::tokenizer::Tokenizer;
+
+/// Read a source file as text
+pub fn extract_text(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read source file: {}", path.display()))?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(text.trim_start_matches('\u{FEFF}').to_string())
+}
+
+/// Programming languages recognised by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    Java,
+    Kotlin,
+    Scala,
+    Swift,
+    CSharp,
+    C,
+    Cpp,
+    Ruby,
+    Php,
+}
+
+/// File extensions (lowercase) handled as source code
+pub const EXTENSIONS: &[&str] = &[
+    "rs", "py", "pyi", "js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts", "go", "java", "kt",
+    "kts", "scala", "swift", "cs", "c", "h", "cc", "cpp", "cxx", "hpp", "hh", "hxx", "rb", "php",
+];
+
+impl Language {
+    /// Detect the language from a file extension (case-insensitive)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        Some(match ext.as_str() {
+            "rs" => Self::Rust,
+            "py" | "pyi" => Self::Python,
+            "js" | "jsx" | "mjs" | "cjs" => Self::JavaScript,
+            "ts" | "tsx" | "mts" | "cts" => Self::TypeScript,
+            "go" => Self::Go,
+            "java" => Self::Java,
+            "kt" | "kts" => Self::Kotlin,
+            "scala" => Self::Scala,
+            "swift" => Self::Swift,
+            "cs" => Self::CSharp,
+            "c" | "h" => Self::C,
+            "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => Self::Cpp,
+            "rb" => Self::Ruby,
+            "php" => Self::Php,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rust => "Rust",
+            Self::Python => "Python",
+            Self::JavaScript => "JavaScript",
+            Self::TypeScript => "TypeScript",
+            Self::Go => "Go",
+            Self::Java => "Java",
+            Self::Kotlin => "Kotlin",
+            Self::Scala => "Scala",
+            Self::Swift => "Swift",
+            Self::CSharp => "C#",
+            Self::C => "C",
+            Self::Cpp => "C++",
+            Self::Ruby => "Ruby",
+            Self::Php => "PHP",
+        }
+    }
+
+    /// Blocks are delimited by indentation rather than braces
+    fn indented(self) -> bool {
+        matches!(self, Self::Python | Self::Ruby)
+    }
+
+    fn line_comments(self) -> &'static [&'static str] {
+        match self {
+            Self::Python | Self::Ruby => &["#"],
+            Self::Php => &["//", "#"],
+            _ => &["//"],
+        }
+    }
+
+    /// Separator between a container and its member in qualified names
+    fn path_separator(self) -> &'static str {
+        match self {
+            Self::Rust | Self::C | Self::Cpp | Self::Php => "::",
+            _ => ".",
+        }
+    }
+
+    fn rules(self) -> &'static Rules {
+        &RULES[self as usize]
+    }
+}
+
+/// A declared symbol with its leading doc comment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub kind: &'static str,
+    pub name: String,
+    pub doc: Option<String>,
+}
+
+/// A piece of a source file that ends on a declaration boundary
+#[derive(Debug, Clone)]
+pub struct CodeChunk {
+    pub text: String,
+    /// Signature of the block this piece sits in when a large item was split
+    pub enclosing: Option<String>,
+    pub symbols: Vec<Symbol>,
+    /// Modules and packages the whole file imports
+    pub imports: Vec<String>,
+}
+
+/// Most imports listed in a chunk's hints
+const MAX_IMPORT_HINTS: usize = 30;
+
+/// Longest doc comment kept as a hint
+const MAX_DOC_CHARS: usize = 200;
+
+impl CodeChunk {
+    /// Render the chunk with a header of node hints for the extraction prompt
+    pub fn with_hints(&self, source: &str, language: Language) -> String {
+        let mut out = format!("Source file: {} ({})\n", source, language.name());
+        if let Some(enclosing) = &self.enclosing {
+            out.push_str(&format!("Enclosing: {}\n", enclosing));
+        }
+        if !self.imports.is_empty() {
+            let shown = &self.imports[..self.imports.len().min(MAX_IMPORT_HINTS)];
+            out.push_str(&format!("Imports: {}\n", shown.join(", ")));
+        }
+        if !self.symbols.is_empty() {
+            out.push_str("Symbols:\n");
+            for symbol in &self.symbols {
+                match &symbol.doc {
+                    Some(doc) => {
+                        out.push_str(&format!("- {} {}: {}\n", symbol.kind, symbol.name, doc))
+                    }
+                    None => out.push_str(&format!("- {} {}\n", symbol.kind, symbol.name)),
+                }
+            }
+        }
+        out.push_str("Code:\n");
+        out.push_str(&self.text);
+        out
+    }
+}
+
+/// Splits source files at declaration boundaries
+pub struct CodeChunker {
+    max_tokens: usize,
+    tokenizer: Arc<Tokenizer>,
+}
+
+impl CodeChunker {
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens: max_tokens.max(1),
+            tokenizer: Arc::default(),
+        }
+    }
+
+    /// Count tokens with a model's vocabulary instead of the heuristic
+    pub fn with_tokenizer(mut self, tokenizer: Arc<Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Split source code into chunks of at most `max_tokens` where possible
+    ///
+    /// Adjacent small items are packed together. An item that is too large
+    /// is split at its members (methods of a class or impl block), and only
+    /// an item without members is cut between lines.
+    pub fn split(&self, source: &str, language: Language) -> Vec<CodeChunk> {
+        let file = SourceFile::parse(source, language, &self.tokenizer);
+        if file.lines.iter().all(|l| l.trim().is_empty()) {
+            return Vec::new();
+        }
+
+        let mut ranges = Vec::new();
+        self.split_range(&file, 0, file.lines.len(), None, &mut ranges);
+
+        let imports = file.imports();
+        ranges
+            .into_iter()
+            .filter_map(|(start, end)| {
+                let text = file.lines[start..end]
+                    .join("\n")
+                    .trim_matches('\n')
+                    .to_string();
+                if text.trim().is_empty() {
+                    return None;
+                }
+                let (symbols, enclosing) = file.symbols_in(start, end);
+                Some(CodeChunk {
+                    text,
+                    enclosing,
+                    symbols,
+                    imports: imports.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn split_range(
+        &self,
+        file: &SourceFile,
+        start: usize,
+        end: usize,
+        header: Option<usize>,
+        out: &mut Vec<(usize, usize)>,
+    ) {
+        let segments = file.segments(start, end, header);
+        let mut current: Option<(usize, usize)> = None;
+
+        for (seg_start, seg_end, decl) in segments {
+            let size = file.size(seg_start, seg_end);
+            if size > self.max_tokens {
+                if let Some(range) = current.take() {
+                    out.push(range);
+                }
+                let has_members = decl.is_some_and(|d| file.has_members(d, seg_end));
+                if has_members {
+                    self.split_range(file, seg_start, seg_end, decl, out);
+                } else {
+                    self.split_lines(file, seg_start, seg_end, out);
+                }
+                continue;
+            }
+
+            current = match current {
+                Some((cur_start, _)) if file.size(cur_start, seg_end) <= self.max_tokens => {
+                    Some((cur_start, seg_end))
+                }
+                Some(range) => {
+                    out.push(range);
+                    Some((seg_start, seg_end))
+                }
+                None => Some((seg_start, seg_end)),
+            };
+        }
+
+        if let Some(range) = current {
+            out.push(range);
+        }
+    }
+
+    /// Last resort for items without members: cut between lines
+    fn split_lines(
+        &self,
+        file: &SourceFile,
+        start: usize,
+        end: usize,
+        out: &mut Vec<(usize, usize)>,
+    ) {
+        let mut chunk_start = start;
+        for i in start..end {
+            if i > chunk_start && file.size(chunk_start, i + 1) > self.max_tokens {
+                out.push((chunk_start, i));
+                chunk_start = i;
+            }
+        }
+        if chunk_start < end {
+            out.push((chunk_start, end));
+        }
+    }
+}
+
+/// Kinds whose members are qualified with the container's name
+const CONTAINER_KINDS: &[&str] = &[
+    "class",
+    "struct",
+    "enum",
+    "union",
+    "trait",
+    "interface",
+    "impl",
+    "module",
+    "namespace",
+    "object",
+    "record",
+    "protocol",
+    "extension",
+];
+
+/// Words that start statements, so a line beginning with them is no declaration
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "if", "else", "for", "while", "do", "switch", "case", "catch", "try", "return", "new", "throw",
+    "yield", "await", "assert", "delete", "typeof", "sizeof", "match", "when", "elif", "with",
+    "foreach", "lock", "loop", "until", "unless", "super", "this",
+];
+
+/// Declaration and import patterns for one language
+struct Rules {
+    /// `(kind, pattern)` tried in order against the trimmed line. Group 1 is
+    /// the name; a second group is appended as `group1.group2` (Go methods).
+    decls: Vec<(&'static str, Regex)>,
+    /// Group 1 of the first matching pattern is the imported path
+    imports: Vec<Regex>,
+}
+
+/// Rules indexed by `Language as usize`
+static RULES: LazyLock<Vec<Rules>> = LazyLock::new(|| {
+    [
+        Language::Rust,
+        Language::Python,
+        Language::JavaScript,
+        Language::TypeScript,
+        Language::Go,
+        Language::Java,
+        Language::Kotlin,
+        Language::Scala,
+        Language::Swift,
+        Language::CSharp,
+        Language::C,
+        Language::Cpp,
+        Language::Ruby,
+        Language::Php,
+    ]
+    .into_iter()
+    .map(build_rules)
+    .collect()
+});
+
+fn build_rules(language: Language) -> Rules {
+    let (decls, imports): (Vec<(&'static str, String)>, Vec<&str>) = match language {
+        Language::Rust => {
+            let vis = r"^(?:pub(?:\([^)]*\))?\s+)?";
+            (
+                vec![
+                    (
+                        "function",
+                        format!(
+                            r#"{vis}(?:(?:const|async|unsafe|default|extern(?:\s+"[^"]*")?)\s+)*fn\s+(\w+)"#
+                        ),
+                    ),
+                    ("struct", format!(r"{vis}struct\s+(\w+)")),
+                    ("enum", format!(r"{vis}enum\s+(\w+)")),
+                    ("union", format!(r"{vis}union\s+(\w+)")),
+                    (
+                        "trait",
+                        format!(r"{vis}(?:unsafe\s+)?(?:auto\s+)?trait\s+(\w+)"),
+                    ),
+                    ("module", format!(r"{vis}mod\s+(\w+)")),
+                    ("type", format!(r"{vis}type\s+(\w+)")),
+                    (
+                        "constant",
+                        format!(r"{vis}(?:const|static)\s+(?:mut\s+)?([A-Z_][A-Z0-9_]*)\s*:"),
+                    ),
+                    ("macro", r"^macro_rules!\s*(\w+)".to_string()),
+                    ("impl", r"^(?:unsafe\s+)?impl\b\s*(.+)".to_string()),
+                ],
+                vec![
+                    r"^(?:pub(?:\([^)]*\))?\s+)?use\s+([^;]+)",
+                    r"^extern\s+crate\s+(\w+)",
+                ],
+            )
+        }
+        Language::Python => (
+            vec![
+                ("class", r"^class\s+(\w+)".to_string()),
+                ("function", r"^(?:async\s+)?def\s+(\w+)".to_string()),
+            ],
+            vec![r"^from\s+([\w.]+)\s+import\b", r"^import\s+([\w.]+)"],
+        ),
+        Language::JavaScript | Language::TypeScript => {
+            let export = r"^(?:export\s+)?(?:default\s+)?(?:declare\s+)?";
+            (
+                vec![
+                    ("class", format!(r"{export}(?:abstract\s+)?class\s+(\w+)")),
+                    ("interface", format!(r"{export}interface\s+(\w+)")),
+                    ("enum", format!(r"{export}(?:const\s+)?enum\s+(\w+)")),
+                    ("type", format!(r"{export}type\s+(\w+)\s*(?:<[^=]*>)?\s*=")),
+                    ("module", format!(r"{export}(?:namespace|module)\s+([\w.]+)")),
+                    ("function", format!(r"{export}(?:async\s+)?function\s*\*?\s*(\w+)")),
+                    (
+                        "function",
+                        format!(
+                            r"{export}(?:const|let|var)\s+(\w+)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|(?:\([^)]*\)|\w+)\s*(?::[^=]+)?=>)"
+                        ),
+                    ),
+                    (
+                        "function",
+                        r"^(?:(?:public|private|protected|static|async|readonly|override|abstract|get|set)\s+)*\*?(\w+)\s*(?:<[^>]*>)?\s*\([^)]*\)?\s*(?::\s*[^{;]+)?\{\s*$"
+                            .to_string(),
+                    ),
+                ],
+                vec![
+                    r#"^import\s.*?from\s+['"]([^'"]+)['"]"#,
+                    r#"^import\s+['"]([^'"]+)['"]"#,
+                    r#"require\(\s*['"]([^'"]+)['"]\s*\)"#,
+                ],
+            )
+        }
+        Language::Go => (
+            vec![
+                (
+                    "function",
+                    r"^func\s+\(\s*(?:\w+\s+)?\*?(\w+)[^)]*\)\s*(\w+)".to_string(),
+                ),
+                ("function", r"^func\s+(\w+)".to_string()),
+                (
+                    "struct",
+                    r"^type\s+(\w+)(?:\[[^\]]*\])?\s+struct\b".to_string(),
+                ),
+                (
+                    "interface",
+                    r"^type\s+(\w+)(?:\[[^\]]*\])?\s+interface\b".to_string(),
+                ),
+                ("type", r"^type\s+(\w+)".to_string()),
+            ],
+            vec![r#"^import\s+(?:[\w.]+\s+)?"([^"]+)""#],
+        ),
+        Language::Java | Language::CSharp => {
+            let mods = r"^(?:(?:public|private|protected|internal|static|final|abstract|sealed|non-sealed|strictfp|partial|readonly|unsafe|virtual|override|async|extern|synchronized|native|default|new)\s+)*";
+            (
+                vec![
+                    ("namespace", r"^namespace\s+([\w.]+)".to_string()),
+                    ("interface", format!(r"{mods}@?interface\s+(\w+)")),
+                    ("enum", format!(r"{mods}enum\s+(\w+)")),
+                    (
+                        "record",
+                        format!(r"{mods}record\s+(?:class\s+|struct\s+)?(\w+)"),
+                    ),
+                    ("struct", format!(r"{mods}struct\s+(\w+)")),
+                    ("class", format!(r"{mods}class\s+(\w+)")),
+                    (
+                        "function",
+                        format!(r"{mods}(?:<[^>]+>\s+)?[\w<>\[\]?,. ]+?\s+(\w+)\s*\([^;]*$"),
+                    ),
+                ],
+                vec![
+                    r"^import\s+(?:static\s+)?([\w.]+(?:\.\*)?)\s*;",
+                    r"^using\s+(?:static\s+)?([\w.]+)\s*;",
+                ],
+            )
+        }
+        Language::Kotlin | Language::Scala | Language::Swift => {
+            let mods = r"^(?:@\w+\s+)*(?:(?:public|private|protected|internal|fileprivate|open|final|abstract|sealed|data|inline|value|enum|annotation|companion|override|suspend|inner|lateinit|operator|infix|tailrec|external|expect|actual|implicit|lazy|case|static|mutating|convenience|required)\s+)*";
+            (
+                vec![
+                    ("interface", format!(r"{mods}(?:fun\s+)?interface\s+(\w+)")),
+                    ("trait", format!(r"{mods}trait\s+(\w+)")),
+                    ("protocol", format!(r"{mods}protocol\s+(\w+)")),
+                    ("extension", format!(r"{mods}extension\s+([\w.]+)")),
+                    ("struct", format!(r"{mods}struct\s+(\w+)")),
+                    ("enum", format!(r"{mods}enum\s+(\w+)")),
+                    ("class", format!(r"{mods}class\s+(\w+)")),
+                    ("object", format!(r"{mods}object\s+(\w+)")),
+                    (
+                        "function",
+                        format!(r"{mods}(?:fun|def|func)\s+(?:<[^>]*>\s*)?(?:[\w.<>?]+\.)?(\w+)"),
+                    ),
+                ],
+                vec![r"^import\s+(?:static\s+)?([\w.]+)"],
+            )
+        }
+        Language::C | Language::Cpp => (
+            vec![
+                ("namespace", r"^namespace\s+(\w+)".to_string()),
+                (
+                    "class",
+                    r"^(?:template\s*<[^>]*>\s*)?class\s+(\w+)[^;]*$".to_string(),
+                ),
+                (
+                    "struct",
+                    r"^(?:template\s*<[^>]*>\s*)?(?:typedef\s+)?struct\s+(\w+)[^;]*$".to_string(),
+                ),
+                (
+                    "enum",
+                    r"^(?:typedef\s+)?enum\s+(?:class\s+)?(\w+)[^;]*$".to_string(),
+                ),
+                ("macro", r"^#\s*define\s+(\w+)".to_string()),
+                (
+                    "function",
+                    r"^(?:template\s*<[^>]*>\s*)?(?:[\w:<>,]+[\s*&]+)+((?:\w+::)*~?\w+)\s*\([^;]*$"
+                        .to_string(),
+                ),
+            ],
+            vec![r#"^#\s*include\s*[<"]([^>"]+)[>"]"#],
+        ),
+        Language::Ruby => (
+            vec![
+                ("module", r"^module\s+([\w:]+)".to_string()),
+                ("class", r"^class\s+([\w:]+)".to_string()),
+                ("function", r"^def\s+((?:self\.)?\w+[?!=]?)".to_string()),
+            ],
+            vec![r#"^require(?:_relative)?\s*\(?\s*['"]([^'"]+)['"]"#],
+        ),
+        Language::Php => {
+            let mods = r"^(?:(?:abstract|final|public|private|protected|static|readonly)\s+)*";
+            (
+                vec![
+                    ("namespace", r"^namespace\s+([\w\\]+)".to_string()),
+                    ("interface", format!(r"{mods}interface\s+(\w+)")),
+                    ("trait", format!(r"{mods}trait\s+(\w+)")),
+                    ("enum", format!(r"{mods}enum\s+(\w+)")),
+                    ("class", format!(r"{mods}class\s+(\w+)")),
+                    ("function", format!(r"{mods}function\s+&?(\w+)")),
+                ],
+                vec![r"^use\s+([\w\\]+)"],
+            )
+        }
+    };
+
+    Rules {
+        decls: decls
+            .into_iter()
+            .map(|(kind, pattern)| (kind, Regex::new(&pattern).expect("valid declaration regex")))
+            .collect(),
+        imports: imports
+            .into_iter()
+            .map(|pattern| Regex::new(pattern).expect("valid import regex"))
+            .collect(),
+    }
+}
+
+/// A declaration found in the source
+struct Decl {
+    line: usize,
+    depth: usize,
+    kind: &'static str,
+    name: String,
+    /// Declaration line without its opening brace or colon
+    signature: String,
+    doc: Option<String>,
+}
+
+/// A source file split into lines, with block depth and declarations
+struct SourceFile<'a> {
+    language: Language,
+    lines: Vec<&'a str>,
+    /// Nesting depth at the start of each line (brace depth or indentation)
+    depths: Vec<usize>,
+    /// Whether each line starts outside comments and multi-line strings
+    code: Vec<bool>,
+    /// Tokens of each line with its newline
+    tokens: Vec<usize>,
+    decls: Vec<Decl>,
+}
+
+impl<'a> SourceFile<'a> {
+    fn parse(source: &'a str, language: Language, tokenizer: &Tokenizer) -> Self {
+        let lines: Vec<&str> = source.lines().collect();
+        let tokens = lines
+            .iter()
+            .map(|l| tokenizer.count(&format!("{}\n", l)))
+            .collect();
+        let (depths, code) = if language.indented() {
+            indent_depths(&lines)
+        } else {
+            brace_depths(&lines, language)
+        };
+        let mut file = Self {
+            language,
+            lines,
+            depths,
+            code,
+            tokens,
+            decls: Vec::new(),
+        };
+        file.find_decls();
+        file
+    }
+
+    fn find_decls(&mut self) {
+        let rules = self.language.rules();
+        let mut decls: Vec<Decl> = Vec::new();
+
+        for (i, line) in self.lines.iter().enumerate() {
+            let trimmed = line.trim();
+            if !self.code[i] || trimmed.is_empty() || self.is_comment(trimmed) {
+                continue;
+            }
+            let first_word = trimmed
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap_or("");
+            if STATEMENT_KEYWORDS.contains(&first_word) {
+                continue;
+            }
+
+            let Some((kind, name)) = rules.decls.iter().find_map(|(kind, re)| {
+                let caps = re.captures(trimmed)?;
+                let name = caps.get(1)?.as_str();
+                let name = match (*kind, caps.get(2)) {
+                    ("impl", _) => impl_name(name),
+                    (_, Some(member)) => format!("{}.{}", name, member.as_str()),
+                    _ => name.to_string(),
+                };
+                (!name.is_empty() && !STATEMENT_KEYWORDS.contains(&name.as_str()))
+                    .then_some((*kind, name))
+            }) else {
+                continue;
+            };
+
+            let depth = self.depths[i];
+            let parent = decls.iter().rev().find(|d| d.depth < depth);
+            let (kind, name) = match parent {
+                Some(p) if CONTAINER_KINDS.contains(&p.kind) => (
+                    if kind == "function" && !matches!(p.kind, "module" | "namespace") {
+                        "method"
+                    } else {
+                        kind
+                    },
+                    format!("{}{}{}", qualifier(p), self.language.path_separator(), name),
+                ),
+                _ => (kind, name),
+            };
+
+            let signature = trimmed.trim_end_matches(['{', ':']).trim_end().to_string();
+            let doc = self.docstring(i).or_else(|| self.leading_doc(i));
+            decls.push(Decl {
+                line: i,
+                depth,
+                kind,
+                name,
+                signature,
+                doc,
+            });
+        }
+
+        self.decls = decls;
+    }
+
+    fn is_comment(&self, trimmed: &str) -> bool {
+        self.language
+            .line_comments()
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix) && !trimmed.starts_with("#["))
+            || trimmed.starts_with("/*")
+            || trimmed.starts_with("*")
+    }
+
+    fn is_attribute(&self, trimmed: &str) -> bool {
+        match self.language {
+            Language::Rust | Language::Php => trimmed.starts_with("#["),
+            Language::CSharp => trimmed.starts_with('['),
+            Language::C | Language::Cpp | Language::Go | Language::Ruby => false,
+            _ => trimmed.starts_with('@'),
+        }
+    }
+
+    /// First line of the comments and attributes directly above `line`
+    fn leading_start(&self, line: usize, floor: usize) -> usize {
+        let mut start = line;
+        while start > floor {
+            let above = self.lines[start - 1].trim();
+            if above.is_empty() || !(self.is_comment(above) || self.is_attribute(above)) {
+                break;
+            }
+            start -= 1;
+        }
+        start
+    }
+
+    /// Comment block above a declaration, skipping attributes and decorators
+    fn leading_doc(&self, line: usize) -> Option<String> {
+        let mut i = line;
+        while i > 0 && self.is_attribute(self.lines[i - 1].trim()) {
+            i -= 1;
+        }
+        let end = i;
+        while i > 0 {
+            let above = self.lines[i - 1].trim();
+            if above.is_empty() || !self.is_comment(above) {
+                break;
+            }
+            i -= 1;
+        }
+        summarize_doc(self.lines[i..end].iter().map(|l| strip_comment(l.trim())))
+    }
+
+    /// Python docstring: the string literal that opens the body
+    fn docstring(&self, line: usize) -> Option<String> {
+        if self.language != Language::Python {
+            return None;
+        }
+        let header_end = (line..self.lines.len().min(line + 10))
+            .find(|&i| self.lines[i].trim_end().ends_with(':'))?;
+        let first = self.lines[header_end + 1..]
+            .iter()
+            .position(|l| !l.trim().is_empty())
+            .map(|offset| header_end + 1 + offset)?;
+
+        let opening = self.lines[first]
+            .trim()
+            .trim_start_matches(['r', 'u', 'R', 'U']);
+        let quote = ["\"\"\"", "'''"]
+            .into_iter()
+            .find(|q| opening.starts_with(q))?;
+        let mut body = Vec::new();
+        let mut rest = &opening[3..];
+        let mut i = first;
+        loop {
+            if let Some(close) = rest.find(quote) {
+                body.push(&rest[..close]);
+                break;
+            }
+            body.push(rest);
+            i += 1;
+            match self.lines.get(i) {
+                Some(next) => rest = next.trim(),
+                None => break,
+            }
+        }
+        summarize_doc(body.into_iter().map(str::trim))
+    }
+
+    /// Tokens in `lines[start..end]`, counted line by line
+    fn size(&self, start: usize, end: usize) -> usize {
+        self.tokens[start..end].iter().sum()
+    }
+
+    /// Split `start..end` into items at the shallowest declarations inside it
+    ///
+    /// With a `header`, only declarations nested in that item count. Each
+    /// segment starts at the comments and attributes above its declaration.
+    /// Text before the first declaration becomes a segment of its own.
+    fn segments(
+        &self,
+        start: usize,
+        end: usize,
+        header: Option<usize>,
+    ) -> Vec<(usize, usize, Option<usize>)> {
+        let inner: Vec<usize> = (0..self.decls.len())
+            .filter(|&d| {
+                let decl = &self.decls[d];
+                decl.line >= start
+                    && decl.line < end
+                    && header.is_none_or(|h| {
+                        decl.line > self.decls[h].line && decl.depth > self.decls[h].depth
+                    })
+            })
+            .collect();
+        let Some(level) = inner.iter().map(|&d| self.decls[d].depth).min() else {
+            return vec![(start, end, None)];
+        };
+
+        let mut floor = header.map_or(start, |h| self.decls[h].line + 1);
+        let mut starts: Vec<(usize, Option<usize>)> = Vec::new();
+        for d in inner.into_iter().filter(|&d| self.decls[d].depth == level) {
+            let seg_start = self.leading_start(self.decls[d].line, floor);
+            starts.push((seg_start, Some(d)));
+            floor = self.decls[d].line + 1;
+        }
+        if starts.first().is_none_or(|&(s, _)| s > start) {
+            starts.insert(0, (start, None));
+        }
+
+        starts
+            .iter()
+            .enumerate()
+            .map(|(i, &(seg_start, decl))| {
+                let seg_end = starts.get(i + 1).map_or(end, |&(next, _)| next);
+                (seg_start, seg_end, decl)
+            })
+            .collect()
+    }
+
+    /// Whether a declaration has nested declarations before `end`
+    fn has_members(&self, decl: usize, end: usize) -> bool {
+        let header = &self.decls[decl];
+        self.decls
+            .iter()
+            .any(|d| d.line > header.line && d.line < end && d.depth > header.depth)
+    }
+
+    /// Symbols declared in `start..end` and the signature of the enclosing item
+    ///
+    /// Only the two shallowest levels are listed: items and their members,
+    /// not functions nested inside function bodies.
+    fn symbols_in(&self, start: usize, end: usize) -> (Vec<Symbol>, Option<String>) {
+        let inside: Vec<&Decl> = self
+            .decls
+            .iter()
+            .filter(|d| d.line >= start && d.line < end)
+            .collect();
+
+        let mut levels: Vec<usize> = inside.iter().map(|d| d.depth).collect();
+        levels.sort_unstable();
+        levels.dedup();
+        levels.truncate(2);
+
+        let symbols = inside
+            .iter()
+            .filter(|d| levels.contains(&d.depth))
+            .map(|d| Symbol {
+                kind: d.kind,
+                name: d.name.clone(),
+                doc: d.doc.clone(),
+            })
+            .collect();
+
+        let depth = self.depths[start];
+        let enclosing = self
+            .decls
+            .iter()
+            .rev()
+            .find(|d| d.line < start && d.depth < depth)
+            .map(|d| d.signature.clone());
+
+        (symbols, enclosing)
+    }
+
+    /// Imported modules and packages, in order of first appearance
+    fn imports(&self) -> Vec<String> {
+        let rules = self.language.rules();
+        let mut imports: Vec<String> = Vec::new();
+        let mut in_go_block = false;
+
+        for (i, line) in self.lines.iter().enumerate() {
+            let trimmed = line.trim();
+            if !self.code[i] || trimmed.is_empty() {
+                continue;
+            }
+
+            let found = if self.language == Language::Go && (in_go_block || trimmed == "import (") {
+                in_go_block = trimmed != ")";
+                trimmed
+                    .rsplit_once('"')
+                    .and_then(|(head, _)| head.rsplit_once('"'))
+                    .map(|(_, path)| path.to_string())
+            } else {
+                rules
+                    .imports
+                    .iter()
+                    .find_map(|re| re.captures(trimmed))
+                    .and_then(|caps| caps.get(1))
+                    .map(|m| m.as_str().trim().trim_end_matches(['{', ':']).to_string())
+            };
+
+            if let Some(import) = found
+                && !import.is_empty()
+                && !imports.contains(&import)
+            {
+                imports.push(import);
+            }
+        }
+
+        imports
+    }
+}
+
+/// `impl<T> Display for Config<T> where ...` → `Display for Config<T>`
+fn impl_name(rest: &str) -> String {
+    let mut rest = rest.trim();
+    if rest.starts_with('<') {
+        let mut depth = 0;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                rest = &rest[i + 1..];
+                break;
+            }
+        }
+    }
+    let rest = rest.split('{').next().unwrap_or("");
+    let rest = rest.split(" where").next().unwrap_or("");
+    rest.trim().to_string()
+}
+
+/// Name members are qualified with: the implementing type for impl blocks
+fn qualifier(decl: &Decl) -> &str {
+    if decl.kind != "impl" {
+        return &decl.name;
+    }
+    let target = decl
+        .name
+        .rsplit_once(" for ")
+        .map_or(decl.name.as_str(), |(_, ty)| ty);
+    let target = target.split('<').next().unwrap_or(target);
+    target.rsplit("::").next().unwrap_or(target).trim()
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut line = line;
+    for prefix in ["///", "//!", "//", "/**", "/*", "*/", "*", "#"] {
+        if let Some(rest) = line.strip_prefix(prefix) {
+            line = rest;
+            break;
+        }
+    }
+    line.trim_end_matches("*/").trim()
+}
+
+/// First paragraph of a doc comment, cut before tags and capped in length
+fn summarize_doc<'s>(lines: impl Iterator<Item = &'s str>) -> Option<String> {
+    let mut words: Vec<&str> = Vec::new();
+    for line in lines {
+        if line.is_empty() {
+            if words.is_empty() {
+                continue;
+            }
+            break;
+        }
+        if line.starts_with('@') || line.starts_with(":param") || line.starts_with("# ") {
+            break;
+        }
+        words.extend(line.split_whitespace());
+    }
+    if words.is_empty() {
+        return None;
+    }
+
+    let doc = words.join(" ");
+    if doc.chars().count() <= MAX_DOC_CHARS {
+        return Some(doc);
+    }
+    let cut: String = doc.chars().take(MAX_DOC_CHARS).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
+
+/// Brace depth at the start of each line, ignoring strings and comments
+fn brace_depths(lines: &[&str], language: Language) -> (Vec<usize>, Vec<bool>) {
+    let mut depths = Vec::with_capacity(lines.len());
+    let mut code = Vec::with_capacity(lines.len());
+    let mut depth: i64 = 0;
+    let mut block_comment = false;
+    let mut string: Option<char> = None;
+
+    for line in lines {
+        depths.push(depth.max(0) as usize);
+        code.push(!block_comment && string.is_none());
+
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            if block_comment {
+                if c == '*' && next == Some('/') {
+                    block_comment = false;
+                    i += 1;
+                }
+            } else if let Some(quote) = string {
+                if c == '\\' {
+                    i += 1;
+                } else if c == quote {
+                    string = None;
+                }
+            } else {
+                match c {
+                    '/' if next == Some('/') => break,
+                    '/' if next == Some('*') => {
+                        block_comment = true;
+                        i += 1;
+                    }
+                    '#' if language == Language::Php => break,
+                    '"' | '`' => string = Some(c),
+                    '\'' if language == Language::Rust => {
+                        // Char literal ('x' or '\n'); otherwise a lifetime
+                        if next == Some('\\') {
+                            i += chars
+                                .get(i + 3..)
+                                .and_then(|rest| rest.iter().position(|&c| c == '\''))
+                                .map_or(0, |p| p + 3);
+                        } else if chars.get(i + 2) == Some(&'\'') {
+                            i += 2;
+                        }
+                    }
+                    '\'' => string = Some(c),
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+            }
+            i += 1;
+        }
+
+        // Only Rust strings and template literals may run across lines
+        if string.is_some_and(|q| q == '\'' || (q == '"' && language != Language::Rust)) {
+            string = None;
+        }
+    }
+
+    (depths, code)
+}
+
+/// Indentation at the start of each line, skipping triple-quoted strings
+fn indent_depths(lines: &[&str]) -> (Vec<usize>, Vec<bool>) {
+    let mut depths = Vec::with_capacity(lines.len());
+    let mut code = Vec::with_capacity(lines.len());
+    let mut last = 0;
+    let mut triple: Option<&str> = None;
+
+    for line in lines {
+        let in_string = triple.is_some();
+        if in_string || line.trim().is_empty() {
+            depths.push(last);
+        } else {
+            last = line
+                .chars()
+                .take_while(|c| c.is_whitespace())
+                .map(|c| if c == '\t' { 4 } else { 1 })
+                .sum();
+            depths.push(last);
+        }
+        code.push(!in_string);
+
+        for quote in ["\"\"\"", "'''"] {
+            if triple.is_some_and(|t| t != quote) {
+                continue;
+            }
+            if line.matches(quote).count() % 2 == 1 {
+                triple = if triple.is_some() { None } else { Some(quote) };
+            }
+        }
+    }
+
+    (depths, code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST_SOURCE: &str = r#"use std::collections::HashMap;
+use crate::config::Config;
+
+/// Maximum number of retries
+pub const MAX_RETRIES: usize = 3;
+
+/// Client for the graph store.
+///
+/// Longer description that is not part of the hint.
+pub struct Client {
+    url: String,
+}
+
+impl Client {
+    /// Connect to the store at `url`
+    pub fn connect(url: &str) -> Self {
+        let s = "not { a brace";
+        Self { url: url.to_string() }
+    }
+
+    #[allow(dead_code)]
+    fn ping(&self) -> bool {
+        true
+    }
+}
+
+impl<'a> std::fmt::Display for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.url)
+    }
+}
+"#;
+
+    #[test]
+    fn test_language_detection() {
+        assert_eq!(
+            Language::from_path(Path::new("src/main.RS")),
+            Some(Language::Rust)
+        );
+        assert_eq!(
+            Language::from_path(Path::new("app.tsx")),
+            Some(Language::TypeScript)
+        );
+        assert_eq!(Language::from_path(Path::new("notes.md")), None);
+        for ext in EXTENSIONS {
+            let path = format!("file.{}", ext);
+            assert!(Language::from_path(Path::new(&path)).is_some(), "{}", ext);
+        }
+    }
+
+    #[test]
+    fn test_rust_symbols_and_docs() {
+        let chunks = CodeChunker::new(2_500).split(RUST_SOURCE, Language::Rust);
+        assert_eq!(chunks.len(), 1);
+        let chunk = &chunks[0];
+        assert_eq!(
+            chunk.imports,
+            vec!["std::collections::HashMap", "crate::config::Config"]
+        );
+
+        let names: Vec<(&str, &str)> = chunk
+            .symbols
+            .iter()
+            .map(|s| (s.kind, s.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("constant", "MAX_RETRIES"),
+                ("struct", "Client"),
+                ("impl", "Client"),
+                ("method", "Client::connect"),
+                ("method", "Client::ping"),
+                ("impl", "std::fmt::Display for Client"),
+                ("method", "Client::fmt"),
+            ]
+        );
+        assert_eq!(
+            chunk.symbols[1].doc.as_deref(),
+            Some("Client for the graph store.")
+        );
+        assert_eq!(
+            chunk.symbols[3].doc.as_deref(),
+            Some("Connect to the store at `url`")
+        );
+        assert_eq!(chunk.symbols[4].doc, None);
+    }
+
+    #[test]
+    fn test_split_at_item_boundaries() {
+        let chunks = CodeChunker::new(75).split(RUST_SOURCE, Language::Rust);
+        assert!(chunks.len() > 1);
+        // Doc comments and attributes stay with the item they describe
+        let client = chunks
+            .iter()
+            .find(|c| c.text.contains("pub struct Client"))
+            .unwrap();
+        assert!(client.text.contains("/// Client for the graph store."));
+        for chunk in &chunks {
+            let opens = chunk.text.matches('{').count();
+            let closes = chunk.text.matches('}').count();
+            if chunk.enclosing.is_none() {
+                assert!(opens.abs_diff(closes) <= 1, "unbalanced: {}", chunk.text);
+            }
+            assert!(Tokenizer::default().count(&chunk.text) <= 75);
+        }
+        // Nothing is lost or duplicated
+        let joined: String = chunks
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let squash = |s: &str| s.split_whitespace().collect::<String>();
+        assert_eq!(squash(&joined), squash(RUST_SOURCE));
+    }
+
+    #[test]
+    fn test_large_impl_split_at_methods() {
+        let chunks = CodeChunker::new(50).split(RUST_SOURCE, Language::Rust);
+        let ping = chunks.iter().find(|c| c.text.contains("fn ping")).unwrap();
+        assert!(ping.text.trim_start().starts_with("#[allow(dead_code)]"));
+        assert_eq!(ping.enclosing.as_deref(), Some("impl Client"));
+        assert_eq!(ping.symbols[0].name, "Client::ping");
+    }
+
+    #[test]
+    fn test_python_classes_and_docstrings() {
+        let source = r#"import os
+from typing import List
+
+class Store:
+    """Persistent key-value store."""
+
+    def get(self, key):
+        """Return the value for key.
+
+        Raises KeyError when missing.
+        """
+        return self.data[key]
+
+    @property
+    def size(self):
+        # Number of keys
+        return len(self.data)
+
+def main():
+    print("class Fake:")
+"#;
+        let chunks = CodeChunker::new(2_500).split(source, Language::Python);
+        let chunk = &chunks[0];
+        assert_eq!(chunk.imports, vec!["os", "typing"]);
+        let symbols: Vec<(&str, &str, Option<&str>)> = chunk
+            .symbols
+            .iter()
+            .map(|s| (s.kind, s.name.as_str(), s.doc.as_deref()))
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![
+                ("class", "Store", Some("Persistent key-value store.")),
+                ("method", "Store.get", Some("Return the value for key.")),
+                ("method", "Store.size", None),
+                ("function", "main", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_go_and_typescript() {
+        let go = "package store\n\nimport (\n\t\"fmt\"\n\tlog \"github.com/sirupsen/logrus\"\n)\n\n// Store keeps values.\ntype Store struct {\n\tdata map[string]string\n}\n\n// Get looks up a key.\nfunc (s *Store) Get(key string) string {\n\treturn s.data[key]\n}\n";
+        let chunk = &CodeChunker::new(2_500).split(go, Language::Go)[0];
+        assert_eq!(chunk.imports, vec!["fmt", "github.com/sirupsen/logrus"]);
+        assert_eq!(chunk.symbols[0].name, "Store");
+        assert_eq!(chunk.symbols[0].doc.as_deref(), Some("Store keeps values."));
+        assert_eq!(chunk.symbols[1].name, "Store.Get");
+
+        let ts = "import { Router } from 'express';\n\nexport interface User {\n  id: string;\n}\n\nexport class UserService {\n  async find(id: string): Promise<User> {\n    if (id) {\n      return db.get(id);\n    }\n  }\n}\n\nexport const handler = async (req) => {\n  return new UserService();\n};\n";
+        let chunk = &CodeChunker::new(2_500).split(ts, Language::TypeScript)[0];
+        assert_eq!(chunk.imports, vec!["express"]);
+        let names: Vec<&str> = chunk.symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["User", "UserService", "UserService.find", "handler"]
+        );
+    }
+
+    #[test]
+    fn test_hints_header() {
+        let chunk = CodeChunk {
+            text: "fn main() {}".into(),
+            enclosing: None,
+            symbols: vec![Symbol {
+                kind: "function",
+                name: "main".into(),
+                doc: Some("Entry point".into()),
+            }],
+            imports: vec!["std::env".into()],
+        };
+        assert_eq!(
+            chunk.with_hints("src/main.rs", Language::Rust),
+            "Source file: src/main.rs (Rust)\nImports: std::env\nSymbols:\n- function main: Entry point\nCode:\nfn main() {}"
+        );
+    }
+}
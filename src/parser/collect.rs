@@ -0,0 +1,564 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Ignore files read in every directory of a scan, in this order
+const IGNORE_FILES: &[&str] = &[".gitignore", ".rknowledgeignore"];
+
+/// Filters applied while walking a directory for documents
+#[derive(Debug, Clone)]
+pub struct CollectOptions {
+    /// Only files matching one of these globs are kept (all files when empty)
+    pub include: Vec<String>,
+    /// Files and directories matching any of these globs are skipped
+    pub exclude: Vec<String>,
+    /// Files larger than this many bytes are skipped
+    pub max_file_size: Option<u64>,
+    /// Honour `.gitignore` and `.rknowledgeignore` files
+    pub use_ignore_files: bool,
+}
+
+impl Default for CollectOptions {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_file_size: None,
+            use_ignore_files: true,
+        }
+    }
+}
+
+/// Why a file or directory was left out of a scan
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkipReason {
+    Ignored { file: String, pattern: String },
+    Excluded(String),
+    NotIncluded,
+    Unsupported,
+    TooLarge { size: u64, limit: u64 },
+    SymlinkLoop(PathBuf),
+    AlreadyVisited(PathBuf),
+    Unreadable(String),
+}
+
+impl SkipReason {
+    /// Short category used to summarize skips
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Ignored { .. } => "ignored",
+            Self::Excluded(_) => "excluded",
+            Self::NotIncluded => "not included",
+            Self::Unsupported => "unsupported",
+            Self::TooLarge { .. } => "too large",
+            Self::SymlinkLoop(_) => "symlink loop",
+            Self::AlreadyVisited(_) => "duplicate",
+            Self::Unreadable(_) => "unreadable",
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ignored { file, pattern } => write!(f, "ignored by {} ({})", file, pattern),
+            Self::Excluded(pattern) => write!(f, "matches --exclude {}", pattern),
+            Self::NotIncluded => write!(f, "matches no --include pattern"),
+            Self::Unsupported => write!(f, "unsupported file type"),
+            Self::TooLarge { size, limit } => write!(
+                f,
+                "{} is over the {} limit",
+                format_size(*size),
+                format_size(*limit)
+            ),
+            Self::SymlinkLoop(target) => write!(f, "symlink loops back to {}", target.display()),
+            Self::AlreadyVisited(target) => {
+                write!(f, "already scanned through {}", target.display())
+            }
+            Self::Unreadable(err) => write!(f, "unreadable: {}", err),
+        }
+    }
+}
+
+/// Documents found by a scan, and everything left out with the reason
+#[derive(Debug, Default)]
+pub struct Collected {
+    pub documents: Vec<PathBuf>,
+    pub skipped: Vec<(PathBuf, SkipReason)>,
+}
+
+/// Walk `root` for documents that `supports` accepts
+///
+/// Symlinks are followed, but a link back into one of its own ancestors and a
+/// directory reached a second time are skipped. `.git` directories are never entered.
+pub fn collect_documents(
+    root: &Path,
+    options: &CollectOptions,
+    supports: impl Fn(&Path) -> bool,
+) -> Result<Collected> {
+    let include = compile_all(&options.include)?;
+    let exclude = compile_all(&options.exclude)?;
+    let mut walker = Walker {
+        options,
+        include,
+        exclude,
+        supports: &supports,
+        ignores: Vec::new(),
+        ancestors: Vec::new(),
+        visited: HashSet::new(),
+        collected: Collected::default(),
+    };
+
+    if root.is_dir() {
+        walker.walk_dir(root, "")?;
+    } else if root.exists() {
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        walker.visit_file(root, &name);
+    }
+    Ok(walker.collected)
+}
+
+struct Walker<'a, F: Fn(&Path) -> bool> {
+    options: &'a CollectOptions,
+    include: Vec<(String, Glob)>,
+    exclude: Vec<(String, Glob)>,
+    supports: &'a F,
+    /// Ignore files of the directories being walked, outermost first
+    ignores: Vec<IgnoreFile>,
+    /// Canonical paths of the directories being walked, for loop detection
+    ancestors: Vec<PathBuf>,
+    visited: HashSet<PathBuf>,
+    collected: Collected,
+}
+
+impl<F: Fn(&Path) -> bool> Walker<'_, F> {
+    fn walk_dir(&mut self, dir: &Path, rel: &str) -> Result<()> {
+        let canonical = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        if self.ancestors.contains(&canonical) {
+            self.skip(dir, SkipReason::SymlinkLoop(canonical));
+            return Ok(());
+        }
+        if !self.visited.insert(canonical.clone()) {
+            self.skip(dir, SkipReason::AlreadyVisited(canonical));
+            return Ok(());
+        }
+
+        let pushed = if self.options.use_ignore_files {
+            let before = self.ignores.len();
+            for name in IGNORE_FILES {
+                if let Some(file) = IgnoreFile::load(&dir.join(name), rel) {
+                    self.ignores.push(file);
+                }
+            }
+            self.ignores.len() - before
+        } else {
+            0
+        };
+        self.ancestors.push(canonical);
+
+        let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+            Err(e) => {
+                self.skip(dir, SkipReason::Unreadable(e.to_string()));
+                Vec::new()
+            }
+        };
+        entries.sort();
+
+        for path in entries {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if name == ".git" {
+                continue;
+            }
+            let child_rel = if rel.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", rel, name)
+            };
+
+            // Follows symlinks; a dangling link fails here
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    self.skip(&path, SkipReason::Unreadable(e.to_string()));
+                    continue;
+                }
+            };
+
+            if metadata.is_dir() {
+                if let Some(reason) = self.ignored(&child_rel, true) {
+                    self.skip(&path, reason);
+                } else if let Some(pattern) = matching(&self.exclude, &child_rel) {
+                    self.skip(&path, SkipReason::Excluded(pattern));
+                } else {
+                    self.walk_dir(&path, &child_rel)?;
+                }
+            } else if let Some(reason) = self.ignored(&child_rel, false) {
+                self.skip(&path, reason);
+            } else {
+                self.visit_file(&path, &child_rel);
+            }
+        }
+
+        self.ancestors.pop();
+        self.ignores.truncate(self.ignores.len() - pushed);
+        Ok(())
+    }
+
+    fn visit_file(&mut self, path: &Path, rel: &str) {
+        let reason = if let Some(pattern) = matching(&self.exclude, rel) {
+            Some(SkipReason::Excluded(pattern))
+        } else if !self.include.is_empty() && matching(&self.include, rel).is_none() {
+            Some(SkipReason::NotIncluded)
+        } else if !(self.supports)(path) {
+            Some(SkipReason::Unsupported)
+        } else {
+            match (self.options.max_file_size, fs::metadata(path)) {
+                (Some(limit), Ok(meta)) if meta.len() > limit => Some(SkipReason::TooLarge {
+                    size: meta.len(),
+                    limit,
+                }),
+                (_, Err(e)) => Some(SkipReason::Unreadable(e.to_string())),
+                _ => None,
+            }
+        };
+        match reason {
+            Some(reason) => self.skip(path, reason),
+            None => self.collected.documents.push(path.to_path_buf()),
+        }
+    }
+
+    /// The last matching rule across all ignore files wins; `!pattern` re-includes
+    fn ignored(&self, rel: &str, is_dir: bool) -> Option<SkipReason> {
+        let mut verdict = None;
+        for file in &self.ignores {
+            let Some(sub) = file.relative(rel) else {
+                continue;
+            };
+            for rule in &file.rules {
+                if (!rule.dir_only || is_dir) && rule.glob.is_match(sub) {
+                    verdict = (!rule.negated).then(|| SkipReason::Ignored {
+                        file: file.name.clone(),
+                        pattern: rule.text.clone(),
+                    });
+                }
+            }
+        }
+        verdict
+    }
+
+    fn skip(&mut self, path: &Path, reason: SkipReason) {
+        self.collected.skipped.push((path.to_path_buf(), reason));
+    }
+}
+
+/// A gitignore-style glob, matched against `/`-separated paths relative to a base directory
+///
+/// Patterns without a slash match a name at any depth, `**` crosses directories,
+/// `*` and `?` stay within one path component, and `[...]` is a character class.
+#[derive(Debug, Clone)]
+pub struct Glob(Regex);
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let trimmed = pattern.trim_end_matches('/');
+        let anchored = trimmed.starts_with('/') || trimmed.contains('/');
+        let body = trimmed.trim_start_matches('/');
+
+        let mut re = String::from("^");
+        if !anchored {
+            re.push_str("(?:.*/)?");
+        }
+        let chars: Vec<char> = body.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    if chars.get(i + 2) == Some(&'/') {
+                        re.push_str("(?:.*/)?");
+                        i += 3;
+                    } else {
+                        re.push_str(".*");
+                        i += 2;
+                    }
+                    continue;
+                }
+                '*' => re.push_str("[^/]*"),
+                '?' => re.push_str("[^/]"),
+                '[' => match chars[i..].iter().position(|&c| c == ']') {
+                    Some(end) if end > 1 => {
+                        let class: String = chars[i + 1..i + end].iter().collect();
+                        let class = class
+                            .strip_prefix('!')
+                            .map_or(class.clone(), |rest| format!("^{}", rest));
+                        re.push('[');
+                        re.push_str(&class.replace('\\', "\\\\"));
+                        re.push(']');
+                        i += end + 1;
+                        continue;
+                    }
+                    _ => re.push_str("\\["),
+                },
+                c => re.push_str(&regex::escape(&c.to_string())),
+            }
+            i += 1;
+        }
+        // A pattern naming a directory also covers everything below it
+        re.push_str("(?:/.*)?$");
+        Ok(Self(Regex::new(&re).map_err(|e| {
+            anyhow::anyhow!("Invalid glob '{}': {}", pattern, e)
+        })?))
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.0.is_match(path)
+    }
+}
+
+fn compile_all(patterns: &[String]) -> Result<Vec<(String, Glob)>> {
+    patterns
+        .iter()
+        .map(|p| Ok((p.clone(), Glob::new(p)?)))
+        .collect()
+}
+
+fn matching(globs: &[(String, Glob)], rel: &str) -> Option<String> {
+    globs
+        .iter()
+        .find(|(_, glob)| glob.is_match(rel))
+        .map(|(pattern, _)| pattern.clone())
+}
+
+#[derive(Debug)]
+struct IgnoreRule {
+    text: String,
+    glob: Glob,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// The rules of one `.gitignore` or `.rknowledgeignore`, relative to its directory
+#[derive(Debug)]
+struct IgnoreFile {
+    /// Display name, e.g. `docs/.gitignore`
+    name: String,
+    /// Directory of the file relative to the scan root (`""` for the root)
+    base: String,
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreFile {
+    fn load(path: &Path, base: &str) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let file_name = path.file_name()?.to_string_lossy();
+        let rules = content
+            .lines()
+            .map(str::trim_end)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|line| {
+                let (negated, pattern) = match line.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, line.strip_prefix('\\').unwrap_or(line)),
+                };
+                Some(IgnoreRule {
+                    text: line.to_string(),
+                    glob: Glob::new(pattern).ok()?,
+                    negated,
+                    dir_only: pattern.ends_with('/'),
+                })
+            })
+            .collect();
+        Some(Self {
+            name: if base.is_empty() {
+                file_name.to_string()
+            } else {
+                format!("{}/{}", base, file_name)
+            },
+            base: base.to_string(),
+            rules,
+        })
+    }
+
+    /// `rel` relative to this file's directory, if it lies below it
+    fn relative<'a>(&self, rel: &'a str) -> Option<&'a str> {
+        if self.base.is_empty() {
+            Some(rel)
+        } else {
+            rel.strip_prefix(&self.base)?.strip_prefix('/')
+        }
+    }
+}
+
+/// Parse a size such as `500K`, `10MB` or `1.5g` into bytes
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", input))?;
+    let multiplier = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1u64,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        other => return Err(format!("unknown size unit '{}'", other)),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.1} GB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.1} MB", b as f64 / (1u64 << 20) as f64),
+        b if b >= 1 << 10 => format!("{:.1} KB", b as f64 / (1u64 << 10) as f64),
+        b => format!("{} B", b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_doc(path: &Path) -> bool {
+        path.extension().is_some_and(|e| e == "md" || e == "txt")
+    }
+
+    fn names(paths: &[PathBuf], root: &Path) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| {
+                p.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_glob_semantics() {
+        let glob = |p: &str| Glob::new(p).unwrap();
+        assert!(glob("*.md").is_match("docs/guide/intro.md"));
+        assert!(!glob("*.md").is_match("docs/intro.mdx"));
+        assert!(glob("/build").is_match("build/out.txt"));
+        assert!(!glob("/build").is_match("src/build/out.txt"));
+        assert!(glob("docs/**/*.md").is_match("docs/a/b/c.md"));
+        assert!(glob("docs/**/*.md").is_match("docs/c.md"));
+        assert!(!glob("docs/*.md").is_match("docs/a/c.md"));
+        assert!(glob("node_modules").is_match("web/node_modules/x/readme.md"));
+        assert!(glob("draft-?.[mt]xt").is_match("draft-1.txt"));
+        assert!(glob("[!a]*.md").is_match("b.md"));
+    }
+
+    #[test]
+    fn test_ignore_files_and_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".gitignore", "target/\n*.log\n");
+        write(root, "README.md", "readme");
+        write(root, "target/out.md", "built");
+        write(root, "docs/.rknowledgeignore", "drafts/\n!drafts/keep.md\n");
+        write(root, "docs/guide.md", "guide");
+        write(root, "docs/drafts/wip.md", "wip");
+        write(root, "docs/notes.txt", "notes");
+        write(root, "docs/huge.md", &"x".repeat(2048));
+        write(root, "image.png", "png");
+        write(root, "archive/old.md", "old");
+
+        let options = CollectOptions {
+            exclude: vec!["archive".to_string()],
+            max_file_size: Some(1024),
+            ..Default::default()
+        };
+        let collected = collect_documents(root, &options, is_doc).unwrap();
+        assert_eq!(
+            names(&collected.documents, root),
+            vec!["README.md", "docs/guide.md", "docs/notes.txt"]
+        );
+
+        let reason = |rel: &str| {
+            collected
+                .skipped
+                .iter()
+                .find(|(p, _)| p.ends_with(rel))
+                .map(|(_, r)| r.clone())
+                .unwrap()
+        };
+        assert_eq!(
+            reason("target"),
+            SkipReason::Ignored {
+                file: ".gitignore".into(),
+                pattern: "target/".into()
+            }
+        );
+        assert_eq!(reason("docs/drafts").kind(), "ignored");
+        assert!(reason("docs/huge.md").to_string().contains("2.0 KB"));
+        assert_eq!(reason("image.png"), SkipReason::Unsupported);
+        assert_eq!(reason("archive"), SkipReason::Excluded("archive".into()));
+
+        let options = CollectOptions {
+            include: vec!["docs/*.md".to_string()],
+            use_ignore_files: false,
+            ..Default::default()
+        };
+        let collected = collect_documents(root, &options, is_doc).unwrap();
+        assert_eq!(
+            names(&collected.documents, root),
+            vec!["docs/guide.md", "docs/huge.md"]
+        );
+    }
+
+    #[test]
+    fn test_negation_reincludes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".rknowledgeignore", "*.md\n!keep.md\n");
+        write(root, "drop.md", "x");
+        write(root, "keep.md", "x");
+        let collected = collect_documents(root, &CollectOptions::default(), is_doc).unwrap();
+        assert_eq!(names(&collected.documents, root), vec!["keep.md"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loop_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "docs/a.md", "a");
+        std::os::unix::fs::symlink(root.join("docs"), root.join("docs/again")).unwrap();
+        std::os::unix::fs::symlink(root.join("docs"), root.join("alias")).unwrap();
+
+        let collected = collect_documents(root, &CollectOptions::default(), is_doc).unwrap();
+        assert_eq!(collected.documents.len(), 1);
+        let kinds: Vec<&str> = collected.skipped.iter().map(|(_, r)| r.kind()).collect();
+        assert!(kinds.contains(&"symlink loop"));
+        assert!(kinds.contains(&"duplicate"));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("10MB").unwrap(), 10 << 20);
+        assert_eq!(parse_size("1.5k").unwrap(), 1536);
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("5 parsecs").is_err());
+    }
+}
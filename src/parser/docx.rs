@@ -0,0 +1,189 @@
+use anyhow::{Context, Result, anyhow};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::archive::{self, attr};
+use super::blocks::{self, Block, heading_level};
+
+/// Extract text from a Word (.docx) document, keeping headings and list items
+pub fn extract_text(path: &Path) -> Result<String> {
+    let mut archive = archive::open(path)?;
+    let document = archive::read_entry(&mut archive, "word/document.xml")?
+        .ok_or_else(|| anyhow!("{} has no word/document.xml", path.display()))?;
+    let styles = match archive::read_entry(&mut archive, "word/styles.xml")? {
+        Some(xml) => heading_styles(&xml)?,
+        None => HashMap::new(),
+    };
+
+    let blocks = document_blocks(&document, &styles)
+        .with_context(|| format!("Failed to parse DOCX: {}", path.display()))?;
+    Ok(blocks::render(&blocks))
+}
+
+/// Map paragraph style ids to heading levels, from names and outline levels in styles.xml
+fn heading_styles(xml: &str) -> Result<HashMap<String, usize>> {
+    let mut reader = Reader::from_str(xml);
+    let mut levels = HashMap::new();
+    let mut current: Option<String> = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                b"w:style" => current = attr(&e, "w:styleId"),
+                b"w:name" => {
+                    if let (Some(id), Some(level)) = (
+                        &current,
+                        attr(&e, "w:val").as_deref().and_then(heading_level),
+                    ) {
+                        levels.insert(id.clone(), level);
+                    }
+                }
+                b"w:outlineLvl" => {
+                    if let (Some(id), Some(level)) = (&current, outline_level(&e)) {
+                        levels.entry(id.clone()).or_insert(level);
+                    }
+                }
+                _ => {}
+            },
+            Event::End(e) if e.name().as_ref() == b"w:style" => current = None,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(levels)
+}
+
+/// `w:outlineLvl` is zero-based; level 9 means body text
+fn outline_level(e: &quick_xml::events::BytesStart) -> Option<usize> {
+    attr(e, "w:val")?
+        .parse::<usize>()
+        .ok()
+        .filter(|&l| l < 9)
+        .map(|l| l + 1)
+}
+
+#[derive(Default)]
+struct Paragraph {
+    text: String,
+    heading: Option<usize>,
+    list: bool,
+}
+
+/// Walk document.xml and turn each `w:p` into a block
+fn document_blocks(xml: &str, styles: &HashMap<String, usize>) -> Result<Vec<Block>> {
+    let mut reader = Reader::from_str(xml);
+    let mut blocks = Vec::new();
+    // Text boxes nest paragraphs inside paragraphs
+    let mut stack: Vec<Paragraph> = Vec::new();
+    let mut in_props = false;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"w:p" => stack.push(Paragraph::default()),
+                b"w:pPr" => in_props = true,
+                b"w:t" => in_text = true,
+                _ => paragraph_property(&e, in_props, styles, stack.last_mut()),
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"w:p" => {}
+                b"w:tab" if !in_props => push_text(&mut stack, "\t"),
+                b"w:br" | b"w:cr" => push_text(&mut stack, "\n"),
+                _ => paragraph_property(&e, in_props, styles, stack.last_mut()),
+            },
+            Event::End(e) => match e.name().as_ref() {
+                b"w:p" => {
+                    if let Some(p) = stack.pop() {
+                        blocks.push(match (p.heading, p.list) {
+                            (Some(level), _) => Block::Heading(level, p.text),
+                            (None, true) => Block::ListItem(p.text),
+                            (None, false) => Block::Paragraph(p.text),
+                        });
+                    }
+                }
+                b"w:pPr" => in_props = false,
+                b"w:t" => in_text = false,
+                _ => {}
+            },
+            Event::Text(t) if in_text => push_text(&mut stack, &t.unescape()?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(blocks)
+}
+
+fn paragraph_property(
+    e: &quick_xml::events::BytesStart,
+    in_props: bool,
+    styles: &HashMap<String, usize>,
+    paragraph: Option<&mut Paragraph>,
+) {
+    let Some(p) = paragraph else { return };
+    if !in_props {
+        return;
+    }
+    match e.name().as_ref() {
+        b"w:pStyle" => {
+            if let Some(style) = attr(e, "w:val") {
+                p.heading = styles
+                    .get(&style)
+                    .copied()
+                    .or_else(|| heading_level(&style))
+                    .or(p.heading);
+            }
+        }
+        b"w:outlineLvl" => p.heading = outline_level(e).or(p.heading),
+        b"w:numPr" => p.list = true,
+        _ => {}
+    }
+}
+
+fn push_text(stack: &mut [Paragraph], text: &str) {
+    if let Some(p) = stack.last_mut() {
+        p.text.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:body>
+    <w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Kafka Guide</w:t></w:r></w:p>
+    <w:p><w:pPr><w:pStyle w:val="Berschrift2"/><w:tabs><w:tab w:val="left" w:pos="720"/></w:tabs></w:pPr><w:r><w:t>Brokers</w:t></w:r></w:p>
+    <w:p>
+      <w:r><w:rPr><w:b/></w:rPr><w:t xml:space="preserve">Brokers store </w:t></w:r>
+      <w:r><w:rPr><w:i/></w:rPr><w:t>partitions</w:t></w:r>
+      <w:r><w:t xml:space="preserve"> &amp; replicas.</w:t></w:r>
+      <w:r><w:delText>removed</w:delText></w:r>
+    </w:p>
+    <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Leader</w:t></w:r></w:p>
+    <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Follower</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#;
+
+    const STYLES: &str = r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:style w:type="paragraph" w:styleId="Berschrift2"><w:name w:val="heading 2"/></w:style>
+  <w:style w:type="paragraph" w:styleId="Normal"><w:name w:val="Normal"/></w:style>
+</w:styles>"#;
+
+    #[test]
+    fn test_docx_structure() {
+        let styles = heading_styles(STYLES).unwrap();
+        assert_eq!(styles.get("Berschrift2"), Some(&2));
+
+        let text = blocks::render(&document_blocks(DOCUMENT, &styles).unwrap());
+        assert_eq!(
+            text,
+            "# Kafka Guide\n\n## Brokers\n\nBrokers store partitions & replicas.\n\n- Leader\n- Follower"
+        );
+    }
+}
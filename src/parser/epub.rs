@@ -0,0 +1,224 @@
+use anyhow::{Context, Result, anyhow};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::archive::{self, attr};
+use super::blocks::{self, Block};
+
+/// Extract text from an EPUB e-book, chapter by chapter in reading order
+pub fn extract_text(path: &Path) -> Result<String> {
+    let mut archive = archive::open(path)?;
+    let container = archive::read_entry(&mut archive, "META-INF/container.xml")?
+        .ok_or_else(|| anyhow!("{} has no META-INF/container.xml", path.display()))?;
+    let opf_path = rootfile(&container)?
+        .ok_or_else(|| anyhow!("{} does not name a package document", path.display()))?;
+    let opf = archive::read_entry(&mut archive, &opf_path)?
+        .ok_or_else(|| anyhow!("{} is missing {}", path.display(), opf_path))?;
+
+    let mut blocks = Vec::new();
+    for chapter in
+        spine(&opf).with_context(|| format!("Failed to parse EPUB package: {}", path.display()))?
+    {
+        let entry = resolve_href(&opf_path, &chapter);
+        if let Some(xhtml) = archive::read_entry(&mut archive, &entry)? {
+            html_blocks(&xhtml, &mut blocks);
+        }
+    }
+
+    Ok(blocks::render(&blocks))
+}
+
+/// Path of the OPF package document named by META-INF/container.xml
+fn rootfile(xml: &str) -> Result<Option<String>> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"rootfile" => {
+                return Ok(attr(&e, "full-path"));
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Hrefs of the spine items, in reading order
+fn spine(opf: &str) -> Result<Vec<String>> {
+    let mut reader = Reader::from_str(opf);
+    let mut manifest: HashMap<String, String> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                b"item" => {
+                    if let (Some(id), Some(href)) = (attr(&e, "id"), attr(&e, "href")) {
+                        manifest.insert(id, href);
+                    }
+                }
+                b"itemref" => order.extend(attr(&e, "idref")),
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(order
+        .into_iter()
+        .filter_map(|id| manifest.get(&id).cloned())
+        .collect())
+}
+
+/// Resolve a manifest href against the directory of the package document
+fn resolve_href(opf_path: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let mut parts: Vec<&str> = opf_path.split('/').collect();
+    parts.pop();
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            s => parts.push(s),
+        }
+    }
+    percent_decode(&parts.join("/"))
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+        {
+            out.push(byte);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Append the blocks of one XHTML chapter
+fn html_blocks(html: &str, blocks: &mut Vec<Block>) {
+    let document = Html::parse_document(html);
+    let root = Selector::parse("body")
+        .ok()
+        .and_then(|s| document.select(&s).next())
+        .unwrap_or_else(|| document.root_element());
+
+    let mut inline = String::new();
+    walk(root, &mut inline, blocks);
+    flush(&mut inline, blocks);
+}
+
+fn walk(element: ElementRef, inline: &mut String, blocks: &mut Vec<Block>) {
+    for node in element.children() {
+        if let Some(text) = node.value().as_text() {
+            inline.push_str(text);
+            continue;
+        }
+        let Some(child) = ElementRef::wrap(node) else {
+            continue;
+        };
+
+        match child.value().name() {
+            "script" | "style" | "head" => {}
+            "br" => inline.push('\n'),
+            name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+                flush(inline, blocks);
+                let level = name[1..].parse().unwrap_or(1);
+                blocks.push(Block::Heading(level, child.text().collect()));
+            }
+            "li" => {
+                flush(inline, blocks);
+                blocks.push(Block::ListItem(child.text().collect()));
+            }
+            "p" | "pre" | "dt" | "dd" | "figcaption" | "caption" => {
+                flush(inline, blocks);
+                blocks.push(Block::Paragraph(child.text().collect()));
+            }
+            "div" | "section" | "article" | "main" | "aside" | "header" | "footer" | "nav"
+            | "blockquote" | "figure" | "ul" | "ol" | "dl" | "table" | "thead" | "tbody" | "tr"
+            | "td" | "th" => {
+                flush(inline, blocks);
+                walk(child, inline, blocks);
+                flush(inline, blocks);
+            }
+            _ => walk(child, inline, blocks),
+        }
+    }
+}
+
+fn flush(inline: &mut String, blocks: &mut Vec<Block>) {
+    if !inline.trim().is_empty() {
+        blocks.push(Block::Paragraph(std::mem::take(inline)));
+    }
+    inline.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spine_order_and_paths() {
+        let container = r#"<container xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+        assert_eq!(
+            rootfile(container).unwrap().as_deref(),
+            Some("OEBPS/content.opf")
+        );
+
+        let opf = r#"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <manifest>
+    <item id="c2" href="text/chapter%202.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c1" href="text/chapter1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="css" href="style.css" media-type="text/css"/>
+  </manifest>
+  <spine><itemref idref="c1"/><itemref idref="c2"/></spine>
+</package>"#;
+        let chapters = spine(opf).unwrap();
+        assert_eq!(
+            chapters,
+            vec!["text/chapter1.xhtml", "text/chapter%202.xhtml"]
+        );
+        assert_eq!(
+            resolve_href("OEBPS/content.opf", &chapters[1]),
+            "OEBPS/text/chapter 2.xhtml"
+        );
+        assert_eq!(resolve_href("content.opf", "../x.xhtml#top"), "x.xhtml");
+    }
+
+    #[test]
+    fn test_chapter_blocks() {
+        let xhtml = r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml"><head><title>Ch 1</title><style>p{color:red}</style></head>
+<body>
+  <section>
+    <h1>Chapter <em>One</em></h1>
+    <p>It was a <b>dark</b>&nbsp;night.</p>
+    <div>Loose text in a div</div>
+    <ul><li>first</li><li>second</li></ul>
+  </section>
+</body></html>"#;
+        let mut blocks = Vec::new();
+        html_blocks(xhtml, &mut blocks);
+        assert_eq!(
+            blocks::render(&blocks),
+            "# Chapter One\n\nIt was a dark night.\n\nLoose text in a div\n\n- first\n- second"
+        );
+    }
+}
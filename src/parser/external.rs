@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use wait_timeout::ChildExt;
+
+use crate::config::ExternalParserConfig;
+
+/// A file type handled by an external command, configured under `[parsers]`
+#[derive(Debug, Clone)]
+pub struct ExternalParser {
+    argv: Vec<String>,
+    timeout: Duration,
+}
+
+impl ExternalParser {
+    /// Split the command line; `{path}` marks where the file goes, or it is appended
+    pub fn new(command: &str, timeout: Duration) -> Result<Self> {
+        let mut argv = shlex::split(command)
+            .with_context(|| format!("Unbalanced quotes in parser command: {}", command))?;
+        if argv.is_empty() {
+            anyhow::bail!("Parser command is empty");
+        }
+        if !argv.iter().any(|arg| arg.contains("{path}")) {
+            argv.push("{path}".to_string());
+        }
+        Ok(Self { argv, timeout })
+    }
+
+    /// Build the parsers from config, keyed by lowercase extension without the dot
+    pub fn from_config(
+        parsers: &HashMap<String, ExternalParserConfig>,
+    ) -> Result<HashMap<String, Self>> {
+        parsers
+            .iter()
+            .map(|(extension, config)| {
+                let parser =
+                    Self::new(config.command(), Duration::from_secs(config.timeout_secs()))
+                        .with_context(|| format!("Invalid [parsers] entry for '{}'", extension))?;
+                Ok((extension.trim_start_matches('.').to_lowercase(), parser))
+            })
+            .collect()
+    }
+
+    /// Run the command on `path` and return its stdout
+    pub fn extract_text(&self, path: &Path) -> Result<String> {
+        let path_str = path.to_string_lossy();
+        let args: Vec<String> = self
+            .argv
+            .iter()
+            .map(|arg| arg.replace("{path}", &path_str))
+            .collect();
+
+        let mut child = Command::new(&args[0])
+            .args(&args[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run '{}'", args[0]))?;
+
+        // Drain both pipes on their own threads so a chatty command cannot block on a full pipe
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+
+        let status = match child.wait_timeout(self.timeout)? {
+            Some(status) => status,
+            None => {
+                child.kill().ok();
+                child.wait().ok();
+                anyhow::bail!(
+                    "'{}' timed out after {}s",
+                    args[0],
+                    self.timeout.as_secs_f64()
+                );
+            }
+        };
+
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        if !status.success() {
+            let reason = String::from_utf8_lossy(&stderr);
+            let reason = reason.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+            anyhow::bail!("'{}' exited with {}: {}", args[0], status, reason.trim());
+        }
+
+        let text = String::from_utf8_lossy(&stdout).trim().to_string();
+        if text.is_empty() {
+            anyhow::bail!("'{}' produced no output", args[0]);
+        }
+        Ok(text)
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buf).ok();
+        }
+        buf
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_output_becomes_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes with space.org");
+        std::fs::write(&path, "* Heading\nTokio is a runtime.\n").unwrap();
+
+        let parser = ExternalParser::new("cat", Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            parser.extract_text(&path).unwrap(),
+            "* Heading\nTokio is a runtime."
+        );
+
+        let parser =
+            ExternalParser::new("sh -c 'tr a-z A-Z < \"$0\"' {path}", Duration::from_secs(5))
+                .unwrap();
+        assert!(parser.extract_text(&path).unwrap().starts_with("* HEADING"));
+    }
+
+    #[test]
+    fn test_failures_are_reported() {
+        let path = Path::new("missing.org");
+        let err = ExternalParser::new("sh -c 'echo boom >&2; exit 3'", Duration::from_secs(5))
+            .unwrap()
+            .extract_text(path)
+            .unwrap_err();
+        assert!(err.to_string().contains("boom"), "{}", err);
+
+        let err = ExternalParser::new("sh -c 'sleep 5'", Duration::from_millis(200))
+            .unwrap()
+            .extract_text(path)
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{}", err);
+
+        assert!(ExternalParser::new("pandoc 'unbalanced", Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn test_from_config_normalizes_extensions() {
+        let mut config = HashMap::new();
+        config.insert(
+            ".ORG".to_string(),
+            ExternalParserConfig::Command("pandoc -t plain {path}".to_string()),
+        );
+        let parsers = ExternalParser::from_config(&config).unwrap();
+        assert!(parsers.contains_key("org"));
+    }
+}
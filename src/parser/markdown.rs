@@ -23,18 +23,19 @@ fn markdown_to_text(markdown: &str) -> String {
             Event::SoftBreak | Event::HardBreak => {
                 text.push('\n');
             }
-            Event::Start(Tag::Paragraph) => {
-                if !text.is_empty() && !text.ends_with('\n') {
-                    text.push('\n');
-                }
+            Event::Start(Tag::Paragraph) if !text.is_empty() && !text.ends_with('\n') => {
+                text.push('\n');
             }
             Event::End(TagEnd::Paragraph) => {
                 text.push_str("\n\n");
             }
-            Event::Start(Tag::Heading { .. }) => {
+            Event::Start(Tag::Heading { level, .. }) => {
                 if !text.is_empty() && !text.ends_with('\n') {
                     text.push('\n');
                 }
+                // Keep the level so chunks can carry their heading breadcrumb
+                text.push_str(&"#".repeat(level as usize));
+                text.push(' ');
             }
             Event::End(TagEnd::Heading(_)) => {
                 text.push_str("\n\n");
@@ -76,7 +77,7 @@ mod tests {
         let md = "# Hello\n\nThis is a **test** with `code`.\n\n- Item 1\n- Item 2";
         let text = markdown_to_text(md);
 
-        assert!(text.contains("Hello"));
+        assert!(text.contains("# Hello\n"));
         assert!(text.contains("test"));
         assert!(text.contains("code"));
         assert!(text.contains("Item 1"));
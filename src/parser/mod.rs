@@ -1,70 +1,129 @@
 mod adaptive_chunker;
+mod archive;
+mod blocks;
 mod chunker;
+mod code;
+mod collect;
+mod docx;
+mod epub;
+mod external;
 mod html;
 mod markdown;
+mod odt;
 mod pdf;
+mod rtf;
+mod semantic;
 mod text;
+mod tokenizer;
 
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
-use uuid::Uuid;
-
-pub use adaptive_chunker::{AdaptiveChunker, Chunk, ModelContextLimits};
-pub use chunker::TextChunker;
-
-/// A document chunk with metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Document {
-    pub id: String,
-    pub text: String,
-    pub source: String,
-    pub chunk_index: usize,
+
+pub use adaptive_chunker::{AdaptiveChunker, Chunk, ModelProfile, ModelRegistry};
+pub use chunker::{ChunkPlan, Chunker};
+pub use code::{CodeChunker, Language};
+pub use collect::{CollectOptions, collect_documents, parse_size};
+pub use external::ExternalParser;
+pub use pdf::{PageTracker, cite_pages};
+pub use semantic::Embedder;
+#[cfg(feature = "local")]
+pub(crate) use tokenizer::BPE_PATTERN;
+pub use tokenizer::Tokenizer;
+
+/// Document file extensions (lowercase) that [`DocumentParser::extract`] understands
+pub const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "pdf", "txt", "md", "markdown", "html", "htm", "docx", "odt", "epub", "rtf",
+];
+
+/// Source code file extensions (lowercase), chunked at declaration boundaries
+pub use code::EXTENSIONS as CODE_EXTENSIONS;
+
+/// Whether a file has an extension the parser understands (case-insensitive)
+pub fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SUPPORTED_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        || Language::from_path(path).is_some()
 }
 
 /// Parser for various document types
+#[derive(Default)]
 pub struct DocumentParser {
-    chunker: TextChunker,
+    /// External commands by extension; these take precedence over built-in parsers
+    external: HashMap<String, ExternalParser>,
 }
 
 impl DocumentParser {
-    pub fn new(chunk_size: usize, chunk_overlap: usize) -> Self {
-        Self {
-            chunker: TextChunker::new(chunk_size, chunk_overlap),
-        }
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hand the given extensions to external commands
+    pub fn with_external_parsers(mut self, external: HashMap<String, ExternalParser>) -> Self {
+        self.external = external;
+        self
     }
 
-    /// Parse a document and return chunks
-    pub fn parse(&self, path: &Path) -> Result<Vec<Document>> {
+    /// Whether a file is handled by a built-in or external parser
+    pub fn supports(&self, path: &Path) -> bool {
+        is_supported(path) || self.external_for(path).is_some()
+    }
+
+    fn external_for(&self, path: &Path) -> Option<&ExternalParser> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        self.external.get(&extension)
+    }
+
+    /// Extract the whole text of a document, plus notes about content that could not be read
+    ///
+    /// PDF pages are kept apart by `[page N]` marker lines, so chunks can be
+    /// traced back to their pages with [`PageTracker`].
+    pub fn extract(&self, path: &Path) -> Result<(String, Vec<String>)> {
         let extension = path
             .extension()
             .and_then(|e| e.to_str())
             .unwrap_or("")
             .to_lowercase();
 
+        if let Some(external) = self.external_for(path) {
+            return Ok((external.extract_text(path)?, Vec::new()));
+        }
+
+        if Language::from_path(path).is_some() {
+            return Ok((code::extract_text(path)?, Vec::new()));
+        }
+
+        let mut notes = Vec::new();
         let text = match extension.as_str() {
-            "pdf" => pdf::extract_text(path)?,
+            "pdf" => {
+                let pdf = pdf::extract_pages(path)?;
+                if !pdf.without_text.is_empty() {
+                    notes.push(format!(
+                        "{}: no text layer on page{} {} (scanned? run OCR to include {})",
+                        path.display(),
+                        if pdf.without_text.len() == 1 { "" } else { "s" },
+                        pdf::page_ranges(&pdf.without_text),
+                        if pdf.without_text.len() == 1 {
+                            "it"
+                        } else {
+                            "them"
+                        },
+                    ));
+                }
+                pdf.to_text()
+            }
             "txt" => text::extract_text(path)?,
             "md" | "markdown" => markdown::extract_text(path)?,
             "html" | "htm" => html::extract_text(path)?,
+            "docx" => docx::extract_text(path)?,
+            "odt" => odt::extract_text(path)?,
+            "epub" => epub::extract_text(path)?,
+            "rtf" => rtf::extract_text(path)?,
             _ => anyhow::bail!("Unsupported file type: {}", extension),
         };
 
-        let source = path.to_string_lossy().to_string();
-        let chunks = self.chunker.split(&text);
-
-        let documents: Vec<Document> = chunks
-            .into_iter()
-            .enumerate()
-            .map(|(i, chunk)| Document {
-                id: Uuid::new_v4().to_string().replace("-", ""),
-                text: chunk,
-                source: source.clone(),
-                chunk_index: i,
-            })
-            .collect();
-
-        Ok(documents)
+        Ok((text, notes))
     }
 }
 
@@ -73,9 +132,58 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_document_parser() {
-        let parser = DocumentParser::new(100, 10);
-        // Basic test that parser can be created
-        assert!(parser.chunker.chunk_size == 100);
+    fn test_is_supported() {
+        assert!(is_supported(Path::new("notes/Report.DOCX")));
+        assert!(is_supported(Path::new("book.epub")));
+        assert!(is_supported(Path::new("letter.rtf")));
+        assert!(!is_supported(Path::new("image.png")));
+        assert!(!is_supported(Path::new("Makefile")));
+        assert!(is_supported(Path::new("src/lib.rs")));
+        assert!(is_supported(Path::new("App.Java")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_parser_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.org");
+        std::fs::write(&path, "Tokio is an async runtime.").unwrap();
+
+        let parser = DocumentParser::new();
+        assert!(!parser.supports(&path));
+
+        let mut external = HashMap::new();
+        external.insert(
+            "org".to_string(),
+            ExternalParser::new("cat {path}", std::time::Duration::from_secs(5)).unwrap(),
+        );
+        let parser = parser.with_external_parsers(external);
+        assert!(parser.supports(Path::new("Todo.ORG")));
+        let (text, _) = parser.extract(&path).unwrap();
+        assert_eq!(text, "Tokio is an async runtime.");
+    }
+
+    #[test]
+    fn test_parse_docx_container() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.docx");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        zip.start_file("word/document.xml", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(
+            br#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Rust</w:t></w:r></w:p>
+<w:p><w:r><w:t>Tokio is an async runtime.</w:t></w:r></w:p>
+</w:body></w:document>"#,
+        )
+        .unwrap();
+        zip.finish().unwrap();
+
+        let (text, notes) = DocumentParser::new().extract(&path).unwrap();
+        assert!(notes.is_empty());
+        assert_eq!(text, "# Rust\n\nTokio is an async runtime.");
     }
 }
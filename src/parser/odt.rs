@@ -0,0 +1,124 @@
+use anyhow::{Context, Result, anyhow};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use std::path::Path;
+
+use super::archive::{self, attr};
+use super::blocks::{self, Block};
+
+/// Extract text from an OpenDocument text (.odt) file, keeping headings and list items
+pub fn extract_text(path: &Path) -> Result<String> {
+    let mut archive = archive::open(path)?;
+    let content = archive::read_entry(&mut archive, "content.xml")?
+        .ok_or_else(|| anyhow!("{} has no content.xml", path.display()))?;
+
+    let blocks = content_blocks(&content)
+        .with_context(|| format!("Failed to parse ODT: {}", path.display()))?;
+    Ok(blocks::render(&blocks))
+}
+
+struct Paragraph {
+    text: String,
+    heading: Option<usize>,
+    list: bool,
+}
+
+/// Walk content.xml and turn each `text:h` / `text:p` into a block
+fn content_blocks(xml: &str) -> Result<Vec<Block>> {
+    let mut reader = Reader::from_str(xml);
+    let mut blocks = Vec::new();
+    let mut stack: Vec<Paragraph> = Vec::new();
+    let mut list_depth = 0usize;
+    // Annotations and footnote bodies are not part of the running text
+    let mut skip_depth = 0usize;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.name().as_ref() {
+                _ if skip_depth > 0 => skip_depth += 1,
+                b"office:annotation" | b"text:note" | b"text:tracked-changes" => skip_depth = 1,
+                b"text:list-item" => list_depth += 1,
+                b"text:h" => stack.push(Paragraph {
+                    text: String::new(),
+                    heading: Some(
+                        attr(&e, "text:outline-level")
+                            .and_then(|l| l.parse().ok())
+                            .unwrap_or(1),
+                    ),
+                    list: false,
+                }),
+                b"text:p" => stack.push(Paragraph {
+                    text: String::new(),
+                    heading: None,
+                    list: list_depth > 0,
+                }),
+                _ => {}
+            },
+            Event::Empty(e) if skip_depth == 0 => match e.name().as_ref() {
+                b"text:s" => {
+                    let count = attr(&e, "text:c").and_then(|c| c.parse().ok()).unwrap_or(1);
+                    push_text(&mut stack, &" ".repeat(count));
+                }
+                b"text:tab" => push_text(&mut stack, "\t"),
+                b"text:line-break" => push_text(&mut stack, "\n"),
+                _ => {}
+            },
+            Event::End(e) => match e.name().as_ref() {
+                _ if skip_depth > 0 => skip_depth -= 1,
+                b"text:list-item" => list_depth = list_depth.saturating_sub(1),
+                b"text:h" | b"text:p" => {
+                    if let Some(p) = stack.pop() {
+                        blocks.push(match (p.heading, p.list) {
+                            (Some(level), _) => Block::Heading(level, p.text),
+                            (None, true) => Block::ListItem(p.text),
+                            (None, false) => Block::Paragraph(p.text),
+                        });
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(t) if skip_depth == 0 => push_text(&mut stack, &t.unescape()?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(blocks)
+}
+
+fn push_text(stack: &mut [Paragraph], text: &str) {
+    if let Some(p) = stack.last_mut() {
+        p.text.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0">
+  <office:automatic-styles/>
+  <office:body>
+    <office:text>
+      <text:h text:style-name="H1" text:outline-level="1">Graph <text:span text:style-name="T1">Theory</text:span></text:h>
+      <text:p text:style-name="P1">Nodes<text:s text:c="3"/>and edges.<office:annotation><text:p>reviewer note</text:p></office:annotation></text:p>
+      <text:h text:outline-level="2">Paths</text:h>
+      <text:list>
+        <text:list-item><text:p>Dijkstra</text:p></text:list-item>
+        <text:list-item><text:p>Yen &amp; friends</text:p></text:list-item>
+      </text:list>
+      <text:p>Line one<text:line-break/>line two</text:p>
+    </office:text>
+  </office:body>
+</office:document-content>"#;
+
+    #[test]
+    fn test_odt_structure() {
+        let text = blocks::render(&content_blocks(CONTENT).unwrap());
+        assert_eq!(
+            text,
+            "# Graph Theory\n\nNodes and edges.\n\n## Paths\n\n- Dijkstra\n- Yen & friends\n\nLine one\nline two"
+        );
+    }
+}
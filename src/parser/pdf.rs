@@ -1,18 +1,485 @@
 use anyhow::{Context, Result};
+use pdf_extract::{MediaBox, OutputDev, OutputError, Transform};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::LazyLock;
 
-/// Extract text from a PDF file
-pub fn extract_text(path: &Path) -> Result<String> {
+/// Line that opens each page in the extracted text, e.g. `[page 12]`
+static PAGE_MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\[page (\d+)\]$").expect("valid page marker regex"));
+
+/// Running headers and footers that are only a page number ("12", "Page 3 of 40", "- iv -")
+static PAGE_NUMBER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[-–—\s]*(page\s+)?([#]+|[ivxlc]+)(\s*(of|/)\s*#+)?[-–—\s]*$")
+        .expect("valid page number regex")
+});
+
+/// Text of one PDF page after layout analysis
+#[derive(Debug, Clone)]
+pub struct PdfPage {
+    pub number: u32,
+    pub text: String,
+}
+
+/// A PDF split into pages, with the pages that carried no text at all
+#[derive(Debug, Clone, Default)]
+pub struct PdfText {
+    pub pages: Vec<PdfPage>,
+    /// Pages without a text layer, usually scans that need OCR
+    pub without_text: Vec<u32>,
+}
+
+impl PdfText {
+    /// Join the pages, each opened by a `[page N]` marker line
+    pub fn to_text(&self) -> String {
+        self.pages
+            .iter()
+            .map(|p| format!("{}\n{}", page_marker(p.number), p.text))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Extract the pages of a PDF file with headers and footers removed and tables flattened to rows
+pub fn extract_pages(path: &Path) -> Result<PdfText> {
     let bytes = std::fs::read(path)
         .with_context(|| format!("Failed to read PDF file: {}", path.display()))?;
 
-    let text = pdf_extract::extract_text_from_mem(&bytes)
+    let glyphs = collect_glyphs(&bytes)
         .with_context(|| format!("Failed to extract text from PDF: {}", path.display()))?;
 
-    // Clean up the extracted text
-    let cleaned = clean_pdf_text(&text);
+    Ok(layout_document(glyphs))
+}
+
+fn collect_glyphs(bytes: &[u8]) -> Result<Vec<(u32, Vec<Glyph>)>, OutputError> {
+    let mut doc = pdf_extract::Document::load_mem(bytes)?;
+    if doc.is_encrypted() {
+        doc.decrypt("")?;
+    }
+    let mut collector = GlyphCollector::default();
+    pdf_extract::output_doc(&doc, &mut collector)?;
+    Ok(collector.pages)
+}
+
+/// The marker line that opens page `number` in extracted text
+pub fn page_marker(number: u32) -> String {
+    format!("[page {}]", number)
+}
+
+/// Whether `line` (surrounding whitespace aside) is a page marker line
+pub fn is_page_marker(line: &str) -> bool {
+    let line = line.trim();
+    PAGE_MARKER
+        .find(line)
+        .is_some_and(|m| m.len() == line.len())
+}
+
+/// Chunk provenance for a paged document: `file.pdf p.12` or `file.pdf p.12-13`
+pub fn cite_pages(source: &str, pages: &[u32]) -> String {
+    match (pages.iter().min(), pages.iter().max()) {
+        (Some(first), Some(last)) if first == last => format!("{} p.{}", source, first),
+        (Some(first), Some(last)) => format!("{} p.{}-{}", source, first, last),
+        _ => source.to_string(),
+    }
+}
+
+/// Follows `[page N]` markers through the consecutive chunks of one document
+#[derive(Debug, Default)]
+pub struct PageTracker {
+    current: Option<u32>,
+}
+
+impl PageTracker {
+    /// Pages the next chunk spans; empty when the document has no page markers
+    pub fn pages(&mut self, chunk: &str) -> Vec<u32> {
+        let mut pages = Vec::new();
+        let starts_with_marker = PAGE_MARKER
+            .find(chunk.trim_start())
+            .is_some_and(|m| m.start() == 0);
+        if !starts_with_marker && let Some(page) = self.current {
+            pages.push(page);
+        }
+        for caps in PAGE_MARKER.captures_iter(chunk) {
+            if let Ok(page) = caps[1].parse() {
+                if !pages.contains(&page) {
+                    pages.push(page);
+                }
+                self.current = Some(page);
+            }
+        }
+        pages
+    }
+}
+
+/// Collapse sorted page numbers into ranges: `3-5, 9`
+pub fn page_ranges(pages: &[u32]) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &page in pages {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == page => *end = page,
+            _ => ranges.push((page, page)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// One character placed on the page, in top-down coordinates
+#[derive(Debug, Clone)]
+struct Glyph {
+    x: f64,
+    y: f64,
+    end: f64,
+    size: f64,
+    text: String,
+}
+
+/// Records every character with its position instead of streaming plain text
+#[derive(Default)]
+struct GlyphCollector {
+    pages: Vec<(u32, Vec<Glyph>)>,
+    page_height: f64,
+}
+
+impl OutputDev for GlyphCollector {
+    fn begin_page(
+        &mut self,
+        page_num: u32,
+        media_box: &MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        self.page_height = media_box.ury - media_box.lly;
+        self.pages.push((page_num, Vec::new()));
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        char: &str,
+    ) -> Result<(), OutputError> {
+        if char.trim().is_empty() {
+            return Ok(());
+        }
+        // Same size estimate as pdf-extract's plain text output
+        let sx = font_size * (trm.m11 + trm.m21);
+        let sy = font_size * (trm.m12 + trm.m22);
+        let size = (sx * sy).abs().sqrt().max(1.0);
+        let x = trm.m31;
+        let y = self.page_height - trm.m32;
+        if let Some((_, glyphs)) = self.pages.last_mut() {
+            glyphs.push(Glyph {
+                x,
+                y,
+                end: x + width * size,
+                size,
+                text: char.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+/// A run of text on one line, separated from its neighbours by a wide gap
+#[derive(Debug, Clone)]
+struct Segment {
+    x: f64,
+    text: String,
+}
+
+/// A visual line: segments sorted left to right
+#[derive(Debug, Clone)]
+struct Line {
+    y: f64,
+    size: f64,
+    segments: Vec<Segment>,
+}
+
+impl Line {
+    fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn layout_document(pages: Vec<(u32, Vec<Glyph>)>) -> PdfText {
+    let mut without_text = Vec::new();
+    let mut laid_out = Vec::new();
+    for (number, glyphs) in pages {
+        if glyphs.is_empty() {
+            without_text.push(number);
+        } else {
+            laid_out.push((number, layout_page(group_lines(glyphs))));
+        }
+    }
+
+    strip_running_lines(&mut laid_out);
+
+    PdfText {
+        pages: laid_out
+            .into_iter()
+            .map(|(number, lines)| PdfPage {
+                number,
+                text: join_lines(&lines),
+            })
+            .collect(),
+        without_text,
+    }
+}
+
+/// Group glyphs into lines by baseline, then split each line at wide gaps
+fn group_lines(mut glyphs: Vec<Glyph>) -> Vec<Line> {
+    glyphs.sort_by(|a, b| a.y.total_cmp(&b.y));
+
+    let mut rows: Vec<Vec<Glyph>> = Vec::new();
+    for glyph in glyphs {
+        match rows.last_mut() {
+            Some(row) if (glyph.y - row[0].y).abs() <= row[0].size * 0.5 => row.push(glyph),
+            _ => rows.push(vec![glyph]),
+        }
+    }
 
-    Ok(cleaned)
+    rows.into_iter()
+        .map(|mut row| {
+            row.sort_by(|a, b| a.x.total_cmp(&b.x));
+            let size = row.iter().map(|g| g.size).fold(0.0, f64::max);
+            let mut segments: Vec<Segment> = Vec::new();
+            let mut last_end = f64::NEG_INFINITY;
+            for glyph in &row {
+                let gap = glyph.x - last_end;
+                match segments.last_mut() {
+                    Some(segment) if gap < size * 1.5 => {
+                        if gap > size * 0.15 {
+                            segment.text.push(' ');
+                        }
+                        segment.text.push_str(&glyph.text);
+                    }
+                    _ => segments.push(Segment {
+                        x: glyph.x,
+                        text: glyph.text.clone(),
+                    }),
+                }
+                last_end = last_end.max(glyph.end);
+            }
+            for segment in &mut segments {
+                segment.text = clean_pdf_text(&segment.text);
+            }
+            Line {
+                y: row[0].y,
+                size,
+                segments,
+            }
+        })
+        .collect()
+}
+
+/// Turn lines into output lines: prose is kept, tables become one line per row,
+/// and two-column layouts are read column by column. Empty strings mark paragraph breaks.
+fn layout_page(lines: Vec<Line>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        // Paragraph break on a vertical gap wider than a blank line
+        if i > 0 {
+            let gap = lines[i].y - lines[i - 1].y;
+            if gap > lines[i].size.max(lines[i - 1].size) * 1.8 {
+                push_break(&mut out);
+            }
+        }
+
+        let run_end = lines[i..]
+            .iter()
+            .position(|l| l.segments.len() < 2)
+            .map_or(lines.len(), |p| i + p);
+        if run_end - i >= 2 {
+            let run = &lines[i..run_end];
+            push_break(&mut out);
+            if is_two_column(run) {
+                out.extend(run.iter().map(|l| l.segments[0].text.clone()));
+                push_break(&mut out);
+                out.extend(
+                    run.iter()
+                        .flat_map(|l| l.segments[1..].iter().map(|s| s.text.clone())),
+                );
+            } else {
+                out.extend(table_rows(run));
+            }
+            push_break(&mut out);
+            i = run_end;
+            continue;
+        }
+
+        let text = lines[i].text();
+        // Re-join words hyphenated across a line break
+        match out.last_mut() {
+            Some(prev)
+                if prev.ends_with('-')
+                    && text.starts_with(|c: char| c.is_lowercase())
+                    && !prev.contains(" | ") =>
+            {
+                prev.pop();
+                prev.push_str(&text);
+            }
+            _ => out.push(text),
+        }
+        i += 1;
+    }
+
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    while out.first().is_some_and(|l| l.is_empty()) {
+        out.remove(0);
+    }
+    out
+}
+
+fn push_break(out: &mut Vec<String>) {
+    if out.last().is_some_and(|l| !l.is_empty()) {
+        out.push(String::new());
+    }
+}
+
+/// Long runs of two wide text segments are columns of prose, not a table
+fn is_two_column(run: &[Line]) -> bool {
+    run.len() >= 4
+        && run.iter().all(|l| l.segments.len() == 2)
+        && run
+            .iter()
+            .map(|l| l.segments.iter().map(|s| s.text.len()).sum::<usize>())
+            .sum::<usize>()
+            / (run.len() * 2)
+            >= 30
+}
+
+/// Render table rows as text, labelling each cell with its column header
+fn table_rows(run: &[Line]) -> Vec<String> {
+    let header: Vec<&str> = run[0].segments.iter().map(|s| s.text.as_str()).collect();
+    let columns: Vec<f64> = run[0].segments.iter().map(|s| s.x).collect();
+
+    let mut rows = vec![header.join(" | ")];
+    for line in &run[1..] {
+        // Put each cell under the header column it starts nearest to
+        let mut cells: Vec<Vec<&str>> = vec![Vec::new(); columns.len()];
+        for segment in &line.segments {
+            let column = columns
+                .iter()
+                .enumerate()
+                .min_by(|a, b| (a.1 - segment.x).abs().total_cmp(&(b.1 - segment.x).abs()))
+                .map_or(0, |(i, _)| i);
+            cells[column].push(&segment.text);
+        }
+        let row = cells
+            .iter()
+            .zip(&header)
+            .filter(|(cell, _)| !cell.is_empty())
+            .map(|(cell, name)| format!("{}: {}", name, cell.join(" ")))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        rows.push(row);
+    }
+    rows
+}
+
+/// Drop headers and footers: page numbers at the top or bottom of a page, and
+/// lines that repeat at the same edge on most pages
+fn strip_running_lines(pages: &mut [(u32, Vec<String>)]) {
+    const EDGE: usize = 2;
+
+    let edges = |lines: &[String]| -> Vec<usize> {
+        let content: Vec<usize> = (0..lines.len()).filter(|&i| !lines[i].is_empty()).collect();
+        let mut idx: Vec<usize> = content.iter().take(EDGE).copied().collect();
+        idx.extend(content.iter().rev().take(EDGE).copied());
+        idx.sort_unstable();
+        idx.dedup();
+        idx
+    };
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (_, lines) in pages.iter() {
+        let unique: HashSet<String> = edges(lines).iter().map(|&i| normalize(&lines[i])).collect();
+        for key in unique {
+            *seen.entry(key).or_default() += 1;
+        }
+    }
+
+    let total = pages.len();
+    for (_, lines) in pages.iter_mut() {
+        let drop: Vec<usize> = edges(lines)
+            .into_iter()
+            .filter(|&i| {
+                let key = normalize(&lines[i]);
+                PAGE_NUMBER.is_match(&key) || (total >= 3 && seen[&key] * 2 > total)
+            })
+            .collect();
+        for i in drop.into_iter().rev() {
+            lines.remove(i);
+        }
+        while lines.first().is_some_and(|l| l.is_empty()) {
+            lines.remove(0);
+        }
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+    }
+}
+
+/// Lowercase, digits folded to `#`, so "Page 3" and "Page 4" compare equal
+fn normalize(line: &str) -> String {
+    line.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_digit() { '#' } else { c })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn join_lines(lines: &[String]) -> String {
+    let mut text = String::new();
+    for line in lines {
+        if line.is_empty() {
+            text.push('\n');
+        } else {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(line);
+        }
+    }
+    text
 }
 
 /// Clean up extracted PDF text
@@ -35,10 +502,232 @@ fn clean_pdf_text(text: &str) -> String {
 mod tests {
     use super::*;
 
+    fn line(y: f64, segments: &[(f64, &str)]) -> Line {
+        Line {
+            y,
+            size: 10.0,
+            segments: segments
+                .iter()
+                .map(|&(x, text)| Segment {
+                    x,
+                    text: text.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn word(x: f64, y: f64, text: &str) -> Vec<Glyph> {
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| Glyph {
+                x: x + i as f64 * 5.0,
+                y,
+                end: x + (i + 1) as f64 * 5.0,
+                size: 10.0,
+                text: c.to_string(),
+            })
+            .collect()
+    }
+
     #[test]
     fn test_clean_pdf_text() {
         let dirty = "  Hello  \n\n\n  World  \n  ";
         let clean = clean_pdf_text(dirty);
         assert_eq!(clean, "Hello\nWorld");
     }
+
+    #[test]
+    fn test_group_lines_spaces_and_segments() {
+        let mut glyphs = word(10.0, 100.0, "Rust");
+        glyphs.extend(word(32.0, 100.5, "lang"));
+        glyphs.extend(word(200.0, 100.0, "2015"));
+        glyphs.extend(word(10.0, 112.0, "next"));
+        let lines = group_lines(glyphs);
+        assert_eq!(lines.len(), 2);
+        let texts: Vec<&str> = lines[0].segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["Rust lang", "2015"]);
+        assert_eq!(lines[1].text(), "next");
+    }
+
+    #[test]
+    fn test_table_rows_are_labelled() {
+        let lines = vec![
+            line(0.0, &[(0.0, "Crate"), (100.0, "License")]),
+            line(12.0, &[(0.0, "tokio"), (102.0, "MIT")]),
+            line(24.0, &[(0.0, "serde"), (99.0, "MIT OR Apache-2.0")]),
+        ];
+        assert_eq!(
+            layout_page(lines),
+            vec![
+                "Crate | License",
+                "Crate: tokio | License: MIT",
+                "Crate: serde | License: MIT OR Apache-2.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_two_columns_read_in_order() {
+        let left = "the left column carries a long sentence";
+        let right = "while the right column continues another";
+        let lines: Vec<Line> = (0..4)
+            .map(|i| line(i as f64 * 12.0, &[(0.0, left), (300.0, right)]))
+            .collect();
+        let out = layout_page(lines);
+        assert_eq!(out[..4], vec![left; 4]);
+        assert_eq!(out[4], "");
+        assert_eq!(out[5..], vec![right; 4]);
+    }
+
+    #[test]
+    fn test_hyphenation_and_paragraphs() {
+        let lines = vec![
+            line(0.0, &[(0.0, "Knowledge graphs are use-")]),
+            line(12.0, &[(0.0, "ful for retrieval.")]),
+            line(60.0, &[(0.0, "A new paragraph.")]),
+        ];
+        assert_eq!(
+            layout_page(lines),
+            vec![
+                "Knowledge graphs are useful for retrieval.",
+                "",
+                "A new paragraph."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_running_headers_and_page_numbers_dropped() {
+        let topics = ["alpha", "beta", "gamma", "delta"];
+        let mut pages: Vec<(u32, Vec<String>)> = (1..=4)
+            .map(|n| {
+                (
+                    n,
+                    vec![
+                        "ACME Annual Report 2025".to_string(),
+                        format!("Body text about {}", topics[n as usize - 1]),
+                        "Distinct closing sentence".to_string() + &"!".repeat(n as usize),
+                        format!("Page {} of 4", n),
+                    ],
+                )
+            })
+            .collect();
+        strip_running_lines(&mut pages);
+        assert_eq!(
+            pages[0].1,
+            vec!["Body text about alpha", "Distinct closing sentence!"]
+        );
+        assert_eq!(pages[3].1[0], "Body text about delta");
+    }
+
+    #[test]
+    fn test_empty_pages_reported() {
+        let pages = vec![
+            (1, word(10.0, 10.0, "Hello")),
+            (2, Vec::new()),
+            (3, word(10.0, 10.0, "World")),
+        ];
+        let doc = layout_document(pages);
+        assert_eq!(doc.without_text, vec![2]);
+        assert_eq!(doc.to_text(), "[page 1]\nHello\n\n[page 3]\nWorld");
+    }
+
+    #[test]
+    fn test_page_tracker_and_citations() {
+        let mut tracker = PageTracker::default();
+        assert_eq!(
+            tracker.pages("[page 1]\nalpha\n\n[page 2]\nbeta"),
+            vec![1, 2]
+        );
+        assert_eq!(tracker.pages("more of beta"), vec![2]);
+        assert_eq!(tracker.pages("[page 3]\ngamma"), vec![3]);
+        assert_eq!(cite_pages("a.pdf", &[2, 3]), "a.pdf p.2-3");
+        assert_eq!(cite_pages("a.pdf", &[12]), "a.pdf p.12");
+        assert_eq!(cite_pages("a.md", &[]), "a.md");
+        assert_eq!(page_ranges(&[3, 4, 5, 9]), "3-5, 9");
+        assert!(PageTracker::default().pages("no markers").is_empty());
+        assert!(is_page_marker(&page_marker(7)));
+        assert!(is_page_marker("  [page 12]\n"));
+        assert!(!is_page_marker("see [page 12]"));
+        assert!(!is_page_marker("[page 12] intro"));
+    }
+
+    #[test]
+    fn test_extract_pages_from_pdf() {
+        use pdf_extract::content::{Content, Operation};
+        use pdf_extract::{Object, Stream, dictionary};
+
+        let mut doc = pdf_extract::Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let text = |x: i64, y: i64, s: &str| {
+            vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![x.into(), y.into()]),
+                Operation::new("Tj", vec![Object::string_literal(s)]),
+                Operation::new("ET", vec![]),
+            ]
+        };
+        let bodies = [
+            vec!["Tokio is an async runtime."],
+            vec![],
+            vec!["Serde serializes data."],
+            vec!["Axum builds on Tokio."],
+        ];
+        let mut kids = Vec::new();
+        for body in bodies {
+            let mut operations = text(50, 800, "Field Notes");
+            for (i, line) in body.iter().enumerate() {
+                operations.extend(text(50, 700 - 20 * i as i64, line));
+            }
+            if body.is_empty() {
+                operations.clear();
+            }
+            let content = Content { operations };
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+            kids.push(
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                })
+                .into(),
+            );
+        }
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.pdf");
+        doc.save(&path).unwrap();
+
+        let pdf = extract_pages(&path).unwrap();
+        assert_eq!(pdf.without_text, vec![2]);
+        let numbers: Vec<u32> = pdf.pages.iter().map(|p| p.number).collect();
+        assert_eq!(numbers, vec![1, 3, 4]);
+        assert_eq!(pdf.pages[0].text, "Tokio is an async runtime.");
+        assert_eq!(pdf.pages[1].text, "Serde serializes data.");
+        assert!(pdf.to_text().starts_with("[page 1]\nTokio"));
+    }
 }
@@ -0,0 +1,341 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::blocks::{self, Block, heading_level};
+
+/// Extract text from a Rich Text Format (.rtf) file, keeping headings and list items
+pub fn extract_text(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read RTF file: {}", path.display()))?;
+    // RTF is 7-bit; anything else is escaped with \'hh or \uN
+    let rtf = String::from_utf8_lossy(&bytes);
+
+    Ok(blocks::render(&rtf_blocks(&rtf)))
+}
+
+/// Destinations whose content is never part of the running text
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "aftnsep",
+    "aftnsepc",
+    "annotation",
+    "atnauthor",
+    "atnid",
+    "bkmkend",
+    "bkmkstart",
+    "colorschememapping",
+    "colortbl",
+    "datastore",
+    "docvar",
+    "filetbl",
+    "fldinst",
+    "fonttbl",
+    "footer",
+    "footerf",
+    "footerl",
+    "footerr",
+    "footnote",
+    "ftncn",
+    "ftnsep",
+    "ftnsepc",
+    "generator",
+    "header",
+    "headerf",
+    "headerl",
+    "headerr",
+    "info",
+    "latentstyles",
+    "listoverridetable",
+    "listtable",
+    "listtext",
+    "mmathPr",
+    "nonshppict",
+    "object",
+    "pgdsctbl",
+    "pict",
+    "pntext",
+    "pntxta",
+    "pntxtb",
+    "revtbl",
+    "rsidtbl",
+    "tc",
+    "template",
+    "themedata",
+    "userprops",
+    "wgrffmtfilter",
+    "xe",
+    "xmlnstbl",
+];
+
+/// Windows-1252 code points for bytes 0x80-0x9F (the rest match Latin-1)
+const CP1252_HIGH: [u32; 32] = [
+    0x20AC, 0xFFFD, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0xFFFD, 0x017D, 0xFFFD, 0xFFFD, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0xFFFD, 0x017E, 0x0178,
+];
+
+fn cp1252(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => char::from_u32(CP1252_HIGH[(byte - 0x80) as usize]).unwrap_or('?'),
+        b => b as char,
+    }
+}
+
+#[derive(Clone)]
+struct GroupState {
+    /// Inside a destination that produces no text
+    skip: bool,
+    /// Inside the stylesheet, collecting style names
+    stylesheet: bool,
+    /// Fallback characters that follow each \uN
+    uc: usize,
+}
+
+struct RtfReader {
+    blocks: Vec<Block>,
+    text: String,
+    heading: Option<usize>,
+    list: bool,
+    /// Paragraph style number to heading level, from the stylesheet
+    styles: HashMap<i32, usize>,
+    style_number: Option<i32>,
+    style_name: String,
+    /// Fallback characters still to drop after a \uN
+    pending_skip: usize,
+}
+
+impl RtfReader {
+    fn emit(&mut self, state: &GroupState, c: char) {
+        if self.pending_skip > 0 {
+            self.pending_skip -= 1;
+            return;
+        }
+        if state.skip {
+            return;
+        }
+        if state.stylesheet {
+            if c == ';' {
+                self.finish_style();
+            } else {
+                self.style_name.push(c);
+            }
+            return;
+        }
+        self.text.push(c);
+    }
+
+    fn finish_style(&mut self) {
+        if let (Some(number), Some(level)) =
+            (self.style_number, heading_level(self.style_name.trim()))
+        {
+            self.styles.insert(number, level);
+        }
+        self.style_number = None;
+        self.style_name.clear();
+    }
+
+    fn end_paragraph(&mut self) {
+        let text = std::mem::take(&mut self.text);
+        self.blocks.push(match (self.heading, self.list) {
+            (Some(level), _) => Block::Heading(level, text),
+            (None, true) => Block::ListItem(text),
+            (None, false) => Block::Paragraph(text),
+        });
+    }
+
+    fn control_word(&mut self, state: &mut GroupState, word: &str, param: Option<i32>) {
+        if SKIPPED_DESTINATIONS.contains(&word) {
+            state.skip = true;
+            return;
+        }
+        if state.skip {
+            return;
+        }
+
+        if word == "stylesheet" {
+            state.stylesheet = true;
+            return;
+        }
+        if state.stylesheet {
+            if word == "s" {
+                self.style_number = param;
+            }
+            return;
+        }
+
+        match word {
+            "par" | "row" | "page" | "sect" => self.end_paragraph(),
+            "pard" => {
+                self.heading = None;
+                self.list = false;
+            }
+            "s" => {
+                if let Some(level) = param.and_then(|p| self.styles.get(&p)) {
+                    self.heading = Some(*level);
+                }
+            }
+            "outlinelevel" => {
+                self.heading = param
+                    .filter(|&p| (0..9).contains(&p))
+                    .map(|p| p as usize + 1);
+            }
+            "ls" | "ilvl" | "pnlvlblt" => self.list = true,
+            "uc" => state.uc = param.unwrap_or(1).max(0) as usize,
+            "u" => {
+                if let Some(n) = param {
+                    let code = if n < 0 { n + 65536 } else { n } as u32;
+                    self.emit(state, char::from_u32(code).unwrap_or('\u{FFFD}'));
+                    self.pending_skip = state.uc;
+                }
+            }
+            "line" => self.emit(state, '\n'),
+            "tab" | "cell" => self.emit(state, '\t'),
+            "emdash" => self.emit(state, '—'),
+            "endash" => self.emit(state, '–'),
+            "bullet" => self.emit(state, '•'),
+            "lquote" => self.emit(state, '‘'),
+            "rquote" => self.emit(state, '’'),
+            "ldblquote" => self.emit(state, '“'),
+            "rdblquote" => self.emit(state, '”'),
+            "emspace" | "enspace" | "qmspace" => self.emit(state, ' '),
+            _ => {}
+        }
+    }
+}
+
+/// Tokenize RTF and collect paragraphs, headings and list items
+fn rtf_blocks(rtf: &str) -> Vec<Block> {
+    let chars: Vec<char> = rtf.chars().collect();
+    let mut reader = RtfReader {
+        blocks: Vec::new(),
+        text: String::new(),
+        heading: None,
+        list: false,
+        styles: HashMap::new(),
+        style_number: None,
+        style_name: String::new(),
+        pending_skip: 0,
+    };
+    let mut stack: Vec<GroupState> = Vec::new();
+    let mut state = GroupState {
+        skip: false,
+        stylesheet: false,
+        uc: 1,
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '{' => {
+                stack.push(state.clone());
+                if state.stylesheet {
+                    reader.style_number = None;
+                    reader.style_name.clear();
+                }
+                reader.pending_skip = 0;
+                i += 1;
+            }
+            '}' => {
+                if state.stylesheet && !state.skip && reader.style_number.is_some() {
+                    reader.finish_style();
+                }
+                if let Some(outer) = stack.pop() {
+                    state = outer;
+                }
+                reader.pending_skip = 0;
+                i += 1;
+            }
+            '\\' => {
+                let Some(&next) = chars.get(i + 1) else { break };
+                if next.is_ascii_alphabetic() {
+                    let start = i + 1;
+                    let mut end = start;
+                    while end < chars.len() && chars[end].is_ascii_alphabetic() {
+                        end += 1;
+                    }
+                    let word: String = chars[start..end].iter().collect();
+                    let num_start = end;
+                    if end < chars.len() && chars[end] == '-' {
+                        end += 1;
+                    }
+                    while end < chars.len() && chars[end].is_ascii_digit() {
+                        end += 1;
+                    }
+                    let param = chars[num_start..end]
+                        .iter()
+                        .collect::<String>()
+                        .parse::<i32>()
+                        .ok();
+                    if end < chars.len() && chars[end] == ' ' {
+                        end += 1;
+                    }
+                    i = end;
+
+                    if word == "bin" {
+                        i += param.unwrap_or(0).max(0) as usize;
+                    } else {
+                        reader.control_word(&mut state, &word, param);
+                    }
+                } else {
+                    i += 2;
+                    match next {
+                        '\'' => {
+                            let hex: String = chars.iter().skip(i).take(2).collect();
+                            i += 2;
+                            if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                                reader.emit(&state, cp1252(byte));
+                            }
+                        }
+                        '*' => state.skip = true,
+                        '~' => reader.emit(&state, '\u{A0}'),
+                        '_' => reader.emit(&state, '-'),
+                        '\n' | '\r' if !state.skip && !state.stylesheet => reader.end_paragraph(),
+                        '{' | '}' | '\\' => reader.emit(&state, next),
+                        _ => {}
+                    }
+                }
+            }
+            '\r' | '\n' => i += 1,
+            c => {
+                reader.emit(&state, c);
+                i += 1;
+            }
+        }
+    }
+
+    if !reader.text.trim().is_empty() {
+        reader.end_paragraph();
+    }
+    reader.blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtf_structure() {
+        let rtf = r#"{\rtf1\ansi\ansicpg1252\deff0
+{\fonttbl{\f0\froman Times New Roman;}{\f1 Arial;}}
+{\colortbl;\red255\green0\blue0;}
+{\stylesheet{\s0 Normal;}{\s1\sbasedon0\snext0 heading 1;}{\s2\outlinelevel1 heading 2;}{\*\cs10 Default Paragraph Font;}}
+{\info{\title Secret title}{\author Someone}}
+\pard\s1\b Graph Databases\b0\par
+\pard\s2 Storage\par
+\pard Neo4j stores \i nodes\i0  and \cf1 relationships\cf0  on disk.\par
+\pard\ls1 {\listtext\'b7\tab}Caf\'e9 \u8364?5 entries\par
+{\pard\ls1 {\listtext\'b7\tab}Braces \{ok\}\par}
+\pard\outlinelevel0 Summary\par
+}"#;
+        assert_eq!(
+            blocks::render(&rtf_blocks(rtf)),
+            "# Graph Databases\n\n## Storage\n\nNeo4j stores nodes and relationships on disk.\n\n- Café €5 entries\n- Braces {ok}\n\n# Summary"
+        );
+    }
+
+    #[test]
+    fn test_rtf_skips_fields_and_pictures() {
+        let rtf = r#"{\rtf1 See {\field{\*\fldinst HYPERLINK "http://x"}{\fldrslt the docs}} {\pict\pngblip 89504e47} now.}"#;
+        assert_eq!(blocks::render(&rtf_blocks(rtf)), "See the docs now.");
+    }
+}
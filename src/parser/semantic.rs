@@ -0,0 +1,501 @@
+//! Semantic chunking: sentences grouped by topic
+//!
+//! Text is split into sentences, never inside one. Each sentence is embedded
+//! together with its neighbours, and a chunk ends where the distance between
+//! consecutive sentences is among the largest in the document, at a Markdown
+//! heading, or when the next sentence would not fit. Chunks open with the
+//! breadcrumb of the headings they sit under.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use regex::Regex;
+use std::ops::Range;
+use std::sync::{Arc, LazyLock};
+
+use super::adaptive_chunker::Chunk;
+use super::chunker::Chunker;
+use super::pdf::is_page_marker;
+use super::tokenizer::Tokenizer;
+
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(#{1,6})\s+(.+?)(?:\s+#+)?\s*$").expect("valid heading regex"));
+
+static LIST_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:[-*+]|\d{1,3}[.)])\s").expect("valid list item regex"));
+
+/// Words that end in a period without ending the sentence
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e", "cf", "fig",
+    "no", "vol", "approx", "inc", "ltd", "co", "al",
+];
+
+/// Sentences on either side that are embedded along with each sentence
+const WINDOW: usize = 1;
+
+/// Turns texts into embedding vectors, one per text and in order
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// A sentence, or a Markdown heading that opens a section
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Byte range in the text
+    pub range: Range<usize>,
+    /// Heading level, for headings
+    pub heading: Option<usize>,
+}
+
+/// Splits text at sentence boundaries and topic shifts
+pub struct SemanticChunker {
+    max_tokens: usize,
+    breakpoint_percentile: f32,
+    tokenizer: Arc<Tokenizer>,
+    embedder: Option<Arc<dyn Embedder>>,
+}
+
+impl SemanticChunker {
+    /// Chunks stay under `max_tokens` unless a single sentence is longer
+    pub fn new(max_tokens: usize, breakpoint_percentile: f32) -> Self {
+        Self {
+            max_tokens: max_tokens.max(1),
+            breakpoint_percentile: breakpoint_percentile.clamp(0.0, 100.0),
+            tokenizer: Arc::default(),
+            embedder: None,
+        }
+    }
+
+    /// Embed sentences with `embedder` when used as a [`Chunker`]
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
+    /// Count chunk sizes with a model's vocabulary instead of the heuristic
+    pub fn with_tokenizer(mut self, tokenizer: Arc<Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Split text into headings and sentences
+    ///
+    /// Paragraphs, list items and `[code]` blocks always end a sentence; page
+    /// markers are left between segments.
+    pub fn segment(&self, text: &str) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut paragraph: Option<Range<usize>> = None;
+        let mut code: Option<usize> = None;
+        let mut offset = 0;
+
+        for line in text.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+            let trimmed = line.trim();
+            let end = start + line.trim_end().len();
+
+            if let Some(code_start) = code {
+                if trimmed == "[/code]" {
+                    segments.push(Segment {
+                        range: code_start..end,
+                        heading: None,
+                    });
+                    code = None;
+                }
+                continue;
+            }
+
+            let structural = trimmed.is_empty()
+                || trimmed == "[code]"
+                || is_page_marker(trimmed)
+                || HEADING.is_match(trimmed)
+                || LIST_ITEM.is_match(line);
+            if structural && let Some(range) = paragraph.take() {
+                split_sentences(text, range, &mut segments);
+            }
+
+            if trimmed == "[code]" {
+                code = Some(start + (line.len() - line.trim_start().len()));
+            } else if let Some(caps) = HEADING.captures(trimmed) {
+                segments.push(Segment {
+                    range: start + (line.len() - line.trim_start().len())..end,
+                    heading: Some(caps[1].len()),
+                });
+            } else if trimmed.is_empty() || is_page_marker(trimmed) {
+                // Paragraph break
+            } else {
+                let line_start = start + (line.len() - line.trim_start().len());
+                paragraph = Some(paragraph.map_or(line_start..end, |p| p.start..end));
+            }
+        }
+
+        if let Some(code_start) = code {
+            segments.push(Segment {
+                range: code_start..text.trim_end().len(),
+                heading: None,
+            });
+        }
+        if let Some(range) = paragraph {
+            split_sentences(text, range, &mut segments);
+        }
+        segments
+    }
+
+    /// Texts to embed, one per sentence: the sentence with its neighbours
+    pub fn embedding_inputs(&self, text: &str, segments: &[Segment]) -> Vec<String> {
+        let sentences: Vec<&str> = segments
+            .iter()
+            .filter(|s| s.heading.is_none())
+            .map(|s| &text[s.range.clone()])
+            .collect();
+
+        (0..sentences.len())
+            .map(|i| {
+                let window = i.saturating_sub(WINDOW)..(i + WINDOW + 1).min(sentences.len());
+                sentences[window].join(" ")
+            })
+            .collect()
+    }
+
+    /// Group segments into chunks, given one embedding per sentence
+    ///
+    /// Each chunk records its character range in `text` as its span. A gap
+    /// only splits on drift once the chunk holds a tenth of `max_tokens`, so
+    /// short documents stay whole.
+    pub fn chunk(&self, text: &str, segments: &[Segment], embeddings: &[Vec<f32>]) -> Vec<Chunk> {
+        // Distance from each sentence to the next
+        let distances: Vec<f32> = embeddings
+            .windows(2)
+            .map(|pair| cosine_distance(&pair[0], &pair[1]))
+            .collect();
+        let threshold = percentile(&distances, self.breakpoint_percentile);
+        let min_tokens = self.max_tokens / 10;
+
+        // Segment index ranges of each chunk, with the breadcrumb in effect at its start
+        let mut groups: Vec<(Range<usize>, Vec<String>)> = Vec::new();
+        let mut headings: Vec<(usize, String)> = Vec::new();
+        let mut start = 0;
+        let mut tokens = 0;
+        let mut sentence = 0;
+        let mut crumbs = Vec::new();
+
+        for (i, segment) in segments.iter().enumerate() {
+            let segment_tokens = self.tokenizer.count(&text[segment.range.clone()]);
+            let drifted = segment.heading.is_none()
+                && sentence > 0
+                && distances.get(sentence - 1).is_some_and(|d| *d > threshold)
+                && tokens >= min_tokens;
+            let split = i > start
+                && (segment.heading.is_some()
+                    || tokens + segment_tokens > self.max_tokens
+                    || drifted);
+
+            if split {
+                groups.push((start..i, std::mem::take(&mut crumbs)));
+                start = i;
+                tokens = 0;
+            }
+            if i == start {
+                let level = segment.heading.unwrap_or(usize::MAX);
+                crumbs = headings
+                    .iter()
+                    .filter(|(l, _)| *l < level)
+                    .map(|(_, title)| title.clone())
+                    .collect();
+            }
+
+            match segment.heading {
+                Some(level) => {
+                    headings.retain(|(l, _)| *l < level);
+                    let title = HEADING
+                        .captures(&text[segment.range.clone()])
+                        .map_or_else(String::new, |caps| caps[2].to_string());
+                    headings.push((level, title));
+                }
+                None => sentence += 1,
+            }
+            tokens += segment_tokens;
+        }
+        if start < segments.len() {
+            groups.push((start..segments.len(), crumbs));
+        }
+
+        // Each chunk runs from the end of the previous one, so markers in between stay with it
+        let mut chunks = Vec::new();
+        let mut previous_end = 0;
+        for (index, (group, crumbs)) in groups.iter().enumerate() {
+            let end = if index + 1 == groups.len() {
+                text.len()
+            } else {
+                segments[group.end - 1].range.end
+            };
+            let raw = &text[previous_end..end];
+            let body_start = previous_end + (raw.len() - raw.trim_start().len());
+            let body_end = previous_end + raw.trim_end().len();
+            previous_end = end;
+
+            let body = &text[body_start..body_end];
+            let chunk_text = if crumbs.is_empty() {
+                body.to_string()
+            } else {
+                format!("{}\n\n{}", crumbs.join(" > "), body)
+            };
+            let char_start = text[..body_start].chars().count();
+            chunks.push(Chunk {
+                estimated_tokens: self.tokenizer.count(&chunk_text),
+                text: chunk_text,
+                chunk_index: index,
+                parent_id: None,
+                span: Some(char_start..char_start + body.chars().count()),
+            });
+        }
+        chunks
+    }
+}
+
+#[async_trait]
+impl Chunker for SemanticChunker {
+    async fn chunk(&self, text: &str) -> Result<Vec<Chunk>> {
+        let embedder = self
+            .embedder
+            .as_ref()
+            .context("Semantic chunking needs an embedding model")?;
+        let segments = self.segment(text);
+        let embeddings = embedder
+            .embed(&self.embedding_inputs(text, &segments))
+            .await?;
+        Ok(SemanticChunker::chunk(self, text, &segments, &embeddings))
+    }
+
+    fn name(&self) -> &'static str {
+        "semantic"
+    }
+}
+
+/// Split a paragraph at sentence-ending punctuation followed by a capitalized word
+fn split_sentences(text: &str, range: Range<usize>, segments: &mut Vec<Segment>) {
+    let paragraph = &text[range.clone()];
+    let chars: Vec<(usize, char)> = paragraph.char_indices().collect();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (_, c) = chars[i];
+        if !matches!(c, '.' | '!' | '?') {
+            i += 1;
+            continue;
+        }
+
+        // Swallow repeated punctuation and closing quotes or brackets
+        let mut end = i + 1;
+        while end < chars.len()
+            && matches!(
+                chars[end].1,
+                '.' | '!' | '?' | '"' | '\'' | ')' | ']' | '”' | '’'
+            )
+        {
+            end += 1;
+        }
+        let mut next = end;
+        while next < chars.len() && chars[next].1.is_whitespace() {
+            next += 1;
+        }
+
+        let at_boundary = next > end
+            && next < chars.len()
+            && (chars[next].1.is_uppercase()
+                || chars[next].1.is_ascii_digit()
+                || matches!(chars[next].1, '"' | '\'' | '(' | '[' | '“' | '‘'))
+            && !(c == '.' && ends_with_abbreviation(&paragraph[..chars[i].0]));
+
+        if at_boundary {
+            let end_byte = chars.get(end).map_or(paragraph.len(), |(b, _)| *b);
+            let sentence_start = chars[start].0;
+            segments.push(Segment {
+                range: range.start + sentence_start..range.start + end_byte,
+                heading: None,
+            });
+            start = next;
+        }
+        i = end;
+    }
+
+    if start < chars.len() {
+        segments.push(Segment {
+            range: range.start + chars[start].0..range.end,
+            heading: None,
+        });
+    }
+}
+
+/// Whether the word before a period is an abbreviation or an initial
+fn ends_with_abbreviation(before: &str) -> bool {
+    let word = before
+        .rsplit(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or("");
+    let is_initial = word.chars().count() == 1 && word.chars().all(char::is_alphabetic);
+    is_initial || ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { 1.0 - dot / norms }
+}
+
+/// Nearest-rank percentile; infinite when there is nothing to rank
+fn percentile(values: &[f32], p: f32) -> f32 {
+    if values.is_empty() {
+        return f32::INFINITY;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let rank = ((p / 100.0) * (sorted.len() - 1) as f32).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(text: &'a str, segments: &[Segment]) -> Vec<&'a str> {
+        segments.iter().map(|s| &text[s.range.clone()]).collect()
+    }
+
+    #[test]
+    fn test_sentences_respect_abbreviations_and_structure() {
+        let chunker = SemanticChunker::new(1000, 90.0);
+        let text = "# Guide\n\nDr. Smith wrote it, e.g. for J. Doe. It works!\nReally? \"Yes.\" Version 2.0 is out.\n\n- first item\n- second item\n\n[code]\nfn main() { a.b(); }\n[/code]\n\n[page 2]\nLast one";
+        let segments = chunker.segment(text);
+
+        assert_eq!(
+            texts(text, &segments),
+            vec![
+                "# Guide",
+                "Dr. Smith wrote it, e.g. for J. Doe.",
+                "It works!",
+                "Really?",
+                "\"Yes.\"",
+                "Version 2.0 is out.",
+                "- first item",
+                "- second item",
+                "[code]\nfn main() { a.b(); }\n[/code]",
+                "Last one",
+            ]
+        );
+        assert_eq!(segments[0].heading, Some(1));
+        assert!(segments[1..].iter().all(|s| s.heading.is_none()));
+    }
+
+    #[test]
+    fn test_embedding_inputs_are_windowed() {
+        let chunker = SemanticChunker::new(1000, 90.0);
+        let text = "# T\n\nOne. Two. Three.";
+        let segments = chunker.segment(text);
+        assert_eq!(
+            chunker.embedding_inputs(text, &segments),
+            vec!["One. Two.", "One. Two. Three.", "Two. Three."]
+        );
+    }
+
+    #[test]
+    fn test_splits_at_topic_shift() {
+        let chunker = SemanticChunker::new(40, 50.0);
+        let text = "Cats purr softly. Cats chase mice. Rust has traits. Rust has lifetimes.";
+        let segments = chunker.segment(text);
+        let embeddings = vec![
+            vec![1.0, 0.0],
+            vec![0.9, 0.1],
+            vec![0.0, 1.0],
+            vec![0.1, 0.9],
+        ];
+        let chunks = chunker.chunk(text, &segments, &embeddings);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].text, "Cats purr softly. Cats chase mice.");
+        assert_eq!(chunks[1].text, "Rust has traits. Rust has lifetimes.");
+        assert_eq!(chunks[0].span, Some(0..34));
+        assert_eq!(chunks[1].span, Some(35..71));
+        assert_eq!(&text[35..71], chunks[1].text);
+    }
+
+    #[test]
+    fn test_headings_break_and_prefix_breadcrumbs() {
+        let chunker = SemanticChunker::new(1000, 100.0);
+        let text = "# Guide\n\nIntro here.\n\n## Install\n\nRun it.\n\n### Linux\n\nUse apt.\nThen reboot.\n\n## Usage\n\nCall it.";
+        let segments = chunker.segment(text);
+        let embeddings = vec![vec![1.0]; 5];
+        let chunks = chunker.chunk(text, &segments, &embeddings);
+
+        let bodies: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            bodies,
+            vec![
+                "# Guide\n\nIntro here.",
+                "Guide\n\n## Install\n\nRun it.",
+                "Guide > Install\n\n### Linux\n\nUse apt.\nThen reboot.",
+                "Guide\n\n## Usage\n\nCall it.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_size_cap_never_splits_a_sentence() {
+        let chunker = SemanticChunker::new(10, 100.0);
+        let long = "This sentence alone is much longer than the ten token limit allows.";
+        let text = format!("{} Short one. {}", long, long);
+        let segments = chunker.segment(&text);
+        let embeddings = vec![vec![1.0]; segments.len()];
+        let chunks = chunker.chunk(&text, &segments, &embeddings);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].text, long);
+        assert_eq!(chunks[1].text, "Short one.");
+        assert_eq!(chunks[2].text, long);
+    }
+
+    /// Embeds sentences about cats and about Rust as opposite vectors
+    struct TopicEmbedder;
+
+    #[async_trait]
+    impl Embedder for TopicEmbedder {
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            Ok(texts
+                .iter()
+                .map(|t| {
+                    let cats = t.matches("Cats").count() as f32;
+                    let rust = t.matches("Rust").count() as f32;
+                    vec![cats, rust]
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunker_trait_embeds_sentences() {
+        let text = "Cats purr softly. Cats chase mice. Cats nap. Rust has traits. Rust has lifetimes. Rust is fast.";
+        let chunker = SemanticChunker::new(40, 50.0);
+        assert!(Chunker::chunk(&chunker, text).await.is_err());
+
+        let chunker = chunker.with_embedder(Arc::new(TopicEmbedder));
+        let chunks = Chunker::chunk(&chunker, text).await.unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].text.ends_with("Cats nap."));
+        assert!(chunks[1].text.starts_with("Rust has traits."));
+    }
+
+    #[test]
+    fn test_page_markers_stay_with_following_chunk() {
+        let chunker = SemanticChunker::new(10, 100.0);
+        let text = "[page 1]\nAlpha text here is long enough.\n[page 2]\nBeta is different.";
+        let segments = chunker.segment(text);
+        let embeddings = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let chunks = chunker.chunk(text, &segments, &embeddings);
+
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].text.starts_with("[page 1]"));
+        assert!(chunks[1].text.starts_with("[page 2]"));
+    }
+}
@@ -0,0 +1,431 @@
+//! Token counting with real vocabularies
+//!
+//! Two local file formats are understood:
+//! - `.tiktoken` BPE rank files (`<base64 token> <rank>` per line), as used by
+//!   OpenAI-style models (`cl100k_base`, `o200k_base`)
+//! - SentencePiece `.model` files, as shipped with Llama, Gemma and Mistral
+//!
+//! Without a vocabulary, counting falls back to [`estimate_tokens`].
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::adaptive_chunker::estimate_tokens;
+
+/// Pre-tokenization split used by `cl100k_base`, minus the `\s+(?!\S)`
+/// look-ahead the regex crate does not support
+pub(crate) const BPE_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+";
+
+/// Pieces longer than this are merged in windows to keep merging linear
+const MAX_PIECE_BYTES: usize = 256;
+
+/// SentencePiece word-boundary marker
+const SPACE_MARKER: char = '\u{2581}';
+
+/// Counts tokens the way a model's tokenizer would
+#[derive(Default)]
+pub enum Tokenizer {
+    /// ~4 characters per token
+    #[default]
+    Heuristic,
+    Bpe(BpeTokenizer),
+    SentencePiece(SentencePieceTokenizer),
+}
+
+impl Tokenizer {
+    /// Load a vocabulary, picking the format from the extension:
+    /// `.model` is SentencePiece, anything else a tiktoken rank file
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read tokenizer {}", path.display()))?;
+        let is_sentencepiece = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("model"));
+
+        let tokenizer = if is_sentencepiece {
+            Self::SentencePiece(SentencePieceTokenizer::from_bytes(&bytes)?)
+        } else {
+            Self::Bpe(BpeTokenizer::from_bytes(&bytes)?)
+        };
+        Ok(tokenizer)
+    }
+
+    /// Number of tokens in `text`
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Self::Heuristic => estimate_tokens(text),
+            Self::Bpe(bpe) => bpe.count(text),
+            Self::SentencePiece(sp) => sp.count(text),
+        }
+    }
+
+    /// Whether counts come from a real vocabulary rather than the heuristic
+    pub fn is_exact(&self) -> bool {
+        !matches!(self, Self::Heuristic)
+    }
+}
+
+/// Byte-pair encoding over a tiktoken rank file
+pub struct BpeTokenizer {
+    ranks: HashMap<Vec<u8>, u32>,
+    pattern: Regex,
+}
+
+impl BpeTokenizer {
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let content = std::str::from_utf8(bytes).context("BPE rank file is not UTF-8")?;
+        let mut ranks = HashMap::new();
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (token, rank) = line
+                .split_once(' ')
+                .with_context(|| format!("line {}: expected `<base64> <rank>`", number + 1))?;
+            let token = STANDARD
+                .decode(token)
+                .with_context(|| format!("line {}: invalid base64", number + 1))?;
+            let rank: u32 = rank
+                .trim()
+                .parse()
+                .with_context(|| format!("line {}: invalid rank", number + 1))?;
+            ranks.insert(token, rank);
+        }
+
+        if ranks.is_empty() {
+            bail!("BPE rank file has no tokens");
+        }
+
+        Ok(Self {
+            ranks,
+            pattern: Regex::new(BPE_PATTERN).expect("valid BPE pattern"),
+        })
+    }
+
+    fn count(&self, text: &str) -> usize {
+        self.pattern
+            .find_iter(text)
+            .map(|m| {
+                m.as_str()
+                    .as_bytes()
+                    .chunks(MAX_PIECE_BYTES)
+                    .map(|piece| self.count_piece(piece))
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Merge the lowest-ranked adjacent pair until none is in the vocabulary
+    fn count_piece(&self, piece: &[u8]) -> usize {
+        if self.ranks.contains_key(piece) {
+            return 1;
+        }
+
+        // Part boundaries; part i is piece[bounds[i]..bounds[i + 1]]
+        let mut bounds: Vec<usize> = (0..=piece.len()).collect();
+        loop {
+            let best = (0..bounds.len().saturating_sub(2))
+                .filter_map(|i| {
+                    self.ranks
+                        .get(&piece[bounds[i]..bounds[i + 2]])
+                        .map(|rank| (*rank, i))
+                })
+                .min();
+            match best {
+                Some((_, i)) => {
+                    bounds.remove(i + 1);
+                }
+                None => break,
+            }
+        }
+        bounds.len() - 1
+    }
+}
+
+/// Unigram segmentation over a SentencePiece model
+pub struct SentencePieceTokenizer {
+    scores: HashMap<String, f32>,
+    max_piece_chars: usize,
+    min_score: f32,
+    byte_fallback: bool,
+}
+
+impl SentencePieceTokenizer {
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut scores = HashMap::new();
+        let mut byte_fallback = false;
+
+        // ModelProto: field 1 holds the repeated pieces
+        let mut reader = ProtoReader::new(bytes);
+        while let Some((field, value)) = reader.next_field()? {
+            let (1, ProtoValue::Bytes(message)) = (field, value) else {
+                continue;
+            };
+
+            // SentencePiece: 1 = piece, 2 = score, 3 = type
+            let mut piece = None;
+            let mut score = 0.0;
+            let mut kind = 1;
+            let mut inner = ProtoReader::new(message);
+            while let Some((field, value)) = inner.next_field()? {
+                match (field, value) {
+                    (1, ProtoValue::Bytes(text)) => {
+                        piece = Some(String::from_utf8_lossy(text).into_owned())
+                    }
+                    (2, ProtoValue::Fixed32(bits)) => score = f32::from_bits(bits),
+                    (3, ProtoValue::Varint(value)) => kind = value,
+                    _ => {}
+                }
+            }
+
+            // NORMAL and USER_DEFINED pieces take part in segmentation
+            match (piece, kind) {
+                (Some(piece), 1 | 4) => {
+                    scores.insert(piece, score);
+                }
+                (_, 6) => byte_fallback = true,
+                _ => {}
+            }
+        }
+
+        if scores.is_empty() {
+            bail!("SentencePiece model has no pieces");
+        }
+
+        let max_piece_chars = scores.keys().map(|p| p.chars().count()).max().unwrap_or(1);
+        let min_score = scores.values().copied().fold(0.0, f32::min);
+
+        Ok(Self {
+            scores,
+            max_piece_chars,
+            min_score,
+            byte_fallback,
+        })
+    }
+
+    fn count(&self, text: &str) -> usize {
+        // Collapse whitespace and add the dummy prefix, as the default normalizer does
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if words.is_empty() {
+            return 0;
+        }
+        let normalized: String = words
+            .iter()
+            .flat_map(|word| std::iter::once(SPACE_MARKER).chain(word.chars()))
+            .collect();
+
+        let offsets: Vec<usize> = normalized
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(normalized.len()))
+            .collect();
+        let chars = offsets.len() - 1;
+
+        // Viterbi over characters: best (score, token count) ending at each position
+        let unknown_score = self.min_score - 10.0;
+        let mut best: Vec<Option<(f32, usize)>> = vec![None; chars + 1];
+        best[0] = Some((0.0, 0));
+
+        for start in 0..chars {
+            let Some((score, count)) = best[start] else {
+                continue;
+            };
+            let mut matched = false;
+            for end in start + 1..=(start + self.max_piece_chars).min(chars) {
+                let piece = &normalized[offsets[start]..offsets[end]];
+                if let Some(piece_score) = self.scores.get(piece) {
+                    matched |= end == start + 1;
+                    relax(&mut best[end], score + piece_score, count + 1);
+                }
+            }
+            if !matched {
+                // Unknown character: one <unk>, or one token per UTF-8 byte
+                let tokens = if self.byte_fallback {
+                    offsets[start + 1] - offsets[start]
+                } else {
+                    1
+                };
+                relax(&mut best[start + 1], score + unknown_score, count + tokens);
+            }
+        }
+
+        best[chars].map_or(0, |(_, count)| count)
+    }
+}
+
+fn relax(slot: &mut Option<(f32, usize)>, score: f32, count: usize) {
+    if slot.is_none_or(|(best, _)| score > best) {
+        *slot = Some((score, count));
+    }
+}
+
+/// Just enough protobuf to walk a SentencePiece model
+struct ProtoReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+enum ProtoValue<'a> {
+    Varint(u64),
+    Fixed32(u32),
+    Bytes(&'a [u8]),
+    Other,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u64, ProtoValue<'a>)>> {
+        if self.pos >= self.buf.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => ProtoValue::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                ProtoValue::Other
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                ProtoValue::Bytes(self.take(len)?)
+            }
+            5 => {
+                let bytes = self.take(4)?;
+                ProtoValue::Fixed32(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            wire => bail!("unsupported protobuf wire type {}", wire),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.buf.get(self.pos).context("truncated protobuf")?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("invalid protobuf varint")
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).context("truncated protobuf")?;
+        let bytes = self.buf.get(self.pos..end).context("truncated protobuf")?;
+        self.pos = end;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_ranks(dir: &TempDir, tokens: &[&str]) -> std::path::PathBuf {
+        let content: String = tokens
+            .iter()
+            .enumerate()
+            .map(|(rank, token)| format!("{} {}\n", STANDARD.encode(token), rank))
+            .collect();
+        let path = dir.path().join("test.tiktoken");
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    fn sentencepiece_model(pieces: &[(&str, f32, u64)]) -> Vec<u8> {
+        let mut model = Vec::new();
+        for (piece, score, kind) in pieces {
+            let mut message = vec![0x0a];
+            varint(piece.len() as u64, &mut message);
+            message.extend_from_slice(piece.as_bytes());
+            message.push(0x15);
+            message.extend_from_slice(&score.to_le_bytes());
+            message.push(0x18);
+            varint(*kind, &mut message);
+
+            model.push(0x0a);
+            varint(message.len() as u64, &mut model);
+            model.extend(message);
+        }
+        model
+    }
+
+    #[test]
+    fn test_bpe_merges_by_rank() {
+        let dir = TempDir::new().unwrap();
+        let mut tokens: Vec<&str> = vec!["a", "b", "c", " "];
+        tokens.extend(["ab", "abc", " ab"]);
+        let tokenizer = Tokenizer::load(&write_ranks(&dir, &tokens)).unwrap();
+
+        assert!(tokenizer.is_exact());
+        assert_eq!(tokenizer.count("abc"), 1);
+        assert_eq!(tokenizer.count("abcab"), 2);
+        // " ab" is one pre-token and one vocabulary entry; "c" stays alone
+        assert_eq!(tokenizer.count("abc abc"), 3);
+        assert_eq!(tokenizer.count(""), 0);
+    }
+
+    #[test]
+    fn test_bpe_rejects_malformed_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bad.tiktoken");
+        std::fs::write(&path, "not-a-rank-line\n").unwrap();
+        assert!(Tokenizer::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_sentencepiece_prefers_high_scoring_pieces() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("test.model");
+        let model = sentencepiece_model(&[
+            ("<unk>", 0.0, 2),
+            ("\u{2581}", -2.0, 1),
+            ("h", -3.0, 1),
+            ("e", -3.0, 1),
+            ("l", -3.0, 1),
+            ("o", -3.0, 1),
+            ("\u{2581}hello", -1.0, 1),
+            ("hel", -1.5, 1),
+        ]);
+        std::fs::write(&path, model).unwrap();
+        let tokenizer = Tokenizer::load(&path).unwrap();
+
+        assert_eq!(tokenizer.count("hello"), 1);
+        assert_eq!(tokenizer.count("  hello   hello "), 2);
+        // "▁" + "hel" + "l" beats five single characters
+        assert_eq!(tokenizer.count("hell"), 3);
+        // Unknown characters cost one token each without byte fallback
+        assert_eq!(tokenizer.count("hx"), 3);
+    }
+
+    #[test]
+    fn test_heuristic_matches_estimate() {
+        let text = "This is a simple test sentence with eight words.";
+        assert_eq!(Tokenizer::Heuristic.count(text), estimate_tokens(text));
+        assert!(!Tokenizer::Heuristic.is_exact());
+    }
+}
@@ -0,0 +1,493 @@
+//! Local HTTP server behind `rknowledge viz`.
+//!
+//! The graph is loaded once into a [`GraphStore`]; the page starts with the
+//! best-connected nodes and pulls in the rest on demand through a small JSON
+//! API, so graphs too large to embed in one HTML file stay explorable.
+//!
+//! | Route | Returns |
+//! |-------|---------|
+//! | `GET /` | the visualization page |
+//! | `GET /api/stats` | node, edge and community counts |
+//! | `GET /api/search?q=&limit=` | concepts matching a name |
+//! | `GET /api/expand?id=&depth=&min_weight=&exclude=` | neighbourhood of a concept |
+//! | `GET /api/path?from=&to=&k=&directed=&exclude=` | k shortest paths |
+//! | `GET /api/communities` | community sizes and top members |
+//! | `GET /api/community?id=&limit=` | members of a community and their edges |
+
+use anyhow::{Context, Result};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Json, Response};
+use axum::routing::get;
+use axum::{Router, serve};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+use crate::cli::commands::viz::{edge_json, node_json};
+use crate::graph::neo4j::{GraphEdge, GraphNode};
+use crate::graph::paths::{PathGraph, PathOptions};
+use crate::graph::resolver::{ConceptResolver, Resolution};
+use crate::graph::subgraph::{SubgraphOptions, ego_network};
+
+/// Most nodes any single API response will carry
+const MAX_RESPONSE_NODES: usize = 2000;
+
+/// In-memory graph snapshot that answers the API queries
+pub struct GraphStore {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+    /// Degree computed from the edges, so JSON files without `degree` still rank
+    degrees: HashMap<String, usize>,
+    paths: PathGraph,
+    resolver: ConceptResolver,
+}
+
+impl GraphStore {
+    pub fn new(nodes: Vec<GraphNode>, edges: Vec<GraphEdge>) -> Self {
+        let mut degrees: HashMap<String, usize> = HashMap::new();
+        for edge in &edges {
+            *degrees.entry(edge.source.clone()).or_default() += 1;
+            *degrees.entry(edge.target.clone()).or_default() += 1;
+        }
+        let paths = PathGraph::from_data(&nodes, &edges);
+        let resolver = ConceptResolver::from_nodes(&nodes);
+        Self {
+            nodes,
+            edges,
+            degrees,
+            paths,
+            resolver,
+        }
+    }
+
+    fn degree(&self, id: &str) -> usize {
+        self.degrees.get(id).copied().unwrap_or(0)
+    }
+
+    /// The `max_nodes` best-connected nodes and the edges between them
+    pub fn overview(&self, max_nodes: usize) -> (Vec<GraphNode>, Vec<GraphEdge>) {
+        let mut ranked: Vec<&GraphNode> = self.nodes.iter().collect();
+        ranked.sort_by_key(|n| std::cmp::Reverse(self.degree(&n.id)));
+        ranked.truncate(max_nodes);
+        self.induced(ranked.into_iter().cloned().collect())
+    }
+
+    /// Nodes plus every edge whose endpoints are both among them
+    fn induced(&self, nodes: Vec<GraphNode>) -> (Vec<GraphNode>, Vec<GraphEdge>) {
+        let ids: HashSet<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+        let edges = self
+            .edges
+            .iter()
+            .filter(|e| ids.contains(e.source.as_str()) && ids.contains(e.target.as_str()))
+            .cloned()
+            .collect();
+        (nodes, edges)
+    }
+
+    fn find(&self, concept: &str) -> Option<&GraphNode> {
+        let wanted = concept.to_lowercase();
+        self.nodes
+            .iter()
+            .find(|n| n.id.to_lowercase() == wanted || n.label.to_lowercase() == wanted)
+    }
+
+    /// Map a typed name to a node label, taking the top candidate if ambiguous
+    fn resolve(&self, concept: &str) -> String {
+        match self.resolver.resolve(concept) {
+            Resolution::Found(m) => m.label,
+            Resolution::Ambiguous(matches) => matches[0].label.clone(),
+            Resolution::NotFound => concept.to_string(),
+        }
+    }
+
+    pub fn stats(&self) -> Value {
+        let communities: HashSet<usize> = self.nodes.iter().filter_map(|n| n.community).collect();
+        json!({
+            "nodes": self.nodes.len(),
+            "edges": self.edges.len(),
+            "communities": communities.len(),
+        })
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Value {
+        let results: Vec<Value> = self
+            .resolver
+            .candidates(query, limit)
+            .into_iter()
+            .filter_map(|m| {
+                let node = self.find(&m.label)?;
+                let mut value = node_json(node);
+                value["score"] = json!(m.score);
+                value["match"] = json!(m.kind.to_string());
+                Some(value)
+            })
+            .collect();
+        json!({ "results": results })
+    }
+
+    pub fn expand(&self, concept: &str, opts: &SubgraphOptions) -> Option<Value> {
+        let concept = self.resolve(concept);
+        let (mut nodes, edges) = ego_network(&self.nodes, &self.edges, &concept, opts)?;
+        let center = self.find(&concept)?.id.clone();
+
+        let truncated = nodes.len() > MAX_RESPONSE_NODES;
+        if truncated {
+            nodes.sort_by_key(|n| (n.id != center, std::cmp::Reverse(self.degree(&n.id))));
+            nodes.truncate(MAX_RESPONSE_NODES);
+        }
+        let ids: HashSet<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+        let edges: Vec<&GraphEdge> = edges
+            .iter()
+            .filter(|e| ids.contains(e.source.as_str()) && ids.contains(e.target.as_str()))
+            .collect();
+
+        Some(json!({
+            "center": center,
+            "truncated": truncated,
+            "nodes": nodes.iter().map(node_json).collect::<Vec<_>>(),
+            "edges": edges.into_iter().map(edge_json).collect::<Vec<_>>(),
+        }))
+    }
+
+    pub fn path(&self, from: &str, to: &str, k: usize, opts: &PathOptions) -> Value {
+        let from = self.resolve(from);
+        let to = self.resolve(to);
+        let paths = self.paths.k_shortest_paths(&from, &to, k.max(1), opts);
+
+        let mut node_ids: Vec<String> = Vec::new();
+        let mut edges: Vec<Value> = Vec::new();
+        let mut seen_edges = HashSet::new();
+        let mut path_values = Vec::new();
+
+        for path in &paths {
+            let ids: Vec<String> = path
+                .nodes
+                .iter()
+                .map(|label| self.find(label).map_or(label.clone(), |n| n.id.clone()))
+                .collect();
+            for (i, hop) in path.hops.iter().enumerate() {
+                let (source, target) = if hop.forward {
+                    (&ids[i], &ids[i + 1])
+                } else {
+                    (&ids[i + 1], &ids[i])
+                };
+                let edge = self.edges.iter().find(|e| {
+                    &e.source == source && &e.target == target && e.relation == hop.relation
+                });
+                if let Some(edge) = edge
+                    && seen_edges.insert((source.clone(), target.clone(), hop.relation.clone()))
+                {
+                    edges.push(edge_json(edge));
+                }
+            }
+            for id in &ids {
+                if !node_ids.contains(id) {
+                    node_ids.push(id.clone());
+                }
+            }
+            path_values.push(json!({
+                "ids": ids,
+                "hops": path.hops.iter().map(|h| json!({
+                    "from": h.from,
+                    "to": h.to,
+                    "relation": h.relation,
+                    "weight": h.weight,
+                    "forward": h.forward,
+                })).collect::<Vec<_>>(),
+                "cost": path.cost,
+            }));
+        }
+
+        let nodes: Vec<Value> = node_ids
+            .iter()
+            .filter_map(|id| self.find(id))
+            .map(node_json)
+            .collect();
+
+        json!({
+            "from": from,
+            "to": to,
+            "paths": path_values,
+            "nodes": nodes,
+            "edges": edges,
+        })
+    }
+
+    pub fn communities(&self) -> Value {
+        let mut members: HashMap<usize, Vec<&GraphNode>> = HashMap::new();
+        for node in &self.nodes {
+            if let Some(c) = node.community {
+                members.entry(c).or_default().push(node);
+            }
+        }
+
+        let mut list: Vec<(usize, Vec<&GraphNode>)> = members.into_iter().collect();
+        list.sort_by_key(|(id, nodes)| (std::cmp::Reverse(nodes.len()), *id));
+
+        let communities: Vec<Value> = list
+            .into_iter()
+            .map(|(id, mut nodes)| {
+                nodes.sort_by_key(|n| std::cmp::Reverse(self.degree(&n.id)));
+                json!({
+                    "id": id,
+                    "size": nodes.len(),
+                    "top": nodes.iter().take(5).map(|n| &n.label).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        json!({ "communities": communities })
+    }
+
+    pub fn community(&self, id: usize, limit: usize) -> Value {
+        let mut members: Vec<&GraphNode> = self
+            .nodes
+            .iter()
+            .filter(|n| n.community == Some(id))
+            .collect();
+        let size = members.len();
+        members.sort_by_key(|n| std::cmp::Reverse(self.degree(&n.id)));
+        members.truncate(limit.min(MAX_RESPONSE_NODES));
+
+        let (nodes, edges) = self.induced(members.into_iter().cloned().collect());
+        json!({
+            "id": id,
+            "size": size,
+            "nodes": nodes.iter().map(node_json).collect::<Vec<_>>(),
+            "edges": edges.iter().map(edge_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Shared state for the request handlers
+pub struct AppState {
+    pub store: GraphStore,
+    /// Pre-rendered page
+    pub page: String,
+}
+
+/// Serve the page and API on `listener` until Ctrl-C
+pub async fn run(listener: TcpListener, state: AppState) -> Result<()> {
+    serve(listener, router(Arc::new(state)))
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await
+        .context("HTTP server failed")
+}
+
+fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(page))
+        .route("/api/stats", get(stats))
+        .route("/api/search", get(search))
+        .route("/api/expand", get(expand))
+        .route("/api/path", get(path))
+        .route("/api/communities", get(communities))
+        .route("/api/community", get(community))
+        .with_state(state)
+}
+
+/// Split a comma-separated query parameter
+fn split_list(list: Option<String>) -> Vec<String> {
+    list.map(|s| {
+        s.split(',')
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+async fn page(State(state): State<Arc<AppState>>) -> Html<String> {
+    Html(state.page.clone())
+}
+
+async fn stats(State(state): State<Arc<AppState>>) -> Json<Value> {
+    Json(state.store.stats())
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    q: String,
+    limit: Option<usize>,
+}
+
+async fn search(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SearchParams>,
+) -> Json<Value> {
+    Json(state.store.search(&params.q, params.limit.unwrap_or(10)))
+}
+
+#[derive(Deserialize)]
+struct ExpandParams {
+    id: String,
+    depth: Option<usize>,
+    min_weight: Option<f64>,
+    /// Comma-separated relations to skip
+    exclude: Option<String>,
+}
+
+async fn expand(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ExpandParams>,
+) -> Response {
+    let opts = SubgraphOptions {
+        depth: params.depth.unwrap_or(1).min(5),
+        min_weight: params.min_weight.unwrap_or(0.0),
+        include_relations: Vec::new(),
+        exclude_relations: split_list(params.exclude),
+    };
+    match state.store.expand(&params.id, &opts) {
+        Some(value) => Json(value).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("concept '{}' not found", params.id) })),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct PathParams {
+    from: String,
+    to: String,
+    k: Option<usize>,
+    directed: Option<bool>,
+    /// Comma-separated relations to skip
+    exclude: Option<String>,
+}
+
+async fn path(State(state): State<Arc<AppState>>, Query(params): Query<PathParams>) -> Json<Value> {
+    let opts = PathOptions {
+        directed: params.directed.unwrap_or(false),
+        exclude_relations: split_list(params.exclude),
+        ..Default::default()
+    };
+    Json(state.store.path(
+        &params.from,
+        &params.to,
+        params.k.unwrap_or(1).min(10),
+        &opts,
+    ))
+}
+
+async fn communities(State(state): State<Arc<AppState>>) -> Json<Value> {
+    Json(state.store.communities())
+}
+
+#[derive(Deserialize)]
+struct CommunityParams {
+    id: usize,
+    limit: Option<usize>,
+}
+
+async fn community(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CommunityParams>,
+) -> Json<Value> {
+    Json(
+        state
+            .store
+            .community(params.id, params.limit.unwrap_or(300)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, community: usize) -> GraphNode {
+        GraphNode {
+            id: id.into(),
+            label: id.into(),
+            community: Some(community),
+            degree: None,
+            entity_type: None,
+            tenant: "default".into(),
+            aliases: Vec::new(),
+        }
+    }
+
+    fn edge(source: &str, target: &str, relation: &str) -> GraphEdge {
+        GraphEdge {
+            source: source.into(),
+            target: target.into(),
+            relation: relation.into(),
+            weight: 1.0,
+            sources: Vec::new(),
+        }
+    }
+
+    fn store() -> GraphStore {
+        GraphStore::new(
+            vec![
+                node("kafka", 0),
+                node("producer", 0),
+                node("consumer", 0),
+                node("zookeeper", 1),
+                node("java", 1),
+            ],
+            vec![
+                edge("producer", "kafka", "writes to"),
+                edge("consumer", "kafka", "reads from"),
+                edge("kafka", "zookeeper", "depends on"),
+                edge("zookeeper", "java", "runs on"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_overview_keeps_best_connected() {
+        let (nodes, edges) = store().overview(2);
+        let ids: HashSet<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, HashSet::from(["kafka", "zookeeper"]));
+        assert_eq!(edges.len(), 1);
+    }
+
+    #[test]
+    fn test_search_returns_node_data() {
+        let value = store().search("zookeper", 5);
+        let results = value["results"].as_array().unwrap();
+        assert_eq!(results[0]["id"], "zookeeper");
+        assert_eq!(results[0]["match"], "fuzzy");
+    }
+
+    #[test]
+    fn test_expand_neighbourhood() {
+        let opts = SubgraphOptions {
+            depth: 1,
+            ..Default::default()
+        };
+        let value = store().expand("Kafka", &opts).unwrap();
+        assert_eq!(value["center"], "kafka");
+        assert_eq!(value["nodes"].as_array().unwrap().len(), 4);
+        assert_eq!(value["edges"].as_array().unwrap().len(), 3);
+        assert!(store().expand("redis", &opts).is_none());
+    }
+
+    #[test]
+    fn test_path_includes_hop_edges() {
+        let value = store().path("producer", "java", 1, &PathOptions::default());
+        let path = &value["paths"][0];
+        assert_eq!(path["ids"].as_array().unwrap().len(), 4);
+        assert_eq!(value["edges"].as_array().unwrap().len(), 3);
+        assert_eq!(value["nodes"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_communities_and_members() {
+        let store = store();
+        let list = store.communities();
+        assert_eq!(list["communities"][0]["id"], 0);
+        assert_eq!(list["communities"][0]["size"], 3);
+
+        let members = store.community(1, 10);
+        assert_eq!(members["nodes"].as_array().unwrap().len(), 2);
+        assert_eq!(members["edges"].as_array().unwrap().len(), 1);
+        assert_eq!(store.stats()["communities"], 2);
+    }
+}
@@ -0,0 +1,563 @@
+//! Where `build` reads documents from: a local path, a web page or sitemap, or a git repository
+//!
+//! Remote sources are materialized into a temporary directory so the regular
+//! parsers can read them, and every file keeps the URL or commit it came from.
+
+use anyhow::{Context, Result};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use reqwest::Url;
+use scraper::{Html, Selector};
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// The `path` argument of `build`, classified
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceSpec {
+    Local(PathBuf),
+    /// A web page or a sitemap (`urlset` or `sitemapindex`)
+    Web(Url),
+    /// A repository URL or local path, checked out at `reference` when given
+    Git {
+        repo: String,
+        reference: Option<String>,
+    },
+}
+
+impl SourceSpec {
+    /// `https://…` is fetched, `git+https://…#ref` and `git+file://…` are cloned,
+    /// and anything else is a local path. A local path with `git_ref` is a git repository.
+    pub fn parse(input: &str, git_ref: Option<&str>) -> Result<Self> {
+        if let Some(rest) = input.strip_prefix("git+") {
+            let (repo, fragment) = match rest.split_once('#') {
+                Some((repo, reference)) => (repo, Some(reference.to_string())),
+                None => (rest, None),
+            };
+            return Ok(Self::Git {
+                repo: repo.to_string(),
+                reference: git_ref.map(str::to_string).or(fragment),
+            });
+        }
+        if input.starts_with("http://") || input.starts_with("https://") {
+            let url = Url::parse(input).with_context(|| format!("Invalid URL: {}", input))?;
+            return Ok(Self::Web(url));
+        }
+        match git_ref {
+            Some(reference) => Ok(Self::Git {
+                repo: input.to_string(),
+                reference: Some(reference.to_string()),
+            }),
+            None => Ok(Self::Local(PathBuf::from(input))),
+        }
+    }
+
+    /// Base path that output files are named after (`<base>.kg.json`)
+    ///
+    /// Remote sources are named after their host or repository, in the current directory.
+    pub fn output_base(&self) -> PathBuf {
+        match self {
+            Self::Local(path) => path.clone(),
+            Self::Web(url) => PathBuf::from(url.host_str().unwrap_or("web").replace('.', "_")),
+            Self::Git { repo, .. } => {
+                let name = repo
+                    .trim_end_matches('/')
+                    .rsplit(['/', ':'])
+                    .next()
+                    .unwrap_or("repo");
+                PathBuf::from(name.trim_end_matches(".git").replace('.', "_"))
+            }
+        }
+    }
+}
+
+/// Limits for following sitemaps and links
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    /// Link hops to follow from each fetched page; sitemap entries do not count as hops
+    pub depth: usize,
+    /// Stop after this many documents
+    pub max_pages: usize,
+    /// Hosts that may be fetched (subdomains included); empty means the start URL's host
+    pub domains: Vec<String>,
+    pub timeout: Duration,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            depth: 0,
+            max_pages: 100,
+            domains: Vec::new(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A document written to a local file, with the URL or commit it came from
+#[derive(Debug, Clone)]
+pub struct FetchedFile {
+    pub path: PathBuf,
+    pub source: String,
+}
+
+/// Documents fetched from the web; the files live as long as this value
+pub struct Fetched {
+    pub files: Vec<FetchedFile>,
+    /// URLs that were not fetched, with the reason
+    pub skipped: Vec<String>,
+    _dir: TempDir,
+}
+
+/// Fetch a page or sitemap, following nested sitemaps and, up to `depth`, links
+pub async fn fetch_web(start: &Url, options: &CrawlOptions) -> Result<Fetched> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("rknowledge/", env!("CARGO_PKG_VERSION")))
+        .timeout(options.timeout)
+        .build()?;
+    let dir = tempfile::tempdir().context("Failed to create a directory for fetched pages")?;
+
+    let domains: Vec<String> = if options.domains.is_empty() {
+        start.host_str().map(str::to_string).into_iter().collect()
+    } else {
+        options.domains.iter().map(|d| d.to_lowercase()).collect()
+    };
+    let allowed = |url: &Url| {
+        url.host_str().is_some_and(|host| {
+            let host = host.to_lowercase();
+            domains
+                .iter()
+                .any(|d| host == *d || host.ends_with(&format!(".{}", d)))
+        })
+    };
+
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    let mut seen: HashSet<Url> = HashSet::new();
+    let mut queue: VecDeque<(Url, usize)> = VecDeque::from([(start.clone(), 0)]);
+
+    while let Some((mut url, depth)) = queue.pop_front() {
+        url.set_fragment(None);
+        if !seen.insert(url.clone()) {
+            continue;
+        }
+        if files.len() >= options.max_pages {
+            skipped.push(format!(
+                "{}: page limit ({}) reached",
+                url, options.max_pages
+            ));
+            continue;
+        }
+        if !allowed(&url) {
+            skipped.push(format!("{}: outside the allowed domains", url));
+            continue;
+        }
+
+        let response = match client.get(url.clone()).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                skipped.push(format!("{}: {}", url, e));
+                continue;
+            }
+        };
+        let status = response.status();
+        if !status.is_success() {
+            skipped.push(format!("{}: HTTP {}", url, status));
+            continue;
+        }
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_lowercase();
+        let body = match response.bytes().await {
+            Ok(body) => body,
+            Err(e) => {
+                skipped.push(format!("{}: {}", url, e));
+                continue;
+            }
+        };
+
+        // Sitemaps list pages (and other sitemaps) rather than being documents
+        if content_type.contains("xml") || url.path().ends_with(".xml") {
+            let text = String::from_utf8_lossy(&body);
+            if let Some(locations) = sitemap_locations(&text) {
+                for location in locations {
+                    match url.join(&location) {
+                        Ok(loc) => queue.push_back((loc, depth)),
+                        Err(_) => skipped.push(format!("{}: invalid URL", location)),
+                    }
+                }
+                continue;
+            }
+        }
+
+        let Some(extension) = document_extension(&url, &content_type) else {
+            skipped.push(format!(
+                "{}: unsupported content type '{}'",
+                url, content_type
+            ));
+            continue;
+        };
+
+        if extension == "html" && depth < options.depth {
+            let html = String::from_utf8_lossy(&body);
+            for link in page_links(&url, &html) {
+                queue.push_back((link, depth + 1));
+            }
+        }
+
+        let path = dir
+            .path()
+            .join(format!("{:04}-{}.{}", files.len(), slug(&url), extension));
+        std::fs::write(&path, &body)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        files.push(FetchedFile {
+            path,
+            source: url.to_string(),
+        });
+    }
+
+    Ok(Fetched {
+        files,
+        skipped,
+        _dir: dir,
+    })
+}
+
+/// The `<loc>` entries of a sitemap or sitemap index, or `None` if the XML is not a sitemap
+fn sitemap_locations(xml: &str) -> Option<Vec<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut is_sitemap = false;
+    let mut in_loc = false;
+    let mut locations = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                b"urlset" | b"sitemapindex" => is_sitemap = true,
+                b"loc" => in_loc = true,
+                _ => {}
+            },
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"loc" => in_loc = false,
+            Ok(Event::Text(t)) if in_loc => {
+                if let Ok(text) = t.unescape() {
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        locations.push(text.to_string());
+                    }
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    is_sitemap.then_some(locations)
+}
+
+/// Absolute http(s) links of a page, without fragments
+fn page_links(base: &Url, html: &str) -> Vec<Url> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href]").unwrap();
+    document
+        .select(&selector)
+        .filter_map(|a| a.value().attr("href"))
+        .filter_map(|href| base.join(href).ok())
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|mut url| {
+            url.set_fragment(None);
+            url
+        })
+        .collect()
+}
+
+/// Extension the fetched body is saved under, so the matching parser reads it
+fn document_extension(url: &Url, content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    let by_type = match mime {
+        "text/html" | "application/xhtml+xml" => Some("html"),
+        "application/pdf" => Some("pdf"),
+        "text/plain" => Some("txt"),
+        "text/markdown" | "text/x-markdown" => Some("md"),
+        "application/rtf" | "text/rtf" => Some("rtf"),
+        "application/epub+zip" => Some("epub"),
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => Some("docx"),
+        "application/vnd.oasis.opendocument.text" => Some("odt"),
+        _ => None,
+    };
+    by_type.or_else(|| {
+        let extension = Path::new(url.path()).extension()?.to_str()?.to_lowercase();
+        crate::parser::SUPPORTED_EXTENSIONS
+            .iter()
+            .find(|e| **e == extension)
+            .copied()
+    })
+}
+
+/// File-name-safe slug of a URL's host and path
+fn slug(url: &Url) -> String {
+    let raw = format!("{}{}", url.host_str().unwrap_or(""), url.path());
+    let slug: String = raw
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug.trim_matches('-');
+    slug.chars().take(80).collect()
+}
+
+/// Provenance for a local file: its path relative to the build root
+///
+/// Sources are quoted in prompts, so they must not depend on where the build
+/// runs from; otherwise a recording made in one directory misses on replay in
+/// another. A root that is itself a file gives the file name.
+pub fn local_source(root: &Path, file: &Path) -> String {
+    let relative = match file.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative,
+        _ => file.file_name().map_or(file, Path::new),
+    };
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+/// A repository checked out into a temporary directory
+pub struct Checkout {
+    pub dir: TempDir,
+    /// Full SHA of the checked out commit
+    pub commit: String,
+}
+
+impl Checkout {
+    /// The commit abbreviated to at most 12 characters
+    pub fn short_commit(&self) -> &str {
+        &self.commit[..self.commit.len().min(12)]
+    }
+
+    /// Provenance for a file in the checkout: `<repo>@<short sha>:<relative path>`
+    pub fn source(&self, repo: &str, file: &Path) -> String {
+        let relative = file.strip_prefix(self.dir.path()).unwrap_or(file);
+        format!(
+            "{}@{}:{}",
+            repo,
+            self.short_commit(),
+            relative.to_string_lossy().replace('\\', "/")
+        )
+    }
+}
+
+/// Clone `repo` (a URL or local path) and check out `reference`, or the default branch
+///
+/// Neither value can be read by git as an option: the repository follows `--`
+/// and a reference starting with `-` is rejected.
+pub fn checkout_git(repo: &str, reference: Option<&str>) -> Result<Checkout> {
+    if let Some(reference) = reference.filter(|r| r.starts_with('-')) {
+        anyhow::bail!("Invalid git reference '{}': it starts with '-'", reference);
+    }
+    let dir = tempfile::tempdir().context("Failed to create a directory for the checkout")?;
+    let target = dir.path().to_string_lossy().to_string();
+
+    git(None, &["clone", "--quiet", "--", repo, &target])
+        .with_context(|| format!("Failed to clone {}", repo))?;
+    if let Some(reference) = reference {
+        git(Some(dir.path()), &["checkout", "--quiet", reference])
+            .with_context(|| format!("Failed to check out '{}' in {}", reference, repo))?;
+    }
+    let commit = git(Some(dir.path()), &["rev-parse", "HEAD"])?;
+
+    Ok(Checkout { dir, commit })
+}
+
+fn git(dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command
+        .args(args)
+        .output()
+        .context("Failed to run git. Is it installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::http::header;
+    use axum::routing::get;
+
+    /// Serve a small site on a random local port and return its base URL
+    async fn stub_site() -> Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let sitemap = format!(
+            r#"<?xml version="1.0"?><sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+               <sitemap><loc>{base}/pages.xml</loc></sitemap></sitemapindex>"#
+        );
+        let pages = format!(
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+               <url><loc>{base}/a</loc></url>
+               <url><loc>/notes.txt</loc></url>
+               <url><loc>https://elsewhere.example/c</loc></url></urlset>"#
+        );
+        let html = |body: &'static str| ([(header::CONTENT_TYPE, "text/html")], body);
+        let xml = |body: String| ([(header::CONTENT_TYPE, "application/xml")], body);
+        let app = Router::new()
+            .route("/sitemap.xml", get(move || async move { xml(sitemap) }))
+            .route("/pages.xml", get(move || async move { xml(pages) }))
+            .route(
+                "/a",
+                get(move || async move {
+                    html(r##"<p>Tokio</p><a href="/b#top">b</a><a href="mailto:x@y">m</a>"##)
+                }),
+            )
+            .route(
+                "/b",
+                get(move || async move { html(r#"<p>Serde</p><a href="/a">a</a>"#) }),
+            )
+            .route("/notes.txt", get(|| async { "Axum uses Tokio." }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        Url::parse(&base).unwrap()
+    }
+
+    fn sources(fetched: &Fetched) -> Vec<String> {
+        fetched.files.iter().map(|f| f.source.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_sitemap_index_is_followed_within_domain() {
+        let base = stub_site().await;
+        let fetched = fetch_web(
+            &base.join("/sitemap.xml").unwrap(),
+            &CrawlOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            sources(&fetched),
+            vec![
+                base.join("/a").unwrap().to_string(),
+                base.join("/notes.txt").unwrap().to_string()
+            ]
+        );
+        assert!(fetched.files[0].path.to_string_lossy().ends_with(".html"));
+        assert!(fetched.files[1].path.to_string_lossy().ends_with(".txt"));
+        assert!(fetched.skipped[0].contains("outside the allowed domains"));
+    }
+
+    #[tokio::test]
+    async fn test_links_followed_to_depth_and_page_limit() {
+        let base = stub_site().await;
+        let start = base.join("/a").unwrap();
+
+        let fetched = fetch_web(&start, &CrawlOptions::default()).await.unwrap();
+        assert_eq!(fetched.files.len(), 1);
+
+        let options = CrawlOptions {
+            depth: 1,
+            ..Default::default()
+        };
+        let fetched = fetch_web(&start, &options).await.unwrap();
+        assert_eq!(
+            sources(&fetched),
+            vec![start.to_string(), base.join("/b").unwrap().to_string()]
+        );
+        let text = std::fs::read_to_string(&fetched.files[1].path).unwrap();
+        assert!(text.contains("Serde"));
+
+        let options = CrawlOptions {
+            depth: 1,
+            max_pages: 1,
+            ..Default::default()
+        };
+        let fetched = fetch_web(&start, &options).await.unwrap();
+        assert_eq!(fetched.files.len(), 1);
+        assert!(fetched.skipped[0].contains("page limit"));
+    }
+
+    #[test]
+    fn test_parse_source_spec() {
+        assert_eq!(
+            SourceSpec::parse("docs", None).unwrap(),
+            SourceSpec::Local(PathBuf::from("docs"))
+        );
+        assert!(matches!(
+            SourceSpec::parse("https://example.com/sitemap.xml", None).unwrap(),
+            SourceSpec::Web(_)
+        ));
+        let spec = SourceSpec::parse("git+https://github.com/org/tool.git#v1.2", None).unwrap();
+        assert_eq!(
+            spec,
+            SourceSpec::Git {
+                repo: "https://github.com/org/tool.git".into(),
+                reference: Some("v1.2".into())
+            }
+        );
+        assert_eq!(spec.output_base(), PathBuf::from("tool"));
+        assert!(matches!(
+            SourceSpec::parse("../repo", Some("main")).unwrap(),
+            SourceSpec::Git { .. }
+        ));
+        assert_eq!(
+            SourceSpec::parse("https://docs.rs/tokio", None)
+                .unwrap()
+                .output_base(),
+            PathBuf::from("docs_rs")
+        );
+    }
+
+    #[test]
+    fn test_local_source_is_relative_to_the_root() {
+        let root = Path::new("/home/me/docs");
+        assert_eq!(
+            local_source(root, &root.join("guide/intro.md")),
+            "guide/intro.md"
+        );
+        assert_eq!(
+            local_source(Path::new("docs"), Path::new("docs/a.md")),
+            "a.md"
+        );
+        let file = Path::new("../notes/a.md");
+        assert_eq!(local_source(file, file), "a.md");
+    }
+
+    #[test]
+    fn test_checkout_local_repository_at_ref() {
+        let repo = tempfile::tempdir().unwrap();
+        let run = |args: &[&str]| git(Some(repo.path()), args).unwrap();
+        run(&["init", "--quiet"]);
+        run(&["config", "user.email", "test@example.com"]);
+        run(&["config", "user.name", "Test"]);
+        std::fs::write(repo.path().join("README.md"), "Version one").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "--quiet", "-m", "one"]);
+        run(&["tag", "v1"]);
+        let first = run(&["rev-parse", "HEAD"]);
+        std::fs::write(repo.path().join("README.md"), "Version two").unwrap();
+        run(&["commit", "--quiet", "-am", "two"]);
+
+        let path = repo.path().to_string_lossy().to_string();
+        let checkout = checkout_git(&path, Some("v1")).unwrap();
+        assert_eq!(checkout.commit, first);
+        let readme = checkout.dir.path().join("README.md");
+        assert_eq!(std::fs::read_to_string(&readme).unwrap(), "Version one");
+        assert_eq!(
+            checkout.source("repo", &readme),
+            format!("repo@{}:README.md", &first[..12])
+        );
+
+        assert!(checkout_git(&path, Some("no-such-ref")).is_err());
+        let err = checkout_git(&path, Some("--upload-pack=touch pwned"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("starts with '-'"), "{}", err);
+        assert!(checkout_git("--upload-pack=touch pwned", None).is_err());
+    }
+}
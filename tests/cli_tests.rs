@@ -50,7 +50,14 @@ fn test_build_help() {
         .stdout(predicate::str::contains("PATH"))
         .stdout(predicate::str::contains("--provider"))
         .stdout(predicate::str::contains("--model"))
-        .stdout(predicate::str::contains("--output"));
+        .stdout(predicate::str::contains("--output"))
+        .stdout(predicate::str::contains("--ref"))
+        .stdout(predicate::str::contains("--max-pages"))
+        .stdout(predicate::str::contains("--allow-domain"))
+        .stdout(predicate::str::contains("--chunker"))
+        .stdout(predicate::str::contains("semantic"))
+        .stdout(predicate::str::contains("Tokens per chunk"))
+        .stdout(predicate::str::contains("--coref"));
 }
 
 #[test]
@@ -64,11 +71,393 @@ fn test_build_requires_path() {
 
 #[test]
 fn test_build_rejects_invalid_provider() {
+    let dir = tempdir().unwrap();
+    let config_dir = dir.path().join("config/rknowledge");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        "[providers]\n\n[neo4j]\nuri = \"bolt://localhost:7687\"\nuser = \"neo4j\"\npassword = \"x\"\n",
+    )
+    .unwrap();
+
     cmd()
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
         .args(["build", "/tmp", "--provider", "invalid_provider"])
         .assert()
         .failure()
-        .stderr(predicate::str::contains("invalid value"));
+        .stderr(predicate::str::contains(
+            "Unknown provider 'invalid_provider'",
+        ));
+}
+
+#[test]
+fn test_build_with_local_provider_checks_the_model() {
+    let dir = tempdir().unwrap();
+    let config_dir = dir.path().join("config/rknowledge");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        "[providers.local]\nmodel = \"missing.gguf\"\n\n[neo4j]\nuri = \"bolt://localhost:7687\"\nuser = \"neo4j\"\npassword = \"x\"\n",
+    )
+    .unwrap();
+
+    let expected = if cfg!(feature = "local") {
+        "Failed to open model missing.gguf"
+    } else {
+        "Rebuild with: cargo build --release --features local"
+    };
+    cmd()
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
+        .current_dir(dir.path())
+        .args(["build", ".", "--provider", "local"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_build_with_http_provider() {
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{Value, json};
+
+    // A bespoke endpoint: key in x-api-key, prompt in one field, text under result
+    let app = Router::new().route(
+        "/v2/generate",
+        post(|headers: HeaderMap, Json(body): Json<Value>| async move {
+            assert_eq!(headers["x-api-key"], "secret");
+            let prompt = body["prompt"].as_str().unwrap();
+            assert!(prompt.contains("Tokio"));
+            // The few-shot example from [domain] comes before the chunk
+            assert!(prompt.contains("context: ```Serde serializes Rust data.```"));
+            let relations =
+                r#"[{"node_1": "tokio", "node_2": "rust", "edge": "is an async runtime for"}]"#;
+            Json(json!({ "result": { "text": relations } }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let dir = tempdir().unwrap();
+    let config_dir = dir.path().join("config/rknowledge");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            r#"default_provider = "gateway"
+
+[providers.http.gateway]
+url = "http://{addr}/v2/generate"
+api_key = "secret"
+auth_header = "x-api-key"
+response_path = "$.result.text"
+body = {{ prompt = "{{system}}\n\n{{user}}", limit = "{{max_tokens}}" }}
+
+[[domain.examples]]
+text = "Serde serializes Rust data."
+relations = [{{ node_1 = "serde", node_2 = "rust", edge = "serializes data of" }}]
+
+[neo4j]
+uri = "bolt://localhost:7687"
+user = "neo4j"
+password = "x"
+"#
+        ),
+    )
+    .unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir_all(&data).unwrap();
+    fs::write(
+        data.join("notes.md"),
+        "# Notes\n\nTokio is an async runtime for Rust.\n",
+    )
+    .unwrap();
+
+    cmd()
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
+        .args(["build", data.to_str().unwrap(), "--output", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Provider: gateway"))
+        .stdout(predicate::str::contains("Few-shot examples: 1 of 1"));
+
+    let json = fs::read_to_string(data.with_extension("kg.json")).unwrap();
+    assert!(json.contains("is an async runtime for"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_build_replays_a_recording_without_the_llm() {
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{Value, json};
+
+    let app = Router::new().route(
+        "/generate",
+        post(|Json(_): Json<Value>| async move {
+            let relations =
+                r#"[{"node_1": "tokio", "node_2": "rust", "edge": "is an async runtime for"}]"#;
+            Json(json!({ "text": relations }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let dir = tempdir().unwrap();
+    let config_dir = dir.path().join("config/rknowledge");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            r#"default_provider = "gateway"
+
+[providers.http.gateway]
+url = "http://{addr}/generate"
+api_key = ""
+response_path = "$.text"
+body = {{ prompt = "{{system}}\n\n{{user}}" }}
+
+[neo4j]
+uri = "bolt://localhost:7687"
+user = "neo4j"
+password = "x"
+"#
+        ),
+    )
+    .unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir_all(&data).unwrap();
+    fs::write(
+        data.join("notes.md"),
+        "Tokio is an async runtime for Rust.\n",
+    )
+    .unwrap();
+    let fixtures = dir.path().join("fixtures");
+    let build = |extra: &[&str]| {
+        let mut command = cmd();
+        command
+            .env("XDG_CONFIG_HOME", dir.path().join("config"))
+            .args(["build", data.to_str().unwrap(), "--output", "json"])
+            .args(["--fixtures", fixtures.to_str().unwrap()])
+            .args(extra);
+        command
+    };
+
+    build(&["--record"]).assert().success();
+    let recorded = fs::read_to_string(data.with_extension("kg.json")).unwrap();
+    assert!(recorded.contains("is an async runtime for"));
+    assert!(fixtures.join("manifest.json").exists());
+
+    // The endpoint is gone; the replay answers from the fixtures alone
+    server.abort();
+    fs::remove_file(data.with_extension("kg.json")).unwrap();
+    build(&["--provider", "replay"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Provider: replay"));
+    let replayed = fs::read_to_string(data.with_extension("kg.json")).unwrap();
+    assert_eq!(replayed, recorded);
+
+    // A colleague's copy, built by relative path from another directory
+    let elsewhere = dir.path().join("elsewhere");
+    fs::create_dir_all(elsewhere.join("data")).unwrap();
+    fs::copy(data.join("notes.md"), elsewhere.join("data/notes.md")).unwrap();
+    let mut command = cmd();
+    command
+        .current_dir(&elsewhere)
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
+        .args(["build", "data", "--output", "json", "--provider", "replay"])
+        .args(["--fixtures", fixtures.to_str().unwrap()])
+        .assert()
+        .success();
+    let copied = fs::read_to_string(elsewhere.join("data.kg.json")).unwrap();
+    assert_eq!(copied, recorded);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_eval_compares_two_prompts() {
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{Value, json};
+
+    // Prompt B finds one of A's relations (reversed) and one of its own
+    let app = Router::new().route(
+        "/generate",
+        post(|Json(body): Json<Value>| async move {
+            let relations = if body["prompt"].as_str().unwrap().contains("PROMPT B") {
+                r#"[{"node_1": "rust", "node_2": "tokio", "edge": "has runtime"},
+                    {"node_1": "tokio", "node_2": "epoll", "edge": "uses"}]"#
+            } else {
+                r#"[{"node_1": "tokio", "node_2": "rust", "edge": "is an async runtime for"}]"#
+            };
+            Json(json!({ "text": relations }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let dir = tempdir().unwrap();
+    let config_dir = dir.path().join("config/rknowledge");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            r#"default_provider = "gateway"
+
+[providers.http.gateway]
+url = "http://{addr}/generate"
+api_key = ""
+response_path = "$.text"
+body = {{ prompt = "{{system}}\n\n{{user}}" }}
+
+[neo4j]
+uri = "bolt://localhost:7687"
+user = "neo4j"
+password = "x"
+"#
+        ),
+    )
+    .unwrap();
+    let data = dir.path().join("data");
+    fs::create_dir_all(&data).unwrap();
+    fs::write(
+        data.join("notes.md"),
+        "Tokio is an async runtime for Rust.\n",
+    )
+    .unwrap();
+    let prompt_b = dir.path().join("b.txt");
+    fs::write(
+        &prompt_b,
+        "PROMPT B: extract relations{{#domain}} about {{domain}}{{/domain}} as JSON.\n",
+    )
+    .unwrap();
+
+    let output = cmd()
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
+        .args(["eval", data.to_str().unwrap(), "--json", "--prompt-b"])
+        .arg(&prompt_b)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["sampled_chunks"], 1);
+    assert_eq!(report["a"]["prompt"], "built-in");
+    assert_eq!(report["a"]["relations"], 1);
+    assert_eq!(report["b"]["relations"], 2);
+    assert_eq!(report["b"]["parse_failures"], 0);
+    assert_ne!(report["a"]["version"], report["b"]["version"]);
+    assert_eq!(report["overlap"]["shared_relations"], 1);
+    assert_eq!(report["overlap"]["relations"], 0.5);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_eval_scores_against_gold_annotations() {
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{Value, json};
+
+    let app = Router::new().route(
+        "/generate",
+        post(|Json(_): Json<Value>| async move {
+            let relations = r#"[{"node_1": "Tokio", "node_1_type": "library",
+                "node_2": "rust", "node_2_type": "programming language",
+                "edge": "is an async runtime for"}]"#;
+            Json(json!({ "text": relations }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let dir = tempdir().unwrap();
+    let config_dir = dir.path().join("config/rknowledge");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            r#"default_provider = "gateway"
+
+[providers.http.gateway]
+url = "http://{addr}/generate"
+api_key = ""
+response_path = "$.text"
+body = {{ prompt = "{{system}}\n\n{{user}}" }}
+
+[neo4j]
+uri = "bolt://localhost:7687"
+user = "neo4j"
+password = "x"
+"#
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("notes.md"),
+        "Tokio is an async runtime for Rust.\n",
+    )
+    .unwrap();
+    let gold = dir.path().join("gold.json");
+    fs::write(
+        &gold,
+        r#"{"documents": [
+            {"path": "notes.md",
+             "entities": [{"label": "tokio", "type": "library"},
+                          {"label": "rust", "type": "programming language"}],
+             "relations": [{"source": "tokio", "target": "rust"}]},
+            {"text": "Axum is a web framework built on Tokio.",
+             "relations": [{"source": "axum", "target": "tokio", "relation": "built on"}]}
+        ]}"#,
+    )
+    .unwrap();
+    let fixtures = dir.path().join("fixtures");
+    let eval = |extra: &[&str]| {
+        let output = cmd()
+            .env("XDG_CONFIG_HOME", dir.path().join("config"))
+            .args(["eval", "--json", "--gold", gold.to_str().unwrap()])
+            .args(["--fixtures", fixtures.to_str().unwrap()])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        serde_json::from_slice::<Value>(&output.stdout).unwrap()
+    };
+
+    let recorded = eval(&["--record"]);
+    assert_eq!(recorded["documents"], 2);
+    assert_eq!(recorded["entities"]["matched"], 3);
+    assert_eq!(recorded["entities"]["precision"], 0.75);
+    assert_eq!(recorded["relations"]["matched"], 1);
+    assert_eq!(recorded["relations"]["recall"], 0.5);
+    assert_eq!(recorded["entity_types"]["accuracy"], 1.0);
+    assert_eq!(recorded["per_document"][0]["relations"]["f1"], 1.0);
+
+    // The same scores from the recording, with the endpoint gone
+    server.abort();
+    let replayed = eval(&["--provider", "replay"]);
+    assert_eq!(replayed["provider"], "replay");
+    assert_eq!(replayed["entities"], recorded["entities"]);
+    assert_eq!(replayed["relations"], recorded["relations"]);
+}
+
+#[test]
+fn test_eval_needs_a_comparison_or_gold_set() {
+    cmd()
+        .args(["eval", "./docs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--prompt-b"));
+}
+
+#[test]
+fn test_eval_rejects_prompt_b_with_gold() {
+    cmd()
+        .args(["eval", "--gold", "gold.json", "--prompt-b", "b.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
 }
 
 #[test]
@@ -80,6 +469,92 @@ fn test_build_rejects_invalid_output() {
         .stderr(predicate::str::contains("invalid value"));
 }
 
+#[test]
+fn test_build_from_mapping_without_llm() {
+    let dir = tempdir().unwrap();
+    let config_dir = dir.path().join("config/rknowledge");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        "[providers]\n\n[neo4j]\nuri = \"bolt://localhost:7687\"\nuser = \"neo4j\"\npassword = \"x\"\n",
+    )
+    .unwrap();
+
+    let data = dir.path().join("data");
+    fs::create_dir_all(&data).unwrap();
+    fs::write(
+        data.join("schema.sql"),
+        "CREATE TABLE users (id INT PRIMARY KEY);\nCREATE TABLE posts (id INT, author_id INT REFERENCES users(id));\n",
+    )
+    .unwrap();
+    let mapping = data.join("mapping.toml");
+    fs::write(
+        &mapping,
+        "[[source]]\npath = \"schema.sql\"\ncolumns = false\n",
+    )
+    .unwrap();
+
+    cmd()
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
+        .args([
+            "build",
+            data.to_str().unwrap(),
+            "--output",
+            "json",
+            "--mapping",
+        ])
+        .arg(&mapping)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 relations from 1 records"));
+
+    let json = fs::read_to_string(data.with_extension("kg.json")).unwrap();
+    assert!(json.contains("references via author_id"));
+}
+
+#[test]
+fn test_build_explains_skipped_files() {
+    let dir = tempdir().unwrap();
+    let config_dir = dir.path().join("config/rknowledge");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        "[providers]\n\n[neo4j]\nuri = \"bolt://localhost:7687\"\nuser = \"neo4j\"\npassword = \"x\"\n",
+    )
+    .unwrap();
+
+    let data = dir.path().join("data");
+    fs::create_dir_all(data.join("drafts")).unwrap();
+    fs::write(data.join(".rknowledgeignore"), "drafts/\n").unwrap();
+    fs::write(data.join("drafts/wip.md"), "# Draft").unwrap();
+    fs::write(data.join("notes.md"), "# Notes").unwrap();
+    fs::write(data.join("schema.sql"), "CREATE TABLE users (id INT);\n").unwrap();
+    let mapping = data.join("mapping.toml");
+    fs::write(&mapping, "[[source]]\npath = \"schema.sql\"\n").unwrap();
+
+    cmd()
+        .env("XDG_CONFIG_HOME", dir.path().join("config"))
+        .args([
+            "build",
+            data.to_str().unwrap(),
+            "--output",
+            "json",
+            "--exclude",
+            "notes.md",
+            "--show-skipped",
+            "--mapping",
+        ])
+        .arg(&mapping)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("found 0"))
+        .stdout(predicate::str::contains(
+            "ignored by .rknowledgeignore (drafts/)",
+        ))
+        .stdout(predicate::str::contains("matches --exclude notes.md"))
+        .stdout(predicate::str::contains("unsupported file type"));
+}
+
 // ─── Export subcommand argument validation ───────────────────────────────
 
 #[test]
@@ -89,7 +564,12 @@ fn test_export_help() {
         .assert()
         .success()
         .stdout(predicate::str::contains("--format"))
-        .stdout(predicate::str::contains("--output"));
+        .stdout(predicate::str::contains("--output"))
+        .stdout(predicate::str::contains("html"))
+        .stdout(predicate::str::contains("turtle"))
+        .stdout(predicate::str::contains("parquet"))
+        .stdout(predicate::str::contains("neo4j-admin"))
+        .stdout(predicate::str::contains("--base-iri"));
 }
 
 #[test]
@@ -110,6 +590,22 @@ fn test_export_rejects_invalid_format() {
         .stderr(predicate::str::contains("invalid value"));
 }
 
+#[test]
+fn test_import_help_and_missing_file() {
+    cmd()
+        .args(["import", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("FILE"))
+        .stdout(predicate::str::contains("--batch-size"));
+
+    cmd()
+        .args(["import", "/nonexistent/graph.jsonl"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to open file"));
+}
+
 // ─── Query subcommand ───────────────────────────────────────────────────
 
 #[test]
@@ -121,6 +617,208 @@ fn test_query_help() {
         .stdout(predicate::str::contains("QUERY"));
 }
 
+// ─── Path subcommand ────────────────────────────────────────────────────
+
+#[test]
+fn test_path_help() {
+    cmd()
+        .args(["path", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--k-paths"))
+        .stdout(predicate::str::contains("--all-paths"))
+        .stdout(predicate::str::contains("--directed"))
+        .stdout(predicate::str::contains("--exclude-relation"))
+        .stdout(predicate::str::contains("--entity-type"))
+        .stdout(predicate::str::contains("--best-match"));
+}
+
+#[test]
+fn test_query_and_add_accept_best_match() {
+    for sub in ["query", "add"] {
+        cmd()
+            .args([sub, "--help"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("--best-match"));
+    }
+}
+
+#[test]
+fn test_path_requires_both_concepts() {
+    cmd()
+        .args(["path", "kafka"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("TO"));
+}
+
+// ─── Subgraph subcommand ────────────────────────────────────────────────
+
+const SAMPLE_GRAPH: &str = r#"{
+  "nodes": [
+    {"id": "kafka", "label": "kafka", "community": 0, "degree": 3},
+    {"id": "producer", "label": "producer", "community": 0, "degree": 1},
+    {"id": "zookeeper", "label": "zookeeper", "community": 1, "degree": 2},
+    {"id": "java", "label": "java", "community": 1, "degree": 1}
+  ],
+  "edges": [
+    {"source": "producer", "target": "kafka", "relation": "writes to", "weight": 2.0},
+    {"source": "kafka", "target": "zookeeper", "relation": "contextual proximity", "weight": 0.5},
+    {"source": "zookeeper", "target": "java", "relation": "runs on", "weight": 1.0}
+  ]
+}"#;
+
+#[test]
+fn test_subgraph_from_json_file() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("graph.json");
+    let output = dir.path().join("sub.json");
+    fs::write(&input, SAMPLE_GRAPH).unwrap();
+
+    cmd()
+        .args(["subgraph", "Kafka", "--depth", "2"])
+        .args(["--exclude-relation", "contextual proximity"])
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(&output)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Extracted 2 nodes, 1 edges"));
+
+    let written: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(written["nodes"].as_array().unwrap().len(), 2);
+    assert_eq!(written["edges"][0]["relation"], "writes to");
+}
+
+#[test]
+fn test_subgraph_stays_in_the_center_tenant() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("graph.json");
+    let output = dir.path().join("sub.json");
+    let graph = r#"{
+        "nodes": [
+            {"id": "kafka", "label": "Kafka", "tenant": "acme"},
+            {"id": "producer", "label": "Producer", "tenant": "acme"},
+            {"id": "kafka", "label": "Kafka", "tenant": "globex"},
+            {"id": "spark", "label": "Spark", "tenant": "globex"}
+        ],
+        "edges": [
+            {"source": "producer", "target": "kafka", "relation": "writes to", "weight": 1.0},
+            {"source": "kafka", "target": "spark", "relation": "feeds", "weight": 1.0}
+        ]
+    }"#;
+    fs::write(&input, graph).unwrap();
+
+    cmd()
+        .args(["subgraph", "Producer", "--depth", "2"])
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(&output)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Extracted 2 nodes, 1 edges"));
+
+    let written: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+    let nodes = written["nodes"].as_array().unwrap();
+    assert!(nodes.iter().all(|n| n["tenant"] == "acme"));
+    let edges = written["edges"].as_array().unwrap();
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0]["relation"], "writes to");
+}
+
+#[test]
+fn test_subgraph_html_and_unknown_concept() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("graph.json");
+    let output = dir.path().join("sub.html");
+    fs::write(&input, SAMPLE_GRAPH).unwrap();
+
+    cmd()
+        .args(["subgraph", "zookeeper", "-d", "1", "-f", "html"])
+        .arg("--input")
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .success();
+    assert!(fs::read_to_string(&output).unwrap().contains("<html>"));
+
+    cmd()
+        .args(["subgraph", "quantum chromodynamics"])
+        .arg("--input")
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not in the graph"));
+}
+
+// ─── Viz server ─────────────────────────────────────────────────────────
+
+#[test]
+fn test_viz_serves_page_and_api() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command as StdCommand, Stdio};
+
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("graph.json");
+    fs::write(&input, SAMPLE_GRAPH).unwrap();
+
+    let mut child = StdCommand::new(assert_cmd::cargo::cargo_bin!("rknowledge"))
+        .args(["viz", "--port", "0", "--no-open", "--max-nodes", "2"])
+        .arg("--input")
+        .arg(&input)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Keep draining stdout so the server never writes to a closed pipe
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let url = lines
+        .by_ref()
+        .map_while(Result::ok)
+        .find_map(|line| {
+            line.split_whitespace()
+                .find(|w| w.starts_with("http://"))
+                .map(str::to_string)
+        })
+        .expect("server URL in output");
+    std::thread::spawn(move || lines.for_each(drop));
+
+    let get = |path: &str| -> serde_json::Value {
+        reqwest::blocking::get(format!("{}{}", url, path))
+            .unwrap()
+            .json()
+            .unwrap()
+    };
+
+    let page = reqwest::blocking::get(&url).unwrap().text().unwrap();
+    assert!(page.contains("setupLive"));
+
+    assert_eq!(get("api/stats")["nodes"], 4);
+    assert_eq!(
+        get("api/search?q=zookeper")["results"][0]["id"],
+        "zookeeper"
+    );
+    let expanded = get("api/expand?id=java&depth=1");
+    assert_eq!(expanded["nodes"].as_array().unwrap().len(), 2);
+    let path = get("api/path?from=producer&to=java");
+    assert_eq!(path["paths"][0]["ids"].as_array().unwrap().len(), 4);
+    assert_eq!(
+        get("api/community?id=1")["nodes"].as_array().unwrap().len(),
+        2
+    );
+
+    child.kill().ok();
+    child.wait().ok();
+}
+
 // ─── Auth subcommand ────────────────────────────────────────────────────
 
 #[test]
@@ -251,7 +949,19 @@ fn test_export_json_from_neo4j() {
 fn test_export_all_formats_from_neo4j() {
     let dir = tempdir().unwrap();
 
-    for format in ["json", "csv", "graphml", "cypher"] {
+    for format in [
+        "json",
+        "jsonl",
+        "csv",
+        "parquet",
+        "graphml",
+        "cypher",
+        "turtle",
+        "json-ld",
+        "gexf",
+        "dot",
+        "neo4j-admin",
+    ] {
         let output = dir.path().join(format!("graph.{}", format));
         cmd()
             .args([